@@ -13,6 +13,7 @@ pub enum AddTypeContext<'a> {
         vis: &'a Visibility,
         ident: Ident,
         attrs: &'a [Attribute],
+        key_ident: &'a Ident,
         fields: Vec<AddTypeFieldContext<'a>>,
     },
 }
@@ -33,13 +34,14 @@ impl AddTypeContext<'_> {
                 ident,
                 attrs,
                 fields,
+                ..
             } => {
                 let fields = fields
                     .iter()
                     .map(AddTypeFieldContext::expand_field_definition);
 
                 quote! {
-                    #[derive(::deli::reexports::serde::Serialize)]
+                    #[derive(::deli::reexports::serde::Serialize, ::deli::reexports::serde::Deserialize)]
                     #(#attrs)*
                     #vis struct #ident {
                         #(#fields),*
@@ -48,6 +50,37 @@ impl AddTypeContext<'_> {
             }
         }
     }
+
+    /// Expands the body of `on_write_add`, which applies the write hook of the model to a value being added (generated
+    /// keys are set to their default value while the hook is called).
+    pub fn expand_on_write_add(&self) -> TokenStream {
+        match self {
+            AddTypeContext::None => quote! {
+                let mut value = value;
+                ::deli::Model::on_write(&mut value);
+                value
+            },
+            AddTypeContext::Some {
+                ident,
+                key_ident,
+                fields,
+                ..
+            } => {
+                let fields = fields.iter().map(|field| field.ident).collect::<Vec<_>>();
+
+                quote! {
+                    let #ident { #(#fields),* } = value;
+                    let mut model = Self {
+                        #key_ident: ::core::default::Default::default(),
+                        #(#fields),*
+                    };
+                    ::deli::Model::on_write(&mut model);
+                    let Self { #(#fields,)* .. } = model;
+                    #ident { #(#fields),* }
+                }
+            }
+        }
+    }
 }
 
 impl<'a> TryFrom<(&'a Model, &'_ KeyContext<'_>)> for AddTypeContext<'a> {
@@ -56,7 +89,7 @@ impl<'a> TryFrom<(&'a Model, &'_ KeyContext<'_>)> for AddTypeContext<'a> {
     fn try_from(
         (model, key_context): (&'a Model, &'_ KeyContext<'_>),
     ) -> Result<Self, Self::Error> {
-        if !key_context.is_auto_increment() {
+        if !key_context.is_generated() {
             return Ok(AddTypeContext::None);
         }
 
@@ -66,11 +99,17 @@ impl<'a> TryFrom<(&'a Model, &'_ KeyContext<'_>)> for AddTypeContext<'a> {
             None => Ident::new(&format!("Add{}", model.ident), model.ident.span()),
         };
         let attrs = &model.attrs;
+        let key_ident = model
+            .fields()
+            .iter()
+            .find(|field| field.is_generated_key())
+            .map(|field| field.ident())
+            .unwrap();
 
         let fields = model
             .fields()
             .iter()
-            .filter(|field| !field.auto_increment.is_present())
+            .filter(|field| !field.is_generated_key())
             .map(|field| AddTypeFieldContext {
                 ident: field.ident.as_ref().unwrap(),
                 ty: &field.ty,
@@ -82,6 +121,7 @@ impl<'a> TryFrom<(&'a Model, &'_ KeyContext<'_>)> for AddTypeContext<'a> {
             vis,
             ident,
             attrs,
+            key_ident,
             fields,
         })
     }
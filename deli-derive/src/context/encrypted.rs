@@ -0,0 +1,75 @@
+use darling::{error::Accumulator, Error};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Ident;
+
+use crate::model::Model;
+
+use super::{IndexContext, KeyContext};
+
+pub struct EncryptedContext<'a> {
+    pub model_ident: &'a Ident,
+    pub field_idents: Vec<&'a Ident>,
+}
+
+impl EncryptedContext<'_> {
+    /// Expands to a compile time check that all the encrypted fields are of type `deli::encryption::Encrypted<T>`.
+    pub fn expand_encrypted_assertion(&self) -> TokenStream {
+        if self.field_idents.is_empty() {
+            return quote! {};
+        }
+
+        let model_ident = self.model_ident;
+        let field_idents = &self.field_idents;
+
+        quote! {
+            const _: () = {
+                fn assert_encrypted<T>(_: &::deli::encryption::Encrypted<T>) {}
+
+                fn assert_encrypted_fields(model: &#model_ident) {
+                    #(assert_encrypted(&model.#field_idents);)*
+                }
+            };
+        }
+    }
+}
+
+impl<'a> TryFrom<(&'a Model, &'_ KeyContext<'a>, &'_ [IndexContext<'a>])> for EncryptedContext<'a> {
+    type Error = Error;
+
+    fn try_from(
+        (model, key, indexes): (&'a Model, &'_ KeyContext<'a>, &'_ [IndexContext<'a>]),
+    ) -> Result<Self, Self::Error> {
+        let mut accumulator = Accumulator::default();
+        let mut field_idents = Vec::new();
+
+        for field in model
+            .fields()
+            .iter()
+            .filter(|field| field.encrypt.is_present())
+        {
+            let name = field.get_name_str();
+
+            if key.contains_key(&name) {
+                accumulator.push(
+                    Error::custom("Encrypted field cannot be used as a key")
+                        .with_span(field.ident()),
+                );
+            } else if indexes.iter().any(|index| index.contains_key(&name)) {
+                accumulator.push(
+                    Error::custom("Encrypted field cannot be used in an index")
+                        .with_span(field.ident()),
+                );
+            } else {
+                field_idents.push(field.ident());
+            }
+        }
+
+        accumulator.finish()?;
+
+        Ok(Self {
+            model_ident: &model.ident,
+            field_idents,
+        })
+    }
+}
@@ -6,21 +6,74 @@ use proc_macro2::TokenStream;
 use quote::quote;
 use syn::{Ident, LitStr, Type, Visibility};
 
-use crate::{index_meta::ModelIndexMeta, model::Model, model_field::ModelField};
-
-pub struct ByFnContext {
-    pub index_ident: Ident,
-    pub by_fn_ident: Ident,
+use crate::{
+    index_meta::{FieldIndexMeta, ModelIndexMeta},
+    model::Model,
+    model_field::ModelField,
+    timestamp_meta::TimestampUnit,
+};
+
+use super::{
+    expand_timestamp_unit, key_path,
+    lookup::{expand_composite_lookup, expand_single_lookup},
+};
+
+pub enum ByFnContext {
+    Index {
+        index_ident: Ident,
+        by_fn_ident: Ident,
+    },
+    Hashed {
+        index_ident: Ident,
+        by_fn_ident: Ident,
+        index_model: Ident,
+        value_ty: TokenStream,
+        unique: bool,
+    },
 }
 
 impl ByFnContext {
     pub fn expand_by_fn_definition(&self) -> TokenStream {
-        let index_ident = &self.index_ident;
-        let by_fn_ident = &self.by_fn_ident;
+        match self {
+            ByFnContext::Index {
+                index_ident,
+                by_fn_ident,
+            } => {
+                quote! {
+                    pub fn #by_fn_ident(&self) -> ::core::result::Result<::deli::Index<'t, #index_ident>, ::deli::Error> {
+                        self.index::<#index_ident>()
+                    }
+                }
+            }
+            ByFnContext::Hashed {
+                index_ident,
+                by_fn_ident,
+                index_model,
+                value_ty,
+                unique,
+            } => {
+                let (output, lookup) = if *unique {
+                    (
+                        quote! { ::core::option::Option<#index_model> },
+                        quote! { get::<::std::string::String>(&hash) },
+                    )
+                } else {
+                    (
+                        quote! { ::std::vec::Vec<#index_model> },
+                        quote! { get_all::<::std::string::String>(&hash, ::core::option::Option::None) },
+                    )
+                };
 
-        quote! {
-            pub fn #by_fn_ident(&self) -> ::core::result::Result<::deli::Index<'t, #index_ident>, ::deli::Error> {
-                self.index::<#index_ident>()
+                quote! {
+                    pub async fn #by_fn_ident<Q>(&self, value: &Q) -> ::core::result::Result<#output, ::deli::Error>
+                    where
+                        #value_ty: ::core::borrow::Borrow<Q>,
+                        Q: ::deli::reexports::serde::Serialize + ?::core::marker::Sized,
+                    {
+                        let hash = ::deli::encryption::hash_value(value)?;
+                        self.index::<#index_ident>()?.#lookup.await
+                    }
+                }
             }
         }
     }
@@ -32,8 +85,10 @@ pub enum IndexContext<'a> {
         key: Cow<'a, LitStr>,
         index_ident: Ident,
         index_name: Cow<'a, LitStr>,
+        alias: Option<&'a LitStr>,
         index_model: &'a Ident,
         index_ty: &'a Type,
+        field_ident: &'a Ident,
         by_fn_ident: Ident,
     },
     SingleUnique {
@@ -41,8 +96,10 @@ pub enum IndexContext<'a> {
         key: Cow<'a, LitStr>,
         index_ident: Ident,
         index_name: Cow<'a, LitStr>,
+        alias: Option<&'a LitStr>,
         index_model: &'a Ident,
         index_ty: &'a Type,
+        field_ident: &'a Ident,
         by_fn_ident: Ident,
     },
     SingleMultiEntry {
@@ -50,8 +107,34 @@ pub enum IndexContext<'a> {
         key: Cow<'a, LitStr>,
         index_ident: Ident,
         index_name: Cow<'a, LitStr>,
+        alias: Option<&'a LitStr>,
         index_model: &'a Ident,
         index_ty: &'a Type,
+        field_ident: &'a Ident,
+        by_fn_ident: Ident,
+    },
+    Hashed {
+        vis: &'a Visibility,
+        key: LitStr,
+        index_ident: Ident,
+        index_name: Cow<'a, LitStr>,
+        alias: Option<&'a LitStr>,
+        index_model: &'a Ident,
+        field: &'a ModelField,
+        unique: bool,
+        by_fn_ident: Ident,
+    },
+    Nested {
+        vis: &'a Visibility,
+        key: LitStr,
+        field_key: Cow<'a, LitStr>,
+        path: &'a LitStr,
+        index_ident: Ident,
+        index_name: Cow<'a, LitStr>,
+        alias: Option<&'a LitStr>,
+        index_model: &'a Ident,
+        index_ty: &'a Type,
+        unique: bool,
         by_fn_ident: Ident,
     },
     Composite {
@@ -59,8 +142,10 @@ pub enum IndexContext<'a> {
         keys: Vec<Cow<'a, LitStr>>,
         index_ident: Ident,
         index_name: Cow<'a, LitStr>,
+        alias: Option<&'a LitStr>,
         index_model: &'a Ident,
         index_tys: Vec<&'a Type>,
+        field_idents: Vec<&'a Ident>,
         by_fn_ident: Ident,
     },
     CompositeUnique {
@@ -68,8 +153,10 @@ pub enum IndexContext<'a> {
         keys: Vec<Cow<'a, LitStr>>,
         index_ident: Ident,
         index_name: Cow<'a, LitStr>,
+        alias: Option<&'a LitStr>,
         index_model: &'a Ident,
         index_tys: Vec<&'a Type>,
+        field_idents: Vec<&'a Ident>,
         by_fn_ident: Ident,
     },
     CompositeMultiEntry {
@@ -77,8 +164,10 @@ pub enum IndexContext<'a> {
         keys: Vec<Cow<'a, LitStr>>,
         index_ident: Ident,
         index_name: Cow<'a, LitStr>,
+        alias: Option<&'a LitStr>,
         index_model: &'a Ident,
         index_tys: Vec<&'a Type>,
+        field_idents: Vec<&'a Ident>,
         by_fn_ident: Ident,
     },
 }
@@ -92,23 +181,172 @@ impl<'a> TryFrom<&'a Model> for Vec<IndexContext<'a>> {
 }
 
 impl IndexContext<'_> {
-    fn ident(&self) -> &Ident {
+    pub fn ident(&self) -> &Ident {
         match self {
             IndexContext::Single { index_ident, .. }
             | IndexContext::SingleUnique { index_ident, .. }
             | IndexContext::SingleMultiEntry { index_ident, .. }
+            | IndexContext::Hashed { index_ident, .. }
+            | IndexContext::Nested { index_ident, .. }
             | IndexContext::Composite { index_ident, .. }
             | IndexContext::CompositeUnique { index_ident, .. }
             | IndexContext::CompositeMultiEntry { index_ident, .. } => index_ident,
         }
     }
 
-    pub fn expand_object_store_builder(&self) -> TokenStream {
+    pub fn name(&self) -> &LitStr {
+        match self {
+            IndexContext::Single { index_name, .. }
+            | IndexContext::SingleUnique { index_name, .. }
+            | IndexContext::SingleMultiEntry { index_name, .. }
+            | IndexContext::Hashed { index_name, .. }
+            | IndexContext::Nested { index_name, .. }
+            | IndexContext::Composite { index_name, .. }
+            | IndexContext::CompositeUnique { index_name, .. }
+            | IndexContext::CompositeMultiEntry { index_name, .. } => index_name,
+        }
+    }
+
+    pub fn by_fn_ident(&self) -> &Ident {
+        match self {
+            IndexContext::Single { by_fn_ident, .. }
+            | IndexContext::SingleUnique { by_fn_ident, .. }
+            | IndexContext::SingleMultiEntry { by_fn_ident, .. }
+            | IndexContext::Hashed { by_fn_ident, .. }
+            | IndexContext::Nested { by_fn_ident, .. }
+            | IndexContext::Composite { by_fn_ident, .. }
+            | IndexContext::CompositeUnique { by_fn_ident, .. }
+            | IndexContext::CompositeMultiEntry { by_fn_ident, .. } => by_fn_ident,
+        }
+    }
+
+    pub fn alias(&self) -> Option<&LitStr> {
+        match self {
+            IndexContext::Single { alias, .. }
+            | IndexContext::SingleUnique { alias, .. }
+            | IndexContext::SingleMultiEntry { alias, .. }
+            | IndexContext::Hashed { alias, .. }
+            | IndexContext::Nested { alias, .. }
+            | IndexContext::Composite { alias, .. }
+            | IndexContext::CompositeUnique { alias, .. }
+            | IndexContext::CompositeMultiEntry { alias, .. } => *alias,
+        }
+    }
+
+    /// Returns the identifier of the index struct if this is a non multi-entry index over the given single key.
+    pub fn single_key_index_ident(&self, field_key: &LitStr) -> Option<&Ident> {
+        match self {
+            IndexContext::Single {
+                key, index_ident, ..
+            }
+            | IndexContext::SingleUnique {
+                key, index_ident, ..
+            } if key.value() == field_key.value() => Some(index_ident),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the index key path contains the given key.
+    pub fn contains_key(&self, field_key: &LitStr) -> bool {
+        match self {
+            IndexContext::Single { key, .. }
+            | IndexContext::SingleUnique { key, .. }
+            | IndexContext::SingleMultiEntry { key, .. } => key.value() == field_key.value(),
+            IndexContext::Hashed { key, .. } => key.value() == field_key.value(),
+            IndexContext::Nested { field_key: key, .. } => key.value() == field_key.value(),
+            IndexContext::Composite { keys, .. }
+            | IndexContext::CompositeUnique { keys, .. }
+            | IndexContext::CompositeMultiEntry { keys, .. } => {
+                keys.iter().any(|key| key.value() == field_key.value())
+            }
+        }
+    }
+
+    /// Expands the description of the index in the schema of the object store, from which its index builder is created.
+    pub fn expand_index_schema(&self, key_path_prefix: &str) -> TokenStream {
         let ident = self.ident();
-        quote! { .add_index( <#ident as ::deli::ModelIndex> ::index_builder()) }
+
+        let single = |key: &LitStr| {
+            let key = key_path(key, key_path_prefix);
+            quote! { ::deli::reexports::idb::KeyPath::new_single( #key ) }
+        };
+        let array = |keys: &[Cow<'_, LitStr>]| {
+            let keys = keys.iter().map(|key| key_path(key, key_path_prefix));
+            quote! { ::deli::reexports::idb::KeyPath::new_array([ #(#keys),* ]) }
+        };
+
+        let (key_path, unique, multi_entry) = match self {
+            IndexContext::Single { key, .. } => (single(key), false, false),
+            IndexContext::SingleUnique { key, .. } => (single(key), true, false),
+            IndexContext::SingleMultiEntry { key, .. } => (single(key), false, true),
+            IndexContext::Hashed { key, unique, .. } => (single(key), *unique, false),
+            IndexContext::Nested { key, unique, .. } => (single(key), *unique, true),
+            IndexContext::Composite { keys, .. } => (array(keys), false, false),
+            IndexContext::CompositeUnique { keys, .. } => (array(keys), true, false),
+            IndexContext::CompositeMultiEntry { keys, .. } => (array(keys), false, true),
+        };
+
+        quote! {
+            ::deli::IndexSchema {
+                name: <#ident as ::deli::ModelIndex>::NAME,
+                key_path: #key_path,
+                unique: #unique,
+                multi_entry: #multi_entry,
+            }
+        }
+    }
+
+    /// Returns the name of the shadow field and the expression of the hashed value if this is a hashed index.
+    pub fn hashed_field(&self, value: &TokenStream) -> Option<(&LitStr, TokenStream)> {
+        match self {
+            IndexContext::Hashed { key, field, .. } => {
+                let ident = field.ident();
+                let expr = if field.encrypt.is_present() {
+                    quote! { &*#value.#ident }
+                } else {
+                    quote! { &#value.#ident }
+                };
+
+                Some((key, expr))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the shadow field and the path of the extracted values if this is a nested index.
+    pub fn nested_field(&self) -> Option<(&LitStr, &LitStr)> {
+        match self {
+            IndexContext::Nested { key, path, .. } => Some((key, path)),
+            _ => None,
+        }
     }
 
     pub fn by_fn_context(&self) -> ByFnContext {
+        if let IndexContext::Hashed {
+            index_ident,
+            index_model,
+            field,
+            unique,
+            by_fn_ident,
+            ..
+        } = self
+        {
+            let ty = &field.ty;
+            let value_ty = if field.encrypt.is_present() {
+                quote! { <#ty as ::core::ops::Deref>::Target }
+            } else {
+                quote! { #ty }
+            };
+
+            return ByFnContext::Hashed {
+                index_ident: index_ident.clone(),
+                by_fn_ident: by_fn_ident.clone(),
+                index_model: (*index_model).clone(),
+                value_ty,
+                unique: *unique,
+            };
+        }
+
         let (index_ident, by_fn_ident) = match self {
             IndexContext::Single {
                 index_ident,
@@ -125,6 +363,11 @@ impl IndexContext<'_> {
                 by_fn_ident,
                 ..
             }
+            | IndexContext::Nested {
+                index_ident,
+                by_fn_ident,
+                ..
+            }
             | IndexContext::Composite {
                 index_ident,
                 by_fn_ident,
@@ -140,186 +383,365 @@ impl IndexContext<'_> {
                 by_fn_ident,
                 ..
             } => (index_ident, by_fn_ident),
+            IndexContext::Hashed { .. } => unreachable!(),
         };
 
-        ByFnContext {
+        ByFnContext::Index {
             index_ident: index_ident.clone(),
             by_fn_ident: by_fn_ident.clone(),
         }
     }
 
-    pub fn expand_model_index_definition(&self) -> TokenStream {
+    /// Expands the hidden constant of the units of the components of the index key path which are stored as dates, if
+    /// any (hashed and nested indexes do not index the value of the field itself).
+    fn expand_datetime(&self, datetime_fields: &[(Cow<'_, LitStr>, TimestampUnit)]) -> TokenStream {
+        let keys = match self {
+            IndexContext::Single { key, .. }
+            | IndexContext::SingleUnique { key, .. }
+            | IndexContext::SingleMultiEntry { key, .. } => vec![key],
+            IndexContext::Composite { keys, .. }
+            | IndexContext::CompositeUnique { keys, .. }
+            | IndexContext::CompositeMultiEntry { keys, .. } => keys.iter().collect(),
+            IndexContext::Hashed { .. } | IndexContext::Nested { .. } => return quote! {},
+        };
+
+        let units = keys
+            .into_iter()
+            .map(|key| {
+                datetime_fields
+                    .iter()
+                    .find(|(name, _)| name.value() == key.value())
+                    .map(|(_, unit)| *unit)
+            })
+            .collect::<Vec<_>>();
+
+        if units.iter().all(Option::is_none) {
+            return quote! {};
+        }
+
+        let units = units.into_iter().map(|unit| match unit {
+            Some(unit) => {
+                let unit = expand_timestamp_unit(unit);
+                quote! { ::core::option::Option::Some(#unit) }
+            }
+            None => quote! { ::core::option::Option::None },
+        });
+
+        quote! {
+            const DATETIME: &'static [::core::option::Option<::deli::timestamps::TimestampUnit>] = &[ #(#units),* ];
+        }
+    }
+
+    /// Expands the serialization of the index key of a record, using references to its fields (hashed and nested
+    /// indexes are computed when writing records, so the default implementation failing to serialize them is kept).
+    fn expand_serialize_key(&self) -> TokenStream {
+        let value = match self {
+            IndexContext::Single { field_ident, .. }
+            | IndexContext::SingleUnique { field_ident, .. }
+            | IndexContext::SingleMultiEntry { field_ident, .. } => quote! { &model.#field_ident },
+            IndexContext::Composite { field_idents, .. }
+            | IndexContext::CompositeUnique { field_idents, .. }
+            | IndexContext::CompositeMultiEntry { field_idents, .. } => {
+                quote! { &( #(&model.#field_idents),* ) }
+            }
+            IndexContext::Hashed { .. } | IndexContext::Nested { .. } => return quote! {},
+        };
+
+        quote! {
+            fn serialize_key<S>(model: &Self::Model, serializer: S) -> ::core::result::Result<S::Ok, S::Error>
+            where
+                S: ::deli::reexports::serde::Serializer,
+            {
+                ::deli::reexports::serde::Serialize::serialize(#value, serializer)
+            }
+        }
+    }
+
+    pub fn expand_model_index_definition(
+        &self,
+        key_path_prefix: &str,
+        datetime_fields: &[(Cow<'_, LitStr>, TimestampUnit)],
+    ) -> TokenStream {
+        // Previous name of the index, used when the object store does not contain an index with the current name
+        let alias = self.alias().map(|alias| {
+            quote! {
+                const ALIAS: ::core::option::Option<&'static str> = ::core::option::Option::Some(#alias);
+            }
+        });
+        let datetime = self.expand_datetime(datetime_fields);
+        let serialize_key = self.expand_serialize_key();
+        let index_schema = self.expand_index_schema(key_path_prefix);
+
         match self {
             IndexContext::Single {
                 vis,
-                key,
                 index_ident,
                 index_name,
                 index_model,
                 index_ty,
                 ..
             } => {
+                let lookup = expand_single_lookup(index_ident, &quote! { #index_ty });
+
                 quote! {
                     #vis struct #index_ident;
 
                     impl ::deli::ModelIndex for #index_ident {
                         const NAME: &'static str = #index_name;
 
+                        #alias
+
+                        #datetime
+
+                        #serialize_key
+
                         type Model = #index_model;
 
                         type Key = #index_ty;
 
                         fn index_builder() -> ::deli::reexports::idb::builder::IndexBuilder {
-                            ::deli::reexports::idb::builder::IndexBuilder::new(
-                                ::std::string::ToString::to_string(<Self as ::deli::ModelIndex>::NAME),
-                                ::deli::reexports::idb::KeyPath::new_single( #key ),
-                            )
+                            ::core::convert::Into::into(#index_schema)
                         }
                     }
+
+                    #lookup
                 }
             }
             IndexContext::SingleUnique {
                 vis,
-                key,
                 index_ident,
                 index_name,
                 index_model,
                 index_ty,
                 ..
             } => {
+                let lookup = expand_single_lookup(index_ident, &quote! { #index_ty });
+
                 quote! {
                     #vis struct #index_ident;
 
                     impl ::deli::ModelIndex for #index_ident {
                         const NAME: &'static str = #index_name;
 
+                        #alias
+
+                        #datetime
+
+                        #serialize_key
+
                         type Model = #index_model;
 
                         type Key = #index_ty;
 
                         fn index_builder() -> ::deli::reexports::idb::builder::IndexBuilder {
-                            ::deli::reexports::idb::builder::IndexBuilder::new(
-                                ::std::string::ToString::to_string(<Self as ::deli::ModelIndex>::NAME),
-                                ::deli::reexports::idb::KeyPath::new_single( #key ),
-                            )
-                            .unique(true)
+                            ::core::convert::Into::into(#index_schema)
                         }
                     }
+
+                    #lookup
                 }
             }
             IndexContext::SingleMultiEntry {
                 vis,
-                key,
                 index_ident,
                 index_name,
                 index_model,
                 index_ty,
                 ..
             } => {
+                let lookup = expand_single_lookup(index_ident, &quote! { #index_ty });
+
+                quote! {
+                    #vis struct #index_ident;
+
+                    impl ::deli::ModelIndex for #index_ident {
+                        const NAME: &'static str = #index_name;
+
+                        #alias
+
+                        #datetime
+
+                        #serialize_key
+
+                        type Model = #index_model;
+
+                        type Key = #index_ty;
+
+                        fn index_builder() -> ::deli::reexports::idb::builder::IndexBuilder {
+                            ::core::convert::Into::into(#index_schema)
+                        }
+                    }
+
+                    #lookup
+                }
+            }
+            IndexContext::Hashed {
+                vis,
+                index_ident,
+                index_name,
+                index_model,
+                ..
+            } => {
+                let lookup = expand_single_lookup(index_ident, &quote! { ::std::string::String });
+
+                quote! {
+                    #vis struct #index_ident;
+
+                    impl ::deli::ModelIndex for #index_ident {
+                        const NAME: &'static str = #index_name;
+
+                        #alias
+
+                        #datetime
+
+                        #serialize_key
+
+                        type Model = #index_model;
+
+                        type Key = ::std::string::String;
+
+                        fn index_builder() -> ::deli::reexports::idb::builder::IndexBuilder {
+                            ::core::convert::Into::into(#index_schema)
+                        }
+                    }
+
+                    #lookup
+                }
+            }
+            IndexContext::Nested {
+                vis,
+                index_ident,
+                index_name,
+                index_model,
+                index_ty,
+                ..
+            } => {
+                let lookup = expand_single_lookup(index_ident, &quote! { #index_ty });
+
                 quote! {
                     #vis struct #index_ident;
 
                     impl ::deli::ModelIndex for #index_ident {
                         const NAME: &'static str = #index_name;
 
+                        #alias
+
+                        #datetime
+
+                        #serialize_key
+
                         type Model = #index_model;
 
                         type Key = #index_ty;
 
                         fn index_builder() -> ::deli::reexports::idb::builder::IndexBuilder {
-                            ::deli::reexports::idb::builder::IndexBuilder::new(
-                                ::std::string::ToString::to_string(<Self as ::deli::ModelIndex>::NAME),
-                                ::deli::reexports::idb::KeyPath::new_single( #key ),
-                            )
-                            .multi_entry(true)
+                            ::core::convert::Into::into(#index_schema)
                         }
                     }
+
+                    #lookup
                 }
             }
             IndexContext::Composite {
                 vis,
-                keys,
                 index_ident,
                 index_name,
                 index_model,
                 index_tys,
                 ..
             } => {
+                let lookup = expand_composite_lookup(index_ident, index_tys);
+
                 quote! {
                     #vis struct #index_ident;
 
                     impl ::deli::ModelIndex for #index_ident {
                         const NAME: &'static str = #index_name;
 
+                        #alias
+
+                        #datetime
+
+                        #serialize_key
+
                         type Model = #index_model;
 
                         type Key = ( #(#index_tys),* );
 
                         fn index_builder() -> ::deli::reexports::idb::builder::IndexBuilder {
-                            ::deli::reexports::idb::builder::IndexBuilder::new(
-                                ::std::string::ToString::to_string(<Self as ::deli::ModelIndex>::NAME),
-                                ::deli::reexports::idb::KeyPath::new_array([ #(#keys),* ]),
-                            )
+                            ::core::convert::Into::into(#index_schema)
                         }
                     }
+
+                    #lookup
                 }
             }
             IndexContext::CompositeUnique {
                 vis,
-                keys,
                 index_ident,
                 index_name,
                 index_model,
                 index_tys,
                 ..
             } => {
+                let lookup = expand_composite_lookup(index_ident, index_tys);
+
                 quote! {
                     #vis struct #index_ident;
 
                     impl ::deli::ModelIndex for #index_ident {
                         const NAME: &'static str = #index_name;
 
+                        #alias
+
+                        #datetime
+
+                        #serialize_key
+
                         type Model = #index_model;
 
                         type Key = ( #(#index_tys),* );
 
                         fn index_builder() -> ::deli::reexports::idb::builder::IndexBuilder {
-                            ::deli::reexports::idb::builder::IndexBuilder::new(
-                                ::std::string::ToString::to_string(<Self as ::deli::ModelIndex>::NAME),
-                                ::deli::reexports::idb::KeyPath::new_array([ #(#keys),* ]),
-                            )
-                            .unique(true)
+                            ::core::convert::Into::into(#index_schema)
                         }
                     }
+
+                    #lookup
                 }
             }
             IndexContext::CompositeMultiEntry {
                 vis,
-                keys,
                 index_ident,
                 index_name,
                 index_model,
                 index_tys,
                 ..
             } => {
+                let lookup = expand_composite_lookup(index_ident, index_tys);
+
                 quote! {
                     #vis struct #index_ident;
 
                     impl ::deli::ModelIndex for #index_ident {
                         const NAME: &'static str = #index_name;
 
+                        #alias
+
+                        #datetime
+
+                        #serialize_key
+
                         type Model = #index_model;
 
                         type Key = ( #(#index_tys),* );
 
                         fn index_builder() -> ::deli::reexports::idb::builder::IndexBuilder {
-                            ::deli::reexports::idb::builder::IndexBuilder::new(
-                                ::std::string::ToString::to_string(<Self as ::deli::ModelIndex>::NAME),
-                                ::deli::reexports::idb::KeyPath::new_array([ #(#keys),* ]),
-                            )
-                            .multi_entry(true)
+                            ::core::convert::Into::into(#index_schema)
                         }
                     }
+
+                    #lookup
                 }
             }
         }
@@ -359,6 +781,34 @@ fn get_indexes(model: &Model) -> Result<Vec<IndexContext<'_>>, Error> {
         }
     }
 
+    for (i, index) in indexes.iter().enumerate() {
+        if indexes[..i]
+            .iter()
+            .any(|other| other.ident() == index.ident())
+        {
+            accumulator.push(
+                Error::custom(format!(
+                    "Index `{}` is declared more than once",
+                    index.ident()
+                ))
+                .with_span(index.ident()),
+            );
+        }
+
+        if indexes[..i]
+            .iter()
+            .any(|other| other.by_fn_ident() == index.by_fn_ident())
+        {
+            accumulator.push(
+                Error::custom(format!(
+                    "Index accessor `{}` is generated more than once",
+                    index.by_fn_ident()
+                ))
+                .with_span(index.by_fn_ident()),
+            );
+        }
+    }
+
     accumulator.finish()?;
 
     Ok(indexes)
@@ -388,200 +838,337 @@ fn get_single_index_for_field<'a>(
         );
     }
 
-    let vis = &model.vis;
-    let key = field.get_name_str();
-    let index_model = &model.ident;
-    let index_ty = &field.ty;
+    if let Some(index) = get_hashed_index_for_field(model, field)? {
+        return Ok(Some(index));
+    }
 
-    if let Some(index_meta) = &field.index {
-        let (index_ident, index_name) = match index_meta {
-            Override::Inherit => (
-                Ident::new(
-                    &format!(
-                        "{}{}Index",
-                        model.ident,
-                        RenameRule::PascalCase.apply_to_field(field.ident().to_string())
-                    ),
-                    field.ident().span(),
-                ),
-                Cow::Owned(LitStr::new(
-                    &format!(
-                        "{}_{}_index",
-                        RenameRule::SnakeCase.apply_to_variant(model.ident.to_string()),
-                        field.ident()
-                    ),
-                    field.ident().span(),
-                )),
+    if let Some(index) = get_nested_index_for_field(model, field)? {
+        return Ok(Some(index));
+    }
+
+    let (kind, meta) = match (
+        field.index.as_deref(),
+        field.unique.as_deref(),
+        field.multi_entry.as_deref(),
+    ) {
+        // `updated_at` fields are implicitly indexed when no other index attribute is present (either on the field or
+        // on the container)
+        (None, None, None) => {
+            if model.declares_single_field_index(field) {
+                return Ok(None);
+            }
+
+            (SingleIndexKind::Index, &Override::Inherit)
+        }
+        (Some(meta), _, _) => (SingleIndexKind::Index, meta),
+        (_, Some(meta), _) => (SingleIndexKind::Unique, meta),
+        (_, _, Some(meta)) => (SingleIndexKind::MultiEntry, meta),
+    };
+
+    let (struct_name, name, alias, fn_name) = match meta {
+        Override::Inherit => (None, None, None, None),
+        Override::Explicit(meta) => (
+            meta.struct_name.as_ref(),
+            meta.name.as_ref(),
+            meta.alias.as_ref(),
+            meta.fn_name.as_ref(),
+        ),
+    };
+
+    Ok(Some(get_single_index(
+        model,
+        field,
+        kind,
+        struct_name,
+        name,
+        alias,
+        fn_name,
+    )))
+}
+
+#[derive(Clone, Copy)]
+enum SingleIndexKind {
+    Index,
+    Unique,
+    MultiEntry,
+}
+
+/// Builds the context of a single field index declared either on the field (e.g. `#[deli(unique)]`) or on the
+/// container with a single field (e.g. `#[deli(unique(fields(email)))]`).
+fn get_single_index<'a>(
+    model: &'a Model,
+    field: &'a ModelField,
+    kind: SingleIndexKind,
+    struct_name: Option<&LitStr>,
+    name: Option<&'a LitStr>,
+    alias: Option<&'a LitStr>,
+    fn_name: Option<&LitStr>,
+) -> IndexContext<'a> {
+    let suffix = match kind {
+        SingleIndexKind::Index => "index",
+        SingleIndexKind::Unique => "unique_index",
+        SingleIndexKind::MultiEntry => "multi_entry_index",
+    };
+
+    let index_ident = match struct_name {
+        None => Ident::new(
+            &format!(
+                "{}{}",
+                model.ident,
+                RenameRule::PascalCase.apply_to_field(format!("{}_{}", field.ident(), suffix))
             ),
-            Override::Explicit(index_meta) => {
-                let index_ident = match &index_meta.struct_name {
-                    None => Ident::new(
-                        &format!(
-                            "{}{}Index",
-                            model.ident,
-                            RenameRule::PascalCase.apply_to_field(field.ident().to_string())
-                        ),
-                        field.ident().span(),
-                    ),
-                    Some(struct_name) => Ident::new(&struct_name.value(), struct_name.span()),
-                };
+            field.ident().span(),
+        ),
+        Some(struct_name) => Ident::new(&struct_name.value(), struct_name.span()),
+    };
 
-                let index_name = match &index_meta.name {
-                    None => Cow::Owned(LitStr::new(
-                        &format!(
-                            "{}_{}_index",
-                            RenameRule::SnakeCase.apply_to_variant(model.ident.to_string()),
-                            field.ident()
-                        ),
-                        field.ident().span(),
-                    )),
-                    Some(name) => Cow::Borrowed(name),
-                };
+    let index_name = match name {
+        None => Cow::Owned(LitStr::new(
+            &model.default_index_name(
+                &field.ident().to_string(),
+                suffix,
+                &field.get_name_str().value(),
+            ),
+            field.ident().span(),
+        )),
+        Some(name) => Cow::Borrowed(name),
+    };
 
-                (index_ident, index_name)
-            }
-        };
+    let by_fn_suffix = match kind {
+        SingleIndexKind::Index => "",
+        SingleIndexKind::Unique => "_unique",
+        SingleIndexKind::MultiEntry => "_multi_entry",
+    };
+    let by_fn_ident = match fn_name {
+        None => Ident::new(
+            &format!("by_{}{}", field.ident(), by_fn_suffix),
+            field.ident().span(),
+        ),
+        Some(fn_name) => Ident::new(&fn_name.value(), fn_name.span()),
+    };
 
-        let by_fn_ident = Ident::new(&format!("by_{}", field.ident()), field.ident().span());
+    let vis = &model.vis;
+    let key = field.get_name_str();
+    let index_model = &model.ident;
+    let index_ty = &field.ty;
 
-        Ok(Some(IndexContext::Single {
+    match kind {
+        SingleIndexKind::Index => IndexContext::Single {
             vis,
             key,
             index_ident,
             index_name,
+            alias,
             index_model,
             index_ty,
+            field_ident: field.ident(),
             by_fn_ident,
-        }))
-    } else if let Some(unique_meta) = &field.unique {
-        let (index_ident, index_name) = match unique_meta {
-            Override::Inherit => (
-                Ident::new(
-                    &format!(
-                        "{}{}UniqueIndex",
-                        model.ident,
-                        RenameRule::PascalCase.apply_to_field(field.ident().to_string())
-                    ),
-                    field.ident().span(),
-                ),
-                Cow::Owned(LitStr::new(
-                    &format!(
-                        "{}_{}_unique_index",
-                        RenameRule::SnakeCase.apply_to_variant(model.ident.to_string()),
-                        field.ident()
-                    ),
-                    field.ident().span(),
-                )),
-            ),
-            Override::Explicit(index_meta) => {
-                let index_ident = match &index_meta.struct_name {
-                    None => Ident::new(
-                        &format!(
-                            "{}{}UniqueIndex",
-                            model.ident,
-                            RenameRule::PascalCase.apply_to_field(field.ident().to_string())
-                        ),
-                        field.ident().span(),
-                    ),
-                    Some(struct_name) => Ident::new(&struct_name.value(), struct_name.span()),
-                };
-
-                let index_name = match &index_meta.name {
-                    None => Cow::Owned(LitStr::new(
-                        &format!(
-                            "{}_{}_unique_index",
-                            RenameRule::SnakeCase.apply_to_variant(model.ident.to_string()),
-                            field.ident()
-                        ),
-                        field.ident().span(),
-                    )),
-                    Some(name) => Cow::Borrowed(name),
-                };
-
-                (index_ident, index_name)
-            }
-        };
-
-        let by_fn_ident = Ident::new(
-            &format!("by_{}_unique", field.ident()),
-            field.ident().span(),
-        );
-
-        Ok(Some(IndexContext::SingleUnique {
+        },
+        SingleIndexKind::Unique => IndexContext::SingleUnique {
             vis,
             key,
             index_ident,
             index_name,
+            alias,
             index_model,
             index_ty,
+            field_ident: field.ident(),
             by_fn_ident,
-        }))
-    } else if let Some(multi_entry_meta) = &field.multi_entry {
-        let (index_ident, index_name) = match multi_entry_meta {
-            Override::Inherit => (
-                Ident::new(
-                    &format!(
-                        "{}{}MultiEntryIndex",
-                        model.ident,
-                        RenameRule::PascalCase.apply_to_field(field.ident().to_string())
-                    ),
-                    field.ident().span(),
-                ),
-                Cow::Owned(LitStr::new(
-                    &format!(
-                        "{}_{}_multi_entry_index",
-                        RenameRule::SnakeCase.apply_to_variant(model.ident.to_string()),
-                        field.ident()
-                    ),
-                    field.ident().span(),
-                )),
-            ),
-            Override::Explicit(index_meta) => {
-                let index_ident = match &index_meta.struct_name {
-                    None => Ident::new(
-                        &format!(
-                            "{}{}MultiEntryIndex",
-                            model.ident,
-                            RenameRule::PascalCase.apply_to_field(field.ident().to_string())
-                        ),
-                        field.ident().span(),
-                    ),
-                    Some(struct_name) => Ident::new(&struct_name.value(), struct_name.span()),
-                };
-
-                let index_name = match &index_meta.name {
-                    None => Cow::Owned(LitStr::new(
-                        &format!(
-                            "{}_{}_multi_entry_index",
-                            RenameRule::SnakeCase.apply_to_variant(model.ident.to_string()),
-                            field.ident()
-                        ),
-                        field.ident().span(),
-                    )),
-                    Some(name) => Cow::Borrowed(name),
-                };
-
-                (index_ident, index_name)
-            }
-        };
-
-        let by_fn_ident = Ident::new(
-            &format!("by_{}_multi_entry", field.ident()),
-            field.ident().span(),
-        );
-
-        Ok(Some(IndexContext::SingleMultiEntry {
+        },
+        SingleIndexKind::MultiEntry => IndexContext::SingleMultiEntry {
             vis,
             key,
             index_ident,
             index_name,
+            alias,
             index_model,
             index_ty,
+            field_ident: field.ident(),
             by_fn_ident,
-        }))
-    } else {
+        },
+    }
+}
+
+fn get_hashed_index_for_field<'a>(
+    model: &'a Model,
+    field: &'a ModelField,
+) -> Result<Option<IndexContext<'a>>, Error> {
+    let (meta, unique, suffix) = match (
+        field.index.as_deref(),
+        field.unique.as_deref(),
+        field.multi_entry.as_deref(),
+    ) {
+        (Some(meta), _, _) if FieldIndexMeta::is_hashed(meta) => (meta, false, "hashed_index"),
+        (_, Some(meta), _) if FieldIndexMeta::is_hashed(meta) => {
+            (meta, true, "hashed_unique_index")
+        }
+        (_, _, Some(meta)) if FieldIndexMeta::is_hashed(meta) => {
+            return Err(
+                Error::custom("Multi entry index cannot be hashed").with_span(field.ident())
+            );
+        }
+        _ => return Ok(None),
+    };
+
+    let (struct_name, name, alias, fn_name) = match meta {
+        Override::Inherit => (None, None, None, None),
+        Override::Explicit(meta) => (
+            meta.struct_name.as_ref(),
+            meta.name.as_ref(),
+            meta.alias.as_ref(),
+            meta.fn_name.as_ref(),
+        ),
+    };
+
+    let index_ident = match struct_name {
+        None => Ident::new(
+            &format!(
+                "{}{}",
+                model.ident,
+                RenameRule::PascalCase.apply_to_field(format!("{}_{}", field.ident(), suffix))
+            ),
+            field.ident().span(),
+        ),
+        Some(struct_name) => Ident::new(&struct_name.value(), struct_name.span()),
+    };
+
+    let index_name = match name {
+        None => Cow::Owned(LitStr::new(
+            &model.default_index_name(
+                &field.ident().to_string(),
+                suffix,
+                &field.get_name_str().value(),
+            ),
+            field.ident().span(),
+        )),
+        Some(name) => Cow::Borrowed(name),
+    };
+
+    let key = LitStr::new(
+        &format!("__deli_hashed_{}", field.get_name_str().value()),
+        field.ident().span(),
+    );
+
+    let by_fn_ident = match fn_name {
+        None => Ident::new(
+            &format!("by_{}_hashed", field.ident()),
+            field.ident().span(),
+        ),
+        Some(fn_name) => Ident::new(&fn_name.value(), fn_name.span()),
+    };
+
+    Ok(Some(IndexContext::Hashed {
+        vis: &model.vis,
+        key,
+        index_ident,
+        index_name,
+        alias,
+        index_model: &model.ident,
+        field,
+        unique,
+        by_fn_ident,
+    }))
+}
+
+fn get_nested_index_for_field<'a>(
+    model: &'a Model,
+    field: &'a ModelField,
+) -> Result<Option<IndexContext<'a>>, Error> {
+    let (meta, path, unique) = match (
+        field.index.as_deref(),
+        field.unique.as_deref(),
+        field.multi_entry.as_deref(),
+    ) {
+        (Some(meta), _, _) | (_, _, Some(meta)) if FieldIndexMeta::key_path(meta).is_some() => {
+            (meta, FieldIndexMeta::key_path(meta).unwrap(), false)
+        }
+        (_, Some(meta), _) if FieldIndexMeta::key_path(meta).is_some() => {
+            (meta, FieldIndexMeta::key_path(meta).unwrap(), true)
+        }
+        _ => return Ok(None),
+    };
+
+    let Override::Explicit(meta) = meta else {
         unreachable!()
+    };
+
+    let field_key = field.get_name_str();
+
+    let path_value = path.value();
+    let segments = path_value
+        .split('.')
+        .map(|segment| segment.trim_end_matches("[]"))
+        .collect::<Vec<_>>();
+
+    if segments[0] != field_key.value()
+        || !path_value.contains("[]")
+        || segments.iter().any(|segment| segment.is_empty())
+    {
+        return Err(Error::custom(format!(
+            "Key path must start with `{}` and contain an array segment (e.g. `{}[].id`)",
+            field_key.value(),
+            field_key.value()
+        ))
+        .with_span(path));
+    }
+
+    if meta.hashed.is_present() {
+        return Err(Error::custom("Nested index cannot be hashed").with_span(path));
     }
+
+    let Some(index_ty) = meta.key_type.as_ref() else {
+        return Err(
+            Error::custom("Nested index requires the `key_type` of the extracted values")
+                .with_span(path),
+        );
+    };
+
+    let path_ident = segments
+        .iter()
+        .map(|segment| RenameRule::SnakeCase.apply_to_variant(segment))
+        .collect::<Vec<_>>()
+        .join("_");
+    let suffix = if unique { "unique_index" } else { "index" };
+
+    let index_ident = match &meta.struct_name {
+        None => Ident::new(
+            &format!(
+                "{}{}",
+                model.ident,
+                RenameRule::PascalCase.apply_to_field(format!("{}_{}", path_ident, suffix))
+            ),
+            field.ident().span(),
+        ),
+        Some(struct_name) => Ident::new(&struct_name.value(), struct_name.span()),
+    };
+
+    let index_name = match &meta.name {
+        None => Cow::Owned(LitStr::new(
+            &model.default_index_name(&path_ident, suffix, &path_value),
+            field.ident().span(),
+        )),
+        Some(name) => Cow::Borrowed(name),
+    };
+
+    let by_fn_ident = match &meta.fn_name {
+        None if unique => Ident::new(&format!("by_{}_unique", path_ident), field.ident().span()),
+        None => Ident::new(&format!("by_{}", path_ident), field.ident().span()),
+        Some(fn_name) => Ident::new(&fn_name.value(), fn_name.span()),
+    };
+
+    Ok(Some(IndexContext::Nested {
+        vis: &model.vis,
+        key: LitStr::new(&format!("__deli_nested_{}", path_ident), path.span()),
+        field_key,
+        path,
+        index_ident,
+        index_name,
+        alias: meta.alias.as_ref(),
+        index_model: &model.ident,
+        index_ty,
+        unique,
+        by_fn_ident,
+    }))
 }
 
 fn get_composite_index_for_meta<'a>(
@@ -590,6 +1177,18 @@ fn get_composite_index_for_meta<'a>(
 ) -> Result<IndexContext<'a>, Error> {
     let fields = model.get_fields_from_path_list(&meta.fields)?;
 
+    if let [field] = fields[..] {
+        return Ok(get_single_index(
+            model,
+            field,
+            SingleIndexKind::Index,
+            meta.struct_name.as_ref(),
+            meta.name.as_ref(),
+            meta.alias.as_ref(),
+            meta.fn_name.as_ref(),
+        ));
+    }
+
     let vis = &model.vis;
     let keys = fields
         .iter()
@@ -601,14 +1200,18 @@ fn get_composite_index_for_meta<'a>(
     let index_name = match &meta.name {
         Some(name) => Cow::Borrowed(name),
         None => Cow::Owned(LitStr::new(
-            &format!(
-                "{}_{}_composite_index",
-                RenameRule::SnakeCase.apply_to_variant(model.ident.to_string()),
-                fields
+            &model.default_index_name(
+                &fields
                     .iter()
                     .map(|field| field.ident().to_string())
                     .collect::<Vec<_>>()
-                    .join("_")
+                    .join("_"),
+                "composite_index",
+                &keys
+                    .iter()
+                    .map(|key| key.value())
+                    .collect::<Vec<_>>()
+                    .join(","),
             ),
             model.ident.span(),
         )),
@@ -630,25 +1233,30 @@ fn get_composite_index_for_meta<'a>(
         ),
     };
 
-    let by_fn_ident = Ident::new(
-        &format!(
-            "by_{}_composite",
-            fields
-                .iter()
-                .map(|field| field.ident().to_string())
-                .collect::<Vec<_>>()
-                .join("_")
+    let by_fn_ident = match &meta.fn_name {
+        Some(fn_name) => Ident::new(&fn_name.value(), fn_name.span()),
+        None => Ident::new(
+            &format!(
+                "by_{}_composite",
+                fields
+                    .iter()
+                    .map(|field| field.ident().to_string())
+                    .collect::<Vec<_>>()
+                    .join("_")
+            ),
+            model.ident.span(),
         ),
-        model.ident.span(),
-    );
+    };
 
     Ok(IndexContext::Composite {
         vis,
         keys,
         index_ident,
         index_name,
+        alias: meta.alias.as_ref(),
         index_model,
         index_tys,
+        field_idents: fields.iter().map(|field| field.ident()).collect(),
         by_fn_ident,
     })
 }
@@ -659,6 +1267,18 @@ fn get_composite_unique_index_for_meta<'a>(
 ) -> Result<IndexContext<'a>, Error> {
     let fields = model.get_fields_from_path_list(&meta.fields)?;
 
+    if let [field] = fields[..] {
+        return Ok(get_single_index(
+            model,
+            field,
+            SingleIndexKind::Unique,
+            meta.struct_name.as_ref(),
+            meta.name.as_ref(),
+            meta.alias.as_ref(),
+            meta.fn_name.as_ref(),
+        ));
+    }
+
     let vis = &model.vis;
     let keys = fields
         .iter()
@@ -670,14 +1290,18 @@ fn get_composite_unique_index_for_meta<'a>(
     let index_name = match &meta.name {
         Some(name) => Cow::Borrowed(name),
         None => Cow::Owned(LitStr::new(
-            &format!(
-                "{}_{}_composite_unique_index",
-                RenameRule::SnakeCase.apply_to_variant(model.ident.to_string()),
-                fields
+            &model.default_index_name(
+                &fields
                     .iter()
                     .map(|field| field.ident().to_string())
                     .collect::<Vec<_>>()
-                    .join("_")
+                    .join("_"),
+                "composite_unique_index",
+                &keys
+                    .iter()
+                    .map(|key| key.value())
+                    .collect::<Vec<_>>()
+                    .join(","),
             ),
             model.ident.span(),
         )),
@@ -699,25 +1323,30 @@ fn get_composite_unique_index_for_meta<'a>(
         ),
     };
 
-    let by_fn_ident = Ident::new(
-        &format!(
-            "by_{}_composite_unique",
-            fields
-                .iter()
-                .map(|field| field.ident().to_string())
-                .collect::<Vec<_>>()
-                .join("_")
+    let by_fn_ident = match &meta.fn_name {
+        Some(fn_name) => Ident::new(&fn_name.value(), fn_name.span()),
+        None => Ident::new(
+            &format!(
+                "by_{}_composite_unique",
+                fields
+                    .iter()
+                    .map(|field| field.ident().to_string())
+                    .collect::<Vec<_>>()
+                    .join("_")
+            ),
+            model.ident.span(),
         ),
-        model.ident.span(),
-    );
+    };
 
     Ok(IndexContext::CompositeUnique {
         vis,
         keys,
         index_ident,
         index_name,
+        alias: meta.alias.as_ref(),
         index_model,
         index_tys,
+        field_idents: fields.iter().map(|field| field.ident()).collect(),
         by_fn_ident,
     })
 }
@@ -728,6 +1357,18 @@ fn get_composite_multi_entry_index_for_meta<'a>(
 ) -> Result<IndexContext<'a>, Error> {
     let fields = model.get_fields_from_path_list(&meta.fields)?;
 
+    if let [field] = fields[..] {
+        return Ok(get_single_index(
+            model,
+            field,
+            SingleIndexKind::MultiEntry,
+            meta.struct_name.as_ref(),
+            meta.name.as_ref(),
+            meta.alias.as_ref(),
+            meta.fn_name.as_ref(),
+        ));
+    }
+
     let vis = &model.vis;
     let keys = fields
         .iter()
@@ -739,14 +1380,18 @@ fn get_composite_multi_entry_index_for_meta<'a>(
     let index_name = match &meta.name {
         Some(name) => Cow::Borrowed(name),
         None => Cow::Owned(LitStr::new(
-            &format!(
-                "{}_{}_composite_multi_entry_index",
-                RenameRule::SnakeCase.apply_to_variant(model.ident.to_string()),
-                fields
+            &model.default_index_name(
+                &fields
                     .iter()
                     .map(|field| field.ident().to_string())
                     .collect::<Vec<_>>()
-                    .join("_")
+                    .join("_"),
+                "composite_multi_entry_index",
+                &keys
+                    .iter()
+                    .map(|key| key.value())
+                    .collect::<Vec<_>>()
+                    .join(","),
             ),
             model.ident.span(),
         )),
@@ -768,25 +1413,30 @@ fn get_composite_multi_entry_index_for_meta<'a>(
         ),
     };
 
-    let by_fn_ident = Ident::new(
-        &format!(
-            "by_{}_composite_multi_entry",
-            fields
-                .iter()
-                .map(|field| field.ident().to_string())
-                .collect::<Vec<_>>()
-                .join("_")
+    let by_fn_ident = match &meta.fn_name {
+        Some(fn_name) => Ident::new(&fn_name.value(), fn_name.span()),
+        None => Ident::new(
+            &format!(
+                "by_{}_composite_multi_entry",
+                fields
+                    .iter()
+                    .map(|field| field.ident().to_string())
+                    .collect::<Vec<_>>()
+                    .join("_")
+            ),
+            model.ident.span(),
         ),
-        model.ident.span(),
-    );
+    };
 
     Ok(IndexContext::CompositeMultiEntry {
         vis,
         keys,
         index_ident,
         index_name,
+        alias: meta.alias.as_ref(),
         index_model,
         index_tys,
+        field_idents: fields.iter().map(|field| field.ident()).collect(),
         by_fn_ident,
     })
 }
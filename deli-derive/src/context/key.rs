@@ -3,58 +3,136 @@ use std::borrow::Cow;
 use darling::{error::Accumulator, Error};
 use proc_macro2::TokenStream;
 use quote::quote;
-use syn::{LitStr, Type};
+use syn::{Ident, LitStr, Path, Type};
 
-use crate::model::Model;
+use crate::{model::Model, model_field::ModelField};
+
+use super::{
+    key_path,
+    lookup::{expand_composite_lookup, expand_single_lookup},
+};
 
 pub enum KeyContext<'a> {
     Single {
+        ident: &'a Ident,
         key: Cow<'a, LitStr>,
         ty: &'a Type,
         auto_increment: bool,
+        key_generator: Option<&'a LitStr>,
     },
     Composite {
+        idents: Vec<&'a Ident>,
         keys: Vec<Cow<'a, LitStr>>,
         tys: Vec<&'a Type>,
+        /// Key path and generator of the component generated by `deli` when adding records, if any
+        generated: Option<(Cow<'a, LitStr>, &'a LitStr)>,
     },
+    /// Key stored outside of the record (`#[deli(external_key = "..")]`), given when writing records
+    External { ty: &'a Type },
 }
 
 impl KeyContext<'_> {
-    pub fn is_auto_increment(&self) -> bool {
+    /// Returns `true` if the key is generated when adding records, so that it is left out of the add type.
+    pub fn is_generated(&self) -> bool {
         match self {
-            KeyContext::Single { auto_increment, .. } => *auto_increment,
-            KeyContext::Composite { .. } => false,
+            KeyContext::Single {
+                auto_increment,
+                key_generator,
+                ..
+            } => *auto_increment || key_generator.is_some(),
+            KeyContext::Composite { generated, .. } => generated.is_some(),
+            KeyContext::External { .. } => false,
         }
     }
 
-    pub fn expand_object_store_builder(&self) -> TokenStream {
+    /// Expands the hidden constant of the key generator of the model (`#[deli(key_generator = "..")]` on the key or on a
+    /// component of the composite key), if any.
+    pub fn expand_key_generator(&self) -> Result<TokenStream, Error> {
+        let (key, key_generator) = match self {
+            KeyContext::Single {
+                key,
+                key_generator: Some(key_generator),
+                ..
+            } => (key, *key_generator),
+            KeyContext::Composite {
+                generated: Some((key, key_generator)),
+                ..
+            } => (key, *key_generator),
+            _ => return Ok(quote! {}),
+        };
+
+        let generator = match key_generator.value().as_str() {
+            "uuid" => quote! { ::deli::key_generator::KeyGenerator::Uuid },
+            "ulid" => quote! { ::deli::key_generator::KeyGenerator::Ulid },
+            "nanoid" => quote! { ::deli::key_generator::KeyGenerator::Nanoid },
+            _ => {
+                let path = key_generator.parse::<Path>().map_err(|_| {
+                    Error::custom(
+                        "Key generator must be `uuid`, `ulid`, `nanoid` or the path of a function",
+                    )
+                    .with_span(key_generator)
+                })?;
+
+                quote! { ::deli::key_generator::KeyGenerator::Custom(#path) }
+            }
+        };
+
+        Ok(quote! {
+            const KEY_GENERATOR: ::core::option::Option<(&'static str, ::deli::key_generator::KeyGenerator)> =
+                ::core::option::Option::Some((#key, #generator));
+        })
+    }
+
+    /// Returns `true` if the key path contains the given key.
+    pub fn contains_key(&self, field_key: &LitStr) -> bool {
         match self {
+            KeyContext::Single { key, .. } => key.value() == field_key.value(),
+            KeyContext::Composite { keys, .. } => {
+                keys.iter().any(|key| key.value() == field_key.value())
+            }
+            KeyContext::External { .. } => false,
+        }
+    }
+
+    /// Expands the key path and auto increment fields of the schema of the object store.
+    pub fn expand_store_schema(&self, key_path_prefix: &str) -> TokenStream {
+        let (key_path, auto_increment) = match self {
             KeyContext::Single {
                 key,
                 auto_increment,
                 ..
             } => {
-                let auto_increment = if *auto_increment {
-                    quote! { .auto_increment(true) }
-                } else {
-                    quote! {}
-                };
-
-                quote! {
-                    .key_path(::core::option::Option::Some(::deli::reexports::idb::KeyPath::new_single( #key )))#auto_increment
-                }
+                let key = key_path(key, key_path_prefix);
+
+                (
+                    quote! {
+                        ::core::option::Option::Some(::deli::reexports::idb::KeyPath::new_single( #key ))
+                    },
+                    *auto_increment,
+                )
             }
             KeyContext::Composite { keys, .. } => {
-                quote! {
-                    .key_path(::core::option::Option::Some(::deli::reexports::idb::KeyPath::new_array([ #(#keys),* ])))
-                }
+                let keys = keys.iter().map(|key| key_path(key, key_path_prefix));
+
+                (
+                    quote! {
+                        ::core::option::Option::Some(::deli::reexports::idb::KeyPath::new_array([ #(#keys),* ]))
+                    },
+                    false,
+                )
             }
+            KeyContext::External { .. } => (quote! { ::core::option::Option::None }, false),
+        };
+
+        quote! {
+            key_path: #key_path,
+            auto_increment: #auto_increment,
         }
     }
 
     pub fn expand_key_type(&self) -> TokenStream {
         match self {
-            KeyContext::Single { ty, .. } => {
+            KeyContext::Single { ty, .. } | KeyContext::External { ty } => {
                 quote! { #ty }
             }
             KeyContext::Composite { tys, .. } => {
@@ -62,6 +140,34 @@ impl KeyContext<'_> {
             }
         }
     }
+
+    /// Expands the serialization of the key of a record, using references to its fields (external keys are not part of
+    /// the record, so the default implementation failing to serialize them is kept).
+    pub fn expand_serialize_key(&self) -> TokenStream {
+        let value = match self {
+            KeyContext::Single { ident, .. } => quote! { &self.#ident },
+            KeyContext::Composite { idents, .. } => quote! { &( #(&self.#idents),* ) },
+            KeyContext::External { .. } => return quote! {},
+        };
+
+        quote! {
+            fn serialize_key<S>(&self, serializer: S) -> ::core::result::Result<S::Ok, S::Error>
+            where
+                S: ::deli::reexports::serde::Serializer,
+            {
+                ::deli::reexports::serde::Serialize::serialize(#value, serializer)
+            }
+        }
+    }
+
+    pub fn expand_lookup(&self, model_ident: &Ident) -> TokenStream {
+        match self {
+            KeyContext::Single { ty, .. } | KeyContext::External { ty } => {
+                expand_single_lookup(model_ident, &quote! { #ty })
+            }
+            KeyContext::Composite { tys, .. } => expand_composite_lookup(model_ident, tys),
+        }
+    }
 }
 
 impl<'a> TryFrom<&'a Model> for KeyContext<'a> {
@@ -73,6 +179,17 @@ impl<'a> TryFrom<&'a Model> for KeyContext<'a> {
 }
 
 fn get_key(model: &Model) -> Result<KeyContext<'_>, Error> {
+    if let Some(ty) = &model.external_key {
+        if model.key.is_some() || model.fields().iter().any(|field| field.is_key()) {
+            return Err(Error::custom(
+                "Model with an external key cannot have composite key or field key",
+            )
+            .with_span(&model.ident));
+        }
+
+        return Ok(KeyContext::External { ty });
+    }
+
     let mut accumulator = Accumulator::default();
 
     let composite_key = get_composite_key(model);
@@ -87,7 +204,7 @@ fn get_key(model: &Model) -> Result<KeyContext<'_>, Error> {
         }
         (Ok(None), Ok(None)) => {
             accumulator.push(
-                Error::custom("Model must have either composite key or field key")
+                Error::custom("Model must have either composite key, field key or external key")
                     .with_span(&model.ident),
             );
         }
@@ -117,22 +234,58 @@ fn get_composite_key(model: &Model) -> Result<Option<KeyContext<'_>>, Error> {
     match model.key.as_ref() {
         None => Ok(None),
         Some(path_list) => {
-            let (keys, tys) = model
-                .get_fields_from_path_list(path_list)?
-                .into_iter()
-                .map(|field| (field.get_name_str(), &field.ty))
-                .unzip();
+            let fields = model.get_fields_from_path_list(path_list)?;
+
+            // IndexedDB only auto increments single key paths, so only components generated by `deli` are supported
+            if let Some(field) = fields
+                .iter()
+                .find(|field| field.auto_increment.is_present())
+            {
+                return Err(Error::custom(
+                    "Composite key cannot be auto incremented, use `key_generator` to generate a component",
+                )
+                .with_span(field.ident()));
+            }
 
-            Ok(Some(KeyContext::Composite { keys, tys }))
+            let mut generated = fields
+                .iter()
+                .filter_map(|field| Some((field.get_name_str(), field.key_generator.as_ref()?)));
+
+            let first = generated.next();
+
+            if let Some((key, _)) = generated.next() {
+                return Err(Error::custom(
+                    "Composite key cannot have more than one generated component",
+                )
+                .with_span(&key.span()));
+            }
+
+            Ok(Some(KeyContext::Composite {
+                idents: fields.iter().map(|field| field.ident()).collect(),
+                keys: fields.iter().map(|field| field.get_name_str()).collect(),
+                tys: fields.iter().map(|field| &field.ty).collect(),
+                generated: first,
+            }))
         }
     }
 }
 
 fn get_single_key(model: &Model) -> Result<Option<KeyContext<'_>>, Error> {
+    // Generated components of the composite key are not field keys
+    let in_composite_key = |field: &ModelField| {
+        model.key.as_ref().is_some_and(|path_list| {
+            path_list
+                .iter()
+                .any(|path| path.get_ident() == field.ident.as_ref())
+        })
+    };
+
     let field = model
         .fields()
         .iter()
-        .filter(|field| field.is_key())
+        .filter(|field| {
+            field.is_key() && !(field.key_generator.is_some() && in_composite_key(field))
+        })
         .collect::<Vec<_>>();
 
     if field.len() > 1 {
@@ -148,8 +301,10 @@ fn get_single_key(model: &Model) -> Result<Option<KeyContext<'_>>, Error> {
     let field = field.first().unwrap();
 
     Ok(Some(KeyContext::Single {
+        ident: field.ident(),
         key: field.get_name_str(),
         auto_increment: field.auto_increment.is_present(),
+        key_generator: field.key_generator.as_ref(),
         ty: &field.ty,
     }))
 }
@@ -0,0 +1,33 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{Ident, Type};
+
+/// Implements `Lookup` for all the types the key can be borrowed as.
+pub fn expand_single_lookup(ident: &Ident, ty: &TokenStream) -> TokenStream {
+    quote! {
+        impl<Q> ::deli::Lookup<Q> for #ident
+        where
+            #ty: ::core::borrow::Borrow<Q>,
+            Q: ?::core::marker::Sized,
+        {
+        }
+    }
+}
+
+/// Implements `Lookup` for the composite key and for tuples of references to the types its elements can be borrowed as.
+pub fn expand_composite_lookup(ident: &Ident, tys: &[&Type]) -> TokenStream {
+    let params = (0..tys.len())
+        .map(|i| Ident::new(&format!("Q{i}"), Span::call_site()))
+        .collect::<Vec<_>>();
+
+    quote! {
+        impl ::deli::Lookup<( #(#tys),* )> for #ident {}
+
+        impl<'q, #(#params),*> ::deli::Lookup<( #(&'q #params),* )> for #ident
+        where
+            #(#tys: ::core::borrow::Borrow<#params>,)*
+            #(#params: ?::core::marker::Sized,)*
+        {
+        }
+    }
+}
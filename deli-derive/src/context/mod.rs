@@ -1,7 +1,32 @@
 mod add_type;
+mod encrypted;
 mod index;
 mod key;
+mod lookup;
 mod model;
 mod object_store;
+mod patch;
+mod query;
+mod tracked;
 
 pub use self::{index::IndexContext, key::KeyContext, model::ModelContext};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::LitStr;
+
+use crate::timestamp_meta::TimestampUnit;
+
+/// Returns the key path of a field in the stored records, e.g. prefixed with `data.` for models stored in an envelope.
+fn key_path(key: &LitStr, prefix: &str) -> LitStr {
+    LitStr::new(&format!("{prefix}{}", key.value()), key.span())
+}
+
+/// Expands the path of the runtime value of a timestamp unit.
+fn expand_timestamp_unit(unit: TimestampUnit) -> TokenStream {
+    match unit {
+        TimestampUnit::Millis => quote! { ::deli::timestamps::TimestampUnit::Millis },
+        TimestampUnit::Seconds => quote! { ::deli::timestamps::TimestampUnit::Seconds },
+        TimestampUnit::Iso8601 => quote! { ::deli::timestamps::TimestampUnit::Iso8601 },
+    }
+}
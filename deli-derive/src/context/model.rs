@@ -1,13 +1,23 @@
 use std::borrow::Cow;
 
-use darling::{error::Accumulator, Error};
+use darling::{error::Accumulator, util::Override, Error};
 use proc_macro2::TokenStream;
 use quote::quote;
-use syn::{Ident, LitStr};
+use syn::{Ident, LitStr, Path};
 
-use crate::model::Model;
+use crate::{
+    envelope_meta::{EnvelopeMeta, MigrateOnRead},
+    model::Model,
+    serde_attrs::serialized_name,
+    timestamp_meta::TimestampUnit,
+    tombstones_meta::TombstonesMeta,
+};
 
-use super::{add_type::AddTypeContext, object_store::ObjectStoreContext, IndexContext, KeyContext};
+use super::{
+    add_type::AddTypeContext, encrypted::EncryptedContext, expand_timestamp_unit,
+    object_store::ObjectStoreContext, patch::PatchContext, query::QueryContext,
+    tracked::TrackedContext, IndexContext, KeyContext,
+};
 
 pub struct ModelContext<'a> {
     pub ident: &'a Ident,
@@ -16,26 +26,56 @@ pub struct ModelContext<'a> {
     pub indexes: Vec<IndexContext<'a>>,
     pub add_type: AddTypeContext<'a>,
     pub object_store: ObjectStoreContext<'a>,
+    pub query: QueryContext<'a>,
+    pub patch: PatchContext<'a>,
+    pub tracked: TrackedContext<'a>,
+    pub encrypted: EncryptedContext<'a>,
+    pub default_limit: Option<u32>,
+    pub on_read: Option<&'a Path>,
+    pub on_write: Option<&'a Path>,
+    pub structured_clone: bool,
+    pub redacted_fields: Vec<Cow<'a, LitStr>>,
+    pub references: Vec<(Cow<'a, LitStr>, &'a Path)>,
+    pub created_at: Option<(Cow<'a, LitStr>, TimestampUnit)>,
+    pub datetime_fields: Vec<(Cow<'a, LitStr>, TimestampUnit)>,
+    pub bytes_fields: Vec<Cow<'a, LitStr>>,
+    pub key_generator: TokenStream,
+    pub tombstones: Option<&'a Override<TombstonesMeta>>,
+    pub envelope: Option<&'a EnvelopeMeta>,
 }
 
 impl ModelContext<'_> {
     pub fn expand(&self) -> TokenStream {
         let model_definition = self.expand_model_definition();
         let add_type_definition = self.add_type.expand_add_type_definition();
-        let index_definitions = self
-            .indexes
-            .iter()
-            .map(|index| index.expand_model_index_definition());
+        let index_definitions = self.indexes.iter().map(|index| {
+            index.expand_model_index_definition(self.key_path_prefix(), &self.datetime_fields)
+        });
         let object_store_definition = self.object_store.expand_object_store_definition();
+        let query_definition = self.query.expand_query_definition();
+        let patch_definition = self.patch.expand_patch_definition();
+        let tracked_definition = self.tracked.expand_tracked_definition();
+        let encrypted_assertion = self.encrypted.expand_encrypted_assertion();
+        let lookup = self.key.expand_lookup(self.ident);
 
         quote! {
             #model_definition
 
+            #lookup
+
             #add_type_definition
 
             #(#index_definitions)*
 
             #object_store_definition
+
+            #query_definition
+
+            #patch_definition
+
+            #tracked_definition
+
+            #encrypted_assertion
         }
     }
 
@@ -45,12 +85,33 @@ impl ModelContext<'_> {
         let key = self.key.expand_key_type();
         let add = &self.add_type.ident();
         let object_store = &self.object_store.ident;
+        let query = &self.query.ident;
+        let patch = &self.patch.ident;
 
-        let key_object_store_builder = self.key.expand_object_store_builder();
-        let indexes_object_store_builder = self
+        let default_limit = self.expand_default_limit();
+        let index_names = self.expand_index_names();
+        let tombstones = self.expand_tombstones();
+        let envelope = self.expand_envelope();
+        let hashed_fields = self.expand_hashed_fields();
+        let nested_fields = self.expand_nested_fields();
+        let redacted_fields = self.expand_redacted_fields();
+        let references = self.expand_references();
+        let created_at = self.expand_created_at();
+        let datetime_fields = self.expand_datetime_fields();
+        let bytes_fields = self.expand_bytes_fields();
+        let key_generator = &self.key_generator;
+        let on_read = self.expand_on_read();
+        let on_write = self.expand_on_write();
+        let structured_clone = self.expand_structured_clone();
+
+        let serialize_key = self.key.expand_serialize_key();
+        let apply_patch = self.patch.expand_apply_patch();
+
+        let key_store_schema = self.key.expand_store_schema(self.key_path_prefix());
+        let index_schemas = self
             .indexes
             .iter()
-            .map(|index| index.expand_object_store_builder());
+            .map(|index| index.expand_index_schema(self.key_path_prefix()));
 
         quote! {
             impl ::deli::Model for #ident {
@@ -62,10 +123,306 @@ impl ModelContext<'_> {
 
                 type ObjectStore<'t> = #object_store<'t>;
 
+                type Query = #query;
+
+                type Patch = #patch;
+
+                #default_limit
+
+                #index_names
+
+                #tombstones
+
+                #envelope
+
+                #hashed_fields
+
+                #nested_fields
+
+                #redacted_fields
+
+                #references
+
+                #created_at
+
+                #datetime_fields
+
+                #bytes_fields
+
+                #key_generator
+
+                #on_read
+
+                #on_write
+
+                #structured_clone
+
+                #serialize_key
+
+                #apply_patch
+
                 fn object_store_builder() -> ::deli::reexports::idb::builder::ObjectStoreBuilder {
-                    ::deli::reexports::idb::builder::ObjectStoreBuilder::new(Self::NAME)
-                        #key_object_store_builder
-                        #(#indexes_object_store_builder)*
+                    ::core::convert::Into::into(<Self as ::deli::Model>::store_schema())
+                }
+
+                fn store_schema() -> ::deli::StoreSchema {
+                    ::deli::StoreSchema {
+                        name: Self::NAME,
+                        #key_store_schema
+                        indexes: ::std::vec![ #(#index_schemas),* ],
+                    }
+                }
+            }
+        }
+    }
+
+    fn expand_default_limit(&self) -> TokenStream {
+        match self.default_limit {
+            None => quote! {},
+            Some(limit) => quote! {
+                const DEFAULT_LIMIT: ::core::option::Option<u32> = ::core::option::Option::Some(#limit);
+            },
+        }
+    }
+
+    fn expand_hashed_fields(&self) -> TokenStream {
+        let self_value = quote! { self };
+        let add_value = quote! { value };
+
+        let (keys, self_exprs): (Vec<_>, Vec<_>) = self
+            .indexes
+            .iter()
+            .filter_map(|index| index.hashed_field(&self_value))
+            .unzip();
+
+        if keys.is_empty() {
+            return quote! {};
+        }
+
+        let add_exprs = self
+            .indexes
+            .iter()
+            .filter_map(|index| index.hashed_field(&add_value))
+            .map(|(_, expr)| expr);
+
+        quote! {
+            const HASHED_FIELDS: &'static [&'static str] = &[ #(#keys),* ];
+
+            fn hashed_fields(&self) -> ::core::result::Result<::std::vec::Vec<(&'static str, ::std::string::String)>, ::deli::Error> {
+                ::core::result::Result::Ok(::std::vec![
+                    #( (#keys, ::deli::encryption::hash_value(#self_exprs)?) ),*
+                ])
+            }
+
+            fn add_hashed_fields(value: &Self::Add) -> ::core::result::Result<::std::vec::Vec<(&'static str, ::std::string::String)>, ::deli::Error> {
+                ::core::result::Result::Ok(::std::vec![
+                    #( (#keys, ::deli::encryption::hash_value(#add_exprs)?) ),*
+                ])
+            }
+        }
+    }
+
+    fn expand_nested_fields(&self) -> TokenStream {
+        let (keys, paths): (Vec<_>, Vec<_>) = self
+            .indexes
+            .iter()
+            .filter_map(|index| index.nested_field())
+            .unzip();
+
+        if keys.is_empty() {
+            return quote! {};
+        }
+
+        quote! {
+            const NESTED_FIELDS: &'static [(&'static str, &'static str)] = &[ #( (#keys, #paths) ),* ];
+        }
+    }
+
+    fn expand_on_read(&self) -> TokenStream {
+        match self.on_read {
+            None => quote! {},
+            Some(on_read) => quote! {
+                fn on_read(&mut self) {
+                    #on_read(self)
+                }
+            },
+        }
+    }
+
+    fn expand_on_write(&self) -> TokenStream {
+        let Some(on_write) = self.on_write else {
+            return quote! {};
+        };
+
+        let on_write_add = self.add_type.expand_on_write_add();
+
+        quote! {
+            const ON_WRITE: bool = true;
+
+            fn on_write(&mut self) {
+                #on_write(self)
+            }
+
+            fn on_write_add(value: &Self::Add) -> ::core::result::Result<::core::option::Option<Self::Add>, ::deli::Error> {
+                let value = <Self as ::deli::Model>::copy_value(value)?;
+                ::core::result::Result::Ok(::core::option::Option::Some({ #on_write_add }))
+            }
+        }
+    }
+
+    fn expand_structured_clone(&self) -> TokenStream {
+        if !self.structured_clone {
+            return quote! {};
+        }
+
+        quote! {
+            const STRUCTURED_CLONE: bool = true;
+        }
+    }
+
+    fn expand_index_names(&self) -> TokenStream {
+        if self.indexes.is_empty() {
+            return quote! {};
+        }
+
+        let index_idents = self.indexes.iter().map(|index| index.ident());
+
+        quote! {
+            const INDEX_NAMES: &'static [&'static str] = &[ #(<#index_idents as ::deli::ModelIndex>::NAME),* ];
+        }
+    }
+
+    fn expand_redacted_fields(&self) -> TokenStream {
+        if self.redacted_fields.is_empty() {
+            return quote! {};
+        }
+
+        let redacted_fields = &self.redacted_fields;
+
+        quote! {
+            const REDACTED_FIELDS: &'static [&'static str] = &[ #(#redacted_fields),* ];
+        }
+    }
+
+    fn expand_references(&self) -> TokenStream {
+        if self.references.is_empty() {
+            return quote! {};
+        }
+
+        let references = self.references.iter().map(|(field, model)| {
+            quote! {
+                ::deli::integrity::Reference {
+                    field: #field,
+                    store: <#model as ::deli::Model>::NAME,
+                }
+            }
+        });
+
+        quote! {
+            const REFERENCES: &'static [::deli::integrity::Reference] = &[ #(#references),* ];
+        }
+    }
+
+    fn expand_created_at(&self) -> TokenStream {
+        let Some((name, unit)) = &self.created_at else {
+            return quote! {};
+        };
+
+        let unit = expand_timestamp_unit(*unit);
+
+        quote! {
+            const CREATED_AT: ::core::option::Option<(&'static str, ::deli::timestamps::TimestampUnit)> =
+                ::core::option::Option::Some((#name, #unit));
+        }
+    }
+
+    fn expand_datetime_fields(&self) -> TokenStream {
+        if self.datetime_fields.is_empty() {
+            return quote! {};
+        }
+
+        let datetime_fields = self.datetime_fields.iter().map(|(name, unit)| {
+            let unit = expand_timestamp_unit(*unit);
+            quote! { (#name, #unit) }
+        });
+
+        quote! {
+            const DATETIME_FIELDS: &'static [(&'static str, ::deli::timestamps::TimestampUnit)] =
+                &[ #(#datetime_fields),* ];
+        }
+    }
+
+    fn expand_bytes_fields(&self) -> TokenStream {
+        if self.bytes_fields.is_empty() {
+            return quote! {};
+        }
+
+        let bytes_fields = &self.bytes_fields;
+
+        quote! {
+            const BYTES_FIELDS: &'static [&'static str] = &[ #(#bytes_fields),* ];
+        }
+    }
+
+    /// Returns the prefix of the key paths of the model, pointing into the data of the envelope if the model is stored
+    /// in one.
+    fn key_path_prefix(&self) -> &'static str {
+        match self.envelope {
+            None => "",
+            Some(_) => "data.",
+        }
+    }
+
+    fn expand_envelope(&self) -> TokenStream {
+        let Some(envelope) = self.envelope else {
+            return quote! {};
+        };
+
+        let version = envelope.version;
+        let (froms, withs): (Vec<_>, Vec<_>) = envelope
+            .upgrade
+            .iter()
+            .map(|upgrade| (upgrade.from, &upgrade.with))
+            .unzip();
+
+        let migrate_on_read = match envelope.migrate_on_read {
+            MigrateOnRead::InMemory => quote! { ::deli::envelope::MigrateOnRead::InMemory },
+            MigrateOnRead::WriteBack => quote! { ::deli::envelope::MigrateOnRead::WriteBack },
+        };
+
+        quote! {
+            const ENVELOPE_VERSION: ::core::option::Option<u32> = ::core::option::Option::Some(#version);
+
+            const MIGRATE_ON_READ: ::deli::envelope::MigrateOnRead = #migrate_on_read;
+
+            fn upgrade(version: u32, data: ::deli::envelope::Data) -> ::core::result::Result<Self, ::deli::Error> {
+                match version {
+                    #( #froms => ::deli::envelope::upgrade_with(data, #withs), )*
+                    _ => ::core::result::Result::Err(::deli::Error::UnsupportedEnvelopeVersion {
+                        store: Self::NAME,
+                        version,
+                    }),
+                }
+            }
+        }
+    }
+
+    fn expand_tombstones(&self) -> TokenStream {
+        match self.tombstones {
+            None => quote! {},
+            Some(meta) => {
+                let expire_after_secs = match meta {
+                    Override::Explicit(TombstonesMeta {
+                        expire_after_secs: Some(secs),
+                    }) => quote! { ::core::option::Option::Some(#secs) },
+                    _ => quote! { ::core::option::Option::None },
+                };
+
+                quote! {
+                    const TOMBSTONES: ::core::option::Option<::deli::sync::TombstonePolicy> =
+                        ::core::option::Option::Some(::deli::sync::TombstonePolicy {
+                            expire_after_secs: #expire_after_secs,
+                        });
                 }
             }
         }
@@ -78,6 +435,10 @@ impl<'a> TryFrom<&'a Model> for ModelContext<'a> {
     fn try_from(model: &'a Model) -> Result<Self, Self::Error> {
         let mut accumulator = Accumulator::default();
 
+        if let Err(err) = model.validate_attributes() {
+            accumulator.push(err);
+        }
+
         let ident = &model.ident;
         let name = model.get_name_str();
         let key = KeyContext::try_from(model);
@@ -104,6 +465,22 @@ impl<'a> TryFrom<&'a Model> for ModelContext<'a> {
         let key = key.unwrap();
         let indexes = indexes.unwrap();
 
+        validate_serialized_names(model, &key, &indexes)?;
+
+        if let Some(envelope) = &model.envelope {
+            validate_envelope(envelope)?;
+        }
+
+        let created_at = get_created_at(model, &key)?;
+        let datetime_fields = get_datetime_fields(model, &key)?;
+        let bytes_fields = get_bytes_fields(model, &key, &indexes)?;
+        let key_generator = key.expand_key_generator()?;
+
+        if let Some(expected_names) = &model.expected_names {
+            let index_names = indexes.iter().map(|index| index.name()).collect::<Vec<_>>();
+            expected_names.validate(&name, &index_names)?;
+        }
+
         let by_fns = indexes
             .iter()
             .map(|index| index.by_fn_context())
@@ -113,6 +490,10 @@ impl<'a> TryFrom<&'a Model> for ModelContext<'a> {
 
         let add_type = AddTypeContext::try_from((model, &key));
         let object_store = ObjectStoreContext::try_from((model, by_fns));
+        let query = QueryContext::try_from((model, indexes.as_slice()));
+        let patch = PatchContext::try_from((model, &key));
+        let tracked = TrackedContext::try_from((model, indexes.as_slice()));
+        let encrypted = EncryptedContext::try_from((model, &key, indexes.as_slice()));
 
         let add_type = match add_type {
             Ok(add_type) => Some(add_type),
@@ -130,10 +511,46 @@ impl<'a> TryFrom<&'a Model> for ModelContext<'a> {
             }
         };
 
+        let query = match query {
+            Ok(query) => Some(query),
+            Err(err) => {
+                accumulator.push(err);
+                None
+            }
+        };
+
+        let patch = match patch {
+            Ok(patch) => Some(patch),
+            Err(err) => {
+                accumulator.push(err);
+                None
+            }
+        };
+
+        let tracked = match tracked {
+            Ok(tracked) => Some(tracked),
+            Err(err) => {
+                accumulator.push(err);
+                None
+            }
+        };
+
+        let encrypted = match encrypted {
+            Ok(encrypted) => Some(encrypted),
+            Err(err) => {
+                accumulator.push(err);
+                None
+            }
+        };
+
         accumulator.finish()?;
 
         let add_type = add_type.unwrap();
         let object_store = object_store.unwrap();
+        let query = query.unwrap();
+        let patch = patch.unwrap();
+        let tracked = tracked.unwrap();
+        let encrypted = encrypted.unwrap();
 
         Ok(Self {
             ident,
@@ -142,6 +559,205 @@ impl<'a> TryFrom<&'a Model> for ModelContext<'a> {
             indexes,
             add_type,
             object_store,
+            query,
+            patch,
+            tracked,
+            encrypted,
+            default_limit: model.default_limit,
+            on_read: model.on_read.as_ref(),
+            on_write: model.on_write.as_ref(),
+            structured_clone: model.structured_clone.is_present(),
+            redacted_fields: model
+                .fields()
+                .iter()
+                .filter(|field| field.redact.is_present())
+                .map(|field| field.get_name_str())
+                .collect(),
+            references: model
+                .fields()
+                .iter()
+                .filter_map(|field| {
+                    field
+                        .references
+                        .as_ref()
+                        .map(|model| (field.get_name_str(), model))
+                })
+                .collect(),
+            created_at,
+            datetime_fields,
+            bytes_fields,
+            key_generator,
+            tombstones: model.tombstones.as_ref(),
+            envelope: model.envelope.as_ref(),
         })
     }
 }
+
+/// Checks that the fields referred to by name in the object store (key paths, hashed fields, redacted fields, etc.)
+/// are serialized under that name by serde. Otherwise, the records would be stored without the field, silently
+/// leaving them out of the indexes.
+fn validate_serialized_names(
+    model: &Model,
+    key: &KeyContext<'_>,
+    indexes: &[IndexContext<'_>],
+) -> Result<(), Error> {
+    let mut accumulator = Accumulator::default();
+
+    for field in model.fields() {
+        let name = field.get_name_str();
+
+        let referred = key.contains_key(&name)
+            || indexes.iter().any(|index| index.contains_key(&name))
+            || field.encrypt.is_present()
+            || field.redact.is_present()
+            || field.references.is_some()
+            || field.created_at.is_some()
+            || field.datetime.is_some()
+            || field.bytes.is_present();
+
+        if !referred {
+            continue;
+        }
+
+        match serialized_name(model, field) {
+            Ok(serialized) if serialized != name.value() => {
+                accumulator.push(
+                    Error::custom(format!(
+                        "Field is serialized as `{serialized}` but referred to as `{}` in the object store, use \
+                         `#[deli(rename = \"{serialized}\")]` to match its serialized name",
+                        name.value()
+                    ))
+                    .with_span(field.ident()),
+                );
+            }
+            Ok(_) => {}
+            Err(err) => accumulator.push(err),
+        }
+    }
+
+    accumulator.finish()
+}
+
+/// Returns the serialized name and the unit of the `created_at` field of the model, if any.
+fn get_created_at<'a>(
+    model: &'a Model,
+    key: &KeyContext<'_>,
+) -> Result<Option<(Cow<'a, LitStr>, TimestampUnit)>, Error> {
+    let mut fields = model
+        .fields()
+        .iter()
+        .filter_map(|field| field.created_at.as_ref().map(|meta| (field, meta)));
+
+    let Some((field, meta)) = fields.next() else {
+        return Ok(None);
+    };
+
+    if let Some((field, _)) = fields.next() {
+        return Err(
+            Error::custom("Model cannot have more than one `created_at` field")
+                .with_span(field.ident()),
+        );
+    }
+
+    if key.contains_key(&field.get_name_str()) {
+        return Err(Error::custom("`created_at` field cannot be a key").with_span(field.ident()));
+    }
+
+    let unit = match meta {
+        Override::Explicit(meta) => meta.unit,
+        Override::Inherit => TimestampUnit::default(),
+    };
+
+    Ok(Some((field.get_name_str(), unit)))
+}
+
+/// Returns the serialized names and the units of the fields of the model stored as dates.
+fn get_datetime_fields<'a>(
+    model: &'a Model,
+    key: &KeyContext<'_>,
+) -> Result<Vec<(Cow<'a, LitStr>, TimestampUnit)>, Error> {
+    let mut accumulator = Accumulator::default();
+    let mut datetime_fields = Vec::new();
+
+    for field in model.fields() {
+        let Some(meta) = field.datetime.as_deref() else {
+            continue;
+        };
+
+        if key.contains_key(&field.get_name_str()) {
+            accumulator
+                .push(Error::custom("`datetime` field cannot be a key").with_span(field.ident()));
+            continue;
+        }
+
+        let unit = match meta {
+            Override::Explicit(meta) => meta.unit,
+            Override::Inherit => TimestampUnit::default(),
+        };
+
+        datetime_fields.push((field.get_name_str(), unit));
+    }
+
+    accumulator.finish_with(datetime_fields)
+}
+
+/// Returns the serialized names of the fields of the model stored as `Uint8Array`.
+fn get_bytes_fields<'a>(
+    model: &'a Model,
+    key: &KeyContext<'_>,
+    indexes: &[IndexContext<'_>],
+) -> Result<Vec<Cow<'a, LitStr>>, Error> {
+    let mut accumulator = Accumulator::default();
+    let mut bytes_fields = Vec::new();
+
+    for field in model
+        .fields()
+        .iter()
+        .filter(|field| field.bytes.is_present())
+    {
+        let name = field.get_name_str();
+
+        // Field level keys and indexes are reported as conflicting attributes, this covers composite ones
+        if key.contains_key(&name) || indexes.iter().any(|index| index.contains_key(&name)) {
+            accumulator.push(
+                Error::custom("`bytes` field cannot be part of a key or an index")
+                    .with_span(field.ident()),
+            );
+            continue;
+        }
+
+        bytes_fields.push(name);
+    }
+
+    accumulator.finish_with(bytes_fields)
+}
+
+/// Checks that the upgrades of an envelope are from distinct versions older than the current one.
+fn validate_envelope(envelope: &EnvelopeMeta) -> Result<(), Error> {
+    let mut accumulator = Accumulator::default();
+    let mut froms = Vec::new();
+
+    for upgrade in envelope.upgrade.iter() {
+        if upgrade.from >= envelope.version {
+            accumulator.push(
+                Error::custom(format!(
+                    "Upgrade from version {} is not older than the current version {}",
+                    upgrade.from, envelope.version
+                ))
+                .with_span(&upgrade.with),
+            );
+        } else if froms.contains(&upgrade.from) {
+            accumulator.push(
+                Error::custom(format!(
+                    "Upgrade from version {} is declared more than once",
+                    upgrade.from
+                ))
+                .with_span(&upgrade.with),
+            );
+        } else {
+            froms.push(upgrade.from);
+        }
+    }
+
+    accumulator.finish()
+}
@@ -43,12 +43,28 @@ impl ObjectStoreContext<'_> {
             .map(|index| index.expand_by_fn_definition())
             .collect::<Vec<_>>();
 
+        let doc = format!(
+            "Object store of [`{model_ident}`] with accessors for its indexes. Methods of [`ObjectStore`](deli::ObjectStore) \
+            are available through `Deref`, so domain specific queries can be added in `impl {ident}<'_>` blocks."
+        );
+
         quote! {
+            #[doc = #doc]
             #vis struct #ident<'t> {
                 object_store: ::deli::ObjectStore<'t, #model_ident>,
             }
 
             impl<'t> #ident<'t> {
+                /// Returns the underlying object store.
+                pub fn as_object_store(&self) -> &::deli::ObjectStore<'t, #model_ident> {
+                    &self.object_store
+                }
+
+                /// Converts into the underlying object store.
+                pub fn into_object_store(self) -> ::deli::ObjectStore<'t, #model_ident> {
+                    self.object_store
+                }
+
                 #(#by_fns)*
             }
 
@@ -0,0 +1,100 @@
+use darling::Error;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Ident, Type, Visibility};
+
+use crate::model::Model;
+
+use super::KeyContext;
+
+pub struct PatchContext<'a> {
+    pub vis: &'a Visibility,
+    pub ident: Ident,
+    pub model_ident: &'a Ident,
+    pub fields: Vec<PatchFieldContext<'a>>,
+}
+
+pub struct PatchFieldContext<'a> {
+    pub ident: &'a Ident,
+    pub ty: &'a Type,
+}
+
+impl<'a> TryFrom<(&'a Model, &'_ KeyContext<'_>)> for PatchContext<'a> {
+    type Error = Error;
+
+    fn try_from((model, key): (&'a Model, &'_ KeyContext<'_>)) -> Result<Self, Self::Error> {
+        let ident = match &model.patch_struct {
+            Some(name) => Ident::new(&name.value(), name.span()),
+            None => Ident::new(&format!("{}Patch", model.ident), model.ident.span()),
+        };
+
+        // Fields of the primary key identify the record to patch, so they cannot be patched
+        let fields = model
+            .fields()
+            .iter()
+            .filter(|field| !key.contains_key(&field.get_name_str()))
+            .map(|field| PatchFieldContext {
+                ident: field.ident(),
+                ty: &field.ty,
+            })
+            .collect();
+
+        Ok(Self {
+            vis: &model.vis,
+            ident,
+            model_ident: &model.ident,
+            fields,
+        })
+    }
+}
+
+impl PatchContext<'_> {
+    pub fn expand_patch_definition(&self) -> TokenStream {
+        let vis = self.vis;
+        let ident = &self.ident;
+        let model_ident = self.model_ident;
+        let fields = self.fields.iter().map(|field| {
+            let ident = field.ident;
+            let ty = field.ty;
+
+            quote! {
+                pub #ident: ::core::option::Option<#ty>
+            }
+        });
+
+        let doc = format!(
+            "Partial update of [`{model_ident}`], replacing the fields which are set (see \
+            [`ObjectStore::patch`](deli::ObjectStore::patch))."
+        );
+
+        quote! {
+            #[doc = #doc]
+            #[derive(Default)]
+            #vis struct #ident {
+                #(#fields),*
+            }
+        }
+    }
+
+    /// Expands `apply_patch`, which replaces the fields of the model set in the patch.
+    pub fn expand_apply_patch(&self) -> TokenStream {
+        let ident = &self.ident;
+        let fields = self
+            .fields
+            .iter()
+            .map(|field| field.ident)
+            .collect::<Vec<_>>();
+
+        quote! {
+            fn apply_patch(&mut self, patch: Self::Patch) {
+                let #ident { #(#fields),* } = patch;
+
+                #(
+                    if let ::core::option::Option::Some(value) = #fields {
+                        self.#fields = value;
+                    }
+                )*
+            }
+        }
+    }
+}
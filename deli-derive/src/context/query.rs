@@ -0,0 +1,140 @@
+use darling::Error;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Ident, Visibility};
+
+use crate::model::Model;
+
+use super::IndexContext;
+
+pub struct QueryContext<'a> {
+    pub vis: &'a Visibility,
+    pub ident: Ident,
+    pub model_ident: &'a Ident,
+    pub fields: Vec<QueryFieldContext<'a>>,
+}
+
+pub struct QueryFieldContext<'a> {
+    pub field_ident: &'a Ident,
+    pub index_ident: Ident,
+    pub by_fn_ident: Ident,
+}
+
+impl<'a> TryFrom<(&'a Model, &'_ [IndexContext<'a>])> for QueryContext<'a> {
+    type Error = Error;
+
+    fn try_from(
+        (model, indexes): (&'a Model, &'_ [IndexContext<'a>]),
+    ) -> Result<Self, Self::Error> {
+        let ident = match &model.query_struct {
+            Some(name) => Ident::new(&name.value(), name.span()),
+            None => Ident::new(&format!("{}Query", model.ident), model.ident.span()),
+        };
+
+        // Only single field indexes can be queried using the value of a field
+        let fields = indexes
+            .iter()
+            .filter_map(|index| match index {
+                IndexContext::Single {
+                    key,
+                    index_ident,
+                    by_fn_ident,
+                    ..
+                }
+                | IndexContext::SingleUnique {
+                    key,
+                    index_ident,
+                    by_fn_ident,
+                    ..
+                } => model
+                    .fields()
+                    .iter()
+                    .find(|field| field.get_name_str().value() == key.value())
+                    .map(|field| QueryFieldContext {
+                        field_ident: field.ident(),
+                        index_ident: index_ident.clone(),
+                        by_fn_ident: by_fn_ident.clone(),
+                    }),
+                _ => None,
+            })
+            .collect();
+
+        Ok(Self {
+            vis: &model.vis,
+            ident,
+            model_ident: &model.ident,
+            fields,
+        })
+    }
+}
+
+impl QueryContext<'_> {
+    pub fn expand_query_definition(&self) -> TokenStream {
+        let vis = self.vis;
+        let ident = &self.ident;
+        let model_ident = self.model_ident;
+        let by_fns = self.fields.iter().map(|field| {
+            let field_ident = field.field_ident;
+            let index_ident = &field.index_ident;
+            let by_fn_ident = &field.by_fn_ident;
+
+            quote! {
+                pub fn #by_fn_ident(self) -> ::deli::query::Field<Self, #index_ident> {
+                    ::deli::query::Field::new(self, |model: &#model_ident| &model.#field_ident)
+                }
+            }
+        });
+
+        let doc = format!(
+            "Query builder of [`{model_ident}`] with conditions on its single field indexes (see \
+            [`query`](deli::query))."
+        );
+
+        quote! {
+            #[doc = #doc]
+            #vis struct #ident {
+                query: ::deli::query::QueryBuilder<#model_ident>,
+            }
+
+            impl #ident {
+                #(#by_fns)*
+
+                /// Limits the number of records returned by the query.
+                pub fn limit(self, limit: u32) -> Self {
+                    Self { query: self.query.limit(limit) }
+                }
+
+                /// Sets the direction in which the records are read.
+                pub fn direction(self, direction: ::deli::CursorDirection) -> Self {
+                    Self { query: self.query.direction(direction) }
+                }
+
+                /// Reads all the records of the object store if the index of a condition does not exist in the opened
+                /// database, instead of failing with `Error::IndexMissing`.
+                pub fn scan_if_index_missing(self) -> Self {
+                    Self { query: self.query.scan_if_index_missing() }
+                }
+
+                /// Retrieves the records matching all the conditions of the query.
+                pub async fn get_all(
+                    self,
+                    transaction: &::deli::Transaction,
+                ) -> ::core::result::Result<::std::vec::Vec<#model_ident>, ::deli::Error> {
+                    self.query.get_all(transaction).await
+                }
+            }
+
+            impl ::core::convert::From<::deli::query::QueryBuilder<#model_ident>> for #ident {
+                fn from(query: ::deli::query::QueryBuilder<#model_ident>) -> Self {
+                    Self { query }
+                }
+            }
+
+            impl ::core::convert::From<#ident> for ::deli::query::QueryBuilder<#model_ident> {
+                fn from(query: #ident) -> Self {
+                    query.query
+                }
+            }
+        }
+    }
+}
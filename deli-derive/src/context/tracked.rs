@@ -0,0 +1,84 @@
+use darling::Error;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Ident, Type};
+
+use crate::model::Model;
+
+use super::IndexContext;
+
+pub enum TrackedContext<'a> {
+    None,
+    Some {
+        model_ident: &'a Ident,
+        field_ident: &'a Ident,
+        field_ty: &'a Type,
+        index_ident: Ident,
+    },
+}
+
+impl TrackedContext<'_> {
+    pub fn expand_tracked_definition(&self) -> TokenStream {
+        match self {
+            TrackedContext::None => quote! {},
+            TrackedContext::Some {
+                model_ident,
+                field_ident,
+                field_ty,
+                index_ident,
+            } => {
+                quote! {
+                    impl ::deli::sync::Tracked for #model_ident {
+                        type UpdatedAtIndex = #index_ident;
+
+                        fn updated_at(&self) -> &#field_ty {
+                            &self.#field_ident
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<'a> TryFrom<(&'a Model, &'_ [IndexContext<'a>])> for TrackedContext<'a> {
+    type Error = Error;
+
+    fn try_from(
+        (model, indexes): (&'a Model, &'_ [IndexContext<'a>]),
+    ) -> Result<Self, Self::Error> {
+        let fields = model
+            .fields()
+            .iter()
+            .filter(|field| field.updated_at.is_present())
+            .collect::<Vec<_>>();
+
+        if fields.len() > 1 {
+            return Err(
+                Error::custom("Model cannot have more than one `updated_at` field")
+                    .with_span(&model.ident),
+            );
+        }
+
+        let Some(field) = fields.first() else {
+            return Ok(TrackedContext::None);
+        };
+
+        let key = field.get_name_str();
+
+        let index_ident = indexes
+            .iter()
+            .find_map(|index| index.single_key_index_ident(&key))
+            .ok_or_else(|| {
+                Error::custom("`updated_at` field cannot be a multi entry or hashed index")
+                    .with_span(field.ident())
+            })?;
+
+        Ok(TrackedContext::Some {
+            model_ident: &model.ident,
+            field_ident: field.ident(),
+            field_ty: &field.ty,
+            index_ident: index_ident.clone(),
+        })
+    }
+}
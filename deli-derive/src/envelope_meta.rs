@@ -0,0 +1,29 @@
+use darling::FromMeta;
+use syn::Path;
+
+#[derive(Debug, FromMeta)]
+pub struct EnvelopeMeta {
+    pub version: u32,
+    #[darling(default)]
+    pub migrate_on_read: MigrateOnRead,
+    #[darling(multiple)]
+    pub upgrade: Vec<UpgradeMeta>,
+}
+
+#[derive(Debug, FromMeta)]
+pub struct UpgradeMeta {
+    pub from: u32,
+    pub with: Path,
+}
+
+/// Behavior for the records of an older version read from the object store.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, FromMeta)]
+pub enum MigrateOnRead {
+    /// Records are only upgraded in memory
+    #[default]
+    #[darling(rename = "in_memory")]
+    InMemory,
+    /// Upgraded records are also written back in the background
+    #[darling(rename = "write_back")]
+    WriteBack,
+}
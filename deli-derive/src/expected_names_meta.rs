@@ -0,0 +1,54 @@
+use darling::{error::Accumulator, Error, FromMeta};
+use syn::LitStr;
+
+/// Store and index names pinned using `#[deli(expected_names(store = "..", index = ".."))]`.
+#[derive(Debug, Default, FromMeta)]
+pub struct ExpectedNamesMeta {
+    #[darling(default)]
+    pub store: Option<LitStr>,
+    #[darling(multiple)]
+    pub index: Vec<LitStr>,
+}
+
+impl ExpectedNamesMeta {
+    /// Fails if the store name differs from the pinned one or if a pinned index name is not generated by the model.
+    /// Indexes which are not pinned are allowed, since adding an index does not orphan existing data.
+    pub fn validate(&self, store: &LitStr, indexes: &[&LitStr]) -> Result<(), Error> {
+        let mut accumulator = Accumulator::default();
+
+        if let Some(expected) = &self.store {
+            if expected.value() != store.value() {
+                accumulator.push(
+                    Error::custom(format!(
+                        "Store name `{}` does not match the expected name `{}`",
+                        store.value(),
+                        expected.value()
+                    ))
+                    .with_span(expected),
+                );
+            }
+        }
+
+        for expected in self.index.iter() {
+            if !indexes
+                .iter()
+                .any(|index| index.value() == expected.value())
+            {
+                accumulator.push(
+                    Error::custom(format!(
+                        "Expected index `{}` is not generated by the model (generated indexes: {})",
+                        expected.value(),
+                        indexes
+                            .iter()
+                            .map(|index| format!("`{}`", index.value()))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ))
+                    .with_span(expected),
+                );
+            }
+        }
+
+        accumulator.finish()
+    }
+}
@@ -1,5 +1,8 @@
-use darling::{util::PathList, FromMeta};
-use syn::LitStr;
+use darling::{
+    util::{Flag, Override, PathList},
+    FromMeta,
+};
+use syn::{LitStr, Type};
 
 #[derive(Debug, FromMeta)]
 pub struct ModelIndexMeta {
@@ -8,6 +11,10 @@ pub struct ModelIndexMeta {
     pub fields: PathList,
     #[darling(default)]
     pub struct_name: Option<LitStr>,
+    #[darling(default)]
+    pub alias: Option<LitStr>,
+    #[darling(default)]
+    pub fn_name: Option<LitStr>,
 }
 
 #[derive(Debug, Default, FromMeta)]
@@ -16,4 +23,43 @@ pub struct FieldIndexMeta {
     pub name: Option<LitStr>,
     #[darling(default)]
     pub struct_name: Option<LitStr>,
+    #[darling(default)]
+    pub alias: Option<LitStr>,
+    #[darling(default)]
+    pub fn_name: Option<LitStr>,
+    #[darling(default)]
+    pub hashed: Flag,
+    #[darling(default)]
+    pub key_path: Option<LitStr>,
+    #[darling(default)]
+    pub key_type: Option<Type>,
+}
+
+impl FieldIndexMeta {
+    pub fn is_hashed(meta: &Override<FieldIndexMeta>) -> bool {
+        match meta {
+            Override::Inherit => false,
+            Override::Explicit(meta) => meta.hashed.is_present(),
+        }
+    }
+
+    pub fn key_path(meta: &Override<FieldIndexMeta>) -> Option<&LitStr> {
+        match meta {
+            Override::Inherit => None,
+            Override::Explicit(meta) => meta.key_path.as_ref(),
+        }
+    }
+}
+
+/// Strategy for generating the default names of indexes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, FromMeta)]
+pub enum IndexNaming {
+    /// Names are the key path of the index (e.g. `age`), so they don't change when the model is renamed
+    #[darling(rename = "short")]
+    Short,
+    /// Names are prefixed with the name of the model and suffixed with the type of the index (e.g.
+    /// `employee_age_index`)
+    #[default]
+    #[darling(rename = "qualified")]
+    Qualified,
 }
@@ -0,0 +1,83 @@
+use darling::{ast::Data, util::Flag, Error, FromDeriveInput, FromField};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Generics, Ident, Type};
+
+#[derive(Debug, FromDeriveInput)]
+#[darling(attributes(deli), supports(struct_newtype))]
+pub struct Key {
+    pub ident: Ident,
+    pub generics: Generics,
+    #[darling(default)]
+    pub transparent: Flag,
+    pub data: Data<(), KeyField>,
+}
+
+#[derive(Debug, FromField)]
+pub struct KeyField {
+    pub ty: Type,
+}
+
+impl Key {
+    pub fn validate(&self) -> Result<(), Error> {
+        if !self.generics.params.is_empty() {
+            return Err(
+                Error::custom("Generic type is not supported by `deli::Key` derive macro")
+                    .with_span(&self.ident),
+            );
+        }
+
+        if !self.transparent.is_present() {
+            return Err(Error::custom(
+                "`deli::Key` derive macro only supports `#[deli(transparent)]` keys",
+            )
+            .with_span(&self.ident));
+        }
+
+        Ok(())
+    }
+
+    fn inner_ty(&self) -> &Type {
+        match self.data {
+            Data::Enum(_) => unreachable!(),
+            Data::Struct(ref data) => &data.fields[0].ty,
+        }
+    }
+
+    pub fn expand(&self) -> TokenStream {
+        let ident = &self.ident;
+        let inner_ty = self.inner_ty();
+
+        // `String` keys can also be borrowed as `str` so that string literals can be used in lookups
+        let borrow_str = is_string(inner_ty).then(|| {
+            quote! {
+                impl ::core::borrow::Borrow<str> for #ident {
+                    fn borrow(&self) -> &str {
+                        &self.0
+                    }
+                }
+            }
+        });
+
+        quote! {
+            impl ::core::borrow::Borrow<#inner_ty> for #ident {
+                fn borrow(&self) -> &#inner_ty {
+                    &self.0
+                }
+            }
+
+            #borrow_str
+        }
+    }
+}
+
+fn is_string(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "String" && segment.arguments.is_empty()),
+        _ => false,
+    }
+}
@@ -1,10 +1,17 @@
 mod context;
+mod envelope_meta;
+mod expected_names_meta;
 mod index_meta;
+mod key;
 mod model;
 mod model_field;
+mod serde_attrs;
+mod timestamp_meta;
+mod tombstones_meta;
 
 use context::ModelContext;
 use darling::FromDeriveInput;
+use key::Key;
 use model::Model;
 use proc_macro::TokenStream;
 use syn::{parse_macro_input, DeriveInput};
@@ -36,3 +43,25 @@ pub fn model(item: TokenStream) -> TokenStream {
     // Return the output of derive macro
     model_context.expand().into()
 }
+
+/// Derive macro for using newtypes (`#[deli(transparent)]`) as keys of models
+#[proc_macro_derive(Key, attributes(deli))]
+pub fn key(item: TokenStream) -> TokenStream {
+    // Parse the input tokens into a syntax tree
+    let derive_input = parse_macro_input!(item as DeriveInput);
+
+    // Create a key from derive input
+    let key = match Key::from_derive_input(&derive_input) {
+        Ok(key) => key,
+        Err(err) => return err.write_errors().into(),
+    };
+
+    // Validate that the key is a transparent newtype without generics
+    match key.validate() {
+        Ok(_) => {}
+        Err(err) => return err.write_errors().into(),
+    }
+
+    // Return the output of derive macro
+    key.expand().into()
+}
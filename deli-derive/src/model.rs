@@ -1,10 +1,21 @@
 use std::borrow::Cow;
 
-use darling::{ast::Data, error::Accumulator, util::PathList, Error, FromDeriveInput};
+use darling::{
+    ast::Data,
+    error::Accumulator,
+    util::{Flag, Override, PathList},
+    Error, FromDeriveInput,
+};
 use ident_case::RenameRule;
-use syn::{Attribute, Generics, Ident, LitStr, Visibility};
+use syn::{Attribute, Generics, Ident, LitStr, Path, Type, Visibility};
 
-use crate::{index_meta::ModelIndexMeta, model_field::ModelField};
+use crate::{
+    envelope_meta::EnvelopeMeta,
+    expected_names_meta::ExpectedNamesMeta,
+    index_meta::{IndexNaming, ModelIndexMeta},
+    model_field::ModelField,
+    tombstones_meta::TombstonesMeta,
+};
 
 #[derive(Debug, FromDeriveInput)]
 #[darling(
@@ -19,14 +30,29 @@ pub struct Model {
     pub name: Option<LitStr>,
     pub object_store_name: Option<LitStr>,
     pub object_store_struct: Option<LitStr>,
+    pub query_struct: Option<LitStr>,
+    pub patch_struct: Option<LitStr>,
     pub add_struct_name: Option<LitStr>,
+    pub default_limit: Option<u32>,
+    pub on_read: Option<Path>,
+    pub on_write: Option<Path>,
+    pub structured_clone: Flag,
     pub key: Option<PathList>,
+    pub external_key: Option<Type>,
+    #[darling(default)]
+    pub index_naming: IndexNaming,
     #[darling(multiple)]
     pub index: Vec<ModelIndexMeta>,
     #[darling(multiple)]
     pub unique: Vec<ModelIndexMeta>,
     #[darling(multiple)]
     pub multi_entry: Vec<ModelIndexMeta>,
+    #[darling(default)]
+    pub expected_names: Option<ExpectedNamesMeta>,
+    #[darling(default)]
+    pub tombstones: Option<Override<TombstonesMeta>>,
+    #[darling(default)]
+    pub envelope: Option<EnvelopeMeta>,
     pub data: Data<(), ModelField>,
     pub attrs: Vec<Attribute>,
 }
@@ -43,6 +69,47 @@ impl Model {
         }
     }
 
+    /// Checks the attributes of the fields for conflicts, reporting all of them at once with the spans of both sides of
+    /// every conflict (e.g. a key field which is also `unique`, or two fields with the same name in the object store).
+    pub fn validate_attributes(&self) -> Result<(), Error> {
+        let mut accumulator = Accumulator::default();
+
+        for field in self.fields() {
+            for error in field.attribute_conflicts() {
+                accumulator.push(error);
+            }
+        }
+
+        for (index, field) in self.fields().iter().enumerate() {
+            let name = field.get_name_str();
+
+            if let Some(other) = self.fields()[..index]
+                .iter()
+                .find(|other| other.get_name_str().value() == name.value())
+            {
+                let other_name = other.get_name_str();
+
+                accumulator.push(Error::multiple(vec![
+                    Error::custom(format!(
+                        "Field `{}` is stored as `{}`, which is already the name of field `{}`",
+                        field.ident(),
+                        name.value(),
+                        other.ident()
+                    ))
+                    .with_span(&name.span()),
+                    Error::custom(format!(
+                        "Field `{}` is stored as `{}` here",
+                        other.ident(),
+                        name.value()
+                    ))
+                    .with_span(&other_name.span()),
+                ]));
+            }
+        }
+
+        accumulator.finish()
+    }
+
     pub fn get_name_str(&self) -> Cow<'_, LitStr> {
         match &self.name {
             Some(name) => Cow::Borrowed(name),
@@ -60,6 +127,35 @@ impl Model {
         }
     }
 
+    /// Returns the default name of an index on the given fields, either qualified with the name of the model and the
+    /// suffix of the index type or the key path of the index (see [`IndexNaming`]).
+    pub fn default_index_name(&self, fields: &str, suffix: &str, key_path: &str) -> String {
+        match self.index_naming {
+            IndexNaming::Short => key_path.to_owned(),
+            IndexNaming::Qualified => format!(
+                "{}_{}_{}",
+                RenameRule::SnakeCase.apply_to_variant(self.ident.to_string()),
+                fields,
+                suffix
+            ),
+        }
+    }
+
+    /// Returns `true` if a single field index on the given field is declared on the container (e.g.
+    /// `#[deli(index(fields(age)))]`).
+    pub fn declares_single_field_index(&self, field: &ModelField) -> bool {
+        self.index
+            .iter()
+            .chain(self.unique.iter())
+            .chain(self.multi_entry.iter())
+            .any(|meta| {
+                meta.fields.len() == 1
+                    && meta.fields[0]
+                        .get_ident()
+                        .is_some_and(|ident| Some(ident) == field.ident.as_ref())
+            })
+    }
+
     pub fn get_fields_from_path_list(
         &self,
         path_list: &PathList,
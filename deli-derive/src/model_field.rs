@@ -1,12 +1,13 @@
 use std::borrow::Cow;
 
 use darling::{
-    util::{Flag, Override},
-    FromField,
+    util::{Flag, Override, SpannedValue},
+    Error, FromField,
 };
-use syn::{Attribute, Ident, LitStr, Type, Visibility};
+use proc_macro2::Span;
+use syn::{Attribute, Ident, LitStr, Path, Type, Visibility};
 
-use crate::index_meta::FieldIndexMeta;
+use crate::{index_meta::FieldIndexMeta, timestamp_meta::TimestampMeta};
 
 #[derive(Debug, FromField)]
 #[darling(attributes(deli), forward_attrs(allow, doc, serde))]
@@ -21,11 +22,27 @@ pub struct ModelField {
     #[darling(default)]
     pub auto_increment: Flag,
     #[darling(default)]
-    pub index: Option<Override<FieldIndexMeta>>,
+    pub key_generator: Option<LitStr>,
     #[darling(default)]
-    pub unique: Option<Override<FieldIndexMeta>>,
+    pub index: Option<SpannedValue<Override<FieldIndexMeta>>>,
     #[darling(default)]
-    pub multi_entry: Option<Override<FieldIndexMeta>>,
+    pub unique: Option<SpannedValue<Override<FieldIndexMeta>>>,
+    #[darling(default)]
+    pub multi_entry: Option<SpannedValue<Override<FieldIndexMeta>>>,
+    #[darling(default)]
+    pub updated_at: Flag,
+    #[darling(default)]
+    pub created_at: Option<Override<TimestampMeta>>,
+    #[darling(default)]
+    pub datetime: Option<SpannedValue<Override<TimestampMeta>>>,
+    #[darling(default)]
+    pub bytes: Flag,
+    #[darling(default)]
+    pub encrypt: Flag,
+    #[darling(default)]
+    pub redact: Flag,
+    #[darling(default)]
+    pub references: Option<Path>,
     pub attrs: Vec<Attribute>,
 }
 
@@ -35,11 +52,20 @@ impl ModelField {
     }
 
     pub fn is_key(&self) -> bool {
-        self.key.is_present() || self.auto_increment.is_present()
+        self.key.is_present() || self.is_generated_key()
+    }
+
+    /// Returns `true` if the field is a key generated when adding records (auto incremented by the database or
+    /// generated by `deli`).
+    pub fn is_generated_key(&self) -> bool {
+        self.auto_increment.is_present() || self.key_generator.is_some()
     }
 
     pub fn is_index(&self) -> bool {
-        self.index.is_some() || self.unique.is_some() || self.multi_entry.is_some()
+        self.index.is_some()
+            || self.unique.is_some()
+            || self.multi_entry.is_some()
+            || self.updated_at.is_present()
     }
 
     pub fn get_name_str(&self) -> Cow<'_, LitStr> {
@@ -51,4 +77,155 @@ impl ModelField {
             }
         }
     }
+
+    /// Returns the errors for the conflicting attributes of the field, each with the spans of both attributes (or of the
+    /// field for attributes without span).
+    pub fn attribute_conflicts(&self) -> Vec<Error> {
+        let ident_span = self.ident().span();
+        let flag_span = |flag: &Flag| {
+            if flag.is_present() {
+                flag.span()
+            } else {
+                ident_span
+            }
+        };
+
+        let keys = [
+            ("key", self.key.is_present(), flag_span(&self.key)),
+            (
+                "auto_increment",
+                self.auto_increment.is_present(),
+                flag_span(&self.auto_increment),
+            ),
+            (
+                "key_generator",
+                self.key_generator.is_some(),
+                self.key_generator
+                    .as_ref()
+                    .map_or(ident_span, |generator| generator.span()),
+            ),
+        ];
+        let indexes = [
+            (
+                "index",
+                self.index.is_some(),
+                self.index.as_ref().map_or(ident_span, SpannedValue::span),
+            ),
+            (
+                "unique",
+                self.unique.is_some(),
+                self.unique.as_ref().map_or(ident_span, SpannedValue::span),
+            ),
+            (
+                "multi_entry",
+                self.multi_entry.is_some(),
+                self.multi_entry
+                    .as_ref()
+                    .map_or(ident_span, SpannedValue::span),
+            ),
+            (
+                "updated_at",
+                self.updated_at.is_present(),
+                flag_span(&self.updated_at),
+            ),
+        ];
+
+        let mut conflicts = Vec::new();
+
+        if keys[1].1 && keys[2].1 {
+            conflicts.push((
+                keys[1],
+                keys[2],
+                "a key cannot be both auto incremented and generated",
+            ));
+        }
+
+        // Lookups serialize byte fields as arrays of numbers, which would not match the stored `Uint8Array`s
+        if self.bytes.is_present() {
+            let bytes = ("bytes", true, flag_span(&self.bytes));
+            let others = [
+                (
+                    "datetime",
+                    self.datetime.is_some(),
+                    self.datetime
+                        .as_ref()
+                        .map_or(ident_span, SpannedValue::span),
+                ),
+                (
+                    "encrypt",
+                    self.encrypt.is_present(),
+                    flag_span(&self.encrypt),
+                ),
+            ];
+
+            for other in keys
+                .iter()
+                .chain(indexes.iter())
+                .chain(others.iter())
+                .filter(|other| other.1)
+            {
+                conflicts.push((
+                    bytes,
+                    *other,
+                    "only plain fields can be stored as `Uint8Array`",
+                ));
+            }
+        }
+
+        // Dates are only converted back for plain fields of records, not for keys or hashed values
+        if self.datetime.is_some() {
+            let datetime = (
+                "datetime",
+                true,
+                self.datetime
+                    .as_ref()
+                    .map_or(ident_span, SpannedValue::span),
+            );
+            let others = [
+                (
+                    "multi_entry",
+                    self.multi_entry.is_some(),
+                    self.multi_entry
+                        .as_ref()
+                        .map_or(ident_span, SpannedValue::span),
+                ),
+                (
+                    "encrypt",
+                    self.encrypt.is_present(),
+                    flag_span(&self.encrypt),
+                ),
+            ];
+
+            for other in keys.iter().chain(others.iter()).filter(|other| other.1) {
+                conflicts.push((datetime, *other, "only plain fields can be stored as dates"));
+            }
+        }
+
+        for key in keys.iter().filter(|key| key.1) {
+            for index in indexes.iter().filter(|index| index.1) {
+                conflicts.push((
+                    *key,
+                    *index,
+                    "primary keys are already unique and can be looked up directly",
+                ));
+            }
+        }
+
+        conflicts
+            .into_iter()
+            .map(
+                |((first, _, first_span), (second, _, second_span), reason): (Attr, Attr, &str)| {
+                    Error::multiple(vec![
+                        Error::custom(format!("`{first}` conflicts with `{second}`: {reason}"))
+                            .with_span(&first_span),
+                        Error::custom(format!("`{second}` conflicts with `{first}`"))
+                            .with_span(&second_span),
+                    ])
+                },
+            )
+            .collect()
+    }
 }
+
+/// Name of an attribute, whether it is present and its span.
+type Attr = (&'static str, bool, Span);
@@ -0,0 +1,95 @@
+use darling::Error;
+use syn::{meta::ParseNestedMeta, Attribute, LitStr};
+
+use crate::{model::Model, model_field::ModelField};
+
+/// Returns the serialized name of a field of the model as determined by its `#[serde(rename)]` attribute and the
+/// `#[serde(rename_all)]` attribute of the model.
+pub fn serialized_name(model: &Model, field: &ModelField) -> Result<String, Error> {
+    if let Some(rename) = serialize_value(&field.attrs, "rename")? {
+        return Ok(rename.value());
+    }
+
+    let name = field.ident().to_string();
+    let name = name.strip_prefix("r#").unwrap_or(&name);
+
+    let Some(rename_all) = serialize_value(&model.attrs, "rename_all")? else {
+        return Ok(name.to_owned());
+    };
+
+    let pascal_case = || {
+        name.split('_')
+            .map(|word| {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                    .unwrap_or_default()
+            })
+            .collect::<String>()
+    };
+
+    // Fields are expected to be snake case, like serde does
+    let name = match rename_all.value().as_str() {
+        "lowercase" | "snake_case" => name.to_owned(),
+        "UPPERCASE" | "SCREAMING_SNAKE_CASE" => name.to_ascii_uppercase(),
+        "PascalCase" => pascal_case(),
+        "camelCase" => {
+            let pascal = pascal_case();
+            let mut chars = pascal.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_lowercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        }
+        "kebab-case" => name.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => name.to_ascii_uppercase().replace('_', "-"),
+        // Unknown rules are reported by serde
+        _ => name.to_owned(),
+    };
+
+    Ok(name)
+}
+
+/// Returns the value used for serialization of a `#[serde(key = "...")]` or `#[serde(key(serialize = "..."))]`
+/// attribute.
+fn serialize_value(attrs: &[Attribute], key: &str) -> Result<Option<LitStr>, Error> {
+    let mut value = None;
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident(key) {
+                if meta.input.peek(syn::Token![=]) {
+                    value = Some(meta.value()?.parse()?);
+                } else {
+                    meta.parse_nested_meta(|meta| {
+                        if meta.path.is_ident("serialize") {
+                            value = Some(meta.value()?.parse()?);
+                        } else {
+                            skip(&meta)?;
+                        }
+
+                        Ok(())
+                    })?;
+                }
+            } else {
+                skip(&meta)?;
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(value)
+}
+
+/// Skips the value or the nested attributes of a serde attribute which is not inspected.
+fn skip(meta: &ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(syn::Token![=]) {
+        meta.value()?.parse::<syn::Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.parse_nested_meta(|meta| skip(&meta))?;
+    }
+
+    Ok(())
+}
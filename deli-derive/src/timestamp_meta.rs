@@ -0,0 +1,22 @@
+use darling::FromMeta;
+
+#[derive(Debug, Default, FromMeta)]
+pub struct TimestampMeta {
+    #[darling(default)]
+    pub unit: TimestampUnit,
+}
+
+/// Format of a timestamp maintained by deli.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, FromMeta)]
+pub enum TimestampUnit {
+    /// Milliseconds since the Unix epoch
+    #[default]
+    #[darling(rename = "millis")]
+    Millis,
+    /// Seconds since the Unix epoch
+    #[darling(rename = "seconds")]
+    Seconds,
+    /// ISO 8601 string in UTC (e.g. `2024-01-31T12:00:00.000Z`)
+    #[darling(rename = "iso8601")]
+    Iso8601,
+}
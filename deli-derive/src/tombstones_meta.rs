@@ -0,0 +1,7 @@
+use darling::FromMeta;
+
+#[derive(Debug, Default, FromMeta)]
+pub struct TombstonesMeta {
+    #[darling(default)]
+    pub expire_after_secs: Option<u64>,
+}
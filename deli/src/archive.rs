@@ -0,0 +1,544 @@
+//! Versioned backup archives with checksums.
+//!
+//! [`ExportBuilder::archive`] exports the selected models as an [`Archive`]: the [`Manifest`] of the export followed by
+//! the records of each store split into [`Chunk`]s of serialized JSON, each with its CRC-32 checksum. Archives are
+//! serializable, so they can be saved as a file or uploaded, and restored using [`Database::import`]:
+//!
+//! ```rust,ignore
+//! let archive = database
+//!     .export()
+//!     .with_model::<Contact>()
+//!     .redact(false)
+//!     .archive(DEFAULT_CHUNK_SIZE)
+//!     .await?;
+//! let json = serde_json::to_string(&archive)?;
+//!
+//! // Later, possibly on another device
+//! let archive: Archive = serde_json::from_str(&json)?;
+//! database.import(&archive).await?;
+//! ```
+//!
+//! The whole archive is validated before anything is written: an archive of another format version, a chunk whose
+//! checksum does not match its data, or a store whose chunks do not add up to the records listed in the manifest (e.g.
+//! a truncated upload) fails with [`Error::InvalidArchive`], leaving the local data untouched. The imported stores are
+//! then cleared and refilled in a single transaction, so an import failing halfway (e.g. on a record violating a unique
+//! index) is rolled back as well. Stores of the database which are not part of the archive are left as is.
+//!
+//! Records are restored from their JSON form, so stores with external keys cannot be imported, and fields stored as JS
+//! objects (dates, `Uint8Array`s or blobs) are imported as their JSON counterparts. Archives exported with redaction
+//! enabled (see [`Archive::redacted`]) can be imported as well, e.g. to reproduce a bug report locally.
+//!
+//! [`Database::restore`] restores part of an archive into the existing records instead of replacing whole stores: only
+//! the chosen stores, or the archived records of a store within a key range, are written, and records whose key is
+//! already used locally are handled according to the [`Conflict`] strategy:
+//!
+//! ```rust,ignore
+//! let summary = database
+//!     .restore(&archive)
+//!     .with_model_range::<Contact, _>(100..200)?
+//!     .conflict(Conflict::KeepLocal)
+//!     .execute()
+//!     .await?;
+//! ```
+use idb::{KeyPath, KeyRange as IdbKeyRange, Query};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[cfg(doc)]
+use crate::export::ExportBuilder;
+use crate::{
+    database::Database,
+    error::Error,
+    export::{Dump, Manifest, StoreDump},
+    gate::Operation,
+    key_range::{KeyRange, UnboundedRange},
+    lookup::Lookup,
+    model::Model,
+    JSON_SERIALIZER,
+};
+
+/// Version of the archive format written by this version of `deli`.
+pub const ARCHIVE_FORMAT: u32 = 1;
+
+/// Default number of records per chunk.
+pub const DEFAULT_CHUNK_SIZE: usize = 1000;
+
+/// Lookup table of the CRC-32 (IEEE) checksum.
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+};
+
+/// Backup of object stores of a database, returned by [`ExportBuilder::archive`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Archive {
+    /// Version of the archive format (see [`ARCHIVE_FORMAT`])
+    pub format: u32,
+    /// Whether the fields annotated with `#[deli(redact)]` were replaced with a placeholder
+    pub redacted: bool,
+    /// Exported stores and their number of records
+    pub manifest: Manifest,
+    /// Records of the exported stores, in the order of the manifest
+    pub chunks: Vec<Chunk>,
+}
+
+/// Consecutive records of an object store in an [`Archive`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Chunk {
+    /// Name of the object store
+    pub store: String,
+    /// Position of the chunk among the chunks of the store
+    pub index: usize,
+    /// Number of records in the chunk
+    pub records: usize,
+    /// Records serialized as a JSON array
+    pub data: String,
+    /// CRC-32 checksum of the data
+    pub crc32: u32,
+}
+
+impl Archive {
+    /// Splits the dumps of an export into chunks of at most `chunk_size` records.
+    pub(crate) fn new(
+        redacted: bool,
+        manifest: Manifest,
+        stores: Vec<StoreDump>,
+        chunk_size: usize,
+    ) -> Self {
+        let chunks = stores
+            .into_iter()
+            .flat_map(|store| {
+                let name = store.name;
+
+                store
+                    .records
+                    .chunks(chunk_size.max(1))
+                    .enumerate()
+                    .map(|(index, records)| Chunk::new(&name, index, records))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        Self {
+            format: ARCHIVE_FORMAT,
+            redacted,
+            manifest,
+            chunks,
+        }
+    }
+
+    /// Checks the format, the checksums and the completeness of the archive.
+    pub fn validate(&self) -> Result<(), Error> {
+        self.decode().map(|_| ())
+    }
+
+    /// Validates the archive and returns its records as a [`Dump`].
+    pub fn into_dump(self) -> Result<Dump, Error> {
+        let stores = self.decode()?;
+
+        Ok(Dump {
+            database: self.manifest.database,
+            version: self.manifest.version,
+            stores,
+        })
+    }
+
+    fn decode(&self) -> Result<Vec<StoreDump>, Error> {
+        if self.format != ARCHIVE_FORMAT {
+            return Err(Error::InvalidArchive(format!(
+                "unsupported format version {}",
+                self.format
+            )));
+        }
+
+        if let Some(chunk) = self.chunks.iter().find(|chunk| {
+            !self
+                .manifest
+                .stores
+                .iter()
+                .any(|entry| entry.name == chunk.store)
+        }) {
+            return Err(Error::InvalidArchive(format!(
+                "chunk of object store {} missing from manifest",
+                chunk.store
+            )));
+        }
+
+        self.manifest
+            .stores
+            .iter()
+            .map(|entry| {
+                let mut records = Vec::with_capacity(entry.records);
+
+                for (expected, chunk) in self
+                    .chunks
+                    .iter()
+                    .filter(|chunk| chunk.store == entry.name)
+                    .enumerate()
+                {
+                    if chunk.index != expected {
+                        return Err(Error::InvalidArchive(format!(
+                            "chunk {expected} of object store {} is missing",
+                            entry.name
+                        )));
+                    }
+
+                    records.extend(chunk.decode()?);
+                }
+
+                if records.len() != entry.records {
+                    return Err(Error::InvalidArchive(format!(
+                        "object store {} has {} of {} records",
+                        entry.name,
+                        records.len(),
+                        entry.records
+                    )));
+                }
+
+                Ok(StoreDump {
+                    name: entry.name.clone(),
+                    records,
+                })
+            })
+            .collect()
+    }
+}
+
+impl Chunk {
+    fn new(store: &str, index: usize, records: &[Value]) -> Self {
+        let data = Value::from(records).to_string();
+
+        Self {
+            store: store.to_owned(),
+            index,
+            records: records.len(),
+            crc32: crc32(data.as_bytes()),
+            data,
+        }
+    }
+
+    /// Verifies the checksum of the chunk and parses its records.
+    fn decode(&self) -> Result<Vec<Value>, Error> {
+        if crc32(self.data.as_bytes()) != self.crc32 {
+            return Err(Error::InvalidArchive(format!(
+                "checksum mismatch in chunk {} of object store {}",
+                self.index, self.store
+            )));
+        }
+
+        let records: Vec<Value> = serde_json::from_str(&self.data).map_err(|error| {
+            Error::InvalidArchive(format!(
+                "chunk {} of object store {}: {error}",
+                self.index, self.store
+            ))
+        })?;
+
+        if records.len() != self.records {
+            return Err(Error::InvalidArchive(format!(
+                "chunk {} of object store {} has {} of {} records",
+                self.index,
+                self.store,
+                records.len(),
+                self.records
+            )));
+        }
+
+        Ok(records)
+    }
+}
+
+/// How [`RestoreBuilder`] handles archived records whose key is already used by a local record.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Conflict {
+    /// Archived record replaces the local record
+    #[default]
+    Overwrite,
+    /// Local record is kept and the archived record is skipped
+    KeepLocal,
+    /// Restore fails and nothing is restored
+    Fail,
+}
+
+/// Number of records written by [`RestoreBuilder::execute`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RestoreSummary {
+    /// Archived records written to the database
+    pub restored: usize,
+    /// Archived records skipped because their key is already used locally (with [`Conflict::KeepLocal`])
+    pub skipped: usize,
+}
+
+#[derive(Debug)]
+struct RestoredStore {
+    name: &'static str,
+    range: Option<IdbKeyRange>,
+}
+
+/// Builder restoring part of an [`Archive`] into the existing records of a database, returned by
+/// [`Database::restore`].
+#[derive(Debug)]
+pub struct RestoreBuilder<'a> {
+    database: &'a Database,
+    archive: &'a Archive,
+    stores: Vec<RestoredStore>,
+    conflict: Conflict,
+}
+
+impl<'a> RestoreBuilder<'a> {
+    pub(crate) fn new(database: &'a Database, archive: &'a Archive) -> Self {
+        Self {
+            database,
+            archive,
+            stores: Vec::new(),
+            conflict: Conflict::default(),
+        }
+    }
+
+    /// Restores all the archived records of a model (all the archived stores are restored if no model is added)
+    pub fn with_model<M>(mut self) -> Self
+    where
+        M: Model,
+    {
+        self.stores.push(RestoredStore {
+            name: M::NAME,
+            range: None,
+        });
+        self
+    }
+
+    /// Restores the archived records of a model whose key is within the key range
+    pub fn with_model_range<'k, M, Q>(
+        mut self,
+        key_range: impl Into<KeyRange<'k, Q, UnboundedRange>>,
+    ) -> Result<Self, Error>
+    where
+        M: Model + Lookup<Q>,
+        Q: Serialize + ?Sized + 'k,
+    {
+        let range = match <Option<Query>>::try_from(&key_range.into())? {
+            Some(Query::Key(key)) => Some(IdbKeyRange::only(&key)?),
+            Some(Query::KeyRange(range)) => Some(range),
+            None => None,
+        };
+
+        self.stores.push(RestoredStore {
+            name: M::NAME,
+            range,
+        });
+        Ok(self)
+    }
+
+    /// Sets how archived records whose key is already used locally are handled (defaults to
+    /// [`Conflict::Overwrite`])
+    pub fn conflict(mut self, conflict: Conflict) -> Self {
+        self.conflict = conflict;
+        self
+    }
+
+    /// Validates the whole archive, then writes the selected records in a single transaction
+    pub async fn execute(self) -> Result<RestoreSummary, Error> {
+        let dumps = self.archive.decode()?;
+
+        let stores = if self.stores.is_empty() {
+            dumps.iter().map(|dump| (dump, None)).collect::<Vec<_>>()
+        } else {
+            self.stores
+                .iter()
+                .map(|store| {
+                    dumps
+                        .iter()
+                        .find(|dump| dump.name == store.name)
+                        .map(|dump| (dump, store.range.as_ref()))
+                        .ok_or_else(|| {
+                            Error::InvalidArchive(format!(
+                                "object store {} is not archived",
+                                store.name
+                            ))
+                        })
+                })
+                .collect::<Result<Vec<_>, Error>>()?
+        };
+
+        let transaction = stores
+            .iter()
+            .fold(
+                self.database.transaction().writable(),
+                |builder, (dump, _)| builder.with_store_name(&dump.name),
+            )
+            .build()?;
+
+        for (dump, _) in &stores {
+            transaction.check(&dump.name, Operation::Add)?;
+
+            if self.conflict == Conflict::Overwrite {
+                transaction.check(&dump.name, Operation::Update)?;
+            }
+        }
+
+        let mut summary = RestoreSummary::default();
+
+        for (dump, range) in stores {
+            let object_store = transaction.idb_object_store(&dump.name)?;
+            let key_path = object_store.key_path()?;
+
+            let mut records = Vec::with_capacity(dump.records.len());
+
+            for record in &dump.records {
+                let key = key_path
+                    .as_ref()
+                    .and_then(|key_path| record_key(record, key_path))
+                    .map(|key| key.serialize(&JSON_SERIALIZER))
+                    .transpose()?;
+
+                // Records without a key cannot be matched and are left to fail when written
+                if let (Some(range), Some(key)) = (range, &key) {
+                    if !range.includes(key)? {
+                        continue;
+                    }
+                }
+
+                records.push((record.serialize(&JSON_SERIALIZER)?, key));
+            }
+
+            if self.conflict == Conflict::KeepLocal {
+                let requests = records
+                    .iter()
+                    .map(|(_, key)| {
+                        key.clone()
+                            .map(|key| object_store.count(Some(Query::Key(key))))
+                            .transpose()
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let mut kept = Vec::with_capacity(records.len());
+
+                for (record, request) in records.into_iter().zip(requests) {
+                    let exists = match request {
+                        Some(request) => request.await? > 0,
+                        None => false,
+                    };
+
+                    if exists {
+                        summary.skipped += 1;
+                    } else {
+                        kept.push(record);
+                    }
+                }
+
+                records = kept;
+            }
+
+            // All the records are written before awaiting any of them, as for imports. Adding a record whose key is
+            // already used fails, which aborts the transaction.
+            if self.conflict == Conflict::Overwrite {
+                let requests = records
+                    .iter()
+                    .map(|(value, _)| object_store.put(value, None))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                for request in requests {
+                    request.await?;
+                }
+            } else {
+                let requests = records
+                    .iter()
+                    .map(|(value, _)| object_store.add(value, None))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                for request in requests {
+                    request.await?;
+                }
+            }
+
+            summary.restored += records.len();
+        }
+
+        if transaction.commit().await? == idb::TransactionResult::Aborted {
+            return Err(Error::TransactionAborted);
+        }
+
+        Ok(summary)
+    }
+}
+
+/// Validates the archive, then replaces the records of the archived stores in a single transaction.
+pub(crate) async fn import(database: &Database, archive: &Archive) -> Result<(), Error> {
+    let stores = archive.decode()?;
+
+    let transaction = stores
+        .iter()
+        .fold(database.transaction().writable(), |builder, store| {
+            builder.with_store_name(&store.name)
+        })
+        .build()?;
+
+    for store in &stores {
+        transaction.check(&store.name, Operation::Delete)?;
+        transaction.check(&store.name, Operation::Add)?;
+    }
+
+    for store in &stores {
+        let object_store = transaction.idb_object_store(&store.name)?;
+        object_store.clear()?.await?;
+
+        // All the records are put before awaiting any of them, as for exports
+        let requests = store
+            .records
+            .iter()
+            .map(|record| Ok(object_store.put(&record.serialize(&JSON_SERIALIZER)?, None)?))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        for request in requests {
+            request.await?;
+        }
+    }
+
+    if transaction.commit().await? == idb::TransactionResult::Aborted {
+        return Err(Error::TransactionAborted);
+    }
+
+    Ok(())
+}
+
+/// Returns the key of an archived record at the key path of its object store (`None` if a component is missing).
+fn record_key(record: &Value, key_path: &KeyPath) -> Option<Value> {
+    let value_at = |path: &str| {
+        path.split('.')
+            .try_fold(record, |value, name| value.get(name))
+            .cloned()
+    };
+
+    match key_path {
+        KeyPath::Single(path) => value_at(path),
+        KeyPath::Array(paths) => paths
+            .iter()
+            .map(|path| value_at(path))
+            .collect::<Option<Vec<_>>>()
+            .map(Value::Array),
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, byte| {
+        CRC32_TABLE[((crc ^ u32::from(*byte)) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
@@ -0,0 +1,95 @@
+use idb::{
+    builder::{DatabaseBuilder, ObjectStoreBuilder},
+    Factory, Query, TransactionMode, TransactionResult,
+};
+use serde::Serialize;
+
+use crate::{error::Error, JSON_SERIALIZER};
+
+/// Name of the database opened by the probe.
+const PROBE_DATABASE_NAME: &str = "__deli_availability_probe";
+
+/// Name of the object store written to by the probe.
+const PROBE_STORE_NAME: &str = "probe";
+
+/// Report of the IndexedDB features which work in the current environment, returned by
+/// [`Database::availability_check`](crate::Database::availability_check).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Availability {
+    /// IndexedDB is exposed by the environment
+    pub indexed_db: bool,
+    /// Databases can be created and opened
+    pub open: bool,
+    /// Records can be written and read back
+    pub write: bool,
+    /// Databases can be deleted
+    pub delete: bool,
+    /// Error of the first step of the probe which failed
+    pub error: Option<String>,
+}
+
+impl Availability {
+    /// Returns `true` if every step of the probe succeeded.
+    pub fn is_available(&self) -> bool {
+        self.indexed_db && self.open && self.write && self.delete
+    }
+}
+
+/// Probes IndexedDB by creating a database, writing a record to it, reading the record back and deleting the database.
+pub(crate) async fn check() -> Availability {
+    let mut availability = Availability::default();
+
+    if let Err(error) = probe(&mut availability).await {
+        availability.error.get_or_insert(error.to_string());
+    }
+
+    availability
+}
+
+async fn probe(availability: &mut Availability) -> Result<(), Error> {
+    let factory = Factory::new()?;
+    availability.indexed_db = true;
+
+    let database = DatabaseBuilder::new(PROBE_DATABASE_NAME)
+        .version(1)
+        .add_object_store(ObjectStoreBuilder::new(PROBE_STORE_NAME))
+        .build()
+        .await?;
+    availability.open = true;
+
+    let written = write(&database).await;
+    database.close();
+
+    // The database is deleted even if writing failed, so that the probe does not leave it behind
+    let deleted = match factory.delete(PROBE_DATABASE_NAME) {
+        Ok(request) => request.await,
+        Err(error) => Err(error),
+    };
+
+    if !written? {
+        availability.error = Some("record written by the probe could not be read back".to_owned());
+        return Ok(());
+    }
+    availability.write = true;
+
+    deleted?;
+    availability.delete = true;
+
+    Ok(())
+}
+
+/// Writes a record to the probe database and returns `true` if it can be read back.
+async fn write(database: &idb::Database) -> Result<bool, Error> {
+    let transaction = database.transaction(&[PROBE_STORE_NAME], TransactionMode::ReadWrite)?;
+    let object_store = transaction.object_store(PROBE_STORE_NAME)?;
+
+    let value = PROBE_STORE_NAME.serialize(&JSON_SERIALIZER)?;
+    object_store.put(&value, Some(&value))?.await?;
+    let read = object_store.get(Query::Key(value.clone()))?.await?;
+
+    if transaction.commit()?.await? == TransactionResult::Aborted {
+        return Err(Error::TransactionAborted);
+    }
+
+    Ok(read == Some(value))
+}
@@ -0,0 +1,89 @@
+//! Bulk writes reporting the outcome of every item.
+//!
+//! [`ObjectStore::add_all_batch`](crate::ObjectStore::add_all_batch),
+//! [`ObjectStore::put_all_batch`](crate::ObjectStore::put_all_batch) and
+//! [`ObjectStore::delete_many_batch`](crate::ObjectStore::delete_many_batch) return a [`BatchResult`] listing which
+//! items succeeded and which failed instead of failing the whole call on the first error.
+//!
+//! By default ([`OnError::Abort`]), IndexedDB aborts the transaction when one of its requests fails, so the writes of
+//! the items listed as succeeded are rolled back and the remaining items fail with an abort error. With
+//! [`OnError::Continue`], the failed requests are kept from aborting the transaction, so the other items are still
+//! written and committed with it. Failures of the transaction itself (e.g. quota errors) still abort it.
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{Event, IdbTransaction};
+
+use crate::error::Error;
+
+/// What happens to the transaction when an item of a batch fails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnError {
+    /// The transaction is aborted (default behavior of IndexedDB)
+    #[default]
+    Abort,
+    /// The transaction continues with the other items
+    Continue,
+}
+
+/// Outcome of every item of a bulk write.
+#[derive(Debug)]
+pub struct BatchResult<K> {
+    /// Index (in the input) and key of the items which were written
+    pub succeeded: Vec<(usize, K)>,
+    /// Items which failed
+    pub failed: Vec<BatchFailure<K>>,
+}
+
+/// Item of a bulk write which failed.
+#[derive(Debug)]
+pub struct BatchFailure<K> {
+    /// Index of the item in the input
+    pub index: usize,
+    /// Key of the item (`None` if the key is only known once the item is written, e.g. for auto incremented keys)
+    pub key: Option<K>,
+    /// Error of the item
+    pub error: Error,
+}
+
+impl<K> BatchResult<K> {
+    pub(crate) fn new() -> Self {
+        Self {
+            succeeded: Vec::new(),
+            failed: Vec::new(),
+        }
+    }
+
+    /// Returns `true` if all the items were written.
+    pub fn is_ok(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Keeps failed requests from aborting the transaction while alive, by cancelling the error events bubbling up to it.
+pub(crate) struct KeepAlive {
+    transaction: IdbTransaction,
+    listener: Closure<dyn FnMut(Event)>,
+}
+
+impl KeepAlive {
+    pub(crate) fn new(transaction: idb::Transaction) -> Self {
+        let transaction = IdbTransaction::from(transaction);
+        let listener = Closure::<dyn FnMut(Event)>::new(|event: Event| event.prevent_default());
+
+        // Adding (or removing) an event listener only throws for invalid arguments
+        let _ = transaction
+            .add_event_listener_with_callback("error", listener.as_ref().unchecked_ref());
+
+        Self {
+            transaction,
+            listener,
+        }
+    }
+}
+
+impl Drop for KeepAlive {
+    fn drop(&mut self) {
+        let _ = self
+            .transaction
+            .remove_event_listener_with_callback("error", self.listener.as_ref().unchecked_ref());
+    }
+}
@@ -0,0 +1,83 @@
+use std::ops::Deref;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use wasm_bindgen::JsCast;
+use web_sys::{Blob, File};
+
+/// `Blob` (or `File`) stored in a model field as is, e.g. to persist files selected by the user alongside their
+/// metadata:
+///
+/// ```rust,ignore
+/// #[derive(Serialize, Deserialize, Model)]
+/// struct Photo {
+///     #[deli(auto_increment)]
+///     id: u32,
+///     caption: String,
+///     image: JsBlob,
+/// }
+///
+/// let image = photos.get(&id).await?.unwrap().image;
+/// let url = web_sys::Url::create_object_url_with_blob(&image)?;
+/// ```
+///
+/// IndexedDB stores blobs using the structured clone algorithm, so the object passed through `serde_wasm_bindgen` is
+/// written and read back without copying its contents into wasm memory. Other serializers (e.g. `serde_json`) cannot
+/// represent blobs: exports contain an empty object instead, and blob fields cannot be part of keys or indexes.
+#[derive(Debug, Clone)]
+pub struct JsBlob(pub Blob);
+
+impl JsBlob {
+    /// Returns the blob as a `File` if it is one (e.g. to read its name), or `None` for other blobs.
+    pub fn as_file(&self) -> Option<&File> {
+        self.0.dyn_ref()
+    }
+
+    /// Returns the wrapped blob.
+    pub fn into_inner(self) -> Blob {
+        self.0
+    }
+}
+
+impl Deref for JsBlob {
+    type Target = Blob;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<Blob> for JsBlob {
+    fn from(blob: Blob) -> Self {
+        Self(blob)
+    }
+}
+
+impl From<File> for JsBlob {
+    fn from(file: File) -> Self {
+        Self(file.into())
+    }
+}
+
+impl From<JsBlob> for Blob {
+    fn from(blob: JsBlob) -> Self {
+        blob.0
+    }
+}
+
+impl Serialize for JsBlob {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serde_wasm_bindgen::preserve::serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for JsBlob {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        serde_wasm_bindgen::preserve::deserialize(deserializer).map(Self)
+    }
+}
@@ -0,0 +1,27 @@
+use js_sys::{Array, Reflect, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::{error::Error, model::Model};
+
+/// Replaces the binary fields of a serialized record being written (serialized as arrays of numbers) with
+/// `Uint8Array`s.
+///
+/// No conversion is needed when reading the records back: `serde_wasm_bindgen` deserializes sequences of bytes (e.g.
+/// `Vec<u8>`) and byte buffers (e.g. `serde_bytes::ByteBuf`) from `Uint8Array`s as well.
+pub(crate) fn write_bytes_fields<M>(value: &JsValue) -> Result<(), Error>
+where
+    M: Model,
+{
+    for name in M::BYTES_FIELDS {
+        let name = JsValue::from_str(name);
+        let field = Reflect::get(value, &name).map_err(serde_wasm_bindgen::Error::from)?;
+
+        // Values which are not arrays (e.g. `None`, or fields already serialized as `Uint8Array`) are stored as is
+        if field.is_instance_of::<Array>() {
+            Reflect::set(value, &name, &Uint8Array::new(&field))
+                .map_err(serde_wasm_bindgen::Error::from)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,200 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+};
+
+use idb::Query;
+use js_sys::JSON;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{Event, IdbTransaction};
+
+use crate::{error::Error, transaction::Transaction};
+
+/// Cache of the results of [`ObjectStore::get_all`](crate::ObjectStore::get_all), enabled using
+/// [`DatabaseBuilder::cache_queries`](crate::DatabaseBuilder::cache_queries).
+///
+/// Each object store has a generation, bumped every time a readwrite transaction with the store in scope completes.
+/// Results are only cached and reused while the generation of their store is the same, so that a write invalidates all
+/// the cached results of its store once it is committed, whether it was made through an
+/// [`ObjectStore`](crate::ObjectStore) or through the idb handles of the transaction.
+///
+/// Transactions are numbered in the order they are created, which is the order in which IndexedDB runs transactions
+/// with overlapping scopes. Transactions created after a readwrite transaction which is not finished yet read the store
+/// as it is once the readwrite transaction finishes, and transactions created before the last readwrite transaction
+/// which completed may read the store as it was before, so neither read nor fill the cache of the store.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct QueryCache(Rc<Inner>);
+
+#[derive(Debug, Default)]
+struct Inner {
+    serial: Cell<u64>,
+    stores: RefCell<HashMap<String, StoreCache>>,
+}
+
+#[derive(Debug, Default)]
+struct StoreCache {
+    generation: u64,
+    /// Numbers of the readwrite transactions with the store in scope which are not finished yet
+    writers: Vec<u64>,
+    /// Number of the last readwrite transaction with the store in scope which completed
+    last_writer: u64,
+    results: HashMap<String, Vec<JsValue>>,
+}
+
+impl StoreCache {
+    /// Returns `true` if the transaction with the given number reads the store as it is cached.
+    fn is_current(&self, serial: u64) -> bool {
+        serial > self.last_writer && self.writers.iter().all(|&writer| writer > serial)
+    }
+}
+
+impl QueryCache {
+    /// Returns the number of a transaction being created.
+    pub(crate) fn begin(&self) -> u64 {
+        let serial = self.0.serial.get() + 1;
+        self.0.serial.set(serial);
+        serial
+    }
+
+    /// Tracks a readwrite transaction with the given stores in scope until it finishes, invalidating the cached results
+    /// of the stores if it completes.
+    pub(crate) fn track_writes(
+        &self,
+        transaction: idb::Transaction,
+        serial: u64,
+        stores: &[&str],
+    ) -> idb::Transaction {
+        let mut caches = self.0.stores.borrow_mut();
+
+        for store in stores {
+            caches
+                .entry((*store).to_owned())
+                .or_default()
+                .writers
+                .push(serial);
+        }
+
+        let cache = self.clone();
+        let stores = stores
+            .iter()
+            .map(|store| (*store).to_owned())
+            .collect::<Vec<_>>();
+        let listener = Closure::once_into_js(move |event: Event| {
+            cache.finish(serial, &stores, event.type_() == "complete")
+        });
+
+        // A transaction either completes or aborts, so exactly one of the listeners is called. Adding an event listener
+        // only throws for invalid arguments.
+        let transaction = IdbTransaction::from(transaction);
+        let _ = transaction.add_event_listener_with_callback("complete", listener.unchecked_ref());
+        let _ = transaction.add_event_listener_with_callback("abort", listener.unchecked_ref());
+
+        transaction.into()
+    }
+
+    /// Invalidates the cached results of all the stores.
+    pub(crate) fn clear(&self) {
+        for cache in self.0.stores.borrow_mut().values_mut() {
+            cache.generation += 1;
+            cache.results.clear();
+        }
+    }
+
+    fn finish(&self, serial: u64, stores: &[String], completed: bool) {
+        let mut caches = self.0.stores.borrow_mut();
+
+        for store in stores {
+            let Some(cache) = caches.get_mut(store) else {
+                continue;
+            };
+
+            cache.writers.retain(|&writer| writer != serial);
+
+            if completed {
+                cache.generation += 1;
+                cache.last_writer = cache.last_writer.max(serial);
+                cache.results.clear();
+            }
+        }
+    }
+
+    fn generation(&self, store: &str) -> u64 {
+        self.0
+            .stores
+            .borrow()
+            .get(store)
+            .map_or(0, |cache| cache.generation)
+    }
+
+    fn get(&self, store: &str, serial: u64, key: &str) -> Option<Vec<JsValue>> {
+        self.0
+            .stores
+            .borrow()
+            .get(store)
+            .filter(|cache| cache.is_current(serial))
+            .and_then(|cache| cache.results.get(key).cloned())
+    }
+
+    fn insert(&self, store: &str, serial: u64, generation: u64, key: String, values: &[JsValue]) {
+        let mut stores = self.0.stores.borrow_mut();
+        let cache = stores.entry(store.to_owned()).or_default();
+
+        // Values read while the store was written to are not cached
+        if cache.generation == generation && cache.is_current(serial) {
+            cache.results.insert(key, values.to_vec());
+        }
+    }
+}
+
+/// Retrieves the values of the records matching the query from the store, reusing the cached result of the same query
+/// if the query cache of the database is enabled.
+pub(crate) async fn get_all(
+    transaction: &Transaction,
+    object_store: &idb::ObjectStore,
+    store: &str,
+    query: Option<Query>,
+    limit: Option<u32>,
+) -> Result<Vec<JsValue>, Error> {
+    let Some((cache, serial)) = transaction.query_cache() else {
+        return Ok(object_store.get_all(query, limit)?.await?);
+    };
+
+    let key = cache_key(query.as_ref(), limit)?;
+
+    if let Some(values) = cache.get(store, serial, &key) {
+        return Ok(values);
+    }
+
+    let generation = cache.generation(store);
+    let values = object_store.get_all(query, limit)?.await?;
+    cache.insert(store, serial, generation, key, &values);
+
+    Ok(values)
+}
+
+/// Returns the key of a query in the cache. Keys are serialized using JSON compatible serialization, so that their JSON
+/// representation identifies them.
+fn cache_key(query: Option<&Query>, limit: Option<u32>) -> Result<String, Error> {
+    let range = match query {
+        None => String::new(),
+        Some(Query::Key(key)) => stringify(key)?,
+        Some(Query::KeyRange(range)) => format!(
+            "{}{},{}{}",
+            if range.lower_open() { "(" } else { "[" },
+            stringify(&range.lower()?)?,
+            stringify(&range.upper()?)?,
+            if range.upper_open() { ")" } else { "]" },
+        ),
+    };
+
+    Ok(format!("{range};{limit:?}"))
+}
+
+/// Returns the JSON representation of a key (or an empty string for the missing bound of a key range).
+fn stringify(key: &JsValue) -> Result<String, Error> {
+    Ok(JSON::stringify(key)
+        .map_err(serde_wasm_bindgen::Error::from)?
+        .as_string()
+        .unwrap_or_default())
+}
@@ -0,0 +1,29 @@
+use std::{cell::Cell, rc::Rc};
+
+/// Token for cancelling long scans and bulk operations, similar to an `AbortSignal`. It is attached to a transaction
+/// using [`TransactionBuilder::cancellation`](crate::TransactionBuilder::cancellation). Once the token is cancelled,
+/// the next operation on the transaction (including advancing a cursor) aborts the transaction and fails with
+/// [`Error::Cancelled`](crate::Error::Cancelled).
+///
+/// Clones of a token share the same state, so a clone can be kept (e.g. by a page) to cancel the work started with it.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Rc<Cell<bool>>,
+}
+
+impl CancellationToken {
+    /// Creates a new [`CancellationToken`] which is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the token (and all its clones).
+    pub fn cancel(&self) {
+        self.cancelled.set(true);
+    }
+
+    /// Returns `true` if the token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.get()
+    }
+}
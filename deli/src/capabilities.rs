@@ -0,0 +1,54 @@
+use std::cell::OnceCell;
+
+use js_sys::Reflect;
+use wasm_bindgen::JsValue;
+
+thread_local! {
+    static CAPABILITIES: OnceCell<Capabilities> = const { OnceCell::new() };
+}
+
+/// IndexedDB and Storage API features available in the current browser, returned by [`capabilities`].
+///
+/// `deli` falls back to other code paths where a feature it uses is missing, e.g. reading records using a cursor
+/// instead of `getAll()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Capabilities {
+    /// `getAll()` on object stores and indexes
+    pub get_all: bool,
+    /// `commit()` on transactions
+    pub commit: bool,
+    /// Durability hints when creating transactions
+    pub durability: bool,
+    /// `indexedDB.databases()`
+    pub databases: bool,
+    /// `navigator.storage.persist()`
+    pub persist: bool,
+}
+
+/// Returns the features available in the current browser (detected once per thread).
+pub fn capabilities() -> Capabilities {
+    CAPABILITIES.with(|capabilities| *capabilities.get_or_init(detect))
+}
+
+fn detect() -> Capabilities {
+    let global = js_sys::global();
+    let has = |interface: &str, member: &str| has_member(&global, interface, member);
+
+    Capabilities {
+        get_all: has("IDBObjectStore", "getAll") && has("IDBIndex", "getAll"),
+        commit: has("IDBTransaction", "commit"),
+        durability: has("IDBTransaction", "durability"),
+        databases: has("IDBFactory", "databases"),
+        persist: has("StorageManager", "persist"),
+    }
+}
+
+/// Returns `true` if the prototype of the interface with the given name has the member.
+fn has_member(global: &JsValue, interface: &str, member: &str) -> bool {
+    Reflect::get(global, &JsValue::from_str(interface))
+        .ok()
+        .filter(JsValue::is_function)
+        .and_then(|interface| Reflect::get(&interface, &JsValue::from_str("prototype")).ok())
+        .and_then(|prototype| Reflect::has(&prototype, &JsValue::from_str(member)).ok())
+        .unwrap_or(false)
+}
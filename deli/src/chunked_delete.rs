@@ -0,0 +1,136 @@
+use std::{fmt, marker::PhantomData};
+
+use idb::Query;
+use serde::Serialize;
+
+use crate::{
+    cancellation::CancellationToken,
+    database::Database,
+    error::Error,
+    key_range::{KeyRange, UnboundedRange},
+    lookup::Lookup,
+    model::Model,
+    TransactionResult,
+};
+
+/// Default number of records deleted in a single transaction by a [`ChunkedDelete`].
+pub const DEFAULT_DELETE_CHUNK_SIZE: u32 = 1000;
+
+/// Deletes the records of a model matching a key range in successive transactions, so that deleting a huge range does
+/// not block the database in a single long transaction. Returned by [`Database::delete_chunked`].
+///
+/// Chunks which are already deleted stay deleted if a later chunk fails or the deletion is cancelled.
+pub struct ChunkedDelete<'a, M> {
+    database: &'a Database,
+    query: Option<Query>,
+    chunk_size: u32,
+    on_progress: Option<Box<dyn FnMut(u32) + 'a>>,
+    cancellation: Option<CancellationToken>,
+    _model: PhantomData<M>,
+}
+
+impl<'a, M> ChunkedDelete<'a, M>
+where
+    M: Model + Lookup<M::Key>,
+{
+    pub(crate) fn new<'k, Q>(
+        database: &'a Database,
+        key_range: impl Into<KeyRange<'k, Q, UnboundedRange>>,
+    ) -> Result<Self, Error>
+    where
+        M: Lookup<Q>,
+        Q: Serialize + ?Sized + 'k,
+    {
+        Ok(Self {
+            database,
+            query: <Option<Query>>::try_from(&key_range.into())?,
+            chunk_size: DEFAULT_DELETE_CHUNK_SIZE,
+            on_progress: None,
+            cancellation: None,
+            _model: PhantomData,
+        })
+    }
+
+    /// Sets the maximum number of records deleted in a single transaction (defaults to
+    /// [`DEFAULT_DELETE_CHUNK_SIZE`]).
+    pub fn chunk_size(mut self, chunk_size: u32) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Sets a callback called with the total number of deleted records after each chunk.
+    pub fn on_progress(mut self, on_progress: impl FnMut(u32) + 'a) -> Self {
+        self.on_progress = Some(Box::new(on_progress));
+        self
+    }
+
+    /// Attaches a [`CancellationToken`]. Once the token is cancelled, the current chunk is aborted and the deletion
+    /// fails with [`Error::Cancelled`].
+    pub fn cancellation(mut self, token: &CancellationToken) -> Self {
+        self.cancellation = Some(token.clone());
+        self
+    }
+
+    /// Deletes the records chunk by chunk, returning the number of deleted records.
+    pub async fn run(mut self) -> Result<u32, Error> {
+        let mut deleted = 0;
+
+        loop {
+            let builder = self.database.transaction().writable().with_model::<M>();
+            let builder = match self.cancellation {
+                Some(ref token) => builder.cancellation(token),
+                None => builder,
+            };
+
+            let transaction = builder.build()?;
+            transaction.check_cancelled()?;
+
+            let count = {
+                let store = transaction.object_store::<M>()?;
+
+                let key_range = match self.query {
+                    Some(ref query) => KeyRange::from(query.clone()),
+                    None => KeyRange::from(..),
+                };
+                let keys = store
+                    .get_all_keys::<M::Key>(key_range, Some(self.chunk_size))
+                    .await?;
+
+                // Keys are in primary key order and the query is a contiguous range, so the chunk is the range between
+                // its first and last keys
+                if let (Some(first), Some(last)) = (keys.first(), keys.last()) {
+                    store.delete(first..=last).await?;
+                }
+
+                keys.len() as u32
+            };
+
+            if transaction.commit().await? == TransactionResult::Aborted {
+                return Err(Error::TransactionAborted);
+            }
+
+            deleted += count;
+
+            if count > 0 {
+                if let Some(ref mut on_progress) = self.on_progress {
+                    on_progress(deleted);
+                }
+            }
+
+            if count < self.chunk_size {
+                return Ok(deleted);
+            }
+        }
+    }
+}
+
+impl<M> fmt::Debug for ChunkedDelete<'_, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChunkedDelete")
+            .field("database", &self.database)
+            .field("query", &self.query)
+            .field("chunk_size", &self.chunk_size)
+            .field("cancellation", &self.cancellation)
+            .finish_non_exhaustive()
+    }
+}
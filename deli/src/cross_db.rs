@@ -0,0 +1,246 @@
+//! Writes spanning two databases.
+//!
+//! IndexedDB transactions cannot span multiple databases. [`CrossDbWrite`] runs a write on a secondary database and a
+//! write on a primary database in two phases with best-effort atomicity:
+//!
+//! 1. A journal entry is stored in the primary database (which must be built using
+//!    [`DatabaseBuilder::enable_journal`](crate::DatabaseBuilder::enable_journal)).
+//! 2. The secondary write is committed.
+//! 3. The primary write is committed in the same transaction that removes the journal entry.
+//!
+//! If the primary write fails, the compensation (which should undo the secondary write) is committed on the secondary
+//! database and the journal entry is removed. If the app is closed (or the compensation fails) between the phases, the
+//! journal entry is left behind, and [`pending`] returns it so that the app can compensate when it is restarted.
+use std::{fmt, future::Future, pin::Pin};
+
+use idb::TransactionResult;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::Database,
+    error::Error,
+    meta::{MetaStore, META_STORE_NAME},
+    model::Model,
+    object_store::ObjectStore,
+    transaction::Transaction,
+};
+
+/// Prefix of the keys of journal entries in the meta store.
+const JOURNAL_PREFIX: &str = "journal/";
+
+/// Future returned by the phases of a [`CrossDbWrite`].
+pub type WriteFuture<'t> = Pin<Box<dyn Future<Output = Result<(), Error>> + 't>>;
+
+type WriteFn = Box<dyn for<'t> FnOnce(&'t Transaction) -> WriteFuture<'t>>;
+
+/// Journal entry of a cross-database write which has not completed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Identifier of the write
+    pub id: String,
+    /// Name of the object store written in the secondary database
+    pub secondary_store: String,
+    /// Name of the object store written in the primary database
+    pub primary_store: String,
+}
+
+/// A write on the model of an object store.
+struct Phase {
+    store: &'static str,
+    write: WriteFn,
+}
+
+impl Phase {
+    fn new<M, F>(write: F) -> Self
+    where
+        M: Model,
+        F: for<'t> FnOnce(ObjectStore<'t, M>) -> WriteFuture<'t> + 'static,
+    {
+        Self {
+            store: M::NAME,
+            write: Box::new(move |transaction| match transaction.object_store::<M>() {
+                Ok(store) => write(store),
+                Err(error) => Box::pin(async move { Err(error) }),
+            }),
+        }
+    }
+
+    /// Runs the write in a transaction on the database, also removing the journal entry if `journal` is given.
+    async fn run(self, database: &Database, journal: Option<&str>) -> Result<(), Error> {
+        let builder = database
+            .transaction()
+            .writable()
+            .with_store_name(self.store);
+        let builder = match journal {
+            Some(_) => builder.with_store_name(META_STORE_NAME),
+            None => builder,
+        };
+
+        let transaction = builder.build()?;
+
+        if let Err(error) = (self.write)(&transaction).await {
+            let _ = transaction.abort().await;
+            return Err(error);
+        }
+
+        if let Some(key) = journal {
+            MetaStore::new(&transaction)?.delete(key).await?;
+        }
+
+        commit(transaction).await
+    }
+}
+
+impl fmt::Debug for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Phase")
+            .field("store", &self.store)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Write spanning a primary and a secondary database (see [module level documentation](self)).
+#[derive(Debug)]
+pub struct CrossDbWrite<'a> {
+    id: String,
+    primary: &'a Database,
+    secondary: &'a Database,
+    primary_write: Option<Phase>,
+    secondary_write: Option<Phase>,
+    compensation: Option<Phase>,
+}
+
+impl<'a> CrossDbWrite<'a> {
+    /// Creates a new [`CrossDbWrite`] with the given identifier (used as the key of its journal entry, so it should be
+    /// unique among concurrent writes).
+    pub fn new(id: &str, primary: &'a Database, secondary: &'a Database) -> Self {
+        Self {
+            id: id.to_owned(),
+            primary,
+            secondary,
+            primary_write: None,
+            secondary_write: None,
+            compensation: None,
+        }
+    }
+
+    /// Sets the write on the primary database, committed after the secondary write.
+    pub fn primary<M, F>(mut self, write: F) -> Self
+    where
+        M: Model,
+        F: for<'t> FnOnce(ObjectStore<'t, M>) -> WriteFuture<'t> + 'static,
+    {
+        self.primary_write = Some(Phase::new::<M, F>(write));
+        self
+    }
+
+    /// Sets the write on the secondary database, committed first.
+    pub fn secondary<M, F>(mut self, write: F) -> Self
+    where
+        M: Model,
+        F: for<'t> FnOnce(ObjectStore<'t, M>) -> WriteFuture<'t> + 'static,
+    {
+        self.secondary_write = Some(Phase::new::<M, F>(write));
+        self
+    }
+
+    /// Sets the compensation undoing the secondary write, committed on the secondary database if the primary write
+    /// fails.
+    pub fn compensate<M, F>(mut self, write: F) -> Self
+    where
+        M: Model,
+        F: for<'t> FnOnce(ObjectStore<'t, M>) -> WriteFuture<'t> + 'static,
+    {
+        self.compensation = Some(Phase::new::<M, F>(write));
+        self
+    }
+
+    /// Executes the write. Returns the error of the failed phase; if the primary write fails after the secondary write
+    /// was committed, the compensation is run before returning. Fails with [`Error::CrossDbWriteIncomplete`] (before
+    /// writing anything) if the primary or the secondary write is not set.
+    pub async fn execute(self) -> Result<(), Error> {
+        let primary_write = self
+            .primary_write
+            .ok_or(Error::CrossDbWriteIncomplete("primary"))?;
+        let secondary_write = self
+            .secondary_write
+            .ok_or(Error::CrossDbWriteIncomplete("secondary"))?;
+
+        let key = format!("{JOURNAL_PREFIX}{}", self.id);
+        let entry = JournalEntry {
+            id: self.id,
+            secondary_store: secondary_write.store.to_owned(),
+            primary_store: primary_write.store.to_owned(),
+        };
+
+        let transaction = journal_transaction(self.primary)?;
+        MetaStore::new(&transaction)?.set(&key, &entry).await?;
+        commit(transaction).await?;
+
+        if let Err(error) = secondary_write.run(self.secondary, None).await {
+            let _ = clear(self.primary, &key).await;
+            return Err(error);
+        }
+
+        if let Err(error) = primary_write.run(self.primary, Some(&key)).await {
+            // The journal entry is kept if the compensation fails, so that it can be retried later
+            let compensated = match self.compensation {
+                Some(compensation) => compensation.run(self.secondary, None).await.is_ok(),
+                None => true,
+            };
+
+            if compensated {
+                let _ = clear(self.primary, &key).await;
+            }
+
+            return Err(error);
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the journal entries of the cross-database writes which were interrupted (e.g. because the app was closed
+/// between phases) or whose compensation failed.
+pub async fn pending(primary: &Database) -> Result<Vec<JournalEntry>, Error> {
+    let transaction = primary
+        .transaction()
+        .with_store_name(META_STORE_NAME)
+        .build()?;
+
+    let entries = MetaStore::new(&transaction)?
+        .get_all_with_prefix(JOURNAL_PREFIX)
+        .await?;
+
+    transaction.done().await?;
+
+    Ok(entries)
+}
+
+/// Removes the journal entry of a cross-database write (e.g. after compensating an interrupted write).
+pub async fn resolve(primary: &Database, id: &str) -> Result<(), Error> {
+    clear(primary, &format!("{JOURNAL_PREFIX}{id}")).await
+}
+
+async fn clear(primary: &Database, key: &str) -> Result<(), Error> {
+    let transaction = journal_transaction(primary)?;
+    MetaStore::new(&transaction)?.delete(key).await?;
+    commit(transaction).await
+}
+
+/// Begins a transaction for writing the journal of the primary database.
+fn journal_transaction(primary: &Database) -> Result<Transaction, Error> {
+    primary
+        .transaction()
+        .writable()
+        .with_store_name(META_STORE_NAME)
+        .build()
+}
+
+async fn commit(transaction: Transaction) -> Result<(), Error> {
+    if transaction.commit().await? == TransactionResult::Aborted {
+        return Err(Error::TransactionAborted);
+    }
+
+    Ok(())
+}
@@ -3,13 +3,20 @@ use std::borrow::Borrow;
 use serde::{de::DeserializeOwned, Serialize};
 use serde_wasm_bindgen::Serializer;
 
-use crate::{error::Error, model::Model, transaction::Transaction, JSON_SERIALIZER};
+use crate::{
+    error::Error,
+    gate::Operation,
+    model::{decode_stored, read_stored, serialize_put, Model, StoredValue},
+    transaction::Transaction,
+    JSON_SERIALIZER,
+};
 
 /// Cursor on an object store or index
 #[derive(Debug)]
 pub struct Cursor<'t, M, K> {
     cursor: idb::ManagedCursor,
-    _transaction: &'t Transaction,
+    transaction: &'t Transaction,
+    returned: bool,
     _marker: std::marker::PhantomData<(M, K)>,
 }
 
@@ -21,7 +28,8 @@ where
     pub(crate) fn new(cursor: idb::ManagedCursor, transaction: &'t Transaction) -> Self {
         Self {
             cursor,
-            _transaction: transaction,
+            transaction,
+            returned: false,
             _marker: std::marker::PhantomData,
         }
     }
@@ -52,14 +60,14 @@ where
     /// Returns the value at the current position of the cursor
     pub fn value(&self) -> Result<Option<M>, Error> {
         let js_value = self.cursor.value()?;
-        js_value
-            .map(serde_wasm_bindgen::from_value)
-            .transpose()
-            .map_err(Into::into)
+        js_value.map(read_stored(self.transaction)).transpose()
     }
 
     /// Advances the cursor through the next count records in range.
     pub async fn advance(&mut self, count: u32) -> Result<(), Error> {
+        self.transaction.check_cancelled()?;
+        self.returned = false;
+
         self.cursor.advance(count).await.map_err(Into::into)
     }
 
@@ -69,6 +77,9 @@ where
         K: Borrow<Q>,
         Q: Serialize,
     {
+        self.transaction.check_cancelled()?;
+        self.returned = false;
+
         let js_value = key
             .map(|key| key.serialize(&Serializer::json_compatible()))
             .transpose()?;
@@ -87,6 +98,9 @@ where
         M::Key: Borrow<R>,
         R: Serialize,
     {
+        self.transaction.check_cancelled()?;
+        self.returned = false;
+
         let js_key = key.serialize(&JSON_SERIALIZER)?;
         let js_primary_key = primary_key.serialize(&JSON_SERIALIZER)?;
         self.cursor
@@ -95,19 +109,273 @@ where
             .map_err(Into::into)
     }
 
-    /// Updates the value at the current position of the cursor
+    /// Returns the primary key and value of the next record (or `None` once the cursor is exhausted). The first call
+    /// returns the record at the current position of the cursor, and each following call advances the cursor to the
+    /// next record first (unless the cursor was moved in between, e.g. using [`next`](Self::next)).
+    pub async fn next_record(&mut self) -> Result<Option<(M::Key, M)>, Error> {
+        if self.returned {
+            // An exhausted cursor cannot be advanced anymore
+            if self.cursor.key()?.is_none() {
+                return Ok(None);
+            }
+
+            self.next::<K>(None).await?;
+        }
+
+        self.returned = true;
+
+        match (self.primary_key()?, self.value()?) {
+            (Some(key), Some(value)) => Ok(Some((key, value))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Updates the value at the current position of the cursor and returns the record as written (with the write hook
+    /// of the model applied)
     pub async fn update<V>(&mut self, value: &V) -> Result<M, Error>
     where
         M: Borrow<V>,
         V: Serialize,
     {
-        let js_value = value.serialize(&JSON_SERIALIZER)?;
-        let updated_js_value = self.cursor.update(&js_value).await?;
-        serde_wasm_bindgen::from_value(updated_js_value).map_err(Into::into)
+        let (js_value, record) = prepare_update::<M, V>(self.transaction, value)?;
+
+        self.cursor.update(&js_value).await?;
+
+        #[cfg(feature = "inspect")]
+        if let Some(primary_key) = self.cursor.primary_key()? {
+            crate::inspect::record::<M>(self.transaction, primary_key, &js_value).await?;
+        }
+
+        Ok(record)
     }
 
     /// Deletes the value at the current position of the cursor
     pub async fn delete(&mut self) -> Result<(), Error> {
-        self.cursor.delete().await.map_err(Into::into)
+        self.transaction.check(M::NAME, Operation::Delete)?;
+
+        #[cfg(any(feature = "inspect", feature = "sync"))]
+        let primary_key = self.cursor.primary_key()?;
+
+        self.cursor.delete().await?;
+
+        #[cfg(feature = "inspect")]
+        if let Some(ref primary_key) = primary_key {
+            crate::inspect::forget::<M>(
+                self.transaction,
+                Some(&idb::Query::Key(primary_key.clone())),
+            )
+            .await?;
+        }
+
+        #[cfg(feature = "sync")]
+        crate::sync::record_tombstones::<M>(self.transaction, primary_key.into_iter().collect())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Returns `false` if the browser does not support `getAll()` (detected with the `capabilities` feature), in which
+/// case records are read using a cursor instead.
+pub(crate) fn has_get_all() -> bool {
+    #[cfg(feature = "capabilities")]
+    return crate::capabilities().get_all;
+
+    #[cfg(not(feature = "capabilities"))]
+    true
+}
+
+/// Retrieves the records matching the query from an object store as stored, using `getAll()` or a cursor if the
+/// browser does not support it.
+#[cfg(any(
+    feature = "cross-db",
+    feature = "dynamic",
+    feature = "export",
+    feature = "inspect",
+    feature = "sync"
+))]
+pub(crate) async fn get_all_stored(
+    transaction: &Transaction,
+    object_store: &idb::ObjectStore,
+    query: Option<idb::Query>,
+    limit: Option<u32>,
+) -> Result<Vec<StoredValue>, Error> {
+    if has_get_all() {
+        return Ok(object_store.get_all(query, limit)?.await?);
+    }
+
+    let cursor = object_store.open_cursor(query, None)?.await?;
+    collect_stored(transaction, cursor, limit).await
+}
+
+/// Retrieves the primary keys of the records matching the query from an object store, using `getAllKeys()` or a key
+/// cursor if the browser does not support it.
+#[cfg(any(feature = "dynamic", feature = "sync"))]
+pub(crate) async fn get_all_keys_stored(
+    transaction: &Transaction,
+    object_store: &idb::ObjectStore,
+    query: Option<idb::Query>,
+    limit: Option<u32>,
+) -> Result<Vec<StoredValue>, Error> {
+    if has_get_all() {
+        return Ok(object_store.get_all_keys(query, limit)?.await?);
+    }
+
+    let cursor = object_store.open_key_cursor(query, None)?.await?;
+    crate::key_cursor::collect_stored_keys(transaction, cursor, limit).await
+}
+
+/// Retrieves the primary keys of the records matching the query from an index, using `getAllKeys()` or a key cursor
+/// if the browser does not support it.
+#[cfg(any(feature = "integrity", feature = "sync"))]
+pub(crate) async fn index_get_all_keys_stored(
+    transaction: &Transaction,
+    index: &idb::Index,
+    query: Option<idb::Query>,
+    limit: Option<u32>,
+) -> Result<Vec<StoredValue>, Error> {
+    if has_get_all() {
+        return Ok(index.get_all_keys(query, limit)?.await?);
     }
+
+    let cursor = index.open_key_cursor(query, None)?.await?;
+    crate::key_cursor::collect_stored_keys(transaction, cursor, limit).await
+}
+
+/// Collects the values of the records visited by a cursor after skipping offset records (up to limit if given). Stops
+/// with [`Error::Cancelled`] if the transaction is cancelled.
+pub(crate) async fn collect_values<M>(
+    transaction: &Transaction,
+    cursor: Option<idb::Cursor>,
+    offset: u32,
+    limit: Option<u32>,
+) -> Result<Vec<M>, Error>
+where
+    M: Model,
+{
+    collect(transaction, cursor, offset, limit, |cursor| {
+        cursor.value()?.map(read_stored(transaction)).transpose()
+    })
+    .await
+}
+
+/// Collects the primary keys and values of the records visited by a cursor (up to limit if given), e.g. when
+/// `getAll()` and `getAllKeys()` are not supported. Stops with [`Error::Cancelled`] if the transaction is cancelled.
+pub(crate) async fn collect_entries<M>(
+    transaction: &Transaction,
+    cursor: Option<idb::Cursor>,
+    limit: Option<u32>,
+) -> Result<Vec<(M::Key, M)>, Error>
+where
+    M: Model,
+{
+    collect(transaction, cursor, 0, limit, |cursor| {
+        match (cursor.primary_key()?, cursor.value()?) {
+            (Some(key), Some(value)) => Ok(Some((
+                serde_wasm_bindgen::from_value(key)?,
+                read_stored(transaction)(value)?,
+            ))),
+            _ => Ok(None),
+        }
+    })
+    .await
+}
+
+/// Collects the values of the records visited by a cursor as stored (up to limit if given), e.g. when `getAll()` is not
+/// supported. Stops with [`Error::Cancelled`] if the transaction is cancelled.
+#[cfg(any(
+    feature = "cross-db",
+    feature = "dynamic",
+    feature = "export",
+    feature = "inspect",
+    feature = "sync"
+))]
+pub(crate) async fn collect_stored(
+    transaction: &Transaction,
+    cursor: Option<idb::Cursor>,
+    limit: Option<u32>,
+) -> Result<Vec<StoredValue>, Error> {
+    collect(transaction, cursor, 0, limit, |cursor| {
+        cursor.value().map_err(Into::into)
+    })
+    .await
+}
+
+/// Collects the items read by the function at every position of a cursor after skipping offset records (up to limit if
+/// given), until the function returns `None`.
+async fn collect<T>(
+    transaction: &Transaction,
+    cursor: Option<idb::Cursor>,
+    offset: u32,
+    limit: Option<u32>,
+    read: impl Fn(&idb::ManagedCursor) -> Result<Option<T>, Error>,
+) -> Result<Vec<T>, Error> {
+    let mut items = Vec::new();
+
+    let Some(cursor) = cursor else {
+        return Ok(items);
+    };
+
+    // A limit of zero means no limit (same as `getAll()`)
+    let limit = limit.filter(|limit| *limit > 0).map(|limit| limit as usize);
+    let mut cursor = cursor.into_managed();
+
+    // `advance()` fails with a count of zero
+    if offset > 0 {
+        transaction.check_cancelled()?;
+        cursor.advance(offset).await?;
+    }
+
+    while let Some(item) = read(&cursor)? {
+        items.push(item);
+
+        if limit.is_some_and(|limit| items.len() >= limit) {
+            break;
+        }
+
+        transaction.check_cancelled()?;
+        cursor.next(None).await?;
+    }
+
+    Ok(items)
+}
+
+/// Turns a cursor into a stream of the values of the records it visits. The stream ends after the first error.
+#[cfg(feature = "stream")]
+pub(crate) fn into_stream<'t, M, K>(
+    cursor: Option<Cursor<'t, M, K>>,
+) -> impl futures_util::Stream<Item = Result<M, Error>> + Unpin + 't
+where
+    M: Model + 't,
+    K: Serialize + DeserializeOwned + 't,
+{
+    // Boxed so that the stream is `Unpin` and can be polled using `StreamExt::next` without pinning it first
+    Box::pin(futures_util::stream::unfold(cursor, |cursor| async move {
+        let mut cursor = cursor?;
+
+        match cursor.next_record().await {
+            Ok(Some((_, value))) => Some((Ok(value), Some(cursor))),
+            Ok(None) => None,
+            Err(err) => Some((Err(err), None)),
+        }
+    }))
+}
+
+/// Checks that the record at the current position of a cursor can be updated and serializes the value like every
+/// update path writes it. Also returns the record as written, since the request of a cursor update resolves with the
+/// primary key.
+pub(crate) fn prepare_update<M, V>(
+    transaction: &Transaction,
+    value: &V,
+) -> Result<(StoredValue, M), Error>
+where
+    M: Model + Borrow<V>,
+    V: Serialize,
+{
+    transaction.check(M::NAME, Operation::Update)?;
+
+    let js_value = serialize_put::<M, V>(value)?;
+    let record = decode_stored(transaction, js_value.clone())?;
+
+    Ok((js_value, record))
 }
@@ -1,16 +1,50 @@
+use std::{cell::Cell, rc::Rc};
+
+use serde::Serialize;
+
 use crate::{
-    database_builder::DatabaseBuilder, error::Error, transaction_builder::TransactionBuilder,
+    availability::{self, Availability},
+    chunked_delete::ChunkedDelete,
+    database_builder::DatabaseBuilder,
+    error::Error,
+    gate::GateHandle,
+    key_range::{KeyRange, UnboundedRange},
+    lookup::Lookup,
+    model::Model,
+    transaction_builder::TransactionBuilder,
 };
 
 /// Provides connection to an indexed db database
 #[derive(Debug)]
 pub struct Database {
-    database: idb::Database,
+    database: Rc<idb::Database>,
+    gate: Option<GateHandle>,
+    closed: Cell<bool>,
+    #[cfg(feature = "cache")]
+    query_cache: Option<crate::cache::QueryCache>,
 }
 
 impl Database {
-    pub(crate) fn new(database: idb::Database) -> Self {
-        Self { database }
+    pub(crate) fn new(database: Rc<idb::Database>) -> Self {
+        Self {
+            database,
+            gate: None,
+            closed: Cell::new(false),
+            #[cfg(feature = "cache")]
+            query_cache: None,
+        }
+    }
+
+    pub(crate) fn with_gate(self, gate: Option<GateHandle>) -> Self {
+        Self { gate, ..self }
+    }
+
+    #[cfg(feature = "cache")]
+    pub(crate) fn with_query_cache(self, enabled: bool) -> Self {
+        Self {
+            query_cache: enabled.then(Default::default),
+            ..self
+        }
     }
 
     /// Returns a builder for [`Database`]
@@ -29,13 +63,89 @@ impl Database {
     }
 
     /// Returns a transaction builder for creating transactions on database
-    pub fn transaction(&self) -> TransactionBuilder {
+    pub fn transaction(&self) -> TransactionBuilder<'_> {
         TransactionBuilder::new(self)
     }
 
+    /// Returns a [`ChunkedDelete`] deleting the records of a model matching the given key range in successive
+    /// transactions
+    pub fn delete_chunked<'a, 'k, M, Q>(
+        &'a self,
+        key_range: impl Into<KeyRange<'k, Q, UnboundedRange>>,
+    ) -> Result<ChunkedDelete<'a, M>, Error>
+    where
+        M: Model + Lookup<M::Key> + Lookup<Q>,
+        Q: Serialize + ?Sized + 'k,
+    {
+        ChunkedDelete::new(self, key_range)
+    }
+
+    /// Returns a builder for exporting object stores of the database (see [`export`](crate::export))
+    #[cfg(feature = "export")]
+    pub fn export(&self) -> crate::export::ExportBuilder<'_> {
+        crate::export::ExportBuilder::new(self)
+    }
+
+    /// Validates a backup archive and replaces the records of its object stores with the archived ones (see
+    /// [`archive`](crate::archive))
+    #[cfg(feature = "export")]
+    pub async fn import(&self, archive: &crate::archive::Archive) -> Result<(), Error> {
+        crate::archive::import(self, archive).await
+    }
+
+    /// Returns a builder restoring chosen object stores or key ranges of a backup archive into the existing records
+    /// (see [`archive`](crate::archive))
+    #[cfg(feature = "export")]
+    pub fn restore<'a>(
+        &'a self,
+        archive: &'a crate::archive::Archive,
+    ) -> crate::archive::RestoreBuilder<'a> {
+        crate::archive::RestoreBuilder::new(self, archive)
+    }
+
+    /// Scans the object store of a model and returns the records violating its invariants (see
+    /// [`integrity`](crate::integrity))
+    #[cfg(feature = "integrity")]
+    pub async fn check_integrity<M>(
+        &self,
+    ) -> Result<crate::integrity::IntegrityReport<M::Key>, Error>
+    where
+        M: crate::model::Model,
+    {
+        crate::integrity::check::<M>(self).await
+    }
+
+    /// Repairs the records of a model which cannot be deserialized using the given strategy (see
+    /// [`integrity`](crate::integrity))
+    #[cfg(feature = "integrity")]
+    pub async fn repair<M>(
+        &self,
+        strategy: crate::integrity::RepairStrategy<M>,
+    ) -> Result<crate::integrity::RepairSummary<M::Key>, Error>
+    where
+        M: crate::model::Model + crate::lookup::Lookup<M::Key>,
+    {
+        crate::integrity::repair::<M>(self, &strategy).await
+    }
+
+    /// Invalidates the cached query results (see [`DatabaseBuilder::cache_queries`]), e.g. when another tab or worker
+    /// wrote to the database
+    #[cfg(feature = "cache")]
+    pub fn clear_query_cache(&self) {
+        if let Some(ref cache) = self.query_cache {
+            cache.clear();
+        }
+    }
+
     /// Closes database connection
     pub fn close(&self) {
         self.database.close();
+        self.closed.set(true);
+    }
+
+    /// Returns `true` if the connection was closed using [`close`](Self::close)
+    pub fn is_closed(&self) -> bool {
+        self.closed.get()
     }
 
     /// Deletes a database
@@ -43,6 +153,23 @@ impl Database {
         idb::Factory::new()?.delete(name)?.await.map_err(Into::into)
     }
 
+    /// Checks whether IndexedDB works in the current environment (it may be missing or fail in private browsing modes
+    /// and some embedded webviews) by creating a probe database, writing a record to it and deleting it again. Apps
+    /// can disable the features relying on persisted data if the returned report is not
+    /// [`is_available`](Availability::is_available).
+    pub async fn availability_check() -> Availability {
+        availability::check().await
+    }
+
+    pub(crate) fn gate(&self) -> Option<&GateHandle> {
+        self.gate.as_ref()
+    }
+
+    #[cfg(feature = "cache")]
+    pub(crate) fn query_cache(&self) -> Option<&crate::cache::QueryCache> {
+        self.query_cache.as_ref()
+    }
+
     pub(crate) fn as_idb_database(&self) -> &idb::Database {
         &self.database
     }
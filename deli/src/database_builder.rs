@@ -1,21 +1,79 @@
-use crate::{database::Database, error::Error, model::Model};
+use crate::{
+    database::Database,
+    error::Error,
+    gate::{Gate, GateHandle},
+    model::Model,
+    object_store::ObjectStore,
+    open_lock,
+    schema::Schema,
+    seed::{self, Seed, SeedFuture},
+};
 
 /// A builder for [`Database`]
 #[derive(Debug)]
 pub struct DatabaseBuilder {
+    name: String,
     builder: idb::builder::DatabaseBuilder,
+    version: Option<u32>,
+    schema: Schema,
+    seeds: Vec<Seed>,
+    gate: Option<GateHandle>,
+    #[cfg(feature = "cache")]
+    cache_queries: bool,
+    #[cfg(any(
+        feature = "cross-db",
+        feature = "fingerprint",
+        feature = "split",
+        feature = "sync"
+    ))]
+    meta: bool,
+    stores: Vec<String>,
+    internal_stores: Vec<String>,
+    #[cfg(feature = "fingerprint")]
+    guard_schema: bool,
+    #[cfg(feature = "storage")]
+    on_forced_close: Option<crate::storage::ForcedCloseHook>,
+    #[cfg(feature = "watchdog")]
+    timeout: Option<std::time::Duration>,
+    #[cfg(feature = "watchdog")]
+    timed_out: std::rc::Rc<std::cell::Cell<bool>>,
 }
 
 impl DatabaseBuilder {
     /// Creates a new instance of [`DatabaseBuilder`]
     pub fn new(name: &str) -> Self {
         Self {
+            name: name.to_owned(),
             builder: idb::builder::DatabaseBuilder::new(name),
+            version: None,
+            schema: Schema::new(),
+            seeds: Vec::new(),
+            gate: None,
+            #[cfg(feature = "cache")]
+            cache_queries: false,
+            #[cfg(any(
+                feature = "cross-db",
+                feature = "fingerprint",
+                feature = "split",
+                feature = "sync"
+            ))]
+            meta: false,
+            stores: Vec::new(),
+            internal_stores: Vec::new(),
+            #[cfg(feature = "fingerprint")]
+            guard_schema: false,
+            #[cfg(feature = "storage")]
+            on_forced_close: None,
+            #[cfg(feature = "watchdog")]
+            timeout: None,
+            #[cfg(feature = "watchdog")]
+            timed_out: Default::default(),
         }
     }
 
     /// Sets the version of the database
     pub fn version(mut self, version: u32) -> Self {
+        self.version = Some(version);
         self.builder = self.builder.version(version);
         self
     }
@@ -25,16 +83,251 @@ impl DatabaseBuilder {
     where
         M: Model,
     {
-        self.builder = self.builder.add_object_store(M::object_store_builder());
+        let object_store_builder = M::object_store_builder();
+
+        self.schema = self.schema.add_model::<M>();
+
+        self.stores.push(M::store_schema().to_string());
+
+        self.builder = self.builder.add_object_store(object_store_builder);
         self
     }
 
-    /// Builds the [`Database`] instance
+    /// Returns the schema of the models added to the database
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// Registers a callback creating the initial records of a model's object store. The callback is called with the
+    /// typed object store right after the database is opened, only if the object store was created while opening it
+    /// (the generated object store struct can be obtained using `into()`). The records are added in a separate
+    /// transaction once the upgrade is complete.
+    pub fn on_create<M, F>(mut self, seed: F) -> Self
+    where
+        M: Model,
+        F: for<'t> FnOnce(ObjectStore<'t, M>) -> SeedFuture<'t> + 'static,
+    {
+        self.seeds.push(Seed::new::<M, F>(seed));
+        self
+    }
+
+    /// Sets the [`Gate`] consulted before every operation on the object stores of the database. Initial records created
+    /// using [`on_create`](Self::on_create) are not checked.
+    pub fn gate(mut self, gate: impl Gate + 'static) -> Self {
+        self.gate = Some(GateHandle::new(gate));
+        self
+    }
+
+    /// Caches the results of [`ObjectStore::get_all`], so that repeating a query is free until the object store is
+    /// written to. Only writes made through this [`Database`] invalidate the cache: use
+    /// [`Database::clear_query_cache`] when the database is written to by another tab or worker.
+    #[cfg(feature = "cache")]
+    pub fn cache_queries(mut self) -> Self {
+        self.cache_queries = true;
+        self
+    }
+
+    /// Creates the internal stores used for synchronising models with a remote backend (see [`sync`](crate::sync)).
+    #[cfg(feature = "sync")]
+    pub fn enable_sync(mut self) -> Self {
+        self.meta = true;
+        self.add_internal_store(crate::sync::tombstone_store_builder())
+            .add_internal_store(crate::sync::conflict_store_builder())
+    }
+
+    /// Creates the internal store used for journaling writes spanning multiple databases (see
+    /// [`cross_db`](crate::cross_db)). Required on the primary database of a [`CrossDbWrite`](crate::cross_db::CrossDbWrite).
+    #[cfg(feature = "cross-db")]
+    pub fn enable_journal(mut self) -> Self {
+        self.meta = true;
+        self
+    }
+
+    /// Creates the internal store used for keeping the state of store splits (see [`split`](crate::split)).
+    #[cfg(feature = "split")]
+    pub fn enable_store_splits(mut self) -> Self {
+        self.meta = true;
+        self
+    }
+
+    /// Creates the internal store used for tracking the approximate size of records (see [`inspect`](crate::inspect)).
+    #[cfg(feature = "inspect")]
+    pub fn track_sizes(self) -> Self {
+        self.add_internal_store(crate::inspect::object_store_builder())
+    }
+
+    /// Guards against version skew between tabs. The fingerprint of the models' schema is stored when the database is
+    /// opened, and [`build`](Self::build) fails with [`Error::SchemaFingerprintMismatch`] if the database was opened
+    /// with a different schema at the same version (e.g. by a tab running an older deployment of the app which did not
+    /// bump the version).
+    #[cfg(feature = "fingerprint")]
+    pub fn guard_schema(mut self) -> Self {
+        self.meta = true;
+        self.guard_schema = true;
+        self
+    }
+
+    /// Sets a hook called when the connection to the database is closed by the browser rather than by the app, e.g.
+    /// because the browser evicted the database under storage pressure or the user cleared site data (see
+    /// [`storage`](crate::storage)). The [`Database`] can no longer be used once the hook is called.
+    #[cfg(feature = "storage")]
+    pub fn on_forced_close(mut self, hook: impl FnOnce() + 'static) -> Self {
+        self.on_forced_close = Some(crate::storage::ForcedCloseHook::new(hook));
+        self
+    }
+
+    /// Fails [`build`](Self::build) with [`Error::Timeout`] if opening the database (including its upgrade and initial
+    /// records) takes longer than the given timeout, e.g. because an upgrade is blocked by a connection in another tab
+    /// (see [`watchdog`](crate::watchdog)). The open keeps running after the timeout, and its connection is closed once
+    /// it completes.
+    #[cfg(feature = "watchdog")]
+    pub fn timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Builds the [`Database`] instance. Fails with [`Error::SchemaConflicts`] if the schema of the models is invalid
+    /// (see [`Schema::validate`]).
+    ///
+    /// Concurrent builds of the same database (with the same version, models and internal stores) coalesce: the first
+    /// one opens the database and the others share its connection once it is open, so closing one of the returned
+    /// [`Database`]s closes the others. Other concurrent builds of databases with the same name are serialized: a build
+    /// waits until the previous ones are complete (including their upgrades and initial records) before opening the
+    /// database.
     pub async fn build(self) -> Result<Database, Error> {
-        self.builder
-            .build()
-            .await
-            .map(Database::new)
-            .map_err(Into::into)
+        #[cfg(feature = "watchdog")]
+        if let Some(timeout) = self.timeout {
+            let timed_out = self.timed_out.clone();
+            return crate::watchdog::with_timeout_detached(
+                "open",
+                timeout,
+                &timed_out,
+                self.open(),
+            )
+            .await;
+        }
+
+        self.open().await
+    }
+
+    /// Adds an internal object store of `deli` to the database.
+    #[cfg(any(feature = "inspect", feature = "sync"))]
+    fn add_internal_store(mut self, builder: idb::builder::ObjectStoreBuilder) -> Self {
+        self.internal_stores.push(builder.name().to_owned());
+        self.builder = self.builder.add_object_store(builder);
+        self
+    }
+
+    /// Describes the database opened by the builder, so that concurrent builds of the same database share one
+    /// connection. Builds with a hook on the connection (see [`on_forced_close`](Self::on_forced_close)) open their own
+    /// connection.
+    fn signature(&self) -> Option<String> {
+        #[cfg(feature = "storage")]
+        if self.on_forced_close.is_some() {
+            return None;
+        }
+
+        let mut parts = self
+            .stores
+            .iter()
+            .chain(&self.internal_stores)
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+
+        #[cfg(any(
+            feature = "cross-db",
+            feature = "fingerprint",
+            feature = "split",
+            feature = "sync"
+        ))]
+        if self.meta {
+            parts.push(crate::meta::META_STORE_NAME);
+        }
+
+        #[cfg(feature = "fingerprint")]
+        if self.guard_schema {
+            parts.push("guard_schema");
+        }
+
+        parts.sort_unstable();
+
+        Some(format!("{:?}\n{}", self.version, parts.join("\n")))
+    }
+
+    async fn open(self) -> Result<Database, Error> {
+        self.schema.validate()?;
+
+        let guard = match open_lock::begin(&self.name, self.signature().as_deref()).await {
+            open_lock::Begin::Open(guard) => guard,
+            open_lock::Begin::Shared(connection) => {
+                let database = Database::new(connection);
+
+                #[cfg(feature = "cache")]
+                let database = database.with_query_cache(self.cache_queries);
+
+                return Ok(database.with_gate(self.gate));
+            }
+        };
+
+        let builder = self.builder;
+
+        #[cfg(any(
+            feature = "cross-db",
+            feature = "fingerprint",
+            feature = "split",
+            feature = "sync"
+        ))]
+        let builder = if self.meta {
+            builder.add_object_store(crate::meta::object_store_builder())
+        } else {
+            builder
+        };
+
+        let existing_store_names = if self.seeds.is_empty() {
+            Vec::new()
+        } else {
+            seed::existing_store_names(&self.name).await?
+        };
+
+        #[cfg_attr(not(feature = "storage"), allow(unused_mut))]
+        let mut database = builder.build().await?;
+
+        #[cfg(feature = "storage")]
+        if let Some(hook) = self.on_forced_close {
+            hook.register(&mut database);
+        }
+
+        let connection = std::rc::Rc::new(database);
+        let database = Database::new(connection.clone());
+
+        #[cfg(feature = "fingerprint")]
+        if self.guard_schema {
+            let fingerprint = crate::fingerprint::fingerprint(&self.stores);
+
+            if let Err(error) = crate::fingerprint::verify(&database, &fingerprint).await {
+                database.close();
+                return Err(error);
+            }
+        }
+
+        seed::run(&database, self.seeds, &existing_store_names).await?;
+
+        // The caller of an open which timed out no longer waits for it, so its connection is closed instead of shared
+        #[cfg(feature = "watchdog")]
+        if self.timed_out.get() {
+            database.close();
+
+            return Err(Error::Timeout {
+                operation: "open",
+                timeout: self.timeout.unwrap_or_default(),
+            });
+        }
+
+        guard.share(&connection);
+
+        #[cfg(feature = "cache")]
+        let database = database.with_query_cache(self.cache_queries);
+
+        Ok(database.with_gate(self.gate))
     }
 }
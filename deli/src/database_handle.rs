@@ -0,0 +1,78 @@
+use std::{cell::RefCell, fmt, rc::Rc};
+
+use crate::{database::Database, database_builder::DatabaseBuilder, error::Error};
+
+/// Lazily opened connection to a database, which can be kept in a `thread_local` (or any other global) instead of
+/// passing a `&Database` through every layer of an app.
+///
+/// The database is opened on first use and the connection is reused until it is closed, after which it is opened again.
+/// Clones of a handle share the same connection.
+///
+/// ```rust,ignore
+/// thread_local! {
+///     static DATABASE: DatabaseHandle = DatabaseHandle::new(|| {
+///         Database::builder("app").version(1).add_model::<Employee>()
+///     });
+/// }
+///
+/// let database = DATABASE.with(DatabaseHandle::clone).get().await?;
+/// ```
+#[derive(Clone)]
+pub struct DatabaseHandle {
+    builder: Rc<dyn Fn() -> DatabaseBuilder>,
+    database: Rc<RefCell<Option<Rc<Database>>>>,
+}
+
+impl DatabaseHandle {
+    /// Creates a new [`DatabaseHandle`] opening the database built by the given function.
+    pub fn new(builder: impl Fn() -> DatabaseBuilder + 'static) -> Self {
+        Self {
+            builder: Rc::new(builder),
+            database: Default::default(),
+        }
+    }
+
+    /// Returns the connection to the database, opening it if it was not opened yet or was closed.
+    pub async fn get(&self) -> Result<Rc<Database>, Error> {
+        if let Some(database) = self.current() {
+            return Ok(database);
+        }
+
+        let database = (self.builder)().build().await?;
+
+        // Another call may have opened the database in the meantime, in which case its connection is kept so that all
+        // the callers share the same one
+        if let Some(current) = self.current() {
+            database.close();
+            return Ok(current);
+        }
+
+        let database = Rc::new(database);
+        *self.database.borrow_mut() = Some(database.clone());
+
+        Ok(database)
+    }
+
+    /// Closes the connection to the database (if opened). The database is opened again on next use.
+    pub fn close(&self) {
+        if let Some(database) = self.database.borrow_mut().take() {
+            database.close();
+        }
+    }
+
+    fn current(&self) -> Option<Rc<Database>> {
+        self.database
+            .borrow()
+            .as_ref()
+            .filter(|database| !database.is_closed())
+            .cloned()
+    }
+}
+
+impl fmt::Debug for DatabaseHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DatabaseHandle")
+            .field("database", &self.database)
+            .finish_non_exhaustive()
+    }
+}
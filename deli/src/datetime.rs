@@ -0,0 +1,230 @@
+//! Date/time fields stored as JS `Date` objects.
+//!
+//! IndexedDB orders keys by type before comparing their values, and compares strings code unit by code unit. Timestamps
+//! serialized as strings (e.g. `chrono::DateTime` with its default serde format) therefore only sort chronologically
+//! as long as they share the same format and offset. A field annotated with `#[deli(datetime)]` is stored as a `Date`
+//! object instead, which IndexedDB compares by its time value, so that indexes over the field support time-range
+//! queries:
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize, Model)]
+//! struct Event {
+//!     #[deli(auto_increment)]
+//!     id: u32,
+//!     #[deli(index, datetime(unit = "iso8601"))]
+//!     starts_at: chrono::DateTime<chrono::Utc>,
+//! }
+//!
+//! let this_week = Event::with_transaction(&transaction)?
+//!     .by_starts_at()?
+//!     .get_all(&monday..&next_monday, None)
+//!     .await?;
+//! ```
+//!
+//! The field keeps its serde representation in Rust: the unit given using `#[deli(datetime(unit = ".."))]` tells how
+//! the field is serialized, so that it is converted to a `Date` when written and back when read:
+//!
+//! - `millis` (default): milliseconds since the Unix epoch (e.g. `u64`, or `chrono::serde::ts_milliseconds`)
+//! - `seconds`: seconds since the Unix epoch (e.g. `chrono::serde::ts_seconds`)
+//! - `iso8601`: ISO 8601 string (e.g. `chrono::DateTime` or `time::serde::rfc3339`), read back in UTC
+//!
+//! Keys given to queries over indexes containing the field are converted the same way. `Date` objects have millisecond
+//! precision, so finer parts of the timestamp are dropped when stored. Optional fields are left out of the conversion
+//! when they are `None`.
+//!
+//! The field cannot be (part of) the primary key, as keys are read back from the object store as is. Index keys read
+//! using cursors (e.g. [`Index::get_grouped`](crate::Index::get_grouped)) and records exported or synced are `Date`
+//! objects as well, so these are not supported over date/time fields either.
+//!
+//! Fields which are already `js_sys::Date` objects do not need the attribute: they are stored as is when serialized
+//! using `#[serde(with = "serde_wasm_bindgen::preserve")]`.
+use idb::{KeyRange, Query};
+use js_sys::{Array, Date, Object, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::{error::Error, model::Model, model_index::ModelIndex, timestamps::TimestampUnit};
+
+/// Replaces the date/time fields of a serialized record being written with dates.
+pub(crate) fn write_datetime_fields<M>(value: &JsValue) -> Result<(), Error>
+where
+    M: Model,
+{
+    for (name, unit) in M::DATETIME_FIELDS {
+        let field = Reflect::get(value, &JsValue::from_str(name))
+            .map_err(serde_wasm_bindgen::Error::from)?;
+
+        if field.is_undefined() || field.is_null() {
+            continue;
+        }
+
+        let date = to_date(*unit, &field).ok_or(Error::InvalidDateTime {
+            store: M::NAME,
+            field: name,
+        })?;
+
+        Reflect::set(value, &JsValue::from_str(name), &date)
+            .map_err(serde_wasm_bindgen::Error::from)?;
+    }
+
+    Ok(())
+}
+
+/// Returns a copy of a record read from the object store with the dates of its date/time fields converted back to
+/// their serialized form (the stored value is left untouched).
+pub(crate) fn read_datetime_fields<M>(value: &JsValue) -> Result<JsValue, Error>
+where
+    M: Model,
+{
+    if M::DATETIME_FIELDS.is_empty() || !value.is_object() {
+        return Ok(value.clone());
+    }
+
+    #[cfg(feature = "envelope")]
+    if M::ENVELOPE_VERSION.is_some() {
+        let data = JsValue::from_str("data");
+        let envelope = copy(value);
+        let record = read_record::<M>(
+            &Reflect::get(value, &data).map_err(serde_wasm_bindgen::Error::from)?,
+        )?;
+
+        Reflect::set(&envelope, &data, &record).map_err(serde_wasm_bindgen::Error::from)?;
+
+        return Ok(envelope);
+    }
+
+    read_record::<M>(value)
+}
+
+/// Converts the dates of the date/time fields of a record being deserialized back to their serialized form.
+pub(crate) fn read_deserializer<M>(
+    deserializer: serde_wasm_bindgen::Deserializer,
+) -> Result<serde_wasm_bindgen::Deserializer, Error>
+where
+    M: Model,
+{
+    if M::DATETIME_FIELDS.is_empty() {
+        return Ok(deserializer);
+    }
+
+    let value: JsValue = serde_wasm_bindgen::preserve::deserialize(deserializer)?;
+    read_datetime_fields::<M>(&value).map(Into::into)
+}
+
+/// Converts the dates of the date/time fields of a record (not stored in an envelope) to their serialized form.
+fn read_record<M>(value: &JsValue) -> Result<JsValue, Error>
+where
+    M: Model,
+{
+    if !value.is_object() {
+        return Ok(value.clone());
+    }
+
+    let record = copy(value);
+
+    for (name, unit) in M::DATETIME_FIELDS {
+        let name = JsValue::from_str(name);
+        let field = Reflect::get(&record, &name).map_err(serde_wasm_bindgen::Error::from)?;
+
+        if let Some(date) = field.dyn_ref::<Date>() {
+            Reflect::set(&record, &name, &from_date(*unit, date))
+                .map_err(serde_wasm_bindgen::Error::from)?;
+        }
+    }
+
+    Ok(record)
+}
+
+/// Converts the keys of a query over an index to dates where the index key path contains date/time fields.
+pub(crate) fn index_query<I>(query: Query) -> Result<Query, Error>
+where
+    I: ModelIndex,
+{
+    match query {
+        Query::Key(value) => Ok(Query::Key(index_key::<I>(value))),
+        Query::KeyRange(range) => index_range::<I>(range).map(Query::KeyRange),
+    }
+}
+
+/// Converts the bounds of a key range over an index to dates where the index key path contains date/time fields.
+pub(crate) fn index_range<I>(range: KeyRange) -> Result<KeyRange, Error>
+where
+    I: ModelIndex,
+{
+    if I::DATETIME.is_empty() {
+        return Ok(range);
+    }
+
+    let key = index_key::<I>;
+    let lower = range.lower()?;
+    let upper = range.upper()?;
+
+    let range = match (lower.is_undefined(), upper.is_undefined()) {
+        (false, false) => KeyRange::bound(
+            &key(lower),
+            &key(upper),
+            Some(range.lower_open()),
+            Some(range.upper_open()),
+        )?,
+        (false, true) => KeyRange::lower_bound(&key(lower), Some(range.lower_open()))?,
+        (true, false) => KeyRange::upper_bound(&key(upper), Some(range.upper_open()))?,
+        (true, true) => range,
+    };
+
+    Ok(range)
+}
+
+/// Converts the date/time components of an index key to dates (keys which are not valid timestamps are kept as is).
+pub(crate) fn index_key<I>(value: JsValue) -> JsValue
+where
+    I: ModelIndex,
+{
+    if I::DATETIME.is_empty() {
+        return value;
+    }
+
+    let convert = |unit: &Option<TimestampUnit>, value: JsValue| match unit {
+        Some(unit) => to_date(*unit, &value).map_or(value, Into::into),
+        None => value,
+    };
+
+    if let [unit] = I::DATETIME {
+        return convert(unit, value);
+    }
+
+    // Keys of composite indexes are arrays, whose components may be a prefix of the key path
+    let Some(components) = value.dyn_ref::<Array>() else {
+        return value;
+    };
+
+    components
+        .iter()
+        .zip(I::DATETIME)
+        .map(|(component, unit)| convert(unit, component))
+        .collect::<Array>()
+        .into()
+}
+
+/// Converts a serialized timestamp in the given format to a date (`None` if it is not a valid timestamp).
+fn to_date(unit: TimestampUnit, value: &JsValue) -> Option<Date> {
+    let time = match unit {
+        TimestampUnit::Millis => value.as_f64()?,
+        TimestampUnit::Seconds => value.as_f64()? * 1000.0,
+        TimestampUnit::Iso8601 => Date::parse(&value.as_string()?),
+    };
+
+    (!time.is_nan()).then(|| Date::new(&JsValue::from_f64(time)))
+}
+
+/// Converts a date to a serialized timestamp in the given format.
+fn from_date(unit: TimestampUnit, date: &Date) -> JsValue {
+    match unit {
+        TimestampUnit::Millis => JsValue::from_f64(date.get_time()),
+        TimestampUnit::Seconds => JsValue::from_f64((date.get_time() / 1000.0).floor()),
+        TimestampUnit::Iso8601 => date.to_iso_string().into(),
+    }
+}
+
+/// Returns a shallow copy of an object.
+fn copy(value: &JsValue) -> JsValue {
+    Object::assign(&Object::new(), value.unchecked_ref()).into()
+}
@@ -0,0 +1,275 @@
+//! Coalescing of rapid successive updates.
+//!
+//! Writing a record on every change (e.g. autosaving a document on each keystroke) creates a transaction per write.
+//! A [`DebouncedWriter`] keeps the last update of each record instead, and writes the pending updates in a single
+//! transaction at most once per interval:
+//!
+//! ```rust,ignore
+//! let writer = DebouncedWriter::<Document>::new(database.clone(), Duration::from_millis(500))
+//!     .on_error(|error| log::warn!("autosave failed: {error}"));
+//!
+//! // Only the last update of the document is written
+//! for text in ["H", "He", "Hello"] {
+//!     writer.update(Document { id: 1, text: text.to_owned() })?;
+//! }
+//! ```
+//!
+//! Updates are written using [`ObjectStore::update`](crate::ObjectStore::update) by a task spawned using
+//! `wasm_bindgen_futures::spawn_local`. Pending updates can be written right away using
+//! [`flush`](DebouncedWriter::flush) (e.g. before the page is hidden). Updates of a failed flush are kept pending (unless
+//! updated again in the meantime) and written by the next flush, and failures of background writes are reported to the
+//! hook registered using [`on_error`](DebouncedWriter::on_error).
+//!
+//! An [`Autosave`] is bound to a single record, which is the usual shape of an editor autosaving the document being
+//! edited:
+//!
+//! ```rust,ignore
+//! let autosave = Autosave::<Document>::new(database.clone(), 1);
+//!
+//! autosave.set(Document { id: 1, text: "Hello".to_owned() })?;
+//! assert!(autosave.dirty());
+//!
+//! // Before closing the editor
+//! autosave.flush().await?;
+//! ```
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    time::Duration,
+};
+
+use js_sys::JSON;
+
+use serde::Serialize;
+use wasm_bindgen::JsValue;
+
+use crate::{
+    database::Database, error::Error, model::Model, timer::sleep, TransactionResult,
+    JSON_SERIALIZER,
+};
+
+/// Interval at which an [`Autosave`] writes the last value set, unless changed using [`Autosave::interval`].
+pub const DEFAULT_AUTOSAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+type ErrorHook = Box<dyn Fn(&Error)>;
+
+/// Writer coalescing successive updates of the same record and writing them at most once per interval (see
+/// [`debounce`](crate::debounce)). Clones of a writer share the same pending updates.
+pub struct DebouncedWriter<M> {
+    inner: Rc<Inner<M>>,
+}
+
+struct Inner<M> {
+    database: Rc<Database>,
+    interval: Cell<Duration>,
+    pending: RefCell<Vec<(String, M)>>,
+    scheduled: Cell<bool>,
+    on_error: RefCell<Option<ErrorHook>>,
+}
+
+impl<M> Clone for DebouncedWriter<M> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<M> DebouncedWriter<M>
+where
+    M: Model + 'static,
+{
+    /// Creates a writer writing the pending updates to the database at most once per interval.
+    pub fn new(database: Rc<Database>, interval: Duration) -> Self {
+        Self {
+            inner: Rc::new(Inner {
+                database,
+                interval: Cell::new(interval),
+                pending: Default::default(),
+                scheduled: Cell::new(false),
+                on_error: Default::default(),
+            }),
+        }
+    }
+
+    /// Registers the hook called when writing the pending updates in the background fails (replacing any previously
+    /// registered hook).
+    pub fn on_error<F>(self, hook: F) -> Self
+    where
+        F: Fn(&Error) + 'static,
+    {
+        *self.inner.on_error.borrow_mut() = Some(Box::new(hook));
+        self
+    }
+
+    /// Changes the interval at which the pending updates are written (updates queued before are written after the
+    /// previous interval). Clones of the writer share the interval.
+    pub fn interval(self, interval: Duration) -> Self {
+        self.inner.interval.set(interval);
+        self
+    }
+
+    /// Queues an update of a record, replacing the pending update of the record with the same key (if any). The
+    /// pending updates are written once the interval elapses.
+    pub fn update(&self, value: M) -> Result<(), Error> {
+        let key = stringify(&value.serialize_key(&JSON_SERIALIZER)?)?;
+
+        queue(&mut self.inner.pending.borrow_mut(), key, value);
+
+        if !self.inner.scheduled.replace(true) {
+            let writer = self.clone();
+
+            wasm_bindgen_futures::spawn_local(async move {
+                sleep(writer.inner.interval.get()).await;
+                writer.inner.scheduled.set(false);
+
+                if let Err(error) = writer.flush().await {
+                    if let Some(hook) = writer.inner.on_error.borrow().as_ref() {
+                        hook(&error);
+                    }
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of pending updates.
+    pub fn pending(&self) -> usize {
+        self.inner.pending.borrow().len()
+    }
+
+    /// Writes the pending updates in a single transaction right away. If writing fails, the updates stay pending
+    /// (unless updated again in the meantime).
+    pub async fn flush(&self) -> Result<(), Error> {
+        let pending = self.inner.pending.take();
+
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let result = self.write(&pending).await;
+
+        if result.is_err() {
+            // Updates queued while writing are newer than the updates of the failed flush
+            let mut current = self.inner.pending.borrow_mut();
+
+            for (key, value) in std::mem::replace(&mut *current, pending) {
+                queue(&mut current, key, value);
+            }
+        }
+
+        result
+    }
+
+    async fn write(&self, pending: &[(String, M)]) -> Result<(), Error> {
+        let transaction = self
+            .inner
+            .database
+            .transaction()
+            .writable()
+            .with_model::<M>()
+            .build()?;
+        let store = transaction.object_store::<M>()?;
+
+        for (_, value) in pending {
+            store.update(value).await?;
+        }
+
+        if transaction.commit().await? == TransactionResult::Aborted {
+            return Err(Error::TransactionAborted);
+        }
+
+        Ok(())
+    }
+}
+
+/// Autosave of a single record, writing the last value set at most once per interval (see
+/// [`debounce`](crate::debounce)).
+pub struct Autosave<M>
+where
+    M: Model,
+{
+    writer: DebouncedWriter<M>,
+    key: M::Key,
+}
+
+impl<M> Autosave<M>
+where
+    M: Model + 'static,
+{
+    /// Creates an autosave of the record with the given key, writing it at most once per
+    /// [`DEFAULT_AUTOSAVE_INTERVAL`].
+    pub fn new(database: Rc<Database>, key: M::Key) -> Self {
+        Self {
+            writer: DebouncedWriter::new(database, DEFAULT_AUTOSAVE_INTERVAL),
+            key,
+        }
+    }
+
+    /// Changes the interval at which the record is written (values set before are written after the previous interval).
+    pub fn interval(self, interval: Duration) -> Self {
+        Self {
+            writer: self.writer.interval(interval),
+            key: self.key,
+        }
+    }
+
+    /// Registers the hook called when writing the record in the background fails (see
+    /// [`DebouncedWriter::on_error`]).
+    pub fn on_error<F>(self, hook: F) -> Self
+    where
+        F: Fn(&Error) + 'static,
+    {
+        Self {
+            writer: self.writer.on_error(hook),
+            key: self.key,
+        }
+    }
+
+    /// Returns the key of the record.
+    pub fn key(&self) -> &M::Key {
+        &self.key
+    }
+
+    /// Sets the value of the record, which is written once the interval elapses. Fails with
+    /// [`Error::AutosaveKeyMismatch`] if the value has another key than the record.
+    pub fn set(&self, value: M) -> Result<(), Error> {
+        let key = stringify(&self.key.serialize(&JSON_SERIALIZER)?)?;
+
+        if stringify(&value.serialize_key(&JSON_SERIALIZER)?)? != key {
+            return Err(Error::AutosaveKeyMismatch { store: M::NAME });
+        }
+
+        self.writer.update(value)
+    }
+
+    /// Returns `true` if a value was set and is not written yet.
+    pub fn dirty(&self) -> bool {
+        self.writer.pending() > 0
+    }
+
+    /// Writes the last value set right away. If writing fails, the value stays pending (see
+    /// [`DebouncedWriter::flush`]).
+    pub async fn flush(&self) -> Result<(), Error> {
+        self.writer.flush().await
+    }
+}
+
+/// Queues the update of the record with the given key, replacing its pending update (if any).
+fn queue<M>(pending: &mut Vec<(String, M)>, key: String, value: M) {
+    match pending
+        .iter_mut()
+        .find(|(pending_key, _)| *pending_key == key)
+    {
+        Some((_, pending_value)) => *pending_value = value,
+        None => pending.push((key, value)),
+    }
+}
+
+/// Returns the JSON representation of a serialized key, used to compare keys.
+fn stringify(key: &JsValue) -> Result<String, Error> {
+    Ok(JSON::stringify(key)
+        .map_err(serde_wasm_bindgen::Error::from)?
+        .into())
+}
@@ -0,0 +1,66 @@
+//! Fire-and-forget writes.
+//!
+//! Writes whose result is not needed by the caller (e.g. telemetry) can be issued using
+//! [`ObjectStore::add_detached`](crate::ObjectStore::add_detached) and
+//! [`ObjectStore::update_detached`](crate::ObjectStore::update_detached). The request is issued immediately and its
+//! completion is handled in a task spawned using `wasm_bindgen_futures::spawn_local`. Failures are reported to the
+//! hook registered using [`set_error_hook`] (and ignored if no hook is registered).
+//!
+//! Like any other request, a detached write is aborted if its transaction is aborted.
+use std::{cell::RefCell, future::Future, rc::Rc};
+
+use crate::{error::Error, gate::Operation};
+
+type ErrorHook = Rc<dyn Fn(&DetachedError)>;
+
+thread_local! {
+    static ERROR_HOOK: RefCell<Option<ErrorHook>> = const { RefCell::new(None) };
+}
+
+/// Details of a failed detached write passed to the error hook.
+#[derive(Debug)]
+pub struct DetachedError<'a> {
+    /// Name of the object store
+    pub store: &'a str,
+    /// Operation of the write ([`Operation::Add`] or [`Operation::Update`])
+    pub operation: Operation,
+    /// Error of the write
+    pub error: &'a Error,
+}
+
+/// Registers the hook called whenever a detached write fails (replacing any previously registered hook).
+pub fn set_error_hook<F>(hook: F)
+where
+    F: Fn(&DetachedError) + 'static,
+{
+    ERROR_HOOK.with(|current| *current.borrow_mut() = Some(Rc::new(hook)));
+}
+
+/// Removes the registered error hook.
+pub fn clear_error_hook() {
+    ERROR_HOOK.with(|current| *current.borrow_mut() = None);
+}
+
+/// Spawns the completion handling of a detached write, reporting its failure to the error hook.
+pub(crate) fn spawn<F>(store: &'static str, operation: Operation, future: F)
+where
+    F: Future<Output = Result<(), Error>> + 'static,
+{
+    wasm_bindgen_futures::spawn_local(async move {
+        if let Err(error) = future.await {
+            report(store, operation, &error);
+        }
+    });
+}
+
+fn report(store: &str, operation: Operation, error: &Error) {
+    let hook = ERROR_HOOK.with(|current| current.borrow().clone());
+
+    if let Some(hook) = hook {
+        hook(&DetachedError {
+            store,
+            operation,
+            error,
+        });
+    }
+}
@@ -0,0 +1,176 @@
+use idb::Query;
+use serde::Serialize;
+use serde_json::Value;
+use wasm_bindgen::JsValue;
+
+use crate::{
+    cursor::{get_all_keys_stored, get_all_stored},
+    error::Error,
+    gate::Operation,
+    transaction::Transaction,
+    JSON_SERIALIZER,
+};
+
+/// Object store accessed by name, without a [`Model`](crate::Model) describing its records, returned by
+/// [`Transaction::dynamic_store`].
+///
+/// Records and keys are exchanged as `serde_json::Value`s, or as `JsValue`s using the methods suffixed with `_js` (e.g.
+/// for records containing dates or binary data, which have no JSON counterpart). This is meant for stores written by
+/// other libraries or by older versions of the app, e.g. to migrate their records into models:
+///
+/// ```rust,ignore
+/// let transaction = database
+///     .transaction()
+///     .writable()
+///     .with_store("legacy_notes")
+///     .with_model::<Note>()
+///     .build()?;
+///
+/// for record in transaction.dynamic_store("legacy_notes")?.get_all(None).await? {
+///     notes.add(&AddNote { text: record["body"].as_str().unwrap_or_default().to_owned() }).await?;
+/// }
+/// ```
+///
+/// Records are written as given: writing to the store of a model skips the conversions `deli` applies to its records
+/// (e.g. date/time fields, envelopes or encrypted fields).
+#[derive(Debug)]
+pub struct DynStore<'t> {
+    name: String,
+    object_store: idb::ObjectStore,
+    transaction: &'t Transaction,
+}
+
+impl<'t> DynStore<'t> {
+    pub(crate) fn new(
+        name: &str,
+        object_store: idb::ObjectStore,
+        transaction: &'t Transaction,
+    ) -> Self {
+        Self {
+            name: name.to_owned(),
+            object_store,
+            transaction,
+        }
+    }
+
+    /// Returns the name of the object store.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the names of the indexes of the object store.
+    pub fn index_names(&self) -> Vec<String> {
+        self.object_store.index_names()
+    }
+
+    /// Retrieves the record with the given key.
+    pub async fn get(&self, key: &Value) -> Result<Option<Value>, Error> {
+        self.get_js(Query::Key(to_js(key)?))
+            .await?
+            .map(from_js)
+            .transpose()
+    }
+
+    /// Retrieves all the records (up to `limit`), ordered by key.
+    pub async fn get_all(&self, limit: Option<u32>) -> Result<Vec<Value>, Error> {
+        self.get_all_js(None, limit)
+            .await?
+            .into_iter()
+            .map(from_js)
+            .collect()
+    }
+
+    /// Retrieves the keys of all the records (up to `limit`), in order.
+    pub async fn get_all_keys(&self, limit: Option<u32>) -> Result<Vec<Value>, Error> {
+        self.check(Operation::Read)?;
+
+        get_all_keys_stored(self.transaction, &self.object_store, None, limit)
+            .await?
+            .into_iter()
+            .map(from_js)
+            .collect()
+    }
+
+    /// Adds a record and returns its key. The key must be given if the store has no key path.
+    pub async fn add(&self, value: &Value, key: Option<&Value>) -> Result<Value, Error> {
+        let key = key.map(to_js).transpose()?;
+        from_js(self.add_js(&to_js(value)?, key.as_ref()).await?)
+    }
+
+    /// Adds or replaces a record and returns its key. The key must be given if the store has no key path.
+    pub async fn put(&self, value: &Value, key: Option<&Value>) -> Result<Value, Error> {
+        let key = key.map(to_js).transpose()?;
+        from_js(self.put_js(&to_js(value)?, key.as_ref()).await?)
+    }
+
+    /// Deletes the record with the given key.
+    pub async fn delete(&self, key: &Value) -> Result<(), Error> {
+        self.delete_js(Query::Key(to_js(key)?)).await
+    }
+
+    /// Retrieves the number of records.
+    pub async fn count(&self) -> Result<u32, Error> {
+        self.check(Operation::Read)?;
+
+        self.object_store.count(None)?.await.map_err(Into::into)
+    }
+
+    /// Clears all records in the store.
+    pub async fn clear(&self) -> Result<(), Error> {
+        self.check(Operation::Delete)?;
+
+        self.object_store.clear()?.await.map_err(Into::into)
+    }
+
+    /// Retrieves the first record matching the query, as stored.
+    pub async fn get_js(&self, query: Query) -> Result<Option<JsValue>, Error> {
+        self.check(Operation::Read)?;
+
+        self.object_store.get(query)?.await.map_err(Into::into)
+    }
+
+    /// Retrieves the records matching the query (all the records if `None`, up to `limit`), as stored.
+    pub async fn get_all_js(
+        &self,
+        query: Option<Query>,
+        limit: Option<u32>,
+    ) -> Result<Vec<JsValue>, Error> {
+        self.check(Operation::Read)?;
+
+        get_all_stored(self.transaction, &self.object_store, query, limit).await
+    }
+
+    /// Adds a record as is and returns its key.
+    pub async fn add_js(&self, value: &JsValue, key: Option<&JsValue>) -> Result<JsValue, Error> {
+        self.check(Operation::Add)?;
+
+        self.object_store.add(value, key)?.await.map_err(Into::into)
+    }
+
+    /// Adds or replaces a record as is and returns its key.
+    pub async fn put_js(&self, value: &JsValue, key: Option<&JsValue>) -> Result<JsValue, Error> {
+        self.check(Operation::Update)?;
+
+        self.object_store.put(value, key)?.await.map_err(Into::into)
+    }
+
+    /// Deletes the records matching the query.
+    pub async fn delete_js(&self, query: Query) -> Result<(), Error> {
+        self.check(Operation::Delete)?;
+
+        self.object_store.delete(query)?.await.map_err(Into::into)
+    }
+
+    /// Fails with [`Error::AccessDenied`] if the gate of the database does not allow the operation on the store.
+    fn check(&self, operation: Operation) -> Result<(), Error> {
+        self.transaction.check(&self.name, operation)
+    }
+}
+
+fn to_js(value: &Value) -> Result<JsValue, Error> {
+    value.serialize(&JSON_SERIALIZER).map_err(Into::into)
+}
+
+fn from_js(value: JsValue) -> Result<Value, Error> {
+    serde_wasm_bindgen::from_value(value).map_err(Into::into)
+}
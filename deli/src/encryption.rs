@@ -0,0 +1,194 @@
+//! Field level envelope encryption.
+//!
+//! Fields of type [`Encrypted<T>`] (annotated with `#[deli(encrypt)]`) are serialized as an [`Envelope`] produced by
+//! the [`Cipher`] registered using [`set_cipher`], so sensitive values never reach IndexedDB in plain text while the
+//! other fields of the model stay indexable. Encrypted fields cannot be used in keys or regular indexes.
+//!
+//! `deli` does not ship any cryptographic primitives. A [`Cipher`] is expected to generate a fresh data key for every
+//! value, encrypt the value with it and wrap the data key with a key encryption key (e.g. using `SubtleCrypto` or the
+//! `aes-gcm` crate).
+//!
+//! Encrypted (or very long) fields can still be looked up by exact value using a hashed index
+//! (`#[deli(index(hashed))]` or `#[deli(unique(hashed))]`). `deli` stores a deterministic keyed hash of the value
+//! (computed using [`Cipher::hash`]) in a shadow field of the record and indexes the shadow field instead of the value,
+//! so the plain text never appears in the index. The derive macro generates a `by_<field>_hashed(&value)` lookup on the
+//! object store.
+use std::{cell::RefCell, fmt, ops::Deref, rc::Rc};
+
+use js_sys::Reflect;
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+use wasm_bindgen::JsValue;
+
+use crate::{error::Error, model::Model};
+
+thread_local! {
+    static CIPHER: RefCell<Option<Rc<dyn Cipher>>> = const { RefCell::new(None) };
+}
+
+/// Encrypted representation of a value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Envelope {
+    /// Identifier of the key encryption key used to wrap the data key
+    pub key_id: String,
+    /// Data key encrypted with the key encryption key
+    pub wrapped_key: Vec<u8>,
+    /// Nonce (initialization vector) used to encrypt the value
+    pub nonce: Vec<u8>,
+    /// Value encrypted with the data key
+    pub ciphertext: Vec<u8>,
+}
+
+/// Trait for envelope encryption of field values.
+pub trait Cipher {
+    /// Encrypts the plain text with a fresh data key and wraps the data key.
+    fn seal(&self, plaintext: &[u8]) -> Result<Envelope, Error>;
+
+    /// Unwraps the data key of the envelope and decrypts the cipher text.
+    fn open(&self, envelope: &Envelope) -> Result<Vec<u8>, Error>;
+
+    /// Computes a deterministic keyed hash of the plain text (e.g. HMAC-SHA256 with a secret salt) used by hashed
+    /// indexes.
+    fn hash(&self, plaintext: &[u8]) -> Result<Vec<u8>, Error>;
+}
+
+/// Registers the cipher used to encrypt and decrypt [`Encrypted`] fields (replacing any previously registered cipher).
+pub fn set_cipher<C>(cipher: C)
+where
+    C: Cipher + 'static,
+{
+    CIPHER.with(|current| *current.borrow_mut() = Some(Rc::new(cipher)));
+}
+
+/// Removes the registered cipher (reading or writing encrypted fields fails afterwards).
+pub fn clear_cipher() {
+    CIPHER.with(|current| *current.borrow_mut() = None);
+}
+
+fn cipher() -> Result<Rc<dyn Cipher>, Error> {
+    CIPHER
+        .with(|current| current.borrow().clone())
+        .ok_or_else(|| Error::EncryptionFailed("no cipher registered".to_owned()))
+}
+
+/// Returns the hex encoded keyed hash of a value, as stored in the shadow field of a hashed index.
+#[doc(hidden)]
+pub fn hash_value<T>(value: &T) -> Result<String, Error>
+where
+    T: Serialize + ?Sized,
+{
+    let plaintext =
+        serde_json::to_vec(value).map_err(|error| Error::EncryptionFailed(error.to_string()))?;
+
+    Ok(cipher()?
+        .hash(&plaintext)?
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+/// Writes the shadow fields of hashed indexes into a serialized record.
+pub(crate) fn write_hashed_fields<M>(value: &JsValue) -> Result<(), Error>
+where
+    M: Model,
+{
+    if M::HASHED_FIELDS.is_empty() {
+        return Ok(());
+    }
+
+    let model: M = serde_wasm_bindgen::from_value(value.clone())?;
+    write_fields(value, model.hashed_fields()?)
+}
+
+/// Writes the shadow fields of hashed indexes into a serialized value being added to the store.
+pub(crate) fn write_add_hashed_fields<M>(add: &M::Add, value: &JsValue) -> Result<(), Error>
+where
+    M: Model,
+{
+    if M::HASHED_FIELDS.is_empty() {
+        return Ok(());
+    }
+
+    write_fields(value, M::add_hashed_fields(add)?)
+}
+
+fn write_fields(value: &JsValue, fields: Vec<(&'static str, String)>) -> Result<(), Error> {
+    for (name, hash) in fields {
+        Reflect::set(value, &JsValue::from_str(name), &JsValue::from_str(&hash))
+            .map_err(serde_wasm_bindgen::Error::from)?;
+    }
+
+    Ok(())
+}
+
+/// A field value that is encrypted when stored in the database.
+#[derive(Default, Clone, PartialEq, Eq, Hash)]
+pub struct Encrypted<T>(T);
+
+impl<T> Encrypted<T> {
+    /// Wraps a plain text value.
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// Returns the plain text value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Encrypted<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> Deref for Encrypted<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> fmt::Debug for Encrypted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Encrypted(..)")
+    }
+}
+
+impl<T> Serialize for Encrypted<T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let plaintext = serde_json::to_vec(&self.0).map_err(serde::ser::Error::custom)?;
+
+        cipher()
+            .and_then(|cipher| cipher.seal(&plaintext))
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Encrypted<T>
+where
+    T: DeserializeOwned,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let envelope = Envelope::deserialize(deserializer)?;
+
+        let plaintext = cipher()
+            .and_then(|cipher| cipher.open(&envelope))
+            .map_err(serde::de::Error::custom)?;
+
+        serde_json::from_slice(&plaintext)
+            .map(Self)
+            .map_err(serde::de::Error::custom)
+    }
+}
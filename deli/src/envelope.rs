@@ -0,0 +1,277 @@
+//! Versioned envelopes for lazily migrated records.
+//!
+//! Records of a model declared with `#[deli(envelope(version = N))]` are stored as `{ v: N, data: {...} }`, where
+//! `data` is the serialized record (key paths of the model and its indexes point into `data`). Records of older
+//! versions are upgraded when read by the functions registered using `upgrade(from = .., with = "fn")`, which take the
+//! older version of the record (any type deserializable from its data) and return the current one:
+//!
+//! ```rust,ignore
+//! #[derive(Deserialize)]
+//! struct EmployeeV1 {
+//!     id: u32,
+//!     name: String,
+//! }
+//!
+//! fn upgrade_v1(employee: EmployeeV1) -> Employee {
+//!     Employee {
+//!         id: employee.id,
+//!         name: employee.name,
+//!         age: None,
+//!     }
+//! }
+//!
+//! #[derive(Serialize, Deserialize, Model)]
+//! #[deli(envelope(version = 2, upgrade(from = 1, with = "upgrade_v1")))]
+//! struct Employee {
+//!     #[deli(key)]
+//!     id: u32,
+//!     name: String,
+//!     age: Option<u32>,
+//! }
+//! ```
+//!
+//! Upgraded records are stored in the current version the next time they are written, so no database version bump is
+//! needed to change the shape of a model. Reading a record of a version without upgrade function fails with
+//! `Error::UnsupportedEnvelopeVersion`.
+//!
+//! With `#[deli(envelope(version = N, migrate_on_read = "write_back"))]` (see [`MigrateOnRead`]), upgraded records are
+//! also written back in the current version by a transaction spawned in the background, so that migrations are
+//! amortized across normal usage. Records read in the same task are written back in a single transaction, and a record
+//! written again since it was read is left untouched. Write-backs are skipped if the gate of the database denies
+//! updates of the object store, and failed write-backs are ignored (the record is upgraded again the next time it is
+//! read). Upgrade functions must keep the primary key of the record.
+//!
+//! The records read by [`export`](crate::export) are not unwrapped from their envelope, so exports do not support
+//! models stored in an envelope.
+use std::cell::RefCell;
+
+use idb::{KeyPath, Query, TransactionMode};
+use js_sys::{Array, Reflect};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+
+use crate::{
+    error::Error,
+    gate::Operation,
+    model::{serialize_put, Model},
+    transaction::Transaction,
+    JSON_SERIALIZER,
+};
+
+#[doc(hidden)]
+pub use wasm_bindgen::JsValue as Data;
+
+/// Behavior for the records of an older version read from the object store.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MigrateOnRead {
+    /// Records are upgraded in memory, and stored in the current version the next time they are written
+    #[default]
+    InMemory,
+    /// Records are also written back in the current version by a transaction spawned in the background
+    WriteBack,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    v: u32,
+    #[serde(with = "serde_wasm_bindgen::preserve")]
+    data: JsValue,
+}
+
+/// Upgraded record waiting to be written back.
+struct WriteBack {
+    database: idb::Database,
+    store: &'static str,
+    version: u32,
+    value: JsValue,
+    #[cfg(feature = "cache")]
+    query_cache: Option<crate::cache::QueryCache>,
+}
+
+thread_local! {
+    static WRITE_BACKS: RefCell<Vec<WriteBack>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Wraps a serialized record in an envelope of the current version of the model (if the model is stored in an
+/// envelope).
+pub(crate) fn wrap<M>(value: JsValue) -> Result<JsValue, Error>
+where
+    M: Model,
+{
+    let Some(version) = M::ENVELOPE_VERSION else {
+        return Ok(value);
+    };
+
+    Envelope {
+        v: version,
+        data: value,
+    }
+    .serialize(&JSON_SERIALIZER)
+    .map_err(Into::into)
+}
+
+/// Returns the serialized record of a stored value, i.e. the data of its envelope if the model is stored in an envelope.
+#[cfg(feature = "integrity")]
+pub(crate) fn data<M>(value: &JsValue) -> Result<JsValue, Error>
+where
+    M: Model,
+{
+    if M::ENVELOPE_VERSION.is_none() {
+        return Ok(value.clone());
+    }
+
+    Reflect::get(value, &JsValue::from_str("data"))
+        .map_err(|error| serde_wasm_bindgen::Error::from(error).into())
+}
+
+/// Deserializes a record stored in an envelope, upgrading it if it was stored by an older version of the model (and
+/// queuing its write-back if the model migrates on read).
+pub(crate) fn unwrap<M>(
+    transaction: &Transaction,
+    deserializer: serde_wasm_bindgen::Deserializer,
+) -> Result<M, Error>
+where
+    M: Model,
+{
+    let envelope = Envelope::deserialize(deserializer)?;
+
+    if Some(envelope.v) == M::ENVELOPE_VERSION {
+        return serde_wasm_bindgen::from_value(envelope.data).map_err(Into::into);
+    }
+
+    let record = M::upgrade(envelope.v, envelope.data)?;
+
+    // The write-back runs in a readwrite transaction of its own, so only the gate decides whether it is allowed
+    if M::MIGRATE_ON_READ == MigrateOnRead::WriteBack
+        && transaction.allows(M::NAME, Operation::Update)
+    {
+        queue_write_back(transaction, envelope.v, &record)?;
+    }
+
+    Ok(record)
+}
+
+/// Serializes an upgraded record like it is written by `update` and queues its write-back, spawning the task writing
+/// back the queued records if there is none pending.
+fn queue_write_back<M>(transaction: &Transaction, version: u32, record: &M) -> Result<(), Error>
+where
+    M: Model,
+{
+    let value = serialize_put::<M, M>(record)?;
+
+    let spawn = WRITE_BACKS.with(|write_backs| {
+        let mut write_backs = write_backs.borrow_mut();
+        write_backs.push(WriteBack {
+            database: transaction.idb_database(),
+            store: M::NAME,
+            version,
+            value,
+            #[cfg(feature = "cache")]
+            query_cache: transaction.query_cache().map(|(cache, _)| cache.clone()),
+        });
+        write_backs.len() == 1
+    });
+
+    if spawn {
+        wasm_bindgen_futures::spawn_local(flush());
+    }
+
+    Ok(())
+}
+
+/// Writes back the queued records, in one transaction per database.
+async fn flush() {
+    let write_backs = WRITE_BACKS.with(|write_backs| write_backs.take());
+    let mut databases: Vec<Vec<WriteBack>> = Vec::new();
+
+    for write_back in write_backs {
+        let name = write_back.database.name();
+
+        match databases
+            .iter_mut()
+            .find(|write_backs| write_backs[0].database.name() == name)
+        {
+            Some(write_backs) => write_backs.push(write_back),
+            None => databases.push(vec![write_back]),
+        }
+    }
+
+    for write_backs in databases {
+        // Failed write-backs are ignored, the records are upgraded again the next time they are read
+        let _ = write_back(write_backs).await;
+    }
+}
+
+/// Writes back upgraded records of a database which are still stored in the version they were read in.
+async fn write_back(write_backs: Vec<WriteBack>) -> Result<(), Error> {
+    let mut store_names = write_backs
+        .iter()
+        .map(|write_back| write_back.store)
+        .collect::<Vec<_>>();
+    store_names.sort_unstable();
+    store_names.dedup();
+
+    let transaction = write_backs[0]
+        .database
+        .transaction(&store_names, TransactionMode::ReadWrite)?;
+
+    #[cfg(feature = "cache")]
+    let transaction = match write_backs[0].query_cache {
+        Some(ref cache) => cache.track_writes(transaction, cache.begin(), &store_names),
+        None => transaction,
+    };
+
+    for write_back in write_backs.iter() {
+        let object_store = transaction.object_store(write_back.store)?;
+
+        let Some(key) = primary_key(&object_store, &write_back.value)? else {
+            continue;
+        };
+
+        // The record may have been written again (or deleted) since it was read
+        let Some(stored) = object_store.get(Query::Key(key))?.await? else {
+            continue;
+        };
+
+        if serde_wasm_bindgen::from_value::<Envelope>(stored)?.v == write_back.version {
+            object_store.put(&write_back.value, None)?.await?;
+        }
+    }
+
+    transaction.await?;
+
+    Ok(())
+}
+
+/// Returns the primary key of a serialized record using the key path of the object store.
+fn primary_key(object_store: &idb::ObjectStore, value: &JsValue) -> Result<Option<JsValue>, Error> {
+    let key = match object_store.key_path()? {
+        None => return Ok(None),
+        Some(KeyPath::Single(path)) => property(value, &path)?,
+        Some(KeyPath::Array(paths)) => paths
+            .iter()
+            .map(|path| property(value, path))
+            .collect::<Result<Array, _>>()?
+            .into(),
+    };
+
+    Ok(Some(key))
+}
+
+/// Returns the value at a dotted key path.
+fn property(value: &JsValue, path: &str) -> Result<JsValue, Error> {
+    path.split('.').try_fold(value.clone(), |value, property| {
+        Reflect::get(&value, &JsValue::from_str(property))
+            .map_err(|error| serde_wasm_bindgen::Error::from(error).into())
+    })
+}
+
+/// Deserializes the data of an older version of a record and upgrades it using the given function.
+#[doc(hidden)]
+pub fn upgrade_with<T, M, F>(data: Data, upgrade: F) -> Result<M, Error>
+where
+    T: DeserializeOwned,
+    F: FnOnce(T) -> M,
+{
+    Ok(upgrade(serde_wasm_bindgen::from_value(data)?))
+}
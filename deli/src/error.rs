@@ -10,4 +10,117 @@ pub enum Error {
     /// WASM serde error
     #[error("wasm serde error")]
     WasmSerdeError(#[from] serde_wasm_bindgen::Error),
+    /// Transaction was aborted instead of being committed
+    #[error("transaction aborted")]
+    TransactionAborted,
+    /// Transaction was aborted because its [`CancellationToken`](crate::CancellationToken) was cancelled
+    #[error("operation cancelled")]
+    Cancelled,
+    /// Operation on an object store was denied by the gate of the database
+    #[error("{operation:?} operation on object store {store} denied by gate")]
+    AccessDenied {
+        /// Name of the object store
+        store: String,
+        /// Denied operation
+        operation: crate::gate::Operation,
+    },
+    /// Write operation on an object store of a read-only transaction (see
+    /// [`TransactionBuilder::writable`](crate::TransactionBuilder::writable))
+    #[error("{operation:?} operation on object store {store} in a read-only transaction")]
+    ReadOnlyTransaction {
+        /// Name of the object store
+        store: String,
+        /// Write operation
+        operation: crate::gate::Operation,
+    },
+    /// Index does not exist in the opened database, which was created with an older schema. Bump the version of the
+    /// database so that the index is created on upgrade, or opt in to a full scan of the object store (see
+    /// [`query`](crate::query))
+    #[error(
+        "index {index} of object store {store} does not exist, bump the database version to create it"
+    )]
+    IndexMissing {
+        /// Name of the object store
+        store: &'static str,
+        /// Name of the index
+        index: &'static str,
+    },
+    /// Schema contains conflicting object store or index names
+    #[error("schema conflicts: {0:?}")]
+    SchemaConflicts(Vec<crate::schema::SchemaConflict>),
+    /// Record added by [`get_or_insert_with`](crate::ObjectStore::get_or_insert_with) is not stored under the
+    /// requested key (e.g. the key of the added value differs from the requested one)
+    #[error("record added to object store {store} is not stored under the requested key")]
+    InsertedKeyMismatch {
+        /// Name of the object store
+        store: &'static str,
+    },
+    /// Encryption or decryption of a field failed
+    #[cfg(feature = "encryption")]
+    #[error("encryption failed: {0}")]
+    EncryptionFailed(String),
+    /// Database was opened by another version of the app with a different schema but the same version number
+    #[cfg(feature = "fingerprint")]
+    #[error(
+        "schema fingerprint mismatch at version {version}: stored {stored}, current {current}"
+    )]
+    SchemaFingerprintMismatch {
+        /// Version of the database
+        version: u32,
+        /// Fingerprint stored by the app which opened the database first
+        stored: String,
+        /// Fingerprint of the current schema
+        current: String,
+    },
+    /// Request to the sync backend failed
+    #[cfg(feature = "sync")]
+    #[error("sync request failed: {0}")]
+    SyncRequestFailed(String),
+    /// Sync backend responded with an unexpected http status
+    #[cfg(feature = "sync")]
+    #[error("sync request failed with http status {0}")]
+    SyncHttpStatus(u16),
+    /// Record is stored in an envelope of a version without upgrade function (see [`envelope`](crate::envelope))
+    #[cfg(feature = "envelope")]
+    #[error("no upgrade from version {version} of object store {store}")]
+    UnsupportedEnvelopeVersion {
+        /// Name of the object store
+        store: &'static str,
+        /// Version of the envelope of the record
+        version: u32,
+    },
+    /// Record set on an autosave has another key than the record the autosave is bound to (see
+    /// [`Autosave`](crate::debounce::Autosave))
+    #[cfg(feature = "debounce")]
+    #[error("record set on autosave of object store {store} has another key")]
+    AutosaveKeyMismatch {
+        /// Name of the object store
+        store: &'static str,
+    },
+    /// Operation did not complete within the timeout of the watchdog (see [`watchdog`](crate::watchdog))
+    #[cfg(feature = "watchdog")]
+    #[error("{operation} operation timed out after {timeout:?}")]
+    Timeout {
+        /// Name of the operation (e.g. `open`)
+        operation: &'static str,
+        /// Timeout of the operation
+        timeout: std::time::Duration,
+    },
+    /// Value of a date/time field cannot be converted to a date (see [`datetime`](crate::datetime))
+    #[cfg(feature = "datetime")]
+    #[error("field {field} of object store {store} is not a valid date/time")]
+    InvalidDateTime {
+        /// Name of the object store
+        store: &'static str,
+        /// Name of the field
+        field: &'static str,
+    },
+    /// Primary or secondary write of a [`CrossDbWrite`](crate::cross_db::CrossDbWrite) is not set
+    #[cfg(feature = "cross-db")]
+    #[error("{0} write of cross-database write is not set")]
+    CrossDbWriteIncomplete(&'static str),
+    /// Backup archive is of an unsupported format, truncated or corrupted (see [`archive`](crate::archive))
+    #[cfg(feature = "export")]
+    #[error("invalid archive: {0}")]
+    InvalidArchive(String),
 }
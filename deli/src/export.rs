@@ -0,0 +1,234 @@
+//! Export of object stores as JSON dumps.
+//!
+//! [`Database::export`] returns an [`ExportBuilder`] which reads the records of the selected models and returns them as
+//! a serializable [`Dump`]. Fields annotated with `#[deli(redact)]` are replaced with [`REDACTED`] by default, so dumps
+//! can be attached to bug reports without leaking personal data. Use [`ExportBuilder::redact`] to export the actual
+//! values (e.g. for backups).
+//!
+//! The records of all the stores are requested at once, so that IndexedDB reads the stores concurrently instead of one
+//! after the other. For large databases, [`ExportBuilder::stream`] hands over the dump of each store as soon as it is
+//! converted (e.g. to append it to an archive) instead of holding the whole dump in memory, and returns a [`Manifest`]
+//! of the exported stores. Converting the records to JSON runs on the calling thread, so run large exports from a web
+//! worker (opening the database there) to keep the page responsive.
+//!
+//! Backups should use [`ExportBuilder::archive`] instead, whose checksums are validated when importing the backup (see
+//! [`archive`](crate::archive)).
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    archive::Archive,
+    cursor::{get_all_stored, has_get_all},
+    database::Database,
+    error::Error,
+    model::Model,
+};
+
+/// Placeholder replacing the values of redacted fields in exported records.
+pub const REDACTED: &str = "<redacted>";
+
+/// Exported records of a database.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dump {
+    /// Name of the exported database
+    pub database: String,
+    /// Version of the exported database
+    pub version: u32,
+    /// Exported object stores
+    pub stores: Vec<StoreDump>,
+}
+
+/// Exported records of an object store.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoreDump {
+    /// Name of the object store
+    pub name: String,
+    /// Records of the object store (ordered by primary key)
+    pub records: Vec<Value>,
+}
+
+/// Summary of an export streamed using [`ExportBuilder::stream`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Name of the exported database
+    pub database: String,
+    /// Version of the exported database
+    pub version: u32,
+    /// Exported object stores, in the order their dumps were streamed
+    pub stores: Vec<ManifestEntry>,
+}
+
+/// Exported object store listed in a [`Manifest`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Name of the object store
+    pub name: String,
+    /// Number of exported records
+    pub records: usize,
+}
+
+#[derive(Debug)]
+struct ExportedStore {
+    name: &'static str,
+    redacted_fields: &'static [&'static str],
+}
+
+/// Builder for exporting object stores of a database.
+#[derive(Debug)]
+pub struct ExportBuilder<'a> {
+    database: &'a Database,
+    stores: Vec<ExportedStore>,
+    redact: bool,
+}
+
+impl<'a> ExportBuilder<'a> {
+    pub(crate) fn new(database: &'a Database) -> Self {
+        Self {
+            database,
+            stores: Vec::new(),
+            redact: true,
+        }
+    }
+
+    /// Adds a model to the export
+    pub fn with_model<M>(mut self) -> Self
+    where
+        M: Model,
+    {
+        if !self.stores.iter().any(|store| store.name == M::NAME) {
+            self.stores.push(ExportedStore {
+                name: M::NAME,
+                redacted_fields: M::REDACTED_FIELDS,
+            });
+        }
+
+        self
+    }
+
+    /// Sets whether the fields annotated with `#[deli(redact)]` are replaced with [`REDACTED`] (defaults to `true`)
+    pub fn redact(mut self, redact: bool) -> Self {
+        self.redact = redact;
+        self
+    }
+
+    /// Reads the records of all the added models in a single read-only transaction
+    pub async fn build(self) -> Result<Dump, Error> {
+        let mut stores = Vec::with_capacity(self.stores.len());
+        let manifest = self
+            .stream(|store| {
+                stores.push(store);
+                Ok(())
+            })
+            .await?;
+
+        Ok(Dump {
+            database: manifest.database,
+            version: manifest.version,
+            stores,
+        })
+    }
+
+    /// Reads the records of all the added models in a single read-only transaction and returns them as a backup
+    /// [`Archive`] with chunks of at most `chunk_size` records (see [`archive`](crate::archive))
+    pub async fn archive(self, chunk_size: usize) -> Result<Archive, Error> {
+        let redacted = self.redact;
+        let mut stores = Vec::with_capacity(self.stores.len());
+        let manifest = self
+            .stream(|store| {
+                stores.push(store);
+                Ok(())
+            })
+            .await?;
+
+        Ok(Archive::new(redacted, manifest, stores, chunk_size))
+    }
+
+    /// Reads the records of all the added models in a single read-only transaction, passing the dump of each store to
+    /// the sink as soon as its records are converted, and returns the manifest of the export. Errors returned by the
+    /// sink stop the export.
+    pub async fn stream<F>(self, mut sink: F) -> Result<Manifest, Error>
+    where
+        F: FnMut(StoreDump) -> Result<(), Error>,
+    {
+        let transaction = self
+            .stores
+            .iter()
+            .fold(self.database.transaction(), |builder, store| {
+                builder.with_store_name(store.name)
+            })
+            .build()?;
+
+        // All the requests are issued before awaiting any of them, so that the stores are read concurrently (stores are
+        // read one by one using a cursor if `getAll()` is not supported)
+        let requests = self
+            .stores
+            .iter()
+            .map(|store| {
+                if !has_get_all() {
+                    return Ok(None);
+                }
+
+                Ok(Some(
+                    transaction
+                        .idb_object_store(store.name)?
+                        .get_all(None, None)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut entries = Vec::with_capacity(self.stores.len());
+
+        for (store, request) in self.stores.iter().zip(requests) {
+            let records = match request {
+                Some(request) => request.await?,
+                None => {
+                    let object_store = transaction.idb_object_store(store.name)?;
+                    get_all_stored(&transaction, &object_store, None, None).await?
+                }
+            };
+
+            let records = records
+                .into_iter()
+                .map(|record| {
+                    let mut record: Value = serde_wasm_bindgen::from_value(record)?;
+
+                    if self.redact {
+                        redact(&mut record, store.redacted_fields);
+                    }
+
+                    Ok(record)
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            entries.push(ManifestEntry {
+                name: store.name.to_owned(),
+                records: records.len(),
+            });
+
+            sink(StoreDump {
+                name: store.name.to_owned(),
+                records,
+            })?;
+        }
+
+        transaction.done().await?;
+
+        Ok(Manifest {
+            database: self.database.name(),
+            version: self.database.version()?,
+            stores: entries,
+        })
+    }
+}
+
+fn redact(record: &mut Value, fields: &[&str]) {
+    let Value::Object(record) = record else {
+        return;
+    };
+
+    for field in fields {
+        if let Some(value) = record.get_mut(*field) {
+            *value = Value::String(REDACTED.to_owned());
+        }
+    }
+}
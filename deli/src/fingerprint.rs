@@ -0,0 +1,72 @@
+use idb::TransactionResult;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::Database,
+    error::Error,
+    meta::{MetaStore, META_STORE_NAME},
+};
+
+/// Key of the schema fingerprint in the meta store.
+const FINGERPRINT_KEY: &str = "schema/fingerprint";
+
+#[derive(Serialize, Deserialize)]
+struct StoredFingerprint {
+    version: u32,
+    fingerprint: String,
+}
+
+/// Computes the fingerprint (64 bit FNV-1a hash) of the schema from the descriptions of its object stores.
+pub(crate) fn fingerprint(stores: &[String]) -> String {
+    let mut stores = stores.iter().collect::<Vec<_>>();
+    stores.sort();
+
+    let hash = stores
+        .iter()
+        .flat_map(|store| store.bytes().chain([0]))
+        .fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        });
+
+    format!("{hash:016x}")
+}
+
+/// Stores the schema fingerprint of the database, failing if a different fingerprint was stored for the same version.
+pub(crate) async fn verify(database: &Database, fingerprint: &str) -> Result<(), Error> {
+    let version = database.version()?;
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_store_name(META_STORE_NAME)
+        .build()?;
+
+    let meta = MetaStore::new(&transaction)?;
+
+    match meta.get::<StoredFingerprint>(FINGERPRINT_KEY).await? {
+        Some(stored) if stored.version == version && stored.fingerprint != fingerprint => {
+            return Err(Error::SchemaFingerprintMismatch {
+                version,
+                stored: stored.fingerprint,
+                current: fingerprint.to_owned(),
+            });
+        }
+        Some(stored) if stored.version == version => {}
+        _ => {
+            meta.set(
+                FINGERPRINT_KEY,
+                &StoredFingerprint {
+                    version,
+                    fingerprint: fingerprint.to_owned(),
+                },
+            )
+            .await?;
+        }
+    }
+
+    if transaction.commit().await? == TransactionResult::Aborted {
+        return Err(Error::TransactionAborted);
+    }
+
+    Ok(())
+}
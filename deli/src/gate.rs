@@ -0,0 +1,67 @@
+use std::{fmt, rc::Rc};
+
+use crate::error::Error;
+
+/// Guard restricting the operations allowed on object stores at runtime, e.g. to limit which stores the code of a
+/// plugin may write to. It is set using [`DatabaseBuilder::gate`](crate::DatabaseBuilder::gate) and consulted by the
+/// methods of [`ObjectStore`](crate::ObjectStore), [`Index`](crate::Index) and cursors, which fail with
+/// [`Error::AccessDenied`] if the operation is not allowed.
+pub trait Gate {
+    /// Returns `true` if the operation is allowed on the object store with the given name
+    fn allow(&self, store: &str, operation: Operation) -> bool;
+}
+
+impl<F> Gate for F
+where
+    F: Fn(&str, Operation) -> bool,
+{
+    fn allow(&self, store: &str, operation: Operation) -> bool {
+        self(store, operation)
+    }
+}
+
+/// Operation on an object store checked by a [`Gate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    /// Reading records or keys (including counting records)
+    Read,
+    /// Adding new records
+    Add,
+    /// Updating records
+    Update,
+    /// Deleting records
+    Delete,
+}
+
+/// Shared handle to the gate of a database.
+#[derive(Clone)]
+pub(crate) struct GateHandle(Rc<dyn Gate>);
+
+impl GateHandle {
+    pub(crate) fn new(gate: impl Gate + 'static) -> Self {
+        Self(Rc::new(gate))
+    }
+
+    /// Returns `true` if the operation is allowed on the object store.
+    pub(crate) fn allows(&self, store: &str, operation: Operation) -> bool {
+        self.0.allow(store, operation)
+    }
+
+    /// Fails with [`Error::AccessDenied`] if the operation is not allowed on the object store.
+    pub(crate) fn check(&self, store: &str, operation: Operation) -> Result<(), Error> {
+        if self.allows(store, operation) {
+            Ok(())
+        } else {
+            Err(Error::AccessDenied {
+                store: store.to_owned(),
+                operation,
+            })
+        }
+    }
+}
+
+impl fmt::Debug for GateHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("GateHandle").finish_non_exhaustive()
+    }
+}
@@ -1,16 +1,22 @@
-use std::borrow::Borrow;
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+};
 
 use idb::{CursorDirection, Query};
 use serde::Serialize;
 
 use crate::{
-    cursor::Cursor,
+    cursor::{collect_entries, collect_values, has_get_all, Cursor},
     error::Error,
-    key_cursor::KeyCursor,
-    key_range::{BoundedRange, KeyRange, UnboundedRange},
-    model::Model,
+    gate::Operation,
+    key_cursor::{collect_primary_keys, KeyCursor},
+    key_range::{BoundedRange, KeyRange, RangeType, UnboundedRange},
+    lookup::Lookup,
+    model::{decode_stored, read_stored, Model},
     model_index::ModelIndex,
     transaction::Transaction,
+    JSON_SERIALIZER,
 };
 
 /// Provides access to an index in a database.
@@ -39,15 +45,109 @@ where
         key_range: impl Into<KeyRange<'a, Q, BoundedRange>>,
     ) -> Result<Option<I::Model>, Error>
     where
-        I::Key: Borrow<Q>,
+        I: Lookup<Q>,
         Q: Serialize + ?Sized + 'a,
     {
+        self.check()?;
+
         self.index
-            .get(Query::try_from(&key_range.into())?)?
+            .get(self.bounded_query(&key_range.into())?)?
             .await?
-            .map(serde_wasm_bindgen::from_value)
+            .map(read_stored(self.transaction))
+            .transpose()
+    }
+
+    /// Returns the key of the record with the given primary key in the index (`None` if there is no such record), e.g.
+    /// to invalidate the entries of a cache keyed by index key after writing the record. The record is read from the
+    /// object store and the key is computed from its fields as stored, before the read hook of the model (the key of a
+    /// multi entry index is the whole array of keys). Fails for hashed and nested indexes, whose keys are only computed when writing records.
+    pub async fn key_of<'a, Q>(
+        &self,
+        primary_key: impl Into<KeyRange<'a, Q, BoundedRange>>,
+    ) -> Result<Option<I::Key>, Error>
+    where
+        I::Model: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+    {
+        self.check()?;
+
+        let record = self
+            .index
+            .object_store()
+            .get(Query::try_from(&primary_key.into())?)?
+            .await?
+            .map(|value| decode_stored::<I::Model, _>(self.transaction, value))
+            .transpose()?;
+
+        record
+            .map(|record| {
+                let key = I::serialize_key(&record, &JSON_SERIALIZER)?;
+                serde_wasm_bindgen::from_value(key).map_err(Into::into)
+            })
             .transpose()
-            .map_err(Into::into)
+    }
+
+    /// Retrieves the value of the first record matching the given key range in the given direction (e.g. the record
+    /// with the greatest index key for [`Prev`](CursorDirection::Prev)). Only the first record is read.
+    pub async fn get_first<'a, Q>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+        direction: CursorDirection,
+    ) -> Result<Option<I::Model>, Error>
+    where
+        I: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+    {
+        match self.cursor(key_range, Some(direction)).await? {
+            Some(cursor) => cursor.value(),
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieves the value of the record with the smallest index key matching the given key range.
+    pub async fn first<'a, Q>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+    ) -> Result<Option<I::Model>, Error>
+    where
+        I: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+    {
+        self.get_first(key_range, CursorDirection::Next).await
+    }
+
+    /// Retrieves the value of the record with the greatest index key matching the given key range.
+    pub async fn last<'a, Q>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+    ) -> Result<Option<I::Model>, Error>
+    where
+        I: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+    {
+        self.get_first(key_range, CursorDirection::Prev).await
+    }
+
+    /// Returns the smallest index key in the index (`None` if the index is empty).
+    pub async fn min_key(&self) -> Result<Option<I::Key>, Error> {
+        self.edge_key(CursorDirection::Next).await
+    }
+
+    /// Returns the greatest index key in the index (`None` if the index is empty).
+    pub async fn max_key(&self) -> Result<Option<I::Key>, Error> {
+        self.edge_key(CursorDirection::Prev).await
+    }
+
+    /// Returns the first index key of the index in the given direction using a key cursor, so that no value is read.
+    async fn edge_key(&self, direction: CursorDirection) -> Result<Option<I::Key>, Error> {
+        self.check()?;
+
+        match self.index.open_key_cursor(None, Some(direction))?.await? {
+            Some(cursor) => {
+                KeyCursor::<I::Model, I::Key>::new(cursor.into_managed(), self.transaction).key()
+            }
+            None => Ok(None),
+        }
     }
 
     /// Retrieves the key of the first record matching the given key range.
@@ -56,53 +156,311 @@ where
         key_range: impl Into<KeyRange<'a, Q, BoundedRange>>,
     ) -> Result<Option<<I::Model as Model>::Key>, Error>
     where
-        I::Key: Borrow<Q>,
+        I: Lookup<Q>,
         Q: Serialize + ?Sized + 'a,
     {
+        self.check()?;
+
         self.index
-            .get_key(Query::try_from(&key_range.into())?)?
+            .get_key(self.bounded_query(&key_range.into())?)?
             .await?
             .map(serde_wasm_bindgen::from_value)
             .transpose()
             .map_err(Into::into)
     }
 
-    /// Retrieves all the values of the records matching the given key range (up to limit if given).
+    /// Returns `true` if a record matches the given key range. The value of the record is not read.
+    pub async fn exists<'a, Q>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, BoundedRange>>,
+    ) -> Result<bool, Error>
+    where
+        I: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+    {
+        self.check()?;
+
+        self.index
+            .get_key(self.bounded_query(&key_range.into())?)?
+            .await
+            .map(|key| key.is_some())
+            .map_err(Into::into)
+    }
+
+    /// Retrieves all the values of the records matching the given key range (up to limit if given, otherwise up to the
+    /// model's [`DEFAULT_LIMIT`](Model::DEFAULT_LIMIT)). Use [`ordered_get_all`](Self::ordered_get_all) if the order
+    /// of the results matters.
     pub async fn get_all<'a, Q>(
         &self,
         key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
         limit: Option<u32>,
     ) -> Result<Vec<I::Model>, Error>
     where
-        I::Key: Borrow<Q>,
+        I: Lookup<Q>,
         Q: Serialize + ?Sized + 'a,
     {
-        self.index
-            .get_all(<Option<Query>>::try_from(&key_range.into())?, limit)?
+        self.check()?;
+
+        let query = self.query(&key_range.into())?;
+        let limit = limit.or(I::Model::DEFAULT_LIMIT);
+
+        if !has_get_all() {
+            let cursor = self.index.open_cursor(query, None)?.await?;
+            return collect_values(self.transaction, cursor, 0, limit).await;
+        }
+
+        let values = self
+            .index
+            .get_all(query, limit)?
             .await?
             .into_iter()
-            .map(serde_wasm_bindgen::from_value)
-            .collect::<Result<_, _>>()
-            .map_err(Into::into)
+            .map(read_stored(self.transaction))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        #[cfg(feature = "shuffle-get-all")]
+        let values = crate::shuffle::shuffled(values);
+
+        Ok(values)
     }
 
-    /// Retrieves all the keys of the records matching the given key range (up to limit if given).
+    /// Retrieves the values of the records matching the given key range after skipping offset records, in
+    /// index key order (and then primary key order) (up to limit if given, otherwise up to the model's [`DEFAULT_LIMIT`](Model::DEFAULT_LIMIT)). The
+    /// records are skipped using a cursor, so they are not deserialized.
+    pub async fn get_all_with_offset<'a, Q>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+        offset: u32,
+        limit: Option<u32>,
+    ) -> Result<Vec<I::Model>, Error>
+    where
+        I: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+    {
+        if offset == 0 {
+            return self.get_all(key_range, limit).await;
+        }
+
+        self.check()?;
+
+        let cursor = self
+            .index
+            .open_cursor(self.query(&key_range.into())?, None)?
+            .await?;
+        collect_values(
+            self.transaction,
+            cursor,
+            offset,
+            limit.or(I::Model::DEFAULT_LIMIT),
+        )
+        .await
+    }
+
+    /// Retrieves all the values of the records matching the given key range ordered by index key and then by primary
+    /// key (ascending for [`Next`](CursorDirection::Next), descending for [`Prev`](CursorDirection::Prev), the
+    /// `*Unique` directions only return the first record of every index key), up to limit if given, otherwise up to
+    /// the model's [`DEFAULT_LIMIT`](Model::DEFAULT_LIMIT).
+    pub async fn ordered_get_all<'a, Q>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+        direction: CursorDirection,
+        limit: Option<u32>,
+    ) -> Result<Vec<I::Model>, Error>
+    where
+        I: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+    {
+        self.check()?;
+
+        let query = self.query(&key_range.into())?;
+        let limit = limit.or(I::Model::DEFAULT_LIMIT);
+
+        match direction {
+            // `getAll()` returns the records ordered by index key and then by primary key
+            CursorDirection::Next if has_get_all() => self
+                .index
+                .get_all(query, limit)?
+                .await?
+                .into_iter()
+                .map(read_stored(self.transaction))
+                .collect::<Result<_, _>>(),
+            _ => {
+                let cursor = self.index.open_cursor(query, Some(direction))?.await?;
+                collect_values(self.transaction, cursor, 0, limit).await
+            }
+        }
+    }
+
+    /// Retrieves all the keys of the records matching the given key range (up to limit if given, otherwise up to the
+    /// model's [`DEFAULT_LIMIT`](Model::DEFAULT_LIMIT)).
     pub async fn get_all_keys<'a, Q>(
         &self,
         key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
         limit: Option<u32>,
     ) -> Result<Vec<<I::Model as Model>::Key>, Error>
     where
-        I::Key: Borrow<Q>,
+        I: Lookup<Q>,
         Q: Serialize + ?Sized + 'a,
     {
-        self.index
-            .get_all_keys(<Option<Query>>::try_from(&key_range.into())?, limit)?
+        self.check()?;
+
+        let query = self.query(&key_range.into())?;
+        let limit = limit.or(I::Model::DEFAULT_LIMIT);
+
+        if !has_get_all() {
+            let cursor = self.index.open_key_cursor(query, None)?.await?;
+            return collect_primary_keys(self.transaction, cursor, 0, limit).await;
+        }
+
+        let keys = self
+            .index
+            .get_all_keys(query, limit)?
             .await?
             .into_iter()
             .map(serde_wasm_bindgen::from_value)
-            .collect::<Result<_, _>>()
-            .map_err(Into::into)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        #[cfg(feature = "shuffle-get-all")]
+        let keys = crate::shuffle::shuffled(keys);
+
+        Ok(keys)
+    }
+
+    /// Retrieves all the primary keys of the records matching the given key range ordered by index key and then by
+    /// primary key (ascending for [`Next`](CursorDirection::Next), descending for [`Prev`](CursorDirection::Prev), the
+    /// `*Unique` directions only return the first record of every index key), up to limit if given, otherwise up to
+    /// the model's [`DEFAULT_LIMIT`](Model::DEFAULT_LIMIT).
+    pub async fn ordered_get_all_keys<'a, Q>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+        direction: CursorDirection,
+        limit: Option<u32>,
+    ) -> Result<Vec<<I::Model as Model>::Key>, Error>
+    where
+        I: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+    {
+        self.check()?;
+
+        let query = self.query(&key_range.into())?;
+        let limit = limit.or(I::Model::DEFAULT_LIMIT);
+
+        match direction {
+            // `getAllKeys()` returns the primary keys ordered by index key and then by primary key
+            CursorDirection::Next if has_get_all() => self
+                .index
+                .get_all_keys(query, limit)?
+                .await?
+                .into_iter()
+                .map(serde_wasm_bindgen::from_value)
+                .collect::<Result<_, _>>()
+                .map_err(Into::into),
+            _ => {
+                let cursor = self.index.open_key_cursor(query, Some(direction))?.await?;
+                collect_primary_keys(self.transaction, cursor, 0, limit).await
+            }
+        }
+    }
+
+    /// Retrieves the primary keys of the records matching the given key range after skipping offset records, in
+    /// index key order (and then primary key order) (up to limit if given, otherwise up to the model's [`DEFAULT_LIMIT`](Model::DEFAULT_LIMIT)).
+    pub async fn get_all_keys_with_offset<'a, Q>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+        offset: u32,
+        limit: Option<u32>,
+    ) -> Result<Vec<<I::Model as Model>::Key>, Error>
+    where
+        I: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+    {
+        if offset == 0 {
+            return self.get_all_keys(key_range, limit).await;
+        }
+
+        self.check()?;
+
+        let cursor = self
+            .index
+            .open_key_cursor(self.query(&key_range.into())?, None)?
+            .await?;
+        collect_primary_keys(
+            self.transaction,
+            cursor,
+            offset,
+            limit.or(I::Model::DEFAULT_LIMIT),
+        )
+        .await
+    }
+
+    /// Retrieves the records matching the given key range (up to limit if given, otherwise up to the model's
+    /// [`DEFAULT_LIMIT`](Model::DEFAULT_LIMIT)) in a map keyed by primary key.
+    pub async fn get_all_map<'a, Q>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+        limit: Option<u32>,
+    ) -> Result<HashMap<<I::Model as Model>::Key, I::Model>, Error>
+    where
+        I: Lookup<Q>,
+        <I::Model as Model>::Key: Eq + Hash,
+        Q: Serialize + ?Sized + 'a,
+    {
+        self.check()?;
+
+        let query = self.query(&key_range.into())?;
+        let limit = limit.or(I::Model::DEFAULT_LIMIT);
+
+        if !has_get_all() {
+            let cursor = self.index.open_cursor(query, None)?.await?;
+            return Ok(collect_entries(self.transaction, cursor, limit)
+                .await?
+                .into_iter()
+                .collect());
+        }
+
+        // Both requests return the records ordered by index key and then by primary key
+        let keys = self.index.get_all_keys(query.clone(), limit)?;
+        let values = self.index.get_all(query, limit)?;
+
+        keys.await?
+            .into_iter()
+            .zip(values.await?)
+            .map(|(key, value)| {
+                Ok((
+                    serde_wasm_bindgen::from_value(key)?,
+                    read_stored(self.transaction)(value)?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Retrieves the records matching the given key range grouped by the key of the index (up to limit records per key
+    /// if given), in a single pass of a cursor. Records of a multi entry index are part of the group of each of their
+    /// keys. Records beyond the limit of their key are skipped without being deserialized.
+    pub async fn get_grouped<'a, Q>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+        limit_per_key: Option<u32>,
+    ) -> Result<BTreeMap<I::Key, Vec<I::Model>>, Error>
+    where
+        I: Lookup<Q>,
+        I::Key: Ord,
+        Q: Serialize + ?Sized + 'a,
+    {
+        let mut groups = BTreeMap::<I::Key, Vec<I::Model>>::new();
+
+        if let Some(mut cursor) = self.cursor(key_range, None).await? {
+            while let Some(key) = cursor.key()? {
+                let group = groups.entry(key).or_default();
+
+                if limit_per_key.is_none_or(|limit| group.len() < limit as usize) {
+                    group.extend(cursor.value()?);
+                }
+
+                cursor.next::<I::Key>(None).await?;
+            }
+        }
+
+        Ok(groups)
     }
 
     /// Retrieves the number of records matching the given key range.
@@ -111,15 +469,78 @@ where
         key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
     ) -> Result<u32, Error>
     where
-        I::Key: Borrow<Q>,
+        I: Lookup<Q>,
         Q: Serialize + ?Sized + 'a,
     {
+        self.check()?;
+
         self.index
-            .count(<Option<Query>>::try_from(&key_range.into())?)?
+            .count(self.query(&key_range.into())?)?
             .await
             .map_err(Into::into)
     }
 
+    /// Folds the records matching the given key range (in the order of the index) into an accumulator. Records are read
+    /// one by one using a cursor, so that aggregating a large range does not load all of its records at once.
+    pub async fn fold<'a, Q, B, F>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+        init: B,
+        mut f: F,
+    ) -> Result<B, Error>
+    where
+        I: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+        F: FnMut(B, I::Model) -> B,
+    {
+        let mut accumulator = init;
+
+        if let Some(mut cursor) = self.cursor(key_range, None).await? {
+            while let Some((_, value)) = cursor.next_record().await? {
+                accumulator = f(accumulator, value);
+            }
+        }
+
+        Ok(accumulator)
+    }
+
+    /// Returns the sum of the field extracted from the records matching the given key range (see
+    /// [`fold`](Self::fold)).
+    pub async fn sum<'a, Q, F>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+        field: F,
+    ) -> Result<f64, Error>
+    where
+        I: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+        F: Fn(&I::Model) -> f64,
+    {
+        self.fold(key_range, 0.0, |sum, value| sum + field(&value))
+            .await
+    }
+
+    /// Returns the average of the field extracted from the records matching the given key range (`None` if no record
+    /// matches, see [`fold`](Self::fold)).
+    pub async fn average<'a, Q, F>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+        field: F,
+    ) -> Result<Option<f64>, Error>
+    where
+        I: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+        F: Fn(&I::Model) -> f64,
+    {
+        let (sum, count) = self
+            .fold(key_range, (0.0, 0u32), |(sum, count), value| {
+                (sum + field(&value), count + 1)
+            })
+            .await?;
+
+        Ok((count > 0).then(|| sum / f64::from(count)))
+    }
+
     /// Opens a [`Cursor`] over the records matching key range, ordered by direction.
     pub async fn cursor<'a, Q>(
         &self,
@@ -127,19 +548,34 @@ where
         cursor_direction: Option<CursorDirection>,
     ) -> Result<Option<Cursor<'t, I::Model, I::Key>>, Error>
     where
-        I::Key: Borrow<Q>,
+        I: Lookup<Q>,
         Q: Serialize + ?Sized + 'a,
     {
+        self.check()?;
+
         Ok(self
             .index
-            .open_cursor(
-                <Option<Query>>::try_from(&key_range.into())?,
-                cursor_direction,
-            )?
+            .open_cursor(self.query(&key_range.into())?, cursor_direction)?
             .await?
             .map(|cursor| Cursor::new(cursor.into_managed(), self.transaction)))
     }
 
+    /// Returns a stream of the records matching key range, ordered by the key of the index and direction. Records are
+    /// read one by one using a cursor (see [`ObjectStore::stream`](crate::ObjectStore::stream)).
+    #[cfg(feature = "stream")]
+    pub async fn stream<'a, Q>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+        cursor_direction: Option<CursorDirection>,
+    ) -> Result<impl futures_util::Stream<Item = Result<I::Model, Error>> + Unpin + 't, Error>
+    where
+        I: Lookup<Q> + 't,
+        Q: Serialize + ?Sized + 'a,
+    {
+        let cursor = self.cursor(key_range, cursor_direction).await?;
+        Ok(crate::cursor::into_stream(cursor))
+    }
+
     /// Opens a [`KeyCursor`] over the records matching key range, ordered by direction.
     pub async fn key_cursor<'a, Q>(
         &self,
@@ -147,16 +583,43 @@ where
         cursor_direction: Option<CursorDirection>,
     ) -> Result<Option<KeyCursor<'t, I::Model, I::Key>>, Error>
     where
-        I::Key: Borrow<Q>,
+        I: Lookup<Q>,
         Q: Serialize + ?Sized + 'a,
     {
+        self.check()?;
+
         Ok(self
             .index
-            .open_key_cursor(
-                <Option<Query>>::try_from(&key_range.into())?,
-                cursor_direction,
-            )?
+            .open_key_cursor(self.query(&key_range.into())?, cursor_direction)?
             .await?
             .map(|cursor| KeyCursor::new(cursor.into_managed(), self.transaction)))
     }
+
+    /// Fails with [`Error::AccessDenied`] if the gate of the database does not allow reading the store of the index.
+    fn check(&self) -> Result<(), Error> {
+        self.transaction.check(I::Model::NAME, Operation::Read)
+    }
+
+    /// Returns the query over the index for a key range (`None` for the full range), with its bounds converted to
+    /// dates if the index contains date/time fields (see [`datetime`](crate::datetime)).
+    fn query<K, R>(&self, key_range: &KeyRange<'_, K, R>) -> Result<Option<Query>, Error>
+    where
+        K: Serialize + ?Sized,
+        R: RangeType,
+    {
+        let query = <Option<Query>>::try_from(key_range)?;
+
+        #[cfg(feature = "datetime")]
+        let query = query.map(crate::datetime::index_query::<I>).transpose()?;
+
+        Ok(query)
+    }
+
+    /// Returns the query over the index for a bounded key range (see [`query`](Self::query)).
+    fn bounded_query<K>(&self, key_range: &KeyRange<'_, K, BoundedRange>) -> Result<Query, Error>
+    where
+        K: Serialize + ?Sized,
+    {
+        self.query(key_range)?.ok_or(Error::FullKeyRangeNotAllowed)
+    }
 }
@@ -0,0 +1,349 @@
+//! Inspection of the storage used by object stores.
+//!
+//! When the database is created with [`DatabaseBuilder::track_sizes`](crate::DatabaseBuilder::track_sizes), `deli`
+//! records the approximate serialized size of every record written to the database in an internal store. The sizes
+//! can be retrieved using [`ObjectStore::approx_bytes`] and [`ObjectStore::record_sizes`] to find out which model is
+//! using up the storage quota.
+//!
+//! Independently of size tracking, a hook registered using [`set_large_value_hook`] is called whenever a record larger
+//! than the threshold (see [`set_large_value_threshold`]) is written, since oversized values are the main cause of
+//! jank and quota errors.
+//!
+//! [`ObjectStore::sample`] picks random records of a store for spot checks without loading the whole store.
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeSet,
+    rc::Rc,
+};
+
+use idb::{builder::ObjectStoreBuilder, CursorDirection, KeyPath, KeyRange, Query};
+use js_sys::{Array, Math, JSON};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+
+use crate::{
+    cursor::get_all_stored,
+    error::Error,
+    model::{read_stored, Model},
+    object_store::ObjectStore,
+    transaction::Transaction,
+    JSON_SERIALIZER,
+};
+
+/// Name of the internal object store keeping the sizes of records.
+pub(crate) const SIZE_STORE_NAME: &str = "__deli_sizes";
+
+/// Default size (in bytes) above which the large value hook is called.
+pub const DEFAULT_LARGE_VALUE_THRESHOLD: u64 = 1024 * 1024;
+
+type LargeValueHook = Rc<dyn Fn(&LargeValue)>;
+
+thread_local! {
+    static LARGE_VALUE_HOOK: RefCell<Option<LargeValueHook>> = const { RefCell::new(None) };
+    static LARGE_VALUE_THRESHOLD: Cell<u64> = const { Cell::new(DEFAULT_LARGE_VALUE_THRESHOLD) };
+}
+
+/// Details of a record exceeding the large value threshold passed to the large value hook.
+#[derive(Debug)]
+pub struct LargeValue<'a> {
+    /// Name of the object store
+    pub store: &'a str,
+    /// Primary key of the record
+    pub key: &'a JsValue,
+    /// Approximate serialized size of the record (in bytes)
+    pub bytes: u64,
+}
+
+/// Registers the hook called whenever a record larger than the threshold is written (replacing any previously
+/// registered hook).
+pub fn set_large_value_hook<F>(hook: F)
+where
+    F: Fn(&LargeValue) + 'static,
+{
+    LARGE_VALUE_HOOK.with(|current| *current.borrow_mut() = Some(Rc::new(hook)));
+}
+
+/// Removes the registered large value hook.
+pub fn clear_large_value_hook() {
+    LARGE_VALUE_HOOK.with(|current| *current.borrow_mut() = None);
+}
+
+/// Sets the size (in bytes) above which the large value hook is called (defaults to
+/// [`DEFAULT_LARGE_VALUE_THRESHOLD`]).
+pub fn set_large_value_threshold(bytes: u64) {
+    LARGE_VALUE_THRESHOLD.with(|threshold| threshold.set(bytes));
+}
+
+/// Approximate serialized size of a record.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct RecordSize<K> {
+    /// Primary key of the record
+    pub key: K,
+    /// Approximate serialized size of the record (in bytes)
+    pub bytes: u64,
+}
+
+#[derive(Serialize)]
+struct SizeRecord<'a> {
+    store: &'a str,
+    #[serde(with = "serde_wasm_bindgen::preserve")]
+    key: JsValue,
+    bytes: u64,
+}
+
+/// Returns the object store builder for the size store.
+pub(crate) fn object_store_builder() -> ObjectStoreBuilder {
+    ObjectStoreBuilder::new(SIZE_STORE_NAME).key_path(Some(KeyPath::new_array(["store", "key"])))
+}
+
+/// Returns the approximate size of a serialized value (length of its JSON representation).
+pub(crate) fn serialized_size(value: &JsValue) -> Result<u64, Error> {
+    JSON::stringify(value)
+        .map(|json| json.length().into())
+        .map_err(|error| serde_wasm_bindgen::Error::from(error).into())
+}
+
+/// Records the size of a written record (if sizes are tracked in the transaction) and calls the large value hook if
+/// the record exceeds the threshold.
+pub(crate) async fn record<M>(
+    transaction: &Transaction,
+    key: JsValue,
+    value: &JsValue,
+) -> Result<(), Error>
+where
+    M: Model,
+{
+    record_in::<M>(size_store(transaction)?, key, value).await
+}
+
+/// Records the size of a written record in the given size store (if any) and calls the large value hook if the record
+/// exceeds the threshold.
+pub(crate) async fn record_in<M>(
+    object_store: Option<idb::ObjectStore>,
+    key: JsValue,
+    value: &JsValue,
+) -> Result<(), Error>
+where
+    M: Model,
+{
+    let hook = LARGE_VALUE_HOOK.with(|current| current.borrow().clone());
+
+    if hook.is_none() && object_store.is_none() {
+        return Ok(());
+    }
+
+    let bytes = serialized_size(value)?;
+
+    if let Some(hook) = hook {
+        if bytes > LARGE_VALUE_THRESHOLD.with(Cell::get) {
+            hook(&LargeValue {
+                store: M::NAME,
+                key: &key,
+                bytes,
+            });
+        }
+    }
+
+    if let Some(object_store) = object_store {
+        let record = SizeRecord {
+            store: M::NAME,
+            key,
+            bytes,
+        }
+        .serialize(&JSON_SERIALIZER)?;
+
+        object_store.put(&record, None)?.await?;
+    }
+
+    Ok(())
+}
+
+/// Forgets the sizes of the records matching the query (all the records of the model if `None`).
+pub(crate) async fn forget<M>(transaction: &Transaction, query: Option<&Query>) -> Result<(), Error>
+where
+    M: Model,
+{
+    let Some(object_store) = size_store(transaction)? else {
+        return Ok(());
+    };
+
+    let name = JsValue::from_str(M::NAME);
+
+    let query = match query {
+        Some(Query::Key(key)) => Query::Key(Array::of2(&name, key).into()),
+        Some(Query::KeyRange(range)) => {
+            let lower = range.lower()?;
+            let upper = range.upper()?;
+
+            let (lower, lower_open) = if lower.is_undefined() {
+                (Array::of1(&name), false)
+            } else {
+                (Array::of2(&name, &lower), range.lower_open())
+            };
+
+            let (upper, upper_open) = if upper.is_undefined() {
+                (Array::of2(&name, &Array::new()), true)
+            } else {
+                (Array::of2(&name, &upper), range.upper_open())
+            };
+
+            Query::KeyRange(KeyRange::bound(
+                &lower,
+                &upper,
+                Some(lower_open),
+                Some(upper_open),
+            )?)
+        }
+        None => model_range::<M>()?,
+    };
+
+    object_store.delete(query)?.await?;
+
+    Ok(())
+}
+
+/// Returns the size store if sizes are tracked in the transaction.
+pub(crate) fn size_store(transaction: &Transaction) -> Result<Option<idb::ObjectStore>, Error> {
+    if !transaction
+        .store_names()
+        .iter()
+        .any(|name| name == SIZE_STORE_NAME)
+    {
+        return Ok(None);
+    }
+
+    transaction.idb_object_store(SIZE_STORE_NAME).map(Some)
+}
+
+fn model_range<M>() -> Result<Query, Error>
+where
+    M: Model,
+{
+    // Arrays sort after every other key type, so `[name, []]` is an upper bound for all `[name, key]` keys
+    let range = KeyRange::bound(
+        &Array::of1(&JsValue::from_str(M::NAME)),
+        &Array::of2(&JsValue::from_str(M::NAME), &Array::new()),
+        Some(false),
+        Some(true),
+    )?;
+
+    Ok(Query::KeyRange(range))
+}
+
+impl<M> ObjectStore<'_, M>
+where
+    M: Model,
+{
+    /// Returns the approximate serialized size of all the records in the store (in bytes).
+    pub async fn approx_bytes(&self) -> Result<u64, Error> {
+        Ok(self
+            .record_sizes()
+            .await?
+            .iter()
+            .map(|record_size| record_size.bytes)
+            .sum())
+    }
+
+    /// Returns the approximate serialized size of every record in the store, ordered by primary key.
+    pub async fn record_sizes(&self) -> Result<Vec<RecordSize<M::Key>>, Error> {
+        let object_store = size_store(self.transaction())?
+            .ok_or_else(|| idb::Error::ObjectStoreNotFound(JsValue::from_str(SIZE_STORE_NAME)))?;
+
+        get_all_stored(
+            self.transaction(),
+            &object_store,
+            Some(model_range::<M>()?),
+            None,
+        )
+        .await?
+        .into_iter()
+        .map(serde_wasm_bindgen::from_value)
+        .collect::<Result<_, _>>()
+        .map_err(Into::into)
+    }
+
+    /// Picks approximately `n` random records of the store without loading all of them. Numeric keys are sampled by
+    /// probing random keys between the smallest and the largest key (so gaps in the key space bias the sample),
+    /// other keys by advancing a cursor to random positions.
+    pub async fn sample(&self, n: usize) -> Result<Vec<M>, Error> {
+        let object_store = self.as_idb_object_store();
+
+        let Some(first) = object_store.open_key_cursor(None, None)?.await? else {
+            return Ok(Vec::new());
+        };
+        let Some(last) = object_store
+            .open_key_cursor(None, Some(CursorDirection::Prev))?
+            .await?
+        else {
+            return Ok(Vec::new());
+        };
+
+        let (first, last) = (first.key()?, last.key()?);
+
+        let values = match (first.as_f64(), last.as_f64()) {
+            (Some(min), Some(max)) => {
+                let mut keys = Vec::with_capacity(n);
+
+                for _ in 0..n {
+                    let probe = JsValue::from_f64(min + Math::random() * (max - min));
+                    let query = Query::KeyRange(KeyRange::lower_bound(&probe, Some(false))?);
+
+                    if let Some(key) = object_store.get_key(query)?.await? {
+                        keys.extend(key.as_f64());
+                    }
+                }
+
+                keys.sort_by(f64::total_cmp);
+                keys.dedup();
+
+                let mut values = Vec::with_capacity(keys.len());
+
+                for key in keys {
+                    values.extend(
+                        object_store
+                            .get(Query::Key(JsValue::from_f64(key)))?
+                            .await?,
+                    );
+                }
+
+                values
+            }
+            _ => {
+                let count = object_store.count(None)?.await?;
+
+                if count as usize <= n {
+                    get_all_stored(self.transaction(), object_store, None, None).await?
+                } else {
+                    let mut offsets = BTreeSet::new();
+
+                    while offsets.len() < n {
+                        offsets.insert((Math::random() * f64::from(count)) as u32);
+                    }
+
+                    let mut values = Vec::with_capacity(n);
+                    let mut position = 0;
+
+                    if let Some(cursor) = object_store.open_cursor(None, None)?.await? {
+                        let mut cursor = cursor.into_managed();
+
+                        for offset in offsets {
+                            if offset > position {
+                                cursor.advance(offset - position).await?;
+                                position = offset;
+                            }
+
+                            values.extend(cursor.value()?);
+                        }
+                    }
+
+                    values
+                }
+            }
+        };
+
+        values
+            .into_iter()
+            .map(read_stored(self.transaction()))
+            .collect::<Result<_, _>>()
+    }
+}
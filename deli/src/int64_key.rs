@@ -0,0 +1,112 @@
+use std::fmt;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Number of decimal digits of the largest `u64`, to which encoded keys are zero-padded.
+const WIDTH: usize = 20;
+
+/// `u64` key stored as a fixed-width decimal string, so that values above 2<sup>53</sup> keep their precision (numbers
+/// are stored as JS doubles) and keys still sort numerically.
+///
+/// Unlike newtypes deriving [`Key`](crate::Key), it does not implement `Borrow<u64>`: a plain `u64` would be stored as
+/// a number, so lookups must use the key type (e.g. `store.get(U64Key(id))`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct U64Key(pub u64);
+
+/// `i64` key stored as a fixed-width decimal string (offset so that negative values sort first), so that values beyond
+/// ±2<sup>53</sup> keep their precision and keys still sort numerically (see [`U64Key`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct I64Key(pub i64);
+
+impl From<u64> for U64Key {
+    fn from(value: u64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<U64Key> for u64 {
+    fn from(key: U64Key) -> Self {
+        key.0
+    }
+}
+
+impl From<i64> for I64Key {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<I64Key> for i64 {
+    fn from(key: I64Key) -> Self {
+        key.0
+    }
+}
+
+impl fmt::Display for U64Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl fmt::Display for I64Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Serialize for U64Key {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&encode(self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for U64Key {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        decode(deserializer).map(Self)
+    }
+}
+
+impl Serialize for I64Key {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        // Flipping the sign bit maps `i64::MIN..=i64::MAX` onto `0..=u64::MAX` in order
+        serializer.serialize_str(&encode(self.0 as u64 ^ (1 << 63)))
+    }
+}
+
+impl<'de> Deserialize<'de> for I64Key {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        decode(deserializer).map(|value| Self((value ^ (1 << 63)) as i64))
+    }
+}
+
+fn encode(value: u64) -> String {
+    format!("{value:0WIDTH$}")
+}
+
+fn decode<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let encoded = String::deserialize(deserializer)?;
+
+    if encoded.len() != WIDTH {
+        return Err(de::Error::invalid_length(
+            encoded.len(),
+            &"a 20 digit encoded key",
+        ));
+    }
+
+    encoded.parse().map_err(de::Error::custom)
+}
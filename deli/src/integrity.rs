@@ -0,0 +1,352 @@
+//! Integrity checks of object stores.
+//!
+//! [`Database::check_integrity`] scans the object store of a model and reports every record that:
+//!
+//! - cannot be deserialized into the model (e.g. written by an older version of the app),
+//! - shares the value of a unique index with another record,
+//! - references a record that does not exist through a field annotated with `#[deli(references = Model)]`.
+//!
+//! Records which cannot be deserialized can be fixed using [`Database::repair`] with a [`RepairStrategy`], so a single
+//! corrupt record cannot permanently break reads of the whole store.
+use std::fmt;
+
+use idb::{CursorDirection, Query, TransactionResult};
+use js_sys::{Array, Object, Reflect};
+use serde::de::DeserializeOwned;
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::{
+    cursor::index_get_all_keys_stored,
+    database::Database,
+    error::Error,
+    lookup::Lookup,
+    model::{decode_stored, record_serializer, Model},
+};
+
+/// Foreign key reference declared using `#[deli(references = Model)]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reference {
+    /// Name of the referencing field
+    pub field: &'static str,
+    /// Name of the object store of the referenced model
+    pub store: &'static str,
+}
+
+/// Result of an integrity check of an object store.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntegrityReport<K> {
+    /// Name of the checked object store
+    pub store: &'static str,
+    /// Number of checked records
+    pub checked: usize,
+    /// Violations found in the object store (ordered by primary key)
+    pub violations: Vec<Violation<K>>,
+}
+
+impl<K> IntegrityReport<K> {
+    /// Returns `true` if no violations were found
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Record violating an invariant of its object store.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation<K> {
+    /// Primary key of the record
+    pub key: K,
+    /// Violated invariant
+    pub kind: ViolationKind,
+}
+
+/// Invariant violated by a record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// Record cannot be deserialized into the model (contains the deserialization error)
+    Malformed(String),
+    /// Record shares the value of a unique index with other records
+    DuplicateUniqueValue {
+        /// Name of the unique index
+        index: String,
+    },
+    /// Referenced record does not exist
+    DanglingReference {
+        /// Name of the referencing field
+        field: &'static str,
+        /// Name of the object store of the referenced model
+        store: &'static str,
+    },
+}
+
+/// Strategy applied to records which cannot be deserialized into their model.
+pub enum RepairStrategy<M> {
+    /// Deletes the records
+    Drop,
+    /// Fills the fields missing from the records with the values of the given record (e.g. `Backfill(M::default)`),
+    /// records which still cannot be deserialized are deleted
+    Backfill(fn() -> M),
+    /// Repairs the records using the given function (called with the raw record, including its envelope for models
+    /// stored in an envelope), records are deleted if it returns `None`
+    Custom(fn(JsValue) -> Option<M>),
+}
+
+impl<M> fmt::Debug for RepairStrategy<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Drop => f.write_str("Drop"),
+            Self::Backfill(_) => f.write_str("Backfill(..)"),
+            Self::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// Result of repairing an object store.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepairSummary<K> {
+    /// Name of the repaired object store
+    pub store: &'static str,
+    /// Primary keys of the repaired records
+    pub repaired: Vec<K>,
+    /// Primary keys of the deleted records
+    pub dropped: Vec<K>,
+}
+
+/// Scans the object store of a model in a single read-only transaction.
+pub(crate) async fn check<M>(database: &Database) -> Result<IntegrityReport<M::Key>, Error>
+where
+    M: Model,
+{
+    let mut stores = vec![M::NAME];
+
+    for reference in M::REFERENCES {
+        if !stores.contains(&reference.store) {
+            stores.push(reference.store);
+        }
+    }
+
+    let transaction = stores[1..]
+        .iter()
+        .fold(
+            database.transaction().with_model::<M>(),
+            |builder, store| builder.with_store_name(store),
+        )
+        .build()?;
+
+    let object_store = transaction.idb_object_store(M::NAME)?;
+
+    let mut checked = 0;
+    let mut violations = Vec::new();
+
+    if let Some(cursor) = object_store.open_cursor(None, None)?.await? {
+        let mut cursor = cursor.into_managed();
+
+        while let (Some(key), Some(value)) = (cursor.primary_key()?, cursor.value()?) {
+            checked += 1;
+
+            if let Err(error) = decode_stored::<M, _>(&transaction, value.clone()) {
+                violations.push(violation(
+                    key.clone(),
+                    ViolationKind::Malformed(error.to_string()),
+                )?);
+            }
+
+            #[cfg(feature = "envelope")]
+            let data = crate::envelope::data::<M>(&value)?;
+            #[cfg(not(feature = "envelope"))]
+            let data = value;
+
+            for reference in M::REFERENCES {
+                let referenced = transaction.idb_object_store(reference.store)?;
+
+                if !resolves(&referenced, &data, reference.field).await? {
+                    violations.push(violation(
+                        key.clone(),
+                        ViolationKind::DanglingReference {
+                            field: reference.field,
+                            store: reference.store,
+                        },
+                    )?);
+                }
+            }
+
+            cursor.next(None).await?;
+        }
+    }
+
+    for name in object_store.index_names() {
+        let index = object_store.index(&name)?;
+
+        if !index.unique() {
+            continue;
+        }
+
+        let Some(cursor) = index
+            .open_key_cursor(None, Some(CursorDirection::NextUnique))?
+            .await?
+        else {
+            continue;
+        };
+
+        let mut cursor = cursor.into_managed();
+
+        while let Some(value) = cursor.key()? {
+            let keys =
+                index_get_all_keys_stored(&transaction, &index, Some(Query::Key(value)), None)
+                    .await?;
+
+            if keys.len() > 1 {
+                for key in keys {
+                    violations.push(violation(
+                        key,
+                        ViolationKind::DuplicateUniqueValue {
+                            index: name.clone(),
+                        },
+                    )?);
+                }
+            }
+
+            cursor.next(None).await?;
+        }
+    }
+
+    transaction.done().await?;
+
+    Ok(IntegrityReport {
+        store: M::NAME,
+        checked,
+        violations,
+    })
+}
+
+/// Repairs the records of a model which cannot be deserialized in a single read-write transaction.
+pub(crate) async fn repair<M>(
+    database: &Database,
+    strategy: &RepairStrategy<M>,
+) -> Result<RepairSummary<M::Key>, Error>
+where
+    M: Model + Lookup<M::Key>,
+{
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<M>()
+        .build()?;
+    let object_store = transaction.idb_object_store(M::NAME)?;
+
+    let mut malformed = Vec::new();
+
+    if let Some(cursor) = object_store.open_cursor(None, None)?.await? {
+        let mut cursor = cursor.into_managed();
+
+        while let (Some(key), Some(value)) = (cursor.primary_key()?, cursor.value()?) {
+            if decode_stored::<M, _>(&transaction, value.clone()).is_err() {
+                malformed.push((key, value));
+            }
+
+            cursor.next(None).await?;
+        }
+    }
+
+    let store = transaction.object_store::<M>()?;
+
+    let mut repaired = Vec::new();
+    let mut dropped = Vec::new();
+
+    for (key, value) in malformed {
+        let record = match strategy {
+            RepairStrategy::Drop => None,
+            RepairStrategy::Backfill(default) => {
+                #[cfg(feature = "envelope")]
+                let value = crate::envelope::data::<M>(&value)?;
+
+                backfill(&default(), &value)?
+            }
+            RepairStrategy::Custom(repair) => repair(value),
+        };
+
+        let key: M::Key = serde_wasm_bindgen::from_value(key)?;
+
+        match record {
+            Some(record) => {
+                store.update(&record).await?;
+                repaired.push(key);
+            }
+            None => {
+                store.delete(&key).await?;
+                dropped.push(key);
+            }
+        }
+    }
+
+    if transaction.commit().await? == TransactionResult::Aborted {
+        return Err(Error::TransactionAborted);
+    }
+
+    Ok(RepairSummary {
+        store: M::NAME,
+        repaired,
+        dropped,
+    })
+}
+
+/// Fills the fields missing from a raw record with the fields of the default record.
+fn backfill<M>(default: &M, value: &JsValue) -> Result<Option<M>, Error>
+where
+    M: Model,
+{
+    let Some(value) = value.dyn_ref::<Object>() else {
+        return Ok(None);
+    };
+
+    let default = default.serialize(record_serializer::<M>())?;
+    let merged = Object::assign(
+        &Object::assign(&Object::new(), default.unchecked_ref()),
+        value,
+    );
+
+    Ok(serde_wasm_bindgen::from_value(merged.into()).ok())
+}
+
+fn violation<K>(key: JsValue, kind: ViolationKind) -> Result<Violation<K>, Error>
+where
+    K: DeserializeOwned,
+{
+    Ok(Violation {
+        key: serde_wasm_bindgen::from_value(key)?,
+        kind,
+    })
+}
+
+/// Returns `true` if the value of the referencing field (or every element of it, for arrays) is the key of a record in
+/// the referenced store. Missing and `null` values are not references.
+async fn resolves(
+    referenced: &idb::ObjectStore,
+    value: &JsValue,
+    field: &str,
+) -> Result<bool, Error> {
+    let reference =
+        Reflect::get(value, &JsValue::from_str(field)).map_err(serde_wasm_bindgen::Error::from)?;
+
+    if reference.is_undefined() || reference.is_null() {
+        return Ok(true);
+    }
+
+    let keys = if Array::is_array(&reference) {
+        Array::from(&reference).to_vec()
+    } else {
+        vec![reference]
+    };
+
+    for key in keys {
+        // Values which are not valid keys cannot reference anything
+        let Ok(request) = referenced.count(Some(Query::Key(key))) else {
+            return Ok(false);
+        };
+
+        if request.await? == 0 {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
@@ -2,13 +2,20 @@ use std::borrow::Borrow;
 
 use serde::{de::DeserializeOwned, Serialize};
 
-use crate::{error::Error, model::Model, transaction::Transaction, JSON_SERIALIZER};
+use crate::{
+    cursor::prepare_update,
+    error::Error,
+    gate::Operation,
+    model::{Model, StoredValue},
+    transaction::Transaction,
+    JSON_SERIALIZER,
+};
 
 /// Cursor on an object store or index
 #[derive(Debug)]
 pub struct KeyCursor<'t, M, K> {
     cursor: idb::ManagedKeyCursor,
-    _transaction: &'t Transaction,
+    transaction: &'t Transaction,
     _marker: std::marker::PhantomData<(M, K)>,
 }
 
@@ -20,7 +27,7 @@ where
     pub(crate) fn new(cursor: idb::ManagedKeyCursor, transaction: &'t Transaction) -> Self {
         Self {
             cursor,
-            _transaction: transaction,
+            transaction,
             _marker: std::marker::PhantomData,
         }
     }
@@ -50,6 +57,8 @@ where
 
     /// Advances the cursor through the next count records in range.
     pub async fn advance(&mut self, count: u32) -> Result<(), Error> {
+        self.transaction.check_cancelled()?;
+
         self.cursor.advance(count).await.map_err(Into::into)
     }
 
@@ -59,6 +68,8 @@ where
         K: Borrow<Q>,
         Q: Serialize,
     {
+        self.transaction.check_cancelled()?;
+
         let js_value = key.map(|key| key.serialize(&JSON_SERIALIZER)).transpose()?;
         self.cursor
             .next(js_value.as_ref())
@@ -75,6 +86,8 @@ where
         M::Key: Borrow<R>,
         R: Serialize,
     {
+        self.transaction.check_cancelled()?;
+
         let js_key = key.serialize(&JSON_SERIALIZER)?;
         let js_primary_key = primary_key.serialize(&JSON_SERIALIZER)?;
         self.cursor
@@ -83,19 +96,114 @@ where
             .map_err(Into::into)
     }
 
-    /// Updates the value at the current position of the cursor
+    /// Updates the value at the current position of the cursor and returns the record as written (with the write hook
+    /// of the model applied)
     pub async fn update<V>(&mut self, value: &V) -> Result<M, Error>
     where
         M: Borrow<V>,
         V: Serialize,
     {
-        let js_value = value.serialize(&JSON_SERIALIZER)?;
-        let updated_js_value = self.cursor.update(&js_value).await?;
-        serde_wasm_bindgen::from_value(updated_js_value).map_err(Into::into)
+        let (js_value, record) = prepare_update::<M, V>(self.transaction, value)?;
+
+        self.cursor.update(&js_value).await?;
+
+        #[cfg(feature = "inspect")]
+        if let Some(primary_key) = self.cursor.primary_key()? {
+            crate::inspect::record::<M>(self.transaction, primary_key, &js_value).await?;
+        }
+
+        Ok(record)
     }
 
     /// Deletes the value at the current position of the cursor
     pub async fn delete(&mut self) -> Result<(), Error> {
-        self.cursor.delete().await.map_err(Into::into)
+        self.transaction.check(M::NAME, Operation::Delete)?;
+
+        #[cfg(any(feature = "inspect", feature = "sync"))]
+        let primary_key = self.cursor.primary_key()?;
+
+        self.cursor.delete().await?;
+
+        #[cfg(feature = "inspect")]
+        if let Some(ref primary_key) = primary_key {
+            crate::inspect::forget::<M>(
+                self.transaction,
+                Some(&idb::Query::Key(primary_key.clone())),
+            )
+            .await?;
+        }
+
+        #[cfg(feature = "sync")]
+        crate::sync::record_tombstones::<M>(self.transaction, primary_key.into_iter().collect())
+            .await?;
+
+        Ok(())
     }
 }
+
+/// Collects the primary keys of the records visited by a key cursor after skipping offset records (up to limit if
+/// given). Stops with [`Error::Cancelled`] if the transaction is cancelled.
+pub(crate) async fn collect_primary_keys<K>(
+    transaction: &Transaction,
+    cursor: Option<idb::KeyCursor>,
+    offset: u32,
+    limit: Option<u32>,
+) -> Result<Vec<K>, Error>
+where
+    K: DeserializeOwned,
+{
+    collect_keys(transaction, cursor, offset, limit, |key| {
+        serde_wasm_bindgen::from_value(key).map_err(Into::into)
+    })
+    .await
+}
+
+/// Collects the primary keys of the records visited by a key cursor as stored (up to limit if given), e.g. when
+/// `getAllKeys()` is not supported. Stops with [`Error::Cancelled`] if the transaction is cancelled.
+#[cfg(any(feature = "dynamic", feature = "integrity", feature = "sync"))]
+pub(crate) async fn collect_stored_keys(
+    transaction: &Transaction,
+    cursor: Option<idb::KeyCursor>,
+    limit: Option<u32>,
+) -> Result<Vec<StoredValue>, Error> {
+    collect_keys(transaction, cursor, 0, limit, Ok).await
+}
+
+/// Collects the primary keys visited by a key cursor after skipping offset records (up to limit if given), converted
+/// by the function.
+async fn collect_keys<T>(
+    transaction: &Transaction,
+    cursor: Option<idb::KeyCursor>,
+    offset: u32,
+    limit: Option<u32>,
+    read: impl Fn(StoredValue) -> Result<T, Error>,
+) -> Result<Vec<T>, Error> {
+    let mut keys = Vec::new();
+
+    let Some(cursor) = cursor else {
+        return Ok(keys);
+    };
+
+    // A limit of zero means no limit (same as `getAllKeys()`)
+    let limit = limit.filter(|limit| *limit > 0).map(|limit| limit as usize);
+    let mut cursor = cursor.into_managed();
+
+    // `advance()` fails with a count of zero
+    if offset > 0 {
+        transaction.check_cancelled()?;
+        cursor.advance(offset).await?;
+    }
+
+    while let Some(key) = cursor.primary_key()? {
+        keys.push(read(key)?);
+
+        if limit.is_some_and(|limit| keys.len() >= limit) {
+            break;
+        }
+
+        transaction.check_cancelled()?;
+        cursor.next(None).await?;
+    }
+
+    Ok(keys)
+}
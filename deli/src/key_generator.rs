@@ -0,0 +1,135 @@
+//! Primary keys generated by `deli` when adding records.
+//!
+//! Keys auto incremented by IndexedDB are only unique within a database, so records created on different devices get
+//! conflicting keys once synced. A key field annotated with `#[deli(key_generator = "..")]` is instead set to a random
+//! string generated by `deli` when a record is added (and, like auto incremented keys, left out of the add type):
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize, Model)]
+//! struct Note {
+//!     #[deli(key_generator = "uuid")]
+//!     id: String,
+//!     text: String,
+//! }
+//!
+//! let id = Note::with_transaction(&transaction)?
+//!     .add(&AddNote { text: "Hello".to_owned() })
+//!     .await?;
+//! ```
+//!
+//! The available generators are:
+//!
+//! - `uuid`: random UUID (version 4), e.g. `0b5c6a9e-3f2d-4c1b-9a7e-5d4f3c2b1a09`
+//! - `ulid`: ULID, sorting in the order the keys were generated (at millisecond precision)
+//! - `nanoid`: 21 URL-safe characters
+//! - the path of a function returning a `String`, e.g. `key_generator = "crate::ids::next_id"`
+//!
+//! Random values are read from the Web Crypto API.
+use js_sys::{Date, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::{error::Error, model::Model};
+
+/// Generator of the primary keys of a model (see [`key_generator`](crate::key_generator)).
+#[derive(Debug, Clone, Copy)]
+pub enum KeyGenerator {
+    /// Random UUID (version 4)
+    Uuid,
+    /// ULID (48 bit timestamp followed by 80 random bits)
+    Ulid,
+    /// 21 random URL-safe characters
+    Nanoid,
+    /// Custom generator
+    Custom(fn() -> String),
+}
+
+impl KeyGenerator {
+    /// Generates a key.
+    pub fn generate(self) -> Result<String, Error> {
+        match self {
+            KeyGenerator::Uuid => uuid(),
+            KeyGenerator::Ulid => ulid(),
+            KeyGenerator::Nanoid => nanoid(),
+            KeyGenerator::Custom(generate) => Ok(generate()),
+        }
+    }
+}
+
+/// Sets the key of a serialized record being added to a generated key.
+pub(crate) fn write_generated_key<M>(value: &JsValue) -> Result<(), Error>
+where
+    M: Model,
+{
+    if let Some((name, generator)) = M::KEY_GENERATOR {
+        Reflect::set(
+            value,
+            &JsValue::from_str(name),
+            &JsValue::from_str(&generator.generate()?),
+        )
+        .map_err(serde_wasm_bindgen::Error::from)?;
+    }
+
+    Ok(())
+}
+
+fn uuid() -> Result<String, Error> {
+    let mut bytes = random_bytes::<16>()?;
+
+    // Version 4 and RFC 4122 variant
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex = bytes
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+
+    Ok(format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    ))
+}
+
+fn ulid() -> Result<String, Error> {
+    const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+    let timestamp = Date::now() as u128 & ((1 << 48) - 1);
+    let randomness = random_bytes::<10>()?
+        .iter()
+        .fold(0u128, |value, byte| (value << 8) | u128::from(*byte));
+    let value = (timestamp << 80) | randomness;
+
+    // 26 characters of 5 bits each encode the 128 bits of the value
+    Ok((0..26)
+        .rev()
+        .map(|index| char::from(ALPHABET[((value >> (index * 5)) & 0x1f) as usize]))
+        .collect())
+}
+
+fn nanoid() -> Result<String, Error> {
+    const ALPHABET: &[u8; 64] = b"useandom-26T198340PX75pxJACKVERYMINDBUSHWOLF_GQZbfghjklqvwyzrict";
+
+    Ok(random_bytes::<21>()?
+        .iter()
+        .map(|byte| char::from(ALPHABET[usize::from(byte & 63)]))
+        .collect())
+}
+
+/// Returns cryptographically strong random bytes.
+fn random_bytes<const N: usize>() -> Result<[u8; N], Error> {
+    let crypto = Reflect::get(&js_sys::global(), &JsValue::from_str("crypto"))
+        .map_err(serde_wasm_bindgen::Error::from)?
+        .dyn_into::<web_sys::Crypto>()
+        .map_err(serde_wasm_bindgen::Error::from)?;
+
+    let mut bytes = [0; N];
+    crypto
+        .get_random_values_with_u8_array(&mut bytes)
+        .map_err(serde_wasm_bindgen::Error::from)?;
+
+    Ok(bytes)
+}
@@ -1,12 +1,18 @@
 use std::{
     marker::PhantomData,
-    ops::{Range, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive},
+    ops::{
+        Bound, Range, RangeBounds, RangeFrom, RangeFull, RangeInclusive, RangeTo, RangeToInclusive,
+    },
 };
 
 use idb::Query;
 use serde::Serialize;
 
-use crate::{error::Error, JSON_SERIALIZER};
+use crate::{
+    error::Error,
+    int64_key::{I64Key, U64Key},
+    JSON_SERIALIZER,
+};
 
 pub trait Sealed {}
 
@@ -31,7 +37,7 @@ pub struct KeyRange<'a, K: ?Sized, R> {
 }
 
 /// Represents a continuous interval over some data type that is used for keys.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum KeyRangeInner<'a, K: ?Sized, R> {
     Single(&'a K),
     Range(Range<&'a K>),
@@ -40,6 +46,53 @@ pub enum KeyRangeInner<'a, K: ?Sized, R> {
     RangeTo(RangeTo<&'a K>),
     RangeToInclusive(RangeToInclusive<&'a K>),
     RangeFull(RangeFull, PhantomData<R>),
+    Raw(Query),
+    Bounds(Bound<&'a K>, Bound<&'a K>),
+    Owned(Box<K>),
+    OwnedRange(Bound<Box<K>>, Bound<Box<K>>),
+}
+
+impl<'a, K: ?Sized, R> KeyRange<'a, K, R> {
+    /// Creates a key range with the given bounds, which allows combinations not expressible using range syntax (e.g.
+    /// an exclusive lower bound with an inclusive upper bound).
+    pub fn from_bounds(lower: Bound<&'a K>, upper: Bound<&'a K>) -> Self {
+        Self {
+            inner: KeyRangeInner::Bounds(lower, upper),
+        }
+    }
+
+    /// Creates a key range of the keys strictly greater than the given key, e.g. to read the records after the last
+    /// key of a page (keyset pagination).
+    pub fn greater_than(k: &'a K) -> Self {
+        Self::from_bounds(Bound::Excluded(k), Bound::Unbounded)
+    }
+
+    /// Creates a key range of the given key only (`IDBKeyRange.only(key)`, same as passing `&key`).
+    pub fn only(k: &'a K) -> Self {
+        Self {
+            inner: KeyRangeInner::Single(k),
+        }
+    }
+
+    /// Creates a key range of the keys strictly less than the given key (`IDBKeyRange.upperBound(key, true)`, same as
+    /// `..&key`).
+    pub fn all_before(k: &'a K) -> Self {
+        Self {
+            inner: KeyRangeInner::RangeTo(..k),
+        }
+    }
+
+    /// Creates a key range of the keys strictly greater than the given key (`IDBKeyRange.lowerBound(key, true)`, same
+    /// as [`greater_than`](Self::greater_than)).
+    pub fn all_after(k: &'a K) -> Self {
+        Self::greater_than(k)
+    }
+
+    /// Creates a key range from any type implementing [`RangeBounds`] over references to keys (e.g. custom range
+    /// types). Standard range types and pairs of [`Bound`]s can be used as key ranges directly.
+    pub fn from_range_bounds(range: impl RangeBounds<&'a K>) -> Self {
+        Self::from_bounds(range.start_bound().cloned(), range.end_bound().cloned())
+    }
 }
 
 impl<'a, K: ?Sized, R> From<&'a K> for KeyRange<'a, K, R> {
@@ -90,6 +143,13 @@ impl<'a, K: ?Sized, R> From<RangeToInclusive<&'a K>> for KeyRange<'a, K, R> {
     }
 }
 
+/// Same as [`KeyRange::from_bounds`].
+impl<'a, K: ?Sized, R> From<(Bound<&'a K>, Bound<&'a K>)> for KeyRange<'a, K, R> {
+    fn from((lower, upper): (Bound<&'a K>, Bound<&'a K>)) -> Self {
+        Self::from_bounds(lower, upper)
+    }
+}
+
 impl<K: ?Sized> From<RangeFull> for KeyRange<'_, K, UnboundedRange> {
     fn from(_: RangeFull) -> Self {
         Self {
@@ -98,6 +158,122 @@ impl<K: ?Sized> From<RangeFull> for KeyRange<'_, K, UnboundedRange> {
     }
 }
 
+/// Implements conversion of owned keys into key ranges.
+macro_rules! impl_from_owned {
+    ($($ty:ty),+) => {
+        $(
+            impl<R> From<$ty> for KeyRange<'_, $ty, R> {
+                fn from(k: $ty) -> Self {
+                    Self {
+                        inner: KeyRangeInner::Owned(Box::new(k)),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_from_owned!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64, String, U64Key, I64Key);
+
+/// Implements conversion of tuples (e.g. `("math", &2)` or `("math".to_owned(), 2)` for a composite key of `String`
+/// and `u32`) into key ranges.
+macro_rules! impl_from_tuple {
+    ($($param:ident),+) => {
+        impl<$($param),+, R> From<($($param,)+)> for KeyRange<'_, ($($param,)+), R> {
+            fn from(tuple: ($($param,)+)) -> Self {
+                Self {
+                    inner: KeyRangeInner::Owned(Box::new(tuple)),
+                }
+            }
+        }
+    };
+}
+
+impl_from_tuple!(A, B);
+impl_from_tuple!(A, B, C);
+impl_from_tuple!(A, B, C, D);
+impl_from_tuple!(A, B, C, D, E);
+impl_from_tuple!(A, B, C, D, E, F);
+
+/// Range of owned keys, e.g. `OwnedRange(from..=to)` with computed bounds.
+///
+/// Ranges of references (e.g. `&from..=&to`) can be used as key ranges directly. Owned bounds are wrapped, so that the
+/// type of the keys can still be inferred from the ranges of references.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OwnedRange<T>(pub T);
+
+/// Implements conversion of ranges of owned keys into key ranges.
+macro_rules! impl_from_owned_range {
+    ($($range:ident => |$r:ident| $bounds:expr),+ $(,)?) => {
+        $(
+            impl<K, R> From<OwnedRange<$range<K>>> for KeyRange<'_, K, R> {
+                fn from(OwnedRange($r): OwnedRange<$range<K>>) -> Self {
+                    let (lower, upper) = $bounds;
+
+                    Self {
+                        inner: KeyRangeInner::OwnedRange(lower, upper),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_from_owned_range! {
+    Range => |range| (Bound::Included(Box::new(range.start)), Bound::Excluded(Box::new(range.end))),
+    RangeInclusive => |range| {
+        let (start, end) = range.into_inner();
+        (Bound::Included(Box::new(start)), Bound::Included(Box::new(end)))
+    },
+    RangeFrom => |range| (Bound::Included(Box::new(range.start)), Bound::Unbounded),
+    RangeTo => |range| (Bound::Unbounded, Bound::Excluded(Box::new(range.end))),
+    RangeToInclusive => |range| (Bound::Unbounded, Bound::Included(Box::new(range.end))),
+}
+
+/// Implements conversion of ranges of tuples (e.g. `("math", &1)..=("math", &2)` for a composite key of `String` and
+/// `u32`) into key ranges, so that composite keys can be queried by range without wrapping the range in
+/// [`OwnedRange`].
+macro_rules! impl_from_tuple_range {
+    ($($param:ident),+) => {
+        impl_from_tuple_range!(@range Range, $($param),+);
+        impl_from_tuple_range!(@range RangeInclusive, $($param),+);
+        impl_from_tuple_range!(@range RangeFrom, $($param),+);
+        impl_from_tuple_range!(@range RangeTo, $($param),+);
+        impl_from_tuple_range!(@range RangeToInclusive, $($param),+);
+    };
+    (@range $range:ident, $($param:ident),+) => {
+        impl<$($param),+, R> From<$range<($($param,)+)>> for KeyRange<'_, ($($param,)+), R> {
+            fn from(range: $range<($($param,)+)>) -> Self {
+                Self::from(OwnedRange(range))
+            }
+        }
+    };
+}
+
+impl_from_tuple_range!(A, B);
+impl_from_tuple_range!(A, B, C);
+impl_from_tuple_range!(A, B, C, D);
+impl_from_tuple_range!(A, B, C, D, E);
+impl_from_tuple_range!(A, B, C, D, E, F);
+
+/// Raw queries bypass serialization, which allows keys that cannot be created using `serde` (e.g. JS `Date`s).
+impl<K: ?Sized, R> From<Query> for KeyRange<'_, K, R> {
+    fn from(query: Query) -> Self {
+        Self {
+            inner: KeyRangeInner::Raw(query),
+        }
+    }
+}
+
+/// Raw key ranges bypass serialization, which allows bounds that cannot be created using `serde` (e.g. JS `Date`s).
+impl<K: ?Sized, R> From<idb::KeyRange> for KeyRange<'_, K, R> {
+    fn from(range: idb::KeyRange) -> Self {
+        Self {
+            inner: KeyRangeInner::Raw(Query::KeyRange(range)),
+        }
+    }
+}
+
 impl<'a, K: ?Sized, R> TryFrom<&KeyRange<'a, K, R>> for Option<Query>
 where
     K: Serialize,
@@ -158,6 +334,16 @@ where
                 )?)))
             }
             KeyRangeInner::RangeFull(_, _) => Ok(None),
+            KeyRangeInner::Raw(query) => Ok(Some(query.clone())),
+            KeyRangeInner::Owned(k) => {
+                let js_value = k.serialize(&JSON_SERIALIZER)?;
+                Ok(Some(Query::Key(js_value)))
+            }
+            KeyRangeInner::Bounds(lower, upper) => bounds_query(*lower, *upper),
+            KeyRangeInner::OwnedRange(lower, upper) => bounds_query(
+                lower.as_ref().map(Box::as_ref),
+                upper.as_ref().map(Box::as_ref),
+            ),
         }
     }
 }
@@ -169,6 +355,36 @@ where
     type Error = Error;
 
     fn try_from(value: &KeyRange<'a, K, BoundedRange>) -> Result<Self, Self::Error> {
-        Ok(<Option<Query>>::try_from(value)?.unwrap())
+        <Option<Query>>::try_from(value)?.ok_or(Error::FullKeyRangeNotAllowed)
     }
 }
+
+/// Returns the query for a key range with the given bounds (`None` if both are unbounded).
+fn bounds_query<K>(lower: Bound<&K>, upper: Bound<&K>) -> Result<Option<Query>, Error>
+where
+    K: Serialize + ?Sized,
+{
+    let lower_open = matches!(lower, Bound::Excluded(_));
+    let upper_open = matches!(upper, Bound::Excluded(_));
+
+    let range = match (lower, upper) {
+        (
+            Bound::Included(lower) | Bound::Excluded(lower),
+            Bound::Included(upper) | Bound::Excluded(upper),
+        ) => idb::KeyRange::bound(
+            &lower.serialize(&JSON_SERIALIZER)?,
+            &upper.serialize(&JSON_SERIALIZER)?,
+            Some(lower_open),
+            Some(upper_open),
+        )?,
+        (Bound::Included(lower) | Bound::Excluded(lower), Bound::Unbounded) => {
+            idb::KeyRange::lower_bound(&lower.serialize(&JSON_SERIALIZER)?, Some(lower_open))?
+        }
+        (Bound::Unbounded, Bound::Included(upper) | Bound::Excluded(upper)) => {
+            idb::KeyRange::upper_bound(&upper.serialize(&JSON_SERIALIZER)?, Some(upper_open))?
+        }
+        (Bound::Unbounded, Bound::Unbounded) => return Ok(None),
+    };
+
+    Ok(Some(Query::KeyRange(range)))
+}
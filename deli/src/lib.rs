@@ -85,11 +85,48 @@
 //! ```
 //!
 //! By default, the object store name is the lowercase version of the struct name (`employee`). The object store struct
-//! name is the struct name followed by `Store` (`EmployeeStore`).
+//! name is the struct name followed by `ObjectStore` (`EmployeeObjectStore`).
+//!
+//! The generated object store struct dereferences to [`ObjectStore`], so you can add domain specific queries to it in
+//! your own `impl` blocks:
+//!
+//! ```rust
+//! use deli::{Error, Model};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, Model)]
+//! pub struct Employee {
+//!     #[deli(auto_increment)]
+//!     id: u32,
+//!     name: String,
+//!     #[deli(index)]
+//!     age: u32,
+//! }
+//!
+//! impl EmployeeObjectStore<'_> {
+//!     pub async fn adults(&self) -> Result<Vec<Employee>, Error> {
+//!         self.by_age()?.get_all(&18.., None).await
+//!     }
+//! }
+//! ```
 //!
 //! To use the generated object store, you need to create a database as follows:
 //!
 //! ```rust
+//! # use deli::Model;
+//! # use serde::{Deserialize, Serialize};
+//! #
+//! # #[derive(Serialize, Deserialize, Model)]
+//! # pub struct Employee {
+//! #     #[deli(auto_increment)]
+//! #     id: u32,
+//! #     name: String,
+//! #     #[deli(unique)]
+//! #     email: String,
+//! #     #[deli(index)]
+//! #     age: u32,
+//! # }
+//! #
 //! use deli::{Database, Error};
 //!
 //! async fn create_database() -> Result<Database, Error> {
@@ -104,6 +141,20 @@
 //! Next, you'll need to begin a transaction to interact with the object store:
 //!
 //! ```rust
+//! # use deli::Model;
+//! # use serde::{Deserialize, Serialize};
+//! #
+//! # #[derive(Serialize, Deserialize, Model)]
+//! # pub struct Employee {
+//! #     #[deli(auto_increment)]
+//! #     id: u32,
+//! #     name: String,
+//! #     #[deli(unique)]
+//! #     email: String,
+//! #     #[deli(index)]
+//! #     age: u32,
+//! # }
+//! #
 //! use deli::{Database, Error, Transaction};
 //!
 //! fn create_read_transaction(database: &Database) -> Result<Transaction, Error> {
@@ -122,9 +173,26 @@
 //! }
 //! ```
 //!
+//! Writing to an object store of a read-only transaction fails with [`Error::ReadOnlyTransaction`] before reaching
+//! IndexedDB. Use [`TransactionBuilder::writable_if`] when whether the transaction writes is only known at runtime.
+//!
 //! To add a record in the object store:
 //!
 //! ```rust
+//! # use deli::Model;
+//! # use serde::{Deserialize, Serialize};
+//! #
+//! # #[derive(Serialize, Deserialize, Model)]
+//! # pub struct Employee {
+//! #     #[deli(auto_increment)]
+//! #     id: u32,
+//! #     name: String,
+//! #     #[deli(unique)]
+//! #     email: String,
+//! #     #[deli(index)]
+//! #     age: u32,
+//! # }
+//! #
 //! use deli::{Error, Transaction};
 //!
 //! async fn add_employee(transaction: &Transaction, employee: &AddEmployee) -> Result<u32, Error> {
@@ -164,6 +232,20 @@
 //! To query records from the object store:
 //!
 //! ```rust
+//! # use deli::Model;
+//! # use serde::{Deserialize, Serialize};
+//! #
+//! # #[derive(Serialize, Deserialize, Model)]
+//! # pub struct Employee {
+//! #     #[deli(auto_increment)]
+//! #     id: u32,
+//! #     name: String,
+//! #     #[deli(unique)]
+//! #     email: String,
+//! #     #[deli(index)]
+//! #     age: u32,
+//! # }
+//! #
 //! use deli::{Error, Transaction};
 //!
 //! async fn get_employee(transaction: &Transaction, id: u32) -> Result<Option<Employee>, Error> {
@@ -184,12 +266,43 @@
 //! }
 //! ```
 //!
+//! Keys of primitive types and `String`s can also be given by value (e.g. `get(id)`), and ranges of owned keys by
+//! wrapping them in [`OwnedRange`] (e.g. `get_all(OwnedRange(from_id..=to_id), None)`).
+//!
+//! Ranges which cannot be written using range syntax (e.g. excluding the lower bound and including the upper bound)
+//! can be created from a pair of [`Bound`](std::ops::Bound)s, e.g. `get_all((Bound::Excluded(&from_id),
+//! Bound::Included(&to_id)), None)` or [`KeyRange::from_bounds`], and [`KeyRange::greater_than`] selects the keys after
+//! a given key (e.g. the last key of the previous page). Custom range types implementing
+//! [`RangeBounds`](std::ops::RangeBounds) over references to keys can be converted using
+//! [`KeyRange::from_range_bounds`].
+//!
+//! For readers used to `IDBKeyRange`, the named constructors [`KeyRange::only`], [`KeyRange::all_before`] and
+//! [`KeyRange::all_after`] are equivalent to `&key`, `..&key` and [`KeyRange::greater_than`] respectively.
+//!
+//! To bound queries which accidentally return every record of a large store, a default limit can be set on the model
+//! using `#[deli(default_limit = 100)]`. It is applied to `get_all` and `get_all_keys` queries on the object store and
+//! its indexes unless a limit is given explicitly.
+//!
+//! Records written by older versions of an app can be patched when they are read using `#[deli(on_read = "fn")]`. The
+//! function is called with `&mut` of every record read using `get`, `get_all`, `ordered_get_all` and cursors (after
+//! deserialization), e.g. to normalize legacy values or to fill computed fields.
+//!
+//! Similarly, `#[deli(on_write = "fn")]` is called with `&mut` of every record written using `add`, `update` and
+//! cursor updates (before serialization), so that stored values are always in canonical form (e.g. trimmed strings or
+//! lowercase emails). Auto-incrementing keys are set to their default value while the function is called on added
+//! records.
+//!
+//! The order of `get_all` results should not be relied upon. Use `ordered_get_all` with an explicit
+//! [`CursorDirection`] to get the records in key order. With the `shuffle-get-all` feature enabled,
+//! [`shuffle::shuffle_results`] makes `get_all` and `get_all_keys` return their results in random order, so tests catch
+//! code which accidentally relies on it.
+//!
 //! After all the operations are done, you can commit the transaction:
 //!
 //! ```rust
-//! use deli::{Error, Transaction};
+//! use deli::{Error, Transaction, TransactionResult};
 //!
-//! async fn commit_transaction(transaction: Transaction) -> Result<(), Error> {
+//! async fn commit_transaction(transaction: Transaction) -> Result<TransactionResult, Error> {
 //!     transaction.commit().await
 //! }
 //! ```
@@ -200,6 +313,23 @@
 //! Also, be careful when using long-lived indexed db transactions as the behavior may change depending on the browser.
 //! For example, the transaction may get auto-committed when doing IO (network request) in the event loop.
 //!
+//! Initial records of an object store can be created using [`DatabaseBuilder::on_create`], which is called with the
+//! typed object store only when the object store is created while opening the database:
+//!
+//! ```rust,ignore
+//! let database = Database::builder("app")
+//!     .version(1)
+//!     .add_model::<Setting>()
+//!     .on_create::<Setting, _>(|store| {
+//!         Box::pin(async move {
+//!             store.add(&Setting::default()).await?;
+//!             Ok(())
+//!         })
+//!     })
+//!     .build()
+//!     .await?;
+//! ```
+//!
 //! ## Primary keys
 //!
 //! In IndexedDB, each object store must have a primary key. `deli` supports three types of primary keys:
@@ -258,6 +388,79 @@
 //! }
 //! ```
 //!
+//! Composite keys can be looked up using tuples of references to borrowed values, e.g. `store.get((&1, "alice"))`
+//! for the key above, without constructing owned `String`s. Ranges of such tuples query composite keys (and
+//! composite indexes) by range, e.g. `(&1, "a")..=(&1, "b")`.
+//!
+//! ### Using newtypes as primary keys
+//!
+//! Newtypes serialized as their inner value (`#[serde(transparent)]`) can be used as keys by deriving `Key` with the
+//! `#[deli(transparent)]` attribute. This implements `Borrow` of the inner value (and of `str` for `String` values), so
+//! lookups accept either the newtype or its inner value.
+//!
+//! ```rust
+//! use deli::{Key, Model};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, Key)]
+//! #[serde(transparent)]
+//! #[deli(transparent)]
+//! pub struct EmployeeId(String);
+//!
+//! #[derive(Serialize, Deserialize, Model)]
+//! pub struct Employee {
+//!     #[deli(key)]
+//!     id: EmployeeId,
+//! }
+//! ```
+//!
+//! With this, `store.get(&EmployeeId("alice".to_owned()))`, `store.get(&"alice".to_owned())` and `store.get("alice")`
+//! are all valid lookups.
+//!
+//! ### 64-bit integer keys
+//!
+//! Numbers are stored as JS doubles, so `u64` and `i64` keys above 2<sup>53</sup> silently lose precision. Use
+//! [`U64Key`] or [`I64Key`] as the type of such keys instead: they are stored as fixed-width decimal strings, which keep
+//! every value exact and sort in numeric order.
+//!
+//! ### Generated keys
+//!
+//! With the `key-generator` feature enabled, `#[deli(key_generator = "uuid")]` on a `String` key field makes `deli`
+//! generate the key of each record being added (`ulid`, `nanoid` and the path of a custom function are also
+//! supported). Unlike auto incremented keys, generated keys are unique across devices, so records can be created
+//! offline and synced later (see [`key_generator`] for details).
+//!
+//! A key generator can also be set on one component of a composite key (e.g. `#[deli(key(tenant, id))]` with a
+//! generated `id`), which is then the only component left out of the add type. Auto incremented components are not
+//! supported, since IndexedDB only auto increments single key paths.
+//!
+//! ### External keys
+//!
+//! Records without a natural key can be stored with a key outside of the record (an object store without key path),
+//! whose type is given using the `#[deli(external_key = "..")]` attribute. The key is given when writing records using
+//! [`ObjectStore::add_with_key`] and [`ObjectStore::put_with_key`].
+//!
+//! ```rust
+//! use deli::{Error, Model, Transaction};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, Model)]
+//! #[deli(external_key = "String")]
+//! pub struct Preference {
+//!     pub value: u32,
+//! }
+//!
+//! async fn set_volume(transaction: &Transaction, volume: u32) -> Result<(), Error> {
+//!     Preference::with_transaction(transaction)?
+//!         .put_with_key("volume", &Preference { value: volume })
+//!         .await?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Writing records without giving their key (e.g. using [`ObjectStore::add`]) fails, and methods reading the key from
+//! the record (e.g. [`ObjectStore::upsert`]) cannot be used on such models.
+//!
 //! ## Indexes
 //!
 //! In IndexedDB, you can create indexes on fields to speed up queries and add constraints. `deli` supports six types of
@@ -325,6 +528,28 @@
 //! > Note that the default naming convention for the generated struct and index name is different for different index
 //! > types.
 //!
+//! Default index names embed the name of the model, so renaming the model changes them (and the renamed indexes of
+//! existing databases are no longer found). With `#[deli(index_naming = "short")]` on the struct, default index names
+//! are just the key path of the index (`name` for the index above and `id,name` for a composite index on `id` and
+//! `name`), which stays stable across renames. The default is `#[deli(index_naming = "qualified")]`.
+//!
+//! If an index was created under a previous name (e.g. by an older version of `deli` with different naming rules), use
+//! `#[deli(index(alias = "old_name"))]` to query the existing index under its old name until the next upgrade of the
+//! database recreates it under the new name:
+//!
+//! ```rust
+//! use deli::Model;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, Model)]
+//! pub struct Employee {
+//!     #[deli(auto_increment)]
+//!     pub id: u32,
+//!     #[deli(index(alias = "name_index"))] // <- Uses the `name_index` index if `employee_name_index` does not exist
+//!     pub name: String,
+//! }
+//! ```
+//!
 //! ### Defining single field unique indexes
 //!
 //! To define a single field unique index, you can use the `#[deli(unique)]` attribute on the field.
@@ -359,6 +584,24 @@
 //! }
 //! ```
 //!
+//! Single field indexes can also be declared on the struct to keep all the index declarations in one place. A
+//! `#[deli(index)]`, `#[deli(unique)]` or `#[deli(multi_entry)]` attribute on the struct with a single field behaves
+//! exactly like the same attribute on the field (including the names of the generated struct and index):
+//!
+//! ```rust
+//! use deli::Model;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, Model)]
+//! #[deli(index(fields(name)), unique(fields(email)))] // <- Same as `#[deli(index)]` on `name` and `#[deli(unique)]` on `email`
+//! pub struct Employee {
+//!     #[deli(auto_increment)]
+//!     pub id: u32,
+//!     pub name: String,
+//!     pub email: String,
+//! }
+//! ```
+//!
 //! ### Defining composite indexes
 //!
 //! To define composite indexes, you can use the `#[deli(index)]` attribute on the struct with all the field names that
@@ -419,7 +662,7 @@
 //! with the index.
 //!
 //! ```rust
-//! use deli::Model;
+//! use deli::{Error, Model, Transaction};
 //! use serde::{Deserialize, Serialize};
 //!
 //! #[derive(Serialize, Deserialize, Model)]
@@ -431,7 +674,7 @@
 //! }
 //!
 //! async fn get_employee_by_name(transaction: &Transaction, name: &str) -> Result<Option<Employee>, Error> {
-//!     Employee::with_transaction(transaction)?.by_name().get(name).await
+//!     Employee::with_transaction(transaction)?.by_name()?.get(name).await
 //! }
 //! ```
 //!
@@ -444,6 +687,158 @@
 //! - Composite unique indexes: `by_{field_name1}_{field_name2}_composite_unique`
 //! - Composite multi-entry indexes: `by_{field_name1}_{field_name2}_composite_multi_entry`
 //!
+//! The name of the generated function can be overridden using `fn_name` (e.g. to follow your own naming conventions
+//! or to avoid a collision with a method defined on the object store struct):
+//!
+//! ```rust
+//! use deli::{Error, Model, Transaction};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, Model)]
+//! #[deli(index(fields(name, age), fn_name = "with_name_and_age"))]
+//! pub struct Employee {
+//!     #[deli(auto_increment)]
+//!     pub id: u32,
+//!     pub name: String,
+//!     #[deli(index(fn_name = "with_age"))] // <- This generates `with_age` instead of `by_age`
+//!     pub age: u32,
+//! }
+//!
+//! async fn get_employees_by_age(transaction: &Transaction, age: u32) -> Result<Vec<Employee>, Error> {
+//!     Employee::with_transaction(transaction)?.with_age()?.get_all(&age, None).await
+//! }
+//! ```
+//!
+//! [`Index::key_of`] goes the other way, returning the index key of the record with a given primary key (e.g. to
+//! invalidate the entries of a cache keyed by index key after writing the record).
+//!
+//! ## Query builder
+//!
+//! `Model` derive macro also generates a query builder for each model (returned by [`Model::query`]) with a `by_*`
+//! function for each single field index, so that records can be filtered on several fields without choosing the index
+//! to query by hand:
+//!
+//! ```rust
+//! use deli::{Error, Model, Transaction};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, Model)]
+//! pub struct Employee {
+//!     #[deli(auto_increment)]
+//!     pub id: u32,
+//!     #[deli(index)]
+//!     pub name: String,
+//!     #[deli(index)]
+//!     pub age: u32,
+//! }
+//!
+//! async fn get_employees(transaction: &Transaction, name: &str) -> Result<Vec<Employee>, Error> {
+//!     Employee::query()
+//!         .by_name()
+//!         .eq(name)
+//!         .by_age()
+//!         .gte(&30)
+//!         .limit(10)
+//!         .get_all(transaction)
+//!         .await
+//! }
+//! ```
+//!
+//! The query reads the records using the index of an equality condition if there is one, and checks the other
+//! conditions on each record (see [`query`] for details). The query builder struct is named after the model followed by
+//! `Query` (`EmployeeQuery`), which can be changed using `#[deli(query_struct = "..")]`.
+//!
+//! Indexes added to a model only exist in the database once its version is bumped. Querying an index which does not
+//! exist fails with [`Error::IndexMissing`] (use [`ObjectStore::has_index`] to check beforehand), unless the query
+//! opts in to a full scan of the object store using `scan_if_index_missing()`.
+//!
+//! ## Partial updates
+//!
+//! `Model` derive macro also generates a patch struct for each model (`EmployeePatch`) with an `Option` of each field
+//! except the primary key. [`ObjectStore::patch`] reads a record, replaces the fields set in the patch and writes it
+//! back in the same transaction:
+//!
+//! ```rust,ignore
+//! let employee = Employee::with_transaction(&transaction)?
+//!     .patch(&id, EmployeePatch { age: Some(31), ..Default::default() })
+//!     .await?;
+//! ```
+//!
+//! The patch struct can be renamed using `#[deli(patch_struct = "..")]`.
+//!
+//! ## Creation timestamps
+//!
+//! With the `timestamps` feature enabled, a field annotated with `#[deli(created_at)]` is set to the current time when
+//! a record is added, in milliseconds since the Unix epoch by default. `#[deli(created_at(unit = "seconds"))]` and
+//! `#[deli(created_at(unit = "iso8601"))]` store seconds or ISO 8601 strings instead (see [`timestamps`] for details).
+//!
+//! ## Date/time fields
+//!
+//! With the `datetime` feature enabled, a field annotated with `#[deli(datetime)]` is stored as a JS `Date` object, so
+//! that indexes over it are ordered by time instead of comparing strings (e.g. ISO 8601 timestamps with different
+//! offsets). The field keeps its serde representation: `#[deli(datetime(unit = "iso8601"))]` converts a field
+//! serialized as an ISO 8601 string (like `chrono::DateTime`), and keys given to index queries are converted as well
+//! (see [`datetime`] for details).
+//!
+//! ## Binary fields
+//!
+//! `Vec<u8>` fields are serialized as arrays of numbers, which take far more space than the bytes themselves and are
+//! slow to write and read. With the `bytes` feature enabled, a field annotated with `#[deli(bytes)]` is stored as a
+//! `Uint8Array` instead. The field is deserialized from the `Uint8Array` directly, so any type serialized as a sequence
+//! of bytes can be annotated (e.g. `Vec<u8>`, `[u8; 32]` or `serde_bytes::ByteBuf`, which deserializes fastest):
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize, Model)]
+//! struct Attachment {
+//!     #[deli(auto_increment)]
+//!     id: u32,
+//!     #[deli(bytes)]
+//!     data: Vec<u8>,
+//! }
+//! ```
+//!
+//! Binary fields cannot be part of keys or indexes, as lookups serialize the bytes as arrays of numbers.
+//!
+//! ## Blob fields
+//!
+//! With the `blob` feature enabled, [`JsBlob`] fields store `web_sys::Blob` and `web_sys::File` objects as is (e.g.
+//! images selected by the user), alongside the typed metadata of the record:
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize, Model)]
+//! struct Photo {
+//!     #[deli(auto_increment)]
+//!     id: u32,
+//!     caption: String,
+//!     image: JsBlob,
+//! }
+//!
+//! photos.add(&AddPhoto { caption, image: file.into() }).await?;
+//! ```
+//!
+//! Blobs cannot be part of keys or indexes, and exported records contain an empty object in their place.
+//!
+//! ## Structured clone serialization
+//!
+//! Records are serialized to JSON compatible values by default: maps are stored as plain objects (so their keys must
+//! serialize as strings), `None` as `null` and bytes as arrays of numbers. A model annotated with
+//! `#[deli(structured_clone)]` is serialized to native JS values instead, which IndexedDB stores using the structured
+//! clone algorithm: maps are stored as `Map`s (so any key type is supported), `None` as `undefined` and bytes as
+//! `Uint8Array`s.
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize, Model)]
+//! #[deli(structured_clone)]
+//! struct Board {
+//!     #[deli(auto_increment)]
+//!     id: u32,
+//!     cells: HashMap<(u32, u32), String>,
+//! }
+//! ```
+//!
+//! Keys and key ranges are serialized the same way in both modes. Exports and sync payloads are JSON, so models whose
+//! maps have non-string keys cannot be exported or synced, and nested indexes cannot reach into `Map`s.
+//!
 //! ## Field renaming
 //!
 //! If you use `#[serde(rename = "new_name")]` attribute on a field, you also need to use `#[deli(rename = "new_name")]`
@@ -466,33 +861,630 @@
 //! If you use `#[serde(rename_all = "camelCase")]` attribute on the struct, you have to use
 //! `#[deli(rename = "new_name")]` for each field individually. Unfortunately, `deli` does not support renaming all
 //! fields at once.
+//!
+//! Since a record is stored under its serialized field names, a key or index over a field whose names differ would
+//! silently stay empty. The derive macro therefore checks the names of the fields used in key paths and indexes against
+//! their `#[serde(rename)]` and `#[serde(rename_all)]` attributes, and fails to compile if they differ:
+//!
+//! ```rust,compile_fail
+//! use deli::Model;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, Model)]
+//! #[serde(rename_all = "camelCase")]
+//! pub struct Employee {
+//!     #[deli(auto_increment)]
+//!     pub id: u32,
+//!     #[deli(unique)] // <- Serialized as `emailAddress`, needs `#[deli(rename = "emailAddress")]`
+//!     pub email_address: String,
+//! }
+//! ```
+//!
+//! ## Pinning names
+//!
+//! Store and index names are derived from the names of the struct and its fields, so a refactor can silently change
+//! them and orphan the data of existing databases. Names can be pinned using `#[deli(expected_names(..))]`, which fails
+//! to compile if the store name differs from `store` or if an index named `index` is no longer generated:
+//!
+//! ```rust
+//! use deli::Model;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, Model)]
+//! #[deli(expected_names(store = "employee", index = "employee_name_index"))]
+//! pub struct Employee {
+//!     #[deli(auto_increment)]
+//!     pub id: u32,
+//!     #[deli(index)]
+//!     pub name: String,
+//! }
+//! ```
+//!
+//! ```rust,compile_fail
+//! use deli::Model;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, Model)]
+//! #[deli(expected_names(store = "employee", index = "employee_name_index"))]
+//! pub struct Staff { // <- Fails to compile because the store and index names are now `staff` and `staff_name_index`
+//!     #[deli(auto_increment)]
+//!     pub id: u32,
+//!     #[deli(index)]
+//!     pub name: String,
+//! }
+//! ```
+//!
+//! ## Nested indexes
+//!
+//! IndexedDB key paths cannot look into arrays of objects. With the `nested-index` feature enabled, an index over the
+//! values of a field of the elements of an array can be declared using a `key_path` where `[]` visits every element of
+//! an array. `deli` maintains a shadow field with the extracted values and indexes it as a multi entry index, so the
+//! `key_type` of the extracted values has to be given explicitly:
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize, Model)]
+//! pub struct Order {
+//!     #[deli(auto_increment)]
+//!     id: u32,
+//!     #[deli(index(key_path = "items[].sku", key_type = "String"))]
+//!     items: Vec<OrderItem>,
+//! }
+//!
+//! let orders = store.by_items_sku()?.get_all("apple", None).await?;
+//! ```
+//!
+//! ## Sync
+//!
+//! With the `sync` feature enabled, the [`sync`] module provides a pull based synchronisation of object stores with a
+//! remote backend. [`sync::HttpSyncAdapter`] is a reference adapter which pulls JSON pages from an http endpoint and
+//! can be used as a template for real backends.
+//!
+//! ```rust,ignore
+//! use deli::{sync::{HttpSyncAdapter, Syncer}, Database, Error};
+//!
+//! async fn pull_employees(database: &Database) -> Result<(), Error> {
+//!     let syncer = Syncer::new(database, HttpSyncAdapter::new("https://example.com/employees")).chunk_size(50);
+//!     syncer.pull::<Employee>().await?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! The sync cursor is stored in an internal meta store, so the database must be created with
+//! [`DatabaseBuilder::enable_sync`].
+//!
+//! For incremental uploads, annotate an indexed timestamp field with `#[deli(updated_at)]`. This implements
+//! [`sync::Tracked`] for the model, which enables `store.changed_since(&timestamp)` and `syncer.push::<Model>()` (only
+//! records modified since the last push are sent to the backend).
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize, Model)]
+//! pub struct Note {
+//!     #[deli(key)]
+//!     id: u32,
+//!     text: String,
+//!     #[deli(updated_at)] // <- Also creates an index on `updated_at` if the field is not already indexed
+//!     updated_at: u64,
+//! }
+//! ```
+//!
+//! Tracked models can also be pulled using a [`sync::ConflictStrategy`], which decides what happens when a pulled
+//! record conflicts with a local change that has not been pushed yet. Conflicts left unresolved are kept in an internal
+//! conflicts store.
+//!
+//! ```rust,ignore
+//! let summary = syncer.pull_with::<Note>(&ConflictStrategy::LatestTimestamp).await?;
+//!
+//! for conflict in syncer.conflicts::<Note>().await? {
+//!     // Resolve the conflict and remove it from the conflicts store
+//!     syncer.clear_conflict::<Note>(&conflict.key).await?;
+//! }
+//! ```
+//!
+//! ## Encryption
+//!
+//! With the `encryption` feature enabled, sensitive fields can be envelope-encrypted by declaring them as
+//! [`encryption::Encrypted<T>`] and annotating them with `#[deli(encrypt)]`. Other fields are stored as is and can still
+//! be indexed (encrypted fields cannot be used in keys or regular indexes). Values are encrypted using the
+//! [`encryption::Cipher`] registered with [`encryption::set_cipher`].
+//!
+//! Encrypted or very long fields can be looked up by exact value using a hashed index. `deli` indexes a keyed hash of
+//! the value (stored in a shadow field of the record) instead of the value itself.
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize, Model)]
+//! pub struct Account {
+//!     #[deli(auto_increment)]
+//!     id: u32,
+//!     #[deli(unique)]
+//!     username: String,
+//!     #[deli(encrypt, index(hashed))] // <- Generates `store.by_token_hashed(&token)`
+//!     token: Encrypted<String>,
+//! }
+//! ```
+//!
+//! ## Export
+//!
+//! With the `export` feature enabled, [`Database::export`] dumps the records of the selected models as JSON. Fields
+//! annotated with `#[deli(redact)]` are replaced with a placeholder unless redaction is disabled, so dumps can be
+//! attached to bug reports without leaking personal data.
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize, Model)]
+//! pub struct Contact {
+//!     #[deli(auto_increment)]
+//!     id: u32,
+//!     name: String,
+//!     #[deli(redact)]
+//!     email: String,
+//! }
+//!
+//! let dump = database.export().with_model::<Contact>().build().await?;
+//! let json = serde_json::to_string(&dump)?;
+//! ```
+//!
+//! The stores are read concurrently. Large exports can be streamed store by store using
+//! [`export::ExportBuilder::stream`], which returns a manifest of the exported stores (see [`export`] for details).
+//!
+//! For backups, [`export::ExportBuilder::archive`] returns a versioned archive whose records are split into chunks with
+//! checksums. [`Database::import`] validates the whole archive before replacing the records of its stores, so truncated
+//! or corrupted backups are rejected without touching the local data (see [`archive`] for details):
+//!
+//! ```rust,ignore
+//! let archive = database.export().with_model::<Contact>().redact(false).archive(1000).await?;
+//! database.import(&archive).await?;
+//! ```
+//!
+//! [`Database::restore`] restores only chosen stores or key ranges of an archive into the existing records instead,
+//! with a strategy for archived records whose key is already used locally.
+//!
+//! ## Inspection
+//!
+//! With the `inspect` feature enabled, databases created with [`DatabaseBuilder::track_sizes`] keep the approximate
+//! serialized size of every record, which can be retrieved using `store.approx_bytes()` and `store.record_sizes()`.
+//! A hook registered using [`inspect::set_large_value_hook`] is called with the store name and key whenever a record
+//! larger than 1MB (configurable using [`inspect::set_large_value_threshold`]) is written.
+//! `store.sample(n)` returns approximately `n` random records of a store using random key probes, without loading the
+//! whole store.
+//!
+//! ## Integrity
+//!
+//! With the `integrity` feature enabled, [`Database::check_integrity`] scans the object store of a model and reports
+//! the records which cannot be deserialized, violate a unique index or reference a missing record. References are
+//! declared using `#[deli(references = Model)]`:
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize, Model)]
+//! pub struct Player {
+//!     #[deli(auto_increment)]
+//!     id: u32,
+//!     #[deli(references = Team)]
+//!     team_id: u32,
+//! }
+//!
+//! let report = database.check_integrity::<Player>().await?;
+//!
+//! for violation in report.violations {
+//!     log::warn!("{:?}: {:?}", violation.key, violation.kind);
+//! }
+//! ```
+//!
+//! Records which cannot be deserialized (e.g. written by an older version of the app) can be deleted or fixed using
+//! [`Database::repair`] with a [`RepairStrategy`](integrity::RepairStrategy):
+//!
+//! ```rust,ignore
+//! let summary = database
+//!     .repair::<Player>(RepairStrategy::Backfill(Player::default))
+//!     .await?;
+//! ```
+//!
+//! ## Schema fingerprint
+//!
+//! With the `fingerprint` feature enabled, [`DatabaseBuilder::guard_schema`] stores a fingerprint of the models' schema
+//! in the database when it is opened. If another tab later opens the database with a different schema at the same
+//! version (e.g. during a deployment which forgot to bump the version), [`DatabaseBuilder::build`] fails with
+//! `Error::SchemaFingerprintMismatch` instead of silently operating on half-migrated data. The fingerprint is computed
+//! from the [`StoreSchema`] of each model (see [`Model::store_schema`]), so it only changes with the object stores and
+//! indexes of the models, and not with the versions of `deli` or its dependencies.
+//!
+//! ## Access control
+//!
+//! Applications running untrusted code (e.g. plugins) on a database can restrict the operations allowed on each object
+//! store using a [`Gate`]. Operations which are not allowed fail with [`Error::AccessDenied`]:
+//!
+//! ```rust,ignore
+//! let database = Database::builder("plugin")
+//!     .add_model::<Employee>()
+//!     .add_model::<Setting>()
+//!     .gate(|store: &str, operation: Operation| {
+//!         operation == Operation::Read || store == Setting::NAME
+//!     })
+//!     .build()
+//!     .await?;
+//! ```
+//!
+//! ## Cancellation
+//!
+//! Long scans can be cancelled (e.g. when navigating away from a page) by attaching a [`CancellationToken`] to their
+//! transaction. Once the token is cancelled, advancing a cursor or starting an operation on the transaction aborts it
+//! and fails with [`Error::Cancelled`]:
+//!
+//! ```rust,ignore
+//! let token = CancellationToken::new();
+//!
+//! let transaction = database
+//!     .transaction()
+//!     .with_model::<Employee>()
+//!     .cancellation(&token)
+//!     .build()?;
+//!
+//! // Later, e.g. in the page's cleanup
+//! token.cancel();
+//! ```
+//!
+//! ## Streams
+//!
+//! With the `stream` feature enabled, [`ObjectStore::stream`] and [`Index::stream`] return the records matching a key
+//! range as a [`Stream`](futures_util::Stream) which reads them one by one using a cursor, so that large stores can be
+//! consumed incrementally:
+//!
+//! ```rust,ignore
+//! use futures_util::StreamExt;
+//!
+//! let mut stream = Employee::with_transaction(&transaction)?.stream::<u32>(.., None).await?;
+//!
+//! while let Some(employee) = stream.next().await {
+//!     render(employee?);
+//! }
+//!
+//! // Lazily render employees ordered by age
+//! let mut stream = Employee::with_transaction(&transaction)?.by_age()?.stream::<u32>(.., None).await?;
+//! ```
+//!
+//! ## Pagination
+//!
+//! [`ObjectStore::page`] retrieves a page of records, skipping the records of the previous pages using a cursor, and
+//! tells whether there are more pages:
+//!
+//! ```rust,ignore
+//! let page = Employee::with_transaction(&transaction)?.page::<u32>(.., None, 2, 20).await?;
+//!
+//! render(&page.records);
+//! show_next_button(page.has_more);
+//! ```
+//!
+//! ## Aggregates
+//!
+//! [`Index::fold`], [`Index::sum`] and [`Index::average`] aggregate the records matching a key range using a cursor,
+//! without collecting them into a `Vec`:
+//!
+//! ```rust,ignore
+//! let average_salary = Employee::with_transaction(&transaction)?
+//!     .by_age()?
+//!     .average(&30..&40, |employee| employee.salary)
+//!     .await?;
+//! ```
+//!
+//! ## Query cache
+//!
+//! With the `cache` feature enabled, `DatabaseBuilder::cache_queries` caches the results of [`ObjectStore::get_all`].
+//! Repeating a query returns the cached records without reading the object store until a writable transaction of the
+//! database with the store in scope completes, which invalidates the cached results of the store. Transactions queued
+//! behind a writable transaction which is not finished yet read the object store:
+//!
+//! ```rust,ignore
+//! let database = Database::builder("app").add_model::<Employee>().cache_queries().build().await?;
+//! ```
+//!
+//! Writes made by other tabs or workers are not observed, so `Database::clear_query_cache` should be called when they
+//! are notified (e.g. using a `BroadcastChannel`).
+//!
+//! ## Versioned envelopes
+//!
+//! With the `envelope` feature enabled, a model declared with `#[deli(envelope(version = N))]` is stored as
+//! `{ v: N, data: {...} }`. Records stored by older versions of the model are upgraded lazily when read, using the
+//! functions registered for their version, so that the shape of a model can change without a database version bump
+//! (see [`envelope`]):
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize, Model)]
+//! #[deli(envelope(version = 2, upgrade(from = 1, with = "upgrade_v1")))]
+//! struct Employee {
+//!     #[deli(key)]
+//!     id: u32,
+//!     name: String,
+//!     age: Option<u32>,
+//! }
+//! ```
+//!
+//! Upgraded records are stored in the current version the next time they are written. With
+//! `migrate_on_read = "write_back"` (see [`MigrateOnRead`](envelope::MigrateOnRead)), they are also written back in the
+//! background as soon as they are read, amortizing the migration across normal usage.
+//!
+//! ## Timeouts
+//!
+//! Some IndexedDB requests never settle (e.g. an upgrade blocked by a connection kept open in another tab). With the
+//! `watchdog` feature enabled, opening a database can be given a timeout after which [`DatabaseBuilder::build`] fails
+//! with `Error::Timeout`, and any other future can be guarded using [`watchdog::with_timeout`]:
+//!
+//! ```rust,ignore
+//! let database = Database::builder("app")
+//!     .version(2)
+//!     .add_model::<Employee>()
+//!     .timeout(Duration::from_secs(5))
+//!     .build()
+//!     .await?;
+//!
+//! watchdog::with_timeout("delete", Duration::from_secs(5), Database::delete("old_app")).await?;
+//! ```
+//!
+//! ## Detached writes
+//!
+//! With the `detached` feature enabled, writes whose result is not needed (e.g. telemetry) can be issued without
+//! awaiting them. Failures are reported to the hook registered using [`detached::set_error_hook`]:
+//!
+//! ```rust,ignore
+//! detached::set_error_hook(|failure| log::warn!("write to {} failed: {}", failure.store, failure.error));
+//!
+//! Event::with_transaction(&transaction)?.add_detached(&AddEvent { name: "page_view".to_owned() })?;
+//! ```
+//!
+//! ## Batch results
+//!
+//! With the `batch` feature enabled, [`ObjectStore::add_all_batch`], [`ObjectStore::put_all_batch`] and
+//! [`ObjectStore::delete_many_batch`] report the outcome of every item in a [`BatchResult`](batch::BatchResult)
+//! instead of failing the whole call on the first error. With [`OnError::Continue`](batch::OnError::Continue), a
+//! failed item does not abort the transaction, so that the other items are still written:
+//!
+//! ```rust,ignore
+//! let result = Employee::with_transaction(&transaction)?
+//!     .add_all_batch(&employees, OnError::Continue)
+//!     .await?;
+//!
+//! for failure in result.failed {
+//!     log::warn!("employee {} not imported: {}", failure.index, failure.error);
+//! }
+//! ```
+//!
+//! ## Debounced writes
+//!
+//! With the `debounce` feature enabled, a [`DebouncedWriter`](debounce::DebouncedWriter) coalesces rapid successive
+//! updates of the same record (e.g. autosaving a document on keystrokes) and writes them at most once per interval,
+//! cutting write amplification and transaction churn:
+//!
+//! ```rust,ignore
+//! let writer = DebouncedWriter::<Document>::new(database.clone(), Duration::from_millis(500));
+//!
+//! writer.update(document)?;
+//! ```
+//!
+//! An [`Autosave`](debounce::Autosave) applies the same to a single record (e.g. the document being edited), tracking
+//! whether the last value set is written yet using [`dirty`](debounce::Autosave::dirty).
+//!
+//! ## Chunked deletes
+//!
+//! Deleting a huge key range in a single transaction blocks other transactions on the object store until it completes.
+//! [`Database::delete_chunked`] deletes the records in successive transactions of bounded size instead, reporting
+//! progress after each chunk:
+//!
+//! ```rust,ignore
+//! let deleted = database
+//!     .delete_chunked::<Employee, u32>(..)?
+//!     .chunk_size(500)
+//!     .on_progress(|deleted| log::info!("deleted {deleted} employees"))
+//!     .cancellation(&token)
+//!     .run()
+//!     .await?;
+//! ```
+//!
+//! ## Background work
+//!
+//! With the `priority` feature enabled, work split into batches can be tagged as
+//! [`Priority::Background`](priority::Priority::Background) so that it waits for the browser to be idle between batches
+//! instead of competing with user-facing queries:
+//!
+//! ```rust,ignore
+//! let syncer = Syncer::new(&database, adapter).priority(Priority::Background);
+//!
+//! // Custom batched work (e.g. a retention sweep) running one transaction per batch
+//! for batch in expired.chunks(100) {
+//!     delete_batch(&database, batch).await?;
+//!     Priority::Background.between_batches().await;
+//! }
+//! ```
+//!
+//! ## Store splits
+//!
+//! With the `split` feature enabled, a model can be split into a new object store without downtime. During the
+//! transitional period, a [`SplitStore`](split::SplitStore) writes to both stores and reads from the new store with a
+//! fallback to the old store. The state of the split is kept in the database (built using
+//! [`DatabaseBuilder::enable_store_splits`]) until [`StoreSplit::complete`](split::StoreSplit::complete) backfills the
+//! new store:
+//!
+//! ```rust,ignore
+//! let split = StoreSplit::<Profile, ProfileBio>::new(
+//!     "profile_bio",
+//!     |profile| ProfileBio { id: profile.id, bio: profile.bio.clone() },
+//!     |bio, profile| profile.map(|profile| Profile { bio: bio.bio.clone(), ..profile }),
+//! );
+//!
+//! let transaction = split.transaction(&database).writable().build()?;
+//! let store = split.with_transaction(&transaction).await?;
+//! let bio = store.get(&id).await?;
+//! ```
+//!
+//! ## Cross-database writes
+//!
+//! With the `cross-db` feature enabled, [`CrossDbWrite`](cross_db::CrossDbWrite) writes to two databases with
+//! best-effort atomicity. The write is journaled in the primary database (built using
+//! [`DatabaseBuilder::enable_journal`]), committed on the secondary database and then on the primary database. If the
+//! primary write fails, the compensation is committed on the secondary database:
+//!
+//! ```rust,ignore
+//! CrossDbWrite::new("transfer-42", &ledger, &cache)
+//!     .secondary::<Balance, _>(|store| Box::pin(async move { store.update(&debited).await.map(drop) }))
+//!     .primary::<Transfer, _>(|store| Box::pin(async move { store.add(&transfer).await.map(drop) }))
+//!     .compensate::<Balance, _>(|store| Box::pin(async move { store.update(&original).await.map(drop) }))
+//!     .execute()
+//!     .await?;
+//! ```
+//!
+//! Writes interrupted between the phases (e.g. because the app was closed) are returned by [`cross_db::pending`].
+//!
+//! ## Dynamic stores
+//!
+//! With the `dynamic` feature enabled, object stores which are not described by a model (e.g. written by another
+//! library or by an older version of the app) can be added to a transaction by name using
+//! [`TransactionBuilder::with_store`] and accessed using [`Transaction::dynamic_store`]. The returned [`DynStore`]
+//! reads and writes records as `serde_json::Value`s, or as `JsValue`s as stored:
+//!
+//! ```rust,ignore
+//! let legacy = transaction.dynamic_store("legacy_notes")?;
+//!
+//! for record in legacy.get_all(None).await? {
+//!     notes.add(&AddNote { text: record["body"].as_str().unwrap_or_default().to_owned() }).await?;
+//! }
+//!
+//! legacy.clear().await?;
+//! ```
+//!
+//! ## Availability check
+//!
+//! IndexedDB may be missing or fail in private browsing modes and some embedded webviews.
+//! [`Database::availability_check`] creates a probe database, writes a record to it and deletes it again, reporting
+//! which of the steps worked in an [`Availability`], so that apps can disable the features relying on persisted data:
+//!
+//! ```rust,ignore
+//! if !Database::availability_check().await.is_available() {
+//!     return Ok(OfflineMode::Disabled);
+//! }
+//! ```
+//!
+//! ## Capability detection
+//!
+//! With the `capabilities` feature enabled, [`capabilities()`] reports which IndexedDB and Storage API features are
+//! available in the current browser (`getAll()`, `commit()`, durability hints, `indexedDB.databases()` and
+//! `navigator.storage.persist()`). `deli` then reads records using cursors where `getAll()` is missing and waits for
+//! transactions to complete where `commit()` is missing.
+//!
+//! ## Storage pressure
+//!
+//! Browsers evict the databases of an origin when the device runs low on storage. With the `storage` feature enabled,
+//! [`storage::watch_pressure`] calls a hook once the storage used by the origin crosses a fraction of its quota, so that
+//! apps can delete old records before the browser wipes the database, and [`storage::persist`] requests persistent
+//! storage. [`DatabaseBuilder::on_forced_close`] sets a hook called when the browser closes the connection to the
+//! database (e.g. after evicting it).
+#[cfg(feature = "export")]
+pub mod archive;
+mod availability;
+#[cfg(feature = "batch")]
+pub mod batch;
+#[cfg(feature = "blob")]
+mod blob;
+#[cfg(feature = "bytes")]
+mod bytes;
+#[cfg(feature = "cache")]
+mod cache;
+mod cancellation;
+#[cfg(feature = "capabilities")]
+mod capabilities;
+mod chunked_delete;
+#[cfg(feature = "cross-db")]
+pub mod cross_db;
 mod cursor;
 mod database;
 mod database_builder;
+mod database_handle;
+#[cfg(feature = "datetime")]
+pub mod datetime;
+#[cfg(feature = "debounce")]
+pub mod debounce;
+#[cfg(feature = "detached")]
+pub mod detached;
+#[cfg(feature = "dynamic")]
+mod dynamic_store;
+#[cfg(feature = "encryption")]
+pub mod encryption;
+#[cfg(feature = "envelope")]
+pub mod envelope;
 mod error;
+#[cfg(feature = "export")]
+pub mod export;
+#[cfg(feature = "fingerprint")]
+mod fingerprint;
+mod gate;
 mod index;
+#[cfg(feature = "inspect")]
+pub mod inspect;
+mod int64_key;
+#[cfg(feature = "integrity")]
+pub mod integrity;
 mod key_cursor;
+#[cfg(feature = "key-generator")]
+pub mod key_generator;
 mod key_range;
+mod lookup;
+#[cfg(any(
+    feature = "cross-db",
+    feature = "fingerprint",
+    feature = "split",
+    feature = "sync"
+))]
+mod meta;
 mod model;
 mod model_index;
+#[cfg(feature = "nested-index")]
+mod nested_index;
 mod object_store;
+mod open_lock;
+mod page;
+#[cfg(feature = "priority")]
+pub mod priority;
+pub mod query;
+mod schema;
+mod seed;
+#[cfg(feature = "shuffle-get-all")]
+pub mod shuffle;
+#[cfg(feature = "split")]
+pub mod split;
+#[cfg(feature = "storage")]
+pub mod storage;
+#[cfg(feature = "sync")]
+pub mod sync;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(any(feature = "debounce", feature = "storage", feature = "watchdog"))]
+mod timer;
+#[cfg(feature = "timestamps")]
+pub mod timestamps;
 mod transaction;
 mod transaction_builder;
+#[cfg(feature = "watchdog")]
+pub mod watchdog;
 
 #[doc(inline)]
-pub use idb::{CursorDirection, TransactionMode, TransactionResult};
+pub use idb::{CursorDirection, Query, TransactionMode, TransactionResult};
 
 pub use self::{
+    availability::Availability,
+    cancellation::CancellationToken,
+    chunked_delete::{ChunkedDelete, DEFAULT_DELETE_CHUNK_SIZE},
     cursor::Cursor,
     database::Database,
     database_builder::DatabaseBuilder,
+    database_handle::DatabaseHandle,
     error::Error,
+    gate::{Gate, Operation},
     index::Index,
+    int64_key::{I64Key, U64Key},
     key_cursor::KeyCursor,
-    key_range::{BoundedRange, KeyRange, RangeType, UnboundedRange},
+    key_range::{BoundedRange, KeyRange, OwnedRange, RangeType, UnboundedRange},
+    lookup::Lookup,
     model::Model,
     model_index::ModelIndex,
-    object_store::ObjectStore,
+    object_store::{ObjectStore, Upsert},
+    page::Page,
+    schema::{IndexSchema, Schema, SchemaConflict, StoreSchema},
+    seed::SeedFuture,
     transaction::Transaction,
     transaction_builder::TransactionBuilder,
 };
@@ -507,5 +1499,12 @@ pub mod reexports {
     pub use serde;
 }
 
+#[cfg(feature = "blob")]
+pub use self::blob::JsBlob;
+#[cfg(feature = "capabilities")]
+pub use self::capabilities::{capabilities, Capabilities};
+#[cfg(feature = "dynamic")]
+pub use self::dynamic_store::DynStore;
+
 #[cfg(feature = "derive")]
-pub use deli_derive::Model;
+pub use deli_derive::{Key, Model};
@@ -0,0 +1,6 @@
+/// Trait implemented by models and indexes for the types which can be used to look up their keys.
+///
+/// `Model` derive macro implements it for every type the key can be borrowed as (e.g. `str` for `String` keys) and, for
+/// composite keys, for tuples of references to such types (e.g. `(&str, &u32)` for `(String, u32)` keys), so lookups
+/// never require constructing owned values.
+pub trait Lookup<Q: ?Sized> {}
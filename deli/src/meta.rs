@@ -0,0 +1,92 @@
+use idb::{builder::ObjectStoreBuilder, Query};
+use serde::{de::DeserializeOwned, Serialize};
+use wasm_bindgen::JsValue;
+
+use crate::{error::Error, transaction::Transaction, JSON_SERIALIZER};
+
+/// Name of the internal object store used by `deli` to keep bookkeeping values.
+pub(crate) const META_STORE_NAME: &str = "__deli_meta";
+
+/// Returns the object store builder for the meta store (values are stored with out-of-line string keys).
+pub(crate) fn object_store_builder() -> ObjectStoreBuilder {
+    ObjectStoreBuilder::new(META_STORE_NAME)
+}
+
+/// Typed key-value access to the internal meta store.
+#[derive(Debug)]
+pub(crate) struct MetaStore<'t> {
+    object_store: idb::ObjectStore,
+    #[cfg_attr(not(feature = "cross-db"), allow(dead_code))]
+    transaction: &'t Transaction,
+}
+
+impl<'t> MetaStore<'t> {
+    /// Returns the meta store in transaction's scope.
+    pub(crate) fn new(transaction: &'t Transaction) -> Result<Self, Error> {
+        Ok(Self {
+            object_store: transaction.idb_object_store(META_STORE_NAME)?,
+            transaction,
+        })
+    }
+
+    /// Retrieves the value stored under the given key.
+    #[cfg(any(feature = "fingerprint", feature = "split", feature = "sync"))]
+    pub(crate) async fn get<T>(&self, key: &str) -> Result<Option<T>, Error>
+    where
+        T: DeserializeOwned,
+    {
+        self.object_store
+            .get(Query::Key(JsValue::from_str(key)))?
+            .await?
+            .map(serde_wasm_bindgen::from_value)
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    /// Stores the value under the given key, replacing any previous value.
+    pub(crate) async fn set<T>(&self, key: &str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let value = value.serialize(&JSON_SERIALIZER)?;
+        self.object_store
+            .put(&value, Some(&JsValue::from_str(key)))?
+            .await?;
+        Ok(())
+    }
+
+    /// Retrieves all the values stored under keys starting with the given prefix.
+    #[cfg(feature = "cross-db")]
+    pub(crate) async fn get_all_with_prefix<T>(&self, prefix: &str) -> Result<Vec<T>, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let range = idb::KeyRange::bound(
+            &JsValue::from_str(prefix),
+            &JsValue::from_str(&format!("{prefix}\u{ffff}")),
+            None,
+            None,
+        )?;
+
+        crate::cursor::get_all_stored(
+            self.transaction,
+            &self.object_store,
+            Some(Query::KeyRange(range)),
+            None,
+        )
+        .await?
+        .into_iter()
+        .map(serde_wasm_bindgen::from_value)
+        .collect::<Result<_, _>>()
+        .map_err(Into::into)
+    }
+
+    /// Deletes the value stored under the given key.
+    #[cfg(feature = "cross-db")]
+    pub(crate) async fn delete(&self, key: &str) -> Result<(), Error> {
+        self.object_store
+            .delete(Query::Key(JsValue::from_str(key)))?
+            .await?;
+        Ok(())
+    }
+}
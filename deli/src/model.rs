@@ -1,11 +1,21 @@
-use std::ops::Deref;
+use std::{borrow::Borrow, ops::Deref};
 
 use idb::builder::ObjectStoreBuilder;
 use serde::{de::DeserializeOwned, Serialize};
 
-use crate::{error::Error, object_store::ObjectStore, transaction::Transaction};
+use crate::{
+    error::Error, object_store::ObjectStore, query::QueryBuilder, schema::StoreSchema,
+    transaction::Transaction, JSON_SERIALIZER,
+};
 
 /// Trait for defining object stores in an indexed db database
+///
+/// The trait is meant to be implemented using `#[derive(Model)]`. Existing manual implementations need to be migrated
+/// to define the following items, which were added to the trait without defaults (breaking change):
+///
+/// - [`Query`](Model::Query): use [`QueryBuilder<Self>`] when no typed query builder is defined for the model.
+/// - [`Patch`](Model::Patch) and [`apply_patch`](Model::apply_patch): use `()` and an empty `apply_patch` if records
+///   of the model are never patched (patching a record then writes it back unchanged).
 pub trait Model: Serialize + DeserializeOwned {
     /// Name of the object store
     const NAME: &'static str;
@@ -19,12 +29,340 @@ pub trait Model: Serialize + DeserializeOwned {
     /// Type of object store for the model
     type ObjectStore<'t>: Deref<Target = ObjectStore<'t, Self>> + From<ObjectStore<'t, Self>>;
 
+    /// Type of query builder for the model (the query builder generated by the derive macro, or [`QueryBuilder<Self>`])
+    type Query: From<QueryBuilder<Self>> + Into<QueryBuilder<Self>>;
+
+    /// Type of partial update of the model, with an optional value for each field except the primary key (`()` if records
+    /// are never patched)
+    type Patch: Default;
+
+    /// Maximum number of records returned by `get_all` and `get_all_keys` queries when no limit is given (set using
+    /// `#[deli(default_limit = ..)]`)
+    const DEFAULT_LIMIT: Option<u32> = None;
+
+    /// Names of the indexes of the model
+    #[doc(hidden)]
+    const INDEX_NAMES: &'static [&'static str] = &[];
+
+    /// Tombstone policy of the model (set using `#[deli(tombstones)]`)
+    #[cfg(feature = "sync")]
+    #[doc(hidden)]
+    const TOMBSTONES: Option<crate::sync::TombstonePolicy> = None;
+
+    /// Names of the shadow fields of hashed indexes (set using `#[deli(index(hashed))]`)
+    #[cfg(feature = "encryption")]
+    #[doc(hidden)]
+    const HASHED_FIELDS: &'static [&'static str] = &[];
+
+    /// Names of the shadow fields of nested indexes and the key paths of their values (set using
+    /// `#[deli(index(key_path = ".."))]`)
+    #[cfg(feature = "nested-index")]
+    #[doc(hidden)]
+    const NESTED_FIELDS: &'static [(&'static str, &'static str)] = &[];
+
+    /// Names of the fields redacted in exports (set using `#[deli(redact)]`)
+    #[cfg(feature = "export")]
+    #[doc(hidden)]
+    const REDACTED_FIELDS: &'static [&'static str] = &[];
+
+    /// Foreign key references of the model's fields (set using `#[deli(references = Model)]`)
+    #[cfg(feature = "integrity")]
+    #[doc(hidden)]
+    const REFERENCES: &'static [crate::integrity::Reference] = &[];
+
+    /// Current version of the records of the model stored in an envelope (set using
+    /// `#[deli(envelope(version = N))]`)
+    #[cfg(feature = "envelope")]
+    #[doc(hidden)]
+    const ENVELOPE_VERSION: Option<u32> = None;
+
+    /// Serialized name of the primary key and generator of the keys of records being added (set using
+    /// `#[deli(key_generator = "..")]`)
+    #[cfg(feature = "key-generator")]
+    #[doc(hidden)]
+    const KEY_GENERATOR: Option<(&'static str, crate::key_generator::KeyGenerator)> = None;
+
+    /// Serialized name and unit of the field set to the current time when a record is added (set using
+    /// `#[deli(created_at)]`)
+    #[cfg(feature = "timestamps")]
+    #[doc(hidden)]
+    const CREATED_AT: Option<(&'static str, crate::timestamps::TimestampUnit)> = None;
+
+    /// Serialized names of the fields stored as `Uint8Array` (set using `#[deli(bytes)]`)
+    #[cfg(feature = "bytes")]
+    #[doc(hidden)]
+    const BYTES_FIELDS: &'static [&'static str] = &[];
+
+    /// Serialized names and units of the fields stored as dates (set using `#[deli(datetime)]`)
+    #[cfg(feature = "datetime")]
+    #[doc(hidden)]
+    const DATETIME_FIELDS: &'static [(&'static str, crate::timestamps::TimestampUnit)] = &[];
+
+    /// Behavior for the records of an older version read from the object store (set using
+    /// `#[deli(envelope(migrate_on_read = ".."))]`)
+    #[cfg(feature = "envelope")]
+    #[doc(hidden)]
+    const MIGRATE_ON_READ: crate::envelope::MigrateOnRead =
+        crate::envelope::MigrateOnRead::InMemory;
+
+    /// Called on every record read from the object store after it is deserialized, e.g. to normalize legacy values or
+    /// to fill computed fields (set using `#[deli(on_read = "fn")]`)
+    fn on_read(&mut self) {}
+
+    /// Called on every record before it is written using `add`, `update` and cursor updates, e.g. to trim strings or
+    /// to lowercase emails so that the stored values are always in canonical form (set using
+    /// `#[deli(on_write = "fn")]`)
+    fn on_write(&mut self) {}
+
+    /// Whether the model has a write hook (records are only copied before being written if it does)
+    #[doc(hidden)]
+    const ON_WRITE: bool = false;
+
+    /// Whether records are serialized to native JS values instead of JSON compatible ones (set using
+    /// `#[deli(structured_clone)]`)
+    #[doc(hidden)]
+    const STRUCTURED_CLONE: bool = false;
+
+    /// Returns a copy of a value being added with the write hook applied (`None` if the model has no write hook)
+    #[doc(hidden)]
+    fn on_write_add(_value: &Self::Add) -> Result<Option<Self::Add>, Error> {
+        Ok(None)
+    }
+
+    /// Copies a value through the serializer of the records of the model (used to apply the write hook to values being
+    /// added, implemented by the derive macro)
+    #[doc(hidden)]
+    fn copy_value<V>(value: &V) -> Result<V, Error>
+    where
+        V: Serialize + DeserializeOwned,
+    {
+        serde_wasm_bindgen::from_value(value.serialize(record_serializer::<Self>())?)
+            .map_err(Into::into)
+    }
+
+    /// Serializes the primary key of the record (implemented by the derive macro)
+    #[doc(hidden)]
+    fn serialize_key<S>(&self, _serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        Err(serde::ser::Error::custom(format!(
+            "the key of `{}` records cannot be serialized",
+            Self::NAME
+        )))
+    }
+
+    /// Replaces the fields of the record which are set in the patch
+    fn apply_patch(&mut self, patch: Self::Patch);
+
     /// Get a store from given transaction
     fn with_transaction(transaction: &Transaction) -> Result<Self::ObjectStore<'_>, Error> {
         transaction.object_store::<Self>().map(Into::into)
     }
 
+    /// Returns a query builder over the records of the model (see [`query`](crate::query))
+    fn query() -> Self::Query {
+        QueryBuilder::new().into()
+    }
+
     /// Returns the object store builder for the model
     #[doc(hidden)]
     fn object_store_builder() -> ObjectStoreBuilder;
+
+    /// Returns the description of the object store of the model, from which schema fingerprints and
+    /// [snapshots](crate::testing::snapshot) are computed (implemented by the derive macro). The default only describes
+    /// the name of the object store, so models implemented manually should describe their key path and indexes as well.
+    fn store_schema() -> StoreSchema {
+        StoreSchema::new(Self::NAME)
+    }
+
+    /// Upgrades the data of a record stored in an envelope of an older version (set using
+    /// `#[deli(envelope(upgrade(from = N, with = "fn")))]`)
+    #[cfg(feature = "envelope")]
+    #[doc(hidden)]
+    fn upgrade(version: u32, _data: crate::envelope::Data) -> Result<Self, Error> {
+        Err(Error::UnsupportedEnvelopeVersion {
+            store: Self::NAME,
+            version,
+        })
+    }
+
+    /// Returns the values of the shadow fields of hashed indexes for the record
+    #[cfg(feature = "encryption")]
+    #[doc(hidden)]
+    fn hashed_fields(&self) -> Result<Vec<(&'static str, String)>, Error> {
+        Ok(Vec::new())
+    }
+
+    /// Returns the values of the shadow fields of hashed indexes for the value being added
+    #[cfg(feature = "encryption")]
+    #[doc(hidden)]
+    fn add_hashed_fields(_value: &Self::Add) -> Result<Vec<(&'static str, String)>, Error> {
+        Ok(Vec::new())
+    }
+}
+
+/// Applies the read hook of the model to a deserialized record.
+pub(crate) fn read<M>(mut value: M) -> M
+where
+    M: Model,
+{
+    value.on_read();
+    value
+}
+
+/// Returns a function deserializing the records read from the object store in the given transaction (unwrapping them
+/// from their envelope, if any) and applying the read hook of the model.
+pub(crate) fn read_stored<M, V>(transaction: &Transaction) -> impl Fn(V) -> Result<M, Error> + '_
+where
+    M: Model,
+    serde_wasm_bindgen::Deserializer: From<V>,
+{
+    move |value| decode_stored(transaction, value).map(read)
+}
+
+/// Deserializes a record as stored in the object store (unwrapping it from its envelope, if any), without applying the
+/// read hook of the model.
+pub(crate) fn decode_stored<M, V>(transaction: &Transaction, value: V) -> Result<M, Error>
+where
+    M: Model,
+    serde_wasm_bindgen::Deserializer: From<V>,
+{
+    let deserializer = serde_wasm_bindgen::Deserializer::from(value);
+
+    #[cfg(feature = "datetime")]
+    let deserializer = crate::datetime::read_deserializer::<M>(deserializer)?;
+
+    #[cfg(feature = "envelope")]
+    if M::ENVELOPE_VERSION.is_some() {
+        return crate::envelope::unwrap(transaction, deserializer);
+    }
+
+    #[cfg(not(feature = "envelope"))]
+    let _ = transaction;
+
+    M::deserialize(deserializer).map_err(Into::into)
+}
+
+/// Serialized record, as written to the object store (a `JsValue`, which cannot be named here as `wasm-bindgen` is an
+/// optional dependency).
+pub(crate) type StoredValue = <&'static serde_wasm_bindgen::Serializer as serde::Serializer>::Ok;
+
+/// Returns the serializer of the records of a model: the JSON compatible serializer, or the default serializer of
+/// `serde_wasm_bindgen` (maps as `Map`s, `None` as `undefined` and bytes as `Uint8Array`s) for models stored as
+/// structured clones.
+pub(crate) fn record_serializer<M>() -> &'static serde_wasm_bindgen::Serializer
+where
+    M: Model,
+{
+    const STRUCTURED_CLONE_SERIALIZER: serde_wasm_bindgen::Serializer =
+        serde_wasm_bindgen::Serializer::new();
+
+    if M::STRUCTURED_CLONE {
+        &STRUCTURED_CLONE_SERIALIZER
+    } else {
+        &JSON_SERIALIZER
+    }
+}
+
+/// Returns a copy of a record being written with the write hook of the model applied (`None` if the model has no write
+/// hook).
+pub(crate) fn write<M, V>(value: &V) -> Result<Option<M>, Error>
+where
+    M: Model,
+    V: Serialize + ?Sized,
+{
+    if !M::ON_WRITE {
+        return Ok(None);
+    }
+
+    let mut value: M = serde_wasm_bindgen::from_value(value.serialize(record_serializer::<M>())?)?;
+    value.on_write();
+
+    Ok(Some(value))
+}
+
+/// Returns a copy of a value being added with the write hook of the model applied (`None` if the model has no write
+/// hook).
+pub(crate) fn write_add<M>(value: &M::Add) -> Result<Option<M::Add>, Error>
+where
+    M: Model,
+{
+    if !M::ON_WRITE {
+        return Ok(None);
+    }
+
+    M::on_write_add(value)
+}
+
+/// Serializes a value being added to the object store of a model, as every add path writes it: the write hook is
+/// applied, the generated key, the shadow fields of hashed and nested indexes and the creation timestamp are filled,
+/// date/time and binary fields are converted and the record is wrapped in its envelope.
+pub(crate) fn serialize_add<M>(value: &M::Add) -> Result<StoredValue, Error>
+where
+    M: Model,
+{
+    let canonical = write_add::<M>(value)?;
+    let value = canonical.as_ref().unwrap_or(value);
+
+    serialize_record::<M, _>(value, |_js_value| {
+        #[cfg(feature = "key-generator")]
+        crate::key_generator::write_generated_key::<M>(_js_value)?;
+
+        #[cfg(feature = "encryption")]
+        crate::encryption::write_add_hashed_fields::<M>(value, _js_value)?;
+
+        #[cfg(feature = "timestamps")]
+        crate::timestamps::write_created_at::<M>(_js_value)?;
+
+        Ok(())
+    })
+}
+
+/// Serializes a record being put in the object store of a model, as every update path (including cursor updates and
+/// envelope write-backs) writes it: the write hook is applied, the shadow fields of hashed and nested indexes are
+/// filled, date/time and binary fields are converted and the record is wrapped in its envelope.
+pub(crate) fn serialize_put<M, V>(value: &V) -> Result<StoredValue, Error>
+where
+    M: Model + Borrow<V>,
+    V: Serialize,
+{
+    let canonical = write::<M, V>(value)?;
+    let value = canonical.as_ref().map_or(value, Borrow::borrow);
+
+    serialize_record::<M, _>(value, |_js_value| {
+        #[cfg(feature = "encryption")]
+        crate::encryption::write_hashed_fields::<M>(_js_value)?;
+
+        Ok(())
+    })
+}
+
+/// Serializes a record, fills the fields specific to the kind of write and applies the conversions shared by all
+/// writes.
+fn serialize_record<M, V>(
+    value: &V,
+    fill: impl FnOnce(&StoredValue) -> Result<(), Error>,
+) -> Result<StoredValue, Error>
+where
+    M: Model,
+    V: Serialize + ?Sized,
+{
+    let js_value = value.serialize(record_serializer::<M>())?;
+
+    fill(&js_value)?;
+
+    #[cfg(feature = "nested-index")]
+    crate::nested_index::write_nested_fields::<M>(&js_value)?;
+
+    #[cfg(feature = "datetime")]
+    crate::datetime::write_datetime_fields::<M>(&js_value)?;
+
+    #[cfg(feature = "bytes")]
+    crate::bytes::write_bytes_fields::<M>(&js_value)?;
+
+    #[cfg(feature = "envelope")]
+    let js_value = crate::envelope::wrap::<M>(js_value)?;
+
+    Ok(js_value)
 }
@@ -1,7 +1,7 @@
 use idb::builder::IndexBuilder;
 use serde::{de::DeserializeOwned, Serialize};
 
-use crate::model::Model;
+use crate::{error::Error, model::Model};
 
 /// Trait for defining indexes in an indexed db database model
 pub trait ModelIndex {
@@ -14,7 +14,66 @@ pub trait ModelIndex {
     /// Type of key for the index
     type Key: Serialize + DeserializeOwned;
 
+    /// Previous name of the index (`#[deli(index(alias = "..."))]`), used when the object store does not contain an
+    /// index named [`NAME`](Self::NAME) (e.g. in databases created with an older naming scheme)
+    #[doc(hidden)]
+    const ALIAS: Option<&'static str> = None;
+
+    /// Units of the components of the index key path which are stored as dates (`None` for other components, empty if
+    /// the index contains no date/time field, see [`datetime`](crate::datetime))
+    #[cfg(feature = "datetime")]
+    #[doc(hidden)]
+    const DATETIME: &'static [Option<crate::timestamps::TimestampUnit>] = &[];
+
     /// Returns the index builder for the index
     #[doc(hidden)]
     fn index_builder() -> IndexBuilder;
+
+    /// Serializes the key of the index for a record (implemented by the derive macro)
+    #[doc(hidden)]
+    fn serialize_key<S>(_model: &Self::Model, _serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        Err(serde::ser::Error::custom(format!(
+            "the key of index `{}` cannot be computed from records",
+            Self::NAME
+        )))
+    }
+}
+
+/// Returns the name of the index in the object store (its name, or its alias if the object store only contains an
+/// index with the previous name), or `None` if the object store does not contain the index.
+fn index_name<I>(object_store: &idb::ObjectStore) -> Option<&'static str>
+where
+    I: ModelIndex,
+{
+    let names = object_store.index_names();
+
+    [Some(I::NAME), I::ALIAS]
+        .into_iter()
+        .flatten()
+        .find(|name| names.iter().any(|index_name| index_name == name))
+}
+
+/// Returns `true` if the object store contains the index (under its name or its alias).
+pub(crate) fn has_index<I>(object_store: &idb::ObjectStore) -> bool
+where
+    I: ModelIndex,
+{
+    index_name::<I>(object_store).is_some()
+}
+
+/// Opens the index of an object store by its name, falling back to its alias if the object store does not contain an
+/// index with the current name. Fails with [`Error::IndexMissing`] if the object store contains neither.
+pub(crate) fn open_index<I>(object_store: &idb::ObjectStore) -> Result<idb::Index, Error>
+where
+    I: ModelIndex,
+{
+    let name = index_name::<I>(object_store).ok_or(Error::IndexMissing {
+        store: <I::Model as Model>::NAME,
+        index: I::NAME,
+    })?;
+
+    object_store.index(name).map_err(Into::into)
 }
@@ -0,0 +1,58 @@
+//! Indexes over values nested in arrays of objects.
+//!
+//! IndexedDB key paths cannot traverse arrays, so `#[deli(index(key_path = "items[].sku", key_type = "String"))]`
+//! creates a multi entry index over a shadow field of the record instead. `deli` extracts the values at the key path
+//! (every `[]` segment visits all the elements of an array) and writes them to the shadow field whenever a record is
+//! written.
+use js_sys::{Array, Reflect};
+use wasm_bindgen::JsValue;
+
+use crate::{error::Error, model::Model};
+
+/// Writes the shadow fields of nested indexes into a serialized record.
+pub(crate) fn write_nested_fields<M>(value: &JsValue) -> Result<(), Error>
+where
+    M: Model,
+{
+    for (name, path) in M::NESTED_FIELDS {
+        Reflect::set(
+            value,
+            &JsValue::from_str(name),
+            &extract(value, path)?.into(),
+        )
+        .map_err(serde_wasm_bindgen::Error::from)?;
+    }
+
+    Ok(())
+}
+
+/// Returns all the values found at the key path (missing values are skipped).
+fn extract(value: &JsValue, path: &str) -> Result<Array, Error> {
+    let mut values = vec![value.clone()];
+
+    for segment in path.split('.') {
+        let (property, each) = match segment.strip_suffix("[]") {
+            Some(property) => (property, true),
+            None => (segment, false),
+        };
+
+        let property = JsValue::from_str(property);
+        let mut next = Vec::new();
+
+        for value in values.iter().filter(|value| value.is_object()) {
+            let value = Reflect::get(value, &property).map_err(serde_wasm_bindgen::Error::from)?;
+
+            if each {
+                if Array::is_array(&value) {
+                    next.extend(Array::from(&value).iter());
+                }
+            } else if !value.is_undefined() && !value.is_null() {
+                next.push(value);
+            }
+        }
+
+        values = next;
+    }
+
+    Ok(values.into_iter().collect())
+}
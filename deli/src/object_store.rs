@@ -1,20 +1,46 @@
-use std::borrow::Borrow;
+use std::{borrow::Borrow, collections::HashMap, hash::Hash};
 
 use idb::{CursorDirection, Query};
 use serde::Serialize;
 
 use crate::{
-    cursor::Cursor,
+    cursor::{collect_entries, collect_values, has_get_all, Cursor},
     error::Error,
+    gate::Operation,
     index::Index,
-    key_cursor::KeyCursor,
+    key_cursor::{collect_primary_keys, KeyCursor},
     key_range::{BoundedRange, KeyRange, UnboundedRange},
-    model::Model,
-    model_index::ModelIndex,
+    lookup::Lookup,
+    model::{read_stored, serialize_add, serialize_put, Model},
+    model_index::{has_index, open_index, ModelIndex},
+    page::Page,
     transaction::Transaction,
     JSON_SERIALIZER,
 };
 
+/// Outcome of [`ObjectStore::upsert`], with the key of the record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Upsert<K> {
+    /// Record was added to the store
+    Created(K),
+    /// Record replaced the stored record with the same key
+    Replaced(K),
+}
+
+impl<K> Upsert<K> {
+    /// Returns the key of the record.
+    pub fn key(&self) -> &K {
+        match self {
+            Self::Created(key) | Self::Replaced(key) => key,
+        }
+    }
+
+    /// Returns `true` if the record was added to the store.
+    pub fn is_created(&self) -> bool {
+        matches!(self, Self::Created(_))
+    }
+}
+
 /// Represents an object store in a database.
 #[derive(Debug)]
 pub struct ObjectStore<'t, M> {
@@ -41,15 +67,116 @@ where
         key_range: impl Into<KeyRange<'a, Q, BoundedRange>>,
     ) -> Result<Option<M>, Error>
     where
-        M::Key: Borrow<Q>,
+        M: Lookup<Q>,
         Q: Serialize + ?Sized + 'a,
     {
+        self.check(Operation::Read)?;
+
         self.object_store
             .get(Query::try_from(&key_range.into())?)?
             .await?
-            .map(serde_wasm_bindgen::from_value)
+            .map(read_stored(self.transaction))
             .transpose()
-            .map_err(Into::into)
+    }
+
+    /// Retrieves the records with the given keys (`None` for keys without a record), in the order of the keys. All the
+    /// requests are issued before awaiting any of them, so the lookups run concurrently within the transaction.
+    pub async fn get_many<Q, K>(&self, keys: &[K]) -> Result<Vec<Option<M>>, Error>
+    where
+        M: Lookup<Q>,
+        K: Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        self.check(Operation::Read)?;
+
+        let mut requests = Vec::with_capacity(keys.len());
+
+        for key in keys {
+            let key_range = KeyRange::<'_, Q, BoundedRange>::from(key.borrow());
+            requests.push(self.object_store.get(Query::try_from(&key_range)?)?);
+        }
+
+        let mut values = Vec::with_capacity(requests.len());
+
+        for request in requests {
+            values.push(
+                request
+                    .await?
+                    .map(read_stored(self.transaction))
+                    .transpose()?,
+            );
+        }
+
+        Ok(values)
+    }
+
+    /// Retrieves the value of the record with the smallest primary key matching the given key range.
+    pub async fn first<'a, Q>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+    ) -> Result<Option<M>, Error>
+    where
+        M: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+    {
+        self.get_first(key_range, CursorDirection::Next).await
+    }
+
+    /// Retrieves the value of the record with the greatest primary key matching the given key range (e.g. the most
+    /// recent record of an auto-incremented store).
+    pub async fn last<'a, Q>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+    ) -> Result<Option<M>, Error>
+    where
+        M: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+    {
+        self.get_first(key_range, CursorDirection::Prev).await
+    }
+
+    /// Retrieves the value of the first record matching the given key range in the given direction. Only the first
+    /// record is read.
+    async fn get_first<'a, Q>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+        direction: CursorDirection,
+    ) -> Result<Option<M>, Error>
+    where
+        M: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+    {
+        match self.cursor(key_range, Some(direction)).await? {
+            Some(cursor) => cursor.value(),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the smallest primary key in the store (`None` if the store is empty).
+    pub async fn min_key(&self) -> Result<Option<M::Key>, Error> {
+        self.edge_key(CursorDirection::Next).await
+    }
+
+    /// Returns the greatest primary key in the store (`None` if the store is empty), e.g. to allocate client-side ids
+    /// or to checkpoint a range-based sync.
+    pub async fn max_key(&self) -> Result<Option<M::Key>, Error> {
+        self.edge_key(CursorDirection::Prev).await
+    }
+
+    /// Returns the first primary key of the store in the given direction using a key cursor, so that no value is read.
+    async fn edge_key(&self, direction: CursorDirection) -> Result<Option<M::Key>, Error> {
+        self.check(Operation::Read)?;
+
+        match self
+            .object_store
+            .open_key_cursor(None, Some(direction))?
+            .await?
+        {
+            Some(cursor) => {
+                KeyCursor::<M, M::Key>::new(cursor.into_managed(), self.transaction).key()
+            }
+            None => Ok(None),
+        }
     }
 
     /// Retrieves the key of the first record matching the given key range.
@@ -58,9 +185,11 @@ where
         key_range: impl Into<KeyRange<'a, Q, BoundedRange>>,
     ) -> Result<Option<M::Key>, Error>
     where
-        M::Key: Borrow<Q>,
+        M: Lookup<Q>,
         Q: Serialize + ?Sized + 'a,
     {
+        self.check(Operation::Read)?;
+
         self.object_store
             .get_key(Query::try_from(&key_range.into())?)?
             .await?
@@ -69,49 +198,313 @@ where
             .map_err(Into::into)
     }
 
-    /// Retrieves all the values of the records matching the given key range (up to limit if given).
+    /// Returns `true` if a record matches the given key range. The value of the record is not read.
+    pub async fn exists<'a, Q>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, BoundedRange>>,
+    ) -> Result<bool, Error>
+    where
+        M: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+    {
+        self.check(Operation::Read)?;
+
+        self.object_store
+            .get_key(Query::try_from(&key_range.into())?)?
+            .await
+            .map(|key| key.is_some())
+            .map_err(Into::into)
+    }
+
+    /// Retrieves all the values of the records matching the given key range (up to limit if given, otherwise up to the
+    /// model's [`DEFAULT_LIMIT`](Model::DEFAULT_LIMIT)). Use [`ordered_get_all`](Self::ordered_get_all) if the order
+    /// of the results matters.
     pub async fn get_all<'a, Q>(
         &self,
         key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
         limit: Option<u32>,
     ) -> Result<Vec<M>, Error>
     where
-        M::Key: Borrow<Q>,
+        M: Lookup<Q>,
         Q: Serialize + ?Sized + 'a,
     {
-        self.object_store
-            .get_all(<Option<Query>>::try_from(&key_range.into())?, limit)?
-            .await?
+        self.check(Operation::Read)?;
+
+        let query = <Option<Query>>::try_from(&key_range.into())?;
+        let limit = limit.or(M::DEFAULT_LIMIT);
+
+        if !has_get_all() {
+            let cursor = self.object_store.open_cursor(query, None)?.await?;
+            return collect_values(self.transaction, cursor, 0, limit).await;
+        }
+
+        #[cfg(feature = "cache")]
+        let values =
+            crate::cache::get_all(self.transaction, &self.object_store, M::NAME, query, limit)
+                .await?;
+
+        #[cfg(not(feature = "cache"))]
+        let values = self.object_store.get_all(query, limit)?.await?;
+
+        let values = values
             .into_iter()
-            .map(serde_wasm_bindgen::from_value)
-            .collect::<Result<_, _>>()
-            .map_err(Into::into)
+            .map(read_stored(self.transaction))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        #[cfg(feature = "shuffle-get-all")]
+        let values = crate::shuffle::shuffled(values);
+
+        Ok(values)
     }
 
-    /// Retrieves all the keys of the records matching the given key range (up to limit if given).
+    /// Retrieves the values of the records matching the given key range for which the predicate returns `true`, in
+    /// primary key order (up to limit if given, otherwise up to the model's [`DEFAULT_LIMIT`](Model::DEFAULT_LIMIT)).
+    /// Records are read one by one using a cursor and reading stops once limit records matched, so that records
+    /// filtered out are never held in memory together.
+    pub async fn get_all_filtered<'a, Q, F>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+        mut predicate: F,
+        limit: Option<u32>,
+    ) -> Result<Vec<M>, Error>
+    where
+        M: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+        F: FnMut(&M) -> bool,
+    {
+        let limit = limit.or(M::DEFAULT_LIMIT);
+        let mut values = Vec::new();
+
+        if let Some(mut cursor) = self.cursor(key_range, None).await? {
+            while limit.is_none_or(|limit| values.len() < limit as usize) {
+                let Some((_, value)) = cursor.next_record().await? else {
+                    break;
+                };
+
+                if predicate(&value) {
+                    values.push(value);
+                }
+            }
+        }
+
+        Ok(values)
+    }
+
+    /// Retrieves the values of the records matching the given key range after skipping offset records, in
+    /// primary key order (up to limit if given, otherwise up to the model's [`DEFAULT_LIMIT`](Model::DEFAULT_LIMIT)). The
+    /// records are skipped using a cursor, so they are not deserialized.
+    pub async fn get_all_with_offset<'a, Q>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+        offset: u32,
+        limit: Option<u32>,
+    ) -> Result<Vec<M>, Error>
+    where
+        M: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+    {
+        if offset == 0 {
+            return self.get_all(key_range, limit).await;
+        }
+
+        self.check(Operation::Read)?;
+
+        let cursor = self
+            .object_store
+            .open_cursor(<Option<Query>>::try_from(&key_range.into())?, None)?
+            .await?;
+        collect_values(self.transaction, cursor, offset, limit.or(M::DEFAULT_LIMIT)).await
+    }
+
+    /// Retrieves all the values of the records matching the given key range in primary key order (ascending for
+    /// [`Next`](CursorDirection::Next), descending for [`Prev`](CursorDirection::Prev)), up to limit if given,
+    /// otherwise up to the model's [`DEFAULT_LIMIT`](Model::DEFAULT_LIMIT).
+    pub async fn ordered_get_all<'a, Q>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+        direction: CursorDirection,
+        limit: Option<u32>,
+    ) -> Result<Vec<M>, Error>
+    where
+        M: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+    {
+        self.check(Operation::Read)?;
+
+        let query = <Option<Query>>::try_from(&key_range.into())?;
+        let limit = limit.or(M::DEFAULT_LIMIT);
+
+        match direction {
+            // `getAll()` returns the records in ascending key order and primary keys are unique
+            CursorDirection::Next | CursorDirection::NextUnique if has_get_all() => self
+                .object_store
+                .get_all(query, limit)?
+                .await?
+                .into_iter()
+                .map(read_stored(self.transaction))
+                .collect::<Result<_, _>>(),
+            _ => {
+                let cursor = self
+                    .object_store
+                    .open_cursor(query, Some(direction))?
+                    .await?;
+                collect_values(self.transaction, cursor, 0, limit).await
+            }
+        }
+    }
+
+    /// Retrieves all the keys of the records matching the given key range (up to limit if given, otherwise up to the
+    /// model's [`DEFAULT_LIMIT`](Model::DEFAULT_LIMIT)).
     pub async fn get_all_keys<'a, Q>(
         &self,
         key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
         limit: Option<u32>,
     ) -> Result<Vec<M::Key>, Error>
     where
-        M::Key: Borrow<Q>,
+        M: Lookup<Q>,
         Q: Serialize + ?Sized + 'a,
     {
-        self.object_store
-            .get_all_keys(<Option<Query>>::try_from(&key_range.into())?, limit)?
+        self.check(Operation::Read)?;
+
+        let query = <Option<Query>>::try_from(&key_range.into())?;
+        let limit = limit.or(M::DEFAULT_LIMIT);
+
+        if !has_get_all() {
+            let cursor = self.object_store.open_key_cursor(query, None)?.await?;
+            return collect_primary_keys(self.transaction, cursor, 0, limit).await;
+        }
+
+        let keys = self
+            .object_store
+            .get_all_keys(query, limit)?
             .await?
             .into_iter()
             .map(serde_wasm_bindgen::from_value)
-            .collect::<Result<_, _>>()
-            .map_err(Into::into)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        #[cfg(feature = "shuffle-get-all")]
+        let keys = crate::shuffle::shuffled(keys);
+
+        Ok(keys)
+    }
+
+    /// Retrieves all the keys of the records matching the given key range in primary key order (ascending for
+    /// [`Next`](CursorDirection::Next), descending for [`Prev`](CursorDirection::Prev)), up to limit if given,
+    /// otherwise up to the model's [`DEFAULT_LIMIT`](Model::DEFAULT_LIMIT).
+    pub async fn ordered_get_all_keys<'a, Q>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+        direction: CursorDirection,
+        limit: Option<u32>,
+    ) -> Result<Vec<M::Key>, Error>
+    where
+        M: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+    {
+        self.check(Operation::Read)?;
+
+        let query = <Option<Query>>::try_from(&key_range.into())?;
+        let limit = limit.or(M::DEFAULT_LIMIT);
+
+        match direction {
+            // `getAllKeys()` returns the keys in ascending order
+            CursorDirection::Next | CursorDirection::NextUnique if has_get_all() => self
+                .object_store
+                .get_all_keys(query, limit)?
+                .await?
+                .into_iter()
+                .map(serde_wasm_bindgen::from_value)
+                .collect::<Result<_, _>>()
+                .map_err(Into::into),
+            _ => {
+                let cursor = self
+                    .object_store
+                    .open_key_cursor(query, Some(direction))?
+                    .await?;
+                collect_primary_keys(self.transaction, cursor, 0, limit).await
+            }
+        }
+    }
+
+    /// Retrieves the primary keys of the records matching the given key range after skipping offset records, in
+    /// primary key order (up to limit if given, otherwise up to the model's [`DEFAULT_LIMIT`](Model::DEFAULT_LIMIT)).
+    pub async fn get_all_keys_with_offset<'a, Q>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+        offset: u32,
+        limit: Option<u32>,
+    ) -> Result<Vec<M::Key>, Error>
+    where
+        M: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+    {
+        if offset == 0 {
+            return self.get_all_keys(key_range, limit).await;
+        }
+
+        self.check(Operation::Read)?;
+
+        let cursor = self
+            .object_store
+            .open_key_cursor(<Option<Query>>::try_from(&key_range.into())?, None)?
+            .await?;
+        collect_primary_keys(self.transaction, cursor, offset, limit.or(M::DEFAULT_LIMIT)).await
+    }
+
+    /// Retrieves the records matching the given key range (up to limit if given, otherwise up to the model's
+    /// [`DEFAULT_LIMIT`](Model::DEFAULT_LIMIT)) in a map keyed by primary key.
+    pub async fn get_all_map<'a, Q>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+        limit: Option<u32>,
+    ) -> Result<HashMap<M::Key, M>, Error>
+    where
+        M: Lookup<Q>,
+        M::Key: Eq + Hash,
+        Q: Serialize + ?Sized + 'a,
+    {
+        self.check(Operation::Read)?;
+
+        let query = <Option<Query>>::try_from(&key_range.into())?;
+        let limit = limit.or(M::DEFAULT_LIMIT);
+
+        if !has_get_all() {
+            let cursor = self.object_store.open_cursor(query, None)?.await?;
+            return Ok(collect_entries(self.transaction, cursor, limit)
+                .await?
+                .into_iter()
+                .collect());
+        }
+
+        // Both requests return the records in primary key order
+        let keys = self.object_store.get_all_keys(query.clone(), limit)?;
+        let values = self.object_store.get_all(query, limit)?;
+
+        keys.await?
+            .into_iter()
+            .zip(values.await?)
+            .map(|(key, value)| {
+                Ok((
+                    serde_wasm_bindgen::from_value(key)?,
+                    read_stored(self.transaction)(value)?,
+                ))
+            })
+            .collect()
     }
 
     /// Adds a record to the store returning its key
     pub async fn add(&self, value: &M::Add) -> Result<M::Key, Error> {
-        let value = value.serialize(&JSON_SERIALIZER)?;
-        let js_key = self.object_store.add(&value, None)?.await?;
-        serde_wasm_bindgen::from_value(js_key).map_err(Into::into)
+        self.add_keyed::<M::Key>(None, value).await
+    }
+
+    /// Adds a record to the store with the given key, for models whose key is stored outside of their records
+    /// (`#[deli(external_key = "..")]`)
+    pub async fn add_with_key<Q>(&self, key: &Q, value: &M::Add) -> Result<M::Key, Error>
+    where
+        M: Lookup<Q>,
+        Q: Serialize + ?Sized,
+    {
+        self.add_keyed(Some(key), value).await
     }
 
     /// Updates a record in the store returning its key
@@ -120,9 +513,162 @@ where
         M: Borrow<V>,
         V: Serialize,
     {
-        let value = value.serialize(&JSON_SERIALIZER)?;
-        let js_key = self.object_store.put(&value, None)?.await?;
-        serde_wasm_bindgen::from_value(js_key).map_err(Into::into)
+        self.put_keyed::<M::Key, V>(None, value).await
+    }
+
+    /// Adds or replaces the record with the given key, for models whose key is stored outside of their records
+    /// (`#[deli(external_key = "..")]`)
+    pub async fn put_with_key<Q, V>(&self, key: &Q, value: &V) -> Result<M::Key, Error>
+    where
+        M: Lookup<Q> + Borrow<V>,
+        Q: Serialize + ?Sized,
+        V: Serialize,
+    {
+        self.put_keyed(Some(key), value).await
+    }
+
+    /// Replaces the fields set in the patch of the record with the given key, and returns the updated record (or `None`
+    /// if the store does not contain a record with the key). The record is read and written in the transaction of the
+    /// store, so that fields updated concurrently by other transactions are not overwritten.
+    pub async fn patch<Q>(&self, key: &Q, patch: M::Patch) -> Result<Option<M>, Error>
+    where
+        M: Lookup<Q>,
+        Q: Serialize + ?Sized,
+    {
+        let Some(mut value) = self.get(key).await? else {
+            return Ok(None);
+        };
+
+        value.apply_patch(patch);
+        self.update(&value).await?;
+
+        Ok(Some(value))
+    }
+
+    /// Updates the records matching the given key range for which the function returns a new value, and returns the
+    /// number of records updated. Records are read and updated one by one using a cursor (see
+    /// [`Cursor::update`](crate::Cursor::update)).
+    pub async fn update_where<'a, Q, F>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+        mut f: F,
+    ) -> Result<u32, Error>
+    where
+        M: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+        F: FnMut(M) -> Option<M>,
+    {
+        self.check(Operation::Update)?;
+
+        let mut updated = 0;
+
+        if let Some(mut cursor) = self.cursor(key_range, None).await? {
+            while let Some((_, value)) = cursor.next_record().await? {
+                if let Some(value) = f(value) {
+                    cursor.update(&value).await?;
+                    updated += 1;
+                }
+            }
+        }
+
+        Ok(updated)
+    }
+
+    /// Adds a value to the store without waiting for the request to complete. Failures are reported to the error hook
+    /// (see [`detached`](crate::detached)).
+    #[cfg(feature = "detached")]
+    pub fn add_detached(&self, value: &M::Add) -> Result<(), Error>
+    where
+        M: 'static,
+    {
+        self.check(Operation::Add)?;
+
+        let js_value = serialize_add::<M>(value)?;
+
+        let request = self.object_store.add(&js_value, None)?;
+
+        #[cfg(feature = "inspect")]
+        let size_store = crate::inspect::size_store(self.transaction)?;
+
+        crate::detached::spawn(M::NAME, Operation::Add, async move {
+            let _js_key = request.await?;
+
+            #[cfg(feature = "inspect")]
+            crate::inspect::record_in::<M>(size_store, _js_key, &js_value).await?;
+
+            Ok(())
+        });
+
+        Ok(())
+    }
+
+    /// Updates (or adds) a record in the store without waiting for the request to complete. Failures are reported to
+    /// the error hook (see [`detached`](crate::detached)).
+    #[cfg(feature = "detached")]
+    pub fn update_detached<V>(&self, value: &V) -> Result<(), Error>
+    where
+        M: Borrow<V> + 'static,
+        V: Serialize,
+    {
+        self.check(Operation::Update)?;
+
+        let js_value = serialize_put::<M, V>(value)?;
+
+        let request = self.object_store.put(&js_value, None)?;
+
+        #[cfg(feature = "inspect")]
+        let size_store = crate::inspect::size_store(self.transaction)?;
+
+        crate::detached::spawn(M::NAME, Operation::Update, async move {
+            let _js_key = request.await?;
+
+            #[cfg(feature = "inspect")]
+            crate::inspect::record_in::<M>(size_store, _js_key, &js_value).await?;
+
+            Ok(())
+        });
+
+        Ok(())
+    }
+
+    /// Updates (or adds) multiple records in the store returning their keys. All the requests are issued before awaiting
+    /// any of them, which avoids a round trip per record compared to calling [`update`](Self::update) in a loop.
+    pub async fn put_all<V>(&self, values: &[V]) -> Result<Vec<M::Key>, Error>
+    where
+        M: Borrow<V>,
+        V: Serialize,
+    {
+        self.check(Operation::Update)?;
+
+        let mut requests = Vec::with_capacity(values.len());
+        #[cfg(feature = "inspect")]
+        let mut js_values = Vec::with_capacity(values.len());
+
+        for value in values {
+            let js_value = serialize_put::<M, V>(value)?;
+
+            requests.push(self.object_store.put(&js_value, None)?);
+
+            #[cfg(feature = "inspect")]
+            js_values.push(js_value);
+        }
+
+        let mut js_keys = Vec::with_capacity(requests.len());
+
+        for request in requests {
+            js_keys.push(request.await?);
+        }
+
+        #[cfg(feature = "inspect")]
+        for (js_key, js_value) in js_keys.iter().zip(js_values.iter()) {
+            crate::inspect::record::<M>(self.transaction, js_key.clone(), js_value).await?;
+        }
+
+        js_keys
+            .into_iter()
+            .map(serde_wasm_bindgen::from_value)
+            .collect::<Result<_, _>>()
+            .map_err(Into::into)
     }
 
     /// Deletes records in store with the given key range.
@@ -131,18 +677,372 @@ where
         key_range: impl Into<KeyRange<'a, Q, BoundedRange>>,
     ) -> Result<(), Error>
     where
-        M::Key: Borrow<Q>,
+        M: Lookup<Q>,
         Q: Serialize + ?Sized + 'a,
     {
-        self.object_store
-            .delete(Query::try_from(&key_range.into())?)?
-            .await
-            .map_err(Into::into)
+        self.check(Operation::Delete)?;
+
+        let key_range = key_range.into();
+
+        #[cfg(feature = "sync")]
+        let keys = self
+            .tombstone_keys(Some(Query::try_from(&key_range)?))
+            .await?;
+
+        let query = Query::try_from(&key_range)?;
+
+        self.object_store.delete(query.clone())?.await?;
+
+        #[cfg(feature = "sync")]
+        crate::sync::record_tombstones::<M>(self.transaction, keys).await?;
+
+        #[cfg(feature = "inspect")]
+        crate::inspect::forget::<M>(self.transaction, Some(&query)).await?;
+
+        Ok(())
+    }
+
+    /// Deletes the records matching the given key range for which the predicate returns `true`, and returns the number
+    /// of records deleted. Records are read and deleted one by one using a cursor (see
+    /// [`Cursor::delete`](crate::Cursor::delete)).
+    pub async fn delete_where<'a, Q, F>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+        mut predicate: F,
+    ) -> Result<u32, Error>
+    where
+        M: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+        F: FnMut(&M) -> bool,
+    {
+        self.check(Operation::Delete)?;
+
+        let mut deleted = 0;
+
+        if let Some(mut cursor) = self.cursor(key_range, None).await? {
+            while let Some((_, value)) = cursor.next_record().await? {
+                if predicate(&value) {
+                    cursor.delete().await?;
+                    deleted += 1;
+                }
+            }
+        }
+
+        Ok(deleted)
+    }
+
+    /// Deletes the records with the given keys. All the deletes are issued before awaiting any of them, within the
+    /// transaction.
+    pub async fn delete_many<Q, K>(&self, keys: &[K]) -> Result<(), Error>
+    where
+        M: Lookup<Q>,
+        K: Borrow<Q>,
+        Q: Serialize + ?Sized,
+    {
+        self.check(Operation::Delete)?;
+
+        let mut queries = Vec::with_capacity(keys.len());
+
+        for key in keys {
+            let key_range = KeyRange::<'_, Q, BoundedRange>::from(key.borrow());
+            queries.push(Query::try_from(&key_range)?);
+        }
+
+        #[cfg(feature = "sync")]
+        let mut tombstone_keys = Vec::new();
+
+        #[cfg(feature = "sync")]
+        for query in queries.iter() {
+            tombstone_keys.extend(self.tombstone_keys(Some(query.clone())).await?);
+        }
+
+        let mut requests = Vec::with_capacity(queries.len());
+
+        for query in queries.iter() {
+            requests.push(self.object_store.delete(query.clone())?);
+        }
+
+        for request in requests {
+            request.await?;
+        }
+
+        #[cfg(feature = "sync")]
+        crate::sync::record_tombstones::<M>(self.transaction, tombstone_keys).await?;
+
+        #[cfg(feature = "inspect")]
+        for query in queries.iter() {
+            crate::inspect::forget::<M>(self.transaction, Some(query)).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds multiple records to the store, reporting the outcome of every record instead of failing on the first error
+    /// (see [`batch`](crate::batch)).
+    #[cfg(feature = "batch")]
+    pub async fn add_all_batch(
+        &self,
+        values: &[M::Add],
+        on_error: crate::batch::OnError,
+    ) -> Result<crate::batch::BatchResult<M::Key>, Error> {
+        self.check(Operation::Add)?;
+
+        let _keep_alive = (on_error == crate::batch::OnError::Continue)
+            .then(|| crate::batch::KeepAlive::new(self.object_store.transaction()));
+
+        let mut result = crate::batch::BatchResult::new();
+        let mut requests = Vec::with_capacity(values.len());
+
+        for (index, value) in values.iter().enumerate() {
+            let request = serialize_add::<M>(value).and_then(|js_value| {
+                let request = self.object_store.add(&js_value, None)?;
+                Ok((request, js_value))
+            });
+
+            match request {
+                Ok((request, js_value)) => requests.push((index, request, js_value)),
+                Err(error) => result.failed.push(crate::batch::BatchFailure {
+                    index,
+                    key: None,
+                    error,
+                }),
+            }
+        }
+
+        let mut written = Vec::with_capacity(requests.len());
+        let mut aborted = false;
+
+        for (index, request, js_value) in requests {
+            match request.await {
+                Ok(js_key) => written.push((index, js_key, js_value)),
+                Err(error) => {
+                    aborted = on_error == crate::batch::OnError::Abort;
+
+                    result.failed.push(crate::batch::BatchFailure {
+                        index,
+                        key: None,
+                        error: error.into(),
+                    });
+                }
+            }
+        }
+
+        self.finish_writes(result, written, aborted).await
+    }
+
+    /// Updates (or adds) multiple records in the store, reporting the outcome of every record instead of failing on the
+    /// first error (see [`batch`](crate::batch)).
+    #[cfg(feature = "batch")]
+    pub async fn put_all_batch<V>(
+        &self,
+        values: &[V],
+        on_error: crate::batch::OnError,
+    ) -> Result<crate::batch::BatchResult<M::Key>, Error>
+    where
+        M: Borrow<V>,
+        V: Serialize,
+    {
+        self.check(Operation::Update)?;
+
+        let _keep_alive = (on_error == crate::batch::OnError::Continue)
+            .then(|| crate::batch::KeepAlive::new(self.object_store.transaction()));
+
+        let mut result = crate::batch::BatchResult::new();
+        let mut requests = Vec::with_capacity(values.len());
+
+        for (index, value) in values.iter().enumerate() {
+            let request = serialize_put::<M, V>(value).and_then(|js_value| {
+                let request = self.object_store.put(&js_value, None)?;
+                Ok((request, js_value))
+            });
+
+            match request {
+                Ok((request, js_value)) => requests.push((index, request, js_value)),
+                Err(error) => result.failed.push(crate::batch::BatchFailure {
+                    index,
+                    key: None,
+                    error,
+                }),
+            }
+        }
+
+        let mut written = Vec::with_capacity(requests.len());
+        let mut aborted = false;
+
+        for (index, request, js_value) in requests {
+            match request.await {
+                Ok(js_key) => written.push((index, js_key, js_value)),
+                Err(error) => {
+                    aborted = on_error == crate::batch::OnError::Abort;
+
+                    result.failed.push(crate::batch::BatchFailure {
+                        index,
+                        key: None,
+                        error: error.into(),
+                    });
+                }
+            }
+        }
+
+        self.finish_writes(result, written, aborted).await
+    }
+
+    /// Deletes the records with the given keys, reporting the outcome of every key instead of failing on the first
+    /// error (see [`batch`](crate::batch)).
+    #[cfg(feature = "batch")]
+    pub async fn delete_many_batch<Q, K>(
+        &self,
+        keys: &[K],
+        on_error: crate::batch::OnError,
+    ) -> Result<crate::batch::BatchResult<K>, Error>
+    where
+        M: Lookup<Q>,
+        K: Borrow<Q> + Clone,
+        Q: Serialize + ?Sized,
+    {
+        self.check(Operation::Delete)?;
+
+        let _keep_alive = (on_error == crate::batch::OnError::Continue)
+            .then(|| crate::batch::KeepAlive::new(self.object_store.transaction()));
+
+        let mut result = crate::batch::BatchResult::new();
+        let mut requests = Vec::with_capacity(keys.len());
+        #[cfg(feature = "sync")]
+        let mut tombstone_keys = Vec::with_capacity(keys.len());
+
+        for (index, key) in keys.iter().enumerate() {
+            let key_range = KeyRange::<'_, Q, BoundedRange>::from(key.borrow());
+
+            let query = match Query::try_from(&key_range) {
+                Ok(query) => query,
+                Err(error) => {
+                    result.failed.push(crate::batch::BatchFailure {
+                        index,
+                        key: Some(key.clone()),
+                        error,
+                    });
+                    continue;
+                }
+            };
+
+            #[cfg(feature = "sync")]
+            tombstone_keys.push(self.tombstone_keys(Some(query.clone())).await?);
+
+            match self.object_store.delete(query.clone()) {
+                Ok(request) => requests.push((index, key, query, request)),
+                Err(error) => {
+                    #[cfg(feature = "sync")]
+                    tombstone_keys.pop();
+
+                    result.failed.push(crate::batch::BatchFailure {
+                        index,
+                        key: Some(key.clone()),
+                        error: error.into(),
+                    });
+                }
+            }
+        }
+
+        let mut deleted = Vec::with_capacity(requests.len());
+        let mut aborted = false;
+
+        for (index, key, _query, request) in requests {
+            match request.await {
+                Ok(()) => {
+                    result.succeeded.push((index, key.clone()));
+                    deleted.push(_query);
+                }
+                Err(error) => {
+                    aborted = on_error == crate::batch::OnError::Abort;
+
+                    result.failed.push(crate::batch::BatchFailure {
+                        index,
+                        key: Some(key.clone()),
+                        error: error.into(),
+                    });
+                }
+            }
+        }
+
+        // Nothing needs to be recorded for deletes rolled back by the abort of the transaction
+        if !aborted {
+            #[cfg(feature = "sync")]
+            crate::sync::record_tombstones::<M>(
+                self.transaction,
+                tombstone_keys.into_iter().flatten().collect(),
+            )
+            .await?;
+
+            #[cfg(feature = "inspect")]
+            for query in deleted.iter() {
+                crate::inspect::forget::<M>(self.transaction, Some(query)).await?;
+            }
+        }
+
+        result.failed.sort_by_key(|failure| failure.index);
+
+        Ok(result)
+    }
+
+    /// Records the sizes of the written records of a batch and adds their keys to the result.
+    #[cfg(feature = "batch")]
+    async fn finish_writes(
+        &self,
+        mut result: crate::batch::BatchResult<M::Key>,
+        written: Vec<(usize, wasm_bindgen::JsValue, wasm_bindgen::JsValue)>,
+        _aborted: bool,
+    ) -> Result<crate::batch::BatchResult<M::Key>, Error> {
+        for (index, js_key, _js_value) in written {
+            // Writes rolled back by the abort of the transaction have no size to record
+            #[cfg(feature = "inspect")]
+            if !_aborted {
+                crate::inspect::record::<M>(self.transaction, js_key.clone(), &_js_value).await?;
+            }
+
+            match serde_wasm_bindgen::from_value(js_key) {
+                Ok(key) => result.succeeded.push((index, key)),
+                Err(error) => result.failed.push(crate::batch::BatchFailure {
+                    index,
+                    key: None,
+                    error: error.into(),
+                }),
+            }
+        }
+
+        result.failed.sort_by_key(|failure| failure.index);
+
+        Ok(result)
     }
 
     /// Clears all records in the store.
     pub async fn delete_all(&self) -> Result<(), Error> {
-        self.object_store.clear()?.await.map_err(Into::into)
+        self.check(Operation::Delete)?;
+
+        #[cfg(feature = "sync")]
+        let keys = self.tombstone_keys(None).await?;
+
+        self.object_store.clear()?.await?;
+
+        #[cfg(feature = "sync")]
+        crate::sync::record_tombstones::<M>(self.transaction, keys).await?;
+
+        #[cfg(feature = "inspect")]
+        crate::inspect::forget::<M>(self.transaction, None).await?;
+
+        Ok(())
+    }
+
+    /// Returns the primary keys which need a tombstone when deleting records matching the query.
+    #[cfg(feature = "sync")]
+    async fn tombstone_keys(
+        &self,
+        query: Option<Query>,
+    ) -> Result<Vec<wasm_bindgen::JsValue>, Error> {
+        if M::TOMBSTONES.is_none() {
+            return Ok(Vec::new());
+        }
+
+        crate::cursor::get_all_keys_stored(self.transaction, &self.object_store, query, None).await
     }
 
     /// Retrieves the number of records matching the given key range.
@@ -151,9 +1051,11 @@ where
         key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
     ) -> Result<u32, Error>
     where
-        M::Key: Borrow<Q>,
+        M: Lookup<Q>,
         Q: Serialize + ?Sized + 'a,
     {
+        self.check(Operation::Read)?;
+
         self.object_store
             .count(<Option<Query>>::try_from(&key_range.into())?)?
             .await
@@ -167,9 +1069,11 @@ where
         cursor_direction: Option<CursorDirection>,
     ) -> Result<Option<Cursor<'t, M, M::Key>>, Error>
     where
-        M::Key: Borrow<Q>,
+        M: Lookup<Q>,
         Q: Serialize + ?Sized + 'a,
     {
+        self.check(Operation::Read)?;
+
         Ok(self
             .object_store
             .open_cursor(
@@ -180,6 +1084,63 @@ where
             .map(|cursor| Cursor::new(cursor.into_managed(), self.transaction)))
     }
 
+    /// Returns a stream of the records matching key range, ordered by direction. Records are read one by one using a
+    /// cursor, so large stores can be consumed incrementally instead of being materialized using
+    /// [`get_all`](Self::get_all).
+    #[cfg(feature = "stream")]
+    pub async fn stream<'a, Q>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+        cursor_direction: Option<CursorDirection>,
+    ) -> Result<impl futures_util::Stream<Item = Result<M, Error>> + Unpin + 't, Error>
+    where
+        M: Lookup<Q> + 't,
+        Q: Serialize + ?Sized + 'a,
+    {
+        let cursor = self.cursor(key_range, cursor_direction).await?;
+        Ok(crate::cursor::into_stream(cursor))
+    }
+
+    /// Retrieves the page with the given (zero based) number of the records matching key range, ordered by direction.
+    /// The records of the previous pages are skipped using a cursor, so they are not deserialized.
+    pub async fn page<'a, Q>(
+        &self,
+        key_range: impl Into<KeyRange<'a, Q, UnboundedRange>>,
+        cursor_direction: Option<CursorDirection>,
+        page: u32,
+        page_size: u32,
+    ) -> Result<Page<M>, Error>
+    where
+        M: Lookup<Q>,
+        Q: Serialize + ?Sized + 'a,
+    {
+        let mut records = Vec::new();
+
+        let Some(mut cursor) = self.cursor(key_range, cursor_direction).await? else {
+            return Ok(Page {
+                records,
+                has_more: false,
+            });
+        };
+
+        // `advance()` fails with a count of zero
+        let offset = page.saturating_mul(page_size);
+        if offset > 0 {
+            cursor.advance(offset).await?;
+        }
+
+        while records.len() < page_size as usize {
+            match cursor.next_record().await? {
+                Some((_, value)) => records.push(value),
+                None => break,
+            }
+        }
+
+        let has_more = records.len() == page_size as usize && cursor.next_record().await?.is_some();
+
+        Ok(Page { records, has_more })
+    }
+
     /// Opens a [`KeyCursor`] over the records matching key range, ordered by direction.
     pub async fn key_cursor<'a, Q>(
         &self,
@@ -187,9 +1148,11 @@ where
         cursor_direction: Option<CursorDirection>,
     ) -> Result<Option<KeyCursor<'t, M, M::Key>>, Error>
     where
-        M::Key: Borrow<Q>,
+        M: Lookup<Q>,
         Q: Serialize + ?Sized + 'a,
     {
+        self.check(Operation::Read)?;
+
         Ok(self
             .object_store
             .open_key_cursor(
@@ -200,6 +1163,120 @@ where
             .map(|cursor| KeyCursor::new(cursor.into_managed(), self.transaction)))
     }
 
+    /// Adds a record to the store with the key if given (otherwise the key is read from the record or generated)
+    async fn add_keyed<Q>(&self, key: Option<&Q>, value: &M::Add) -> Result<M::Key, Error>
+    where
+        Q: Serialize + ?Sized,
+    {
+        self.check(Operation::Add)?;
+
+        let key = key.map(|key| key.serialize(&JSON_SERIALIZER)).transpose()?;
+
+        let js_value = serialize_add::<M>(value)?;
+
+        let js_key = self.object_store.add(&js_value, key.as_ref())?.await?;
+
+        #[cfg(feature = "inspect")]
+        crate::inspect::record::<M>(self.transaction, js_key.clone(), &js_value).await?;
+
+        serde_wasm_bindgen::from_value(js_key).map_err(Into::into)
+    }
+
+    /// Puts a record into the store with the key if given (otherwise the key is read from the record)
+    async fn put_keyed<Q, V>(&self, key: Option<&Q>, value: &V) -> Result<M::Key, Error>
+    where
+        M: Borrow<V>,
+        Q: Serialize + ?Sized,
+        V: Serialize,
+    {
+        self.check(Operation::Update)?;
+
+        let key = key.map(|key| key.serialize(&JSON_SERIALIZER)).transpose()?;
+
+        let js_value = serialize_put::<M, V>(value)?;
+
+        let js_key = self.object_store.put(&js_value, key.as_ref())?.await?;
+
+        #[cfg(feature = "inspect")]
+        crate::inspect::record::<M>(self.transaction, js_key.clone(), &js_value).await?;
+
+        serde_wasm_bindgen::from_value(js_key).map_err(Into::into)
+    }
+
+    /// Retrieves the record with the given key, or adds the value returned by the function and retrieves the added
+    /// record if the store does not contain one. The value is only created if needed, and both steps happen in the
+    /// transaction of the store, so that no other transaction can add the record in between. Returns
+    /// [`Error::InsertedKeyMismatch`] if the added value is stored under another key than the requested one.
+    pub async fn get_or_insert_with<Q, F>(&self, key: &Q, f: F) -> Result<M, Error>
+    where
+        M: Lookup<Q>,
+        Q: Serialize + ?Sized,
+        F: FnOnce() -> M::Add,
+    {
+        if let Some(value) = self.get(key).await? {
+            return Ok(value);
+        }
+
+        let key = key.serialize(&JSON_SERIALIZER)?;
+        let added = self.add(&f()).await?.serialize(&JSON_SERIALIZER)?;
+
+        let mismatch = || Error::InsertedKeyMismatch { store: M::NAME };
+
+        if !idb::KeyRange::only(&key)?.includes(&added)? {
+            return Err(mismatch());
+        }
+
+        let value = self
+            .object_store
+            .get(Query::Key(key))?
+            .await?
+            .ok_or_else(mismatch)?;
+
+        read_stored(self.transaction)(value)
+    }
+
+    /// Adds the record if the store does not contain a record with the same key, or replaces the stored record
+    /// otherwise. Unlike [`update`](Self::update), the result tells whether the record was created or replaced, which
+    /// makes writes received more than once (e.g. while syncing) idempotent.
+    pub async fn upsert(&self, value: &M) -> Result<Upsert<M::Key>, Error> {
+        self.check(Operation::Read)?;
+
+        let key = value.serialize_key(&JSON_SERIALIZER)?;
+        let exists = self.object_store.get_key(Query::Key(key))?.await?.is_some();
+
+        let key = self.update(value).await?;
+
+        Ok(if exists {
+            Upsert::Replaced(key)
+        } else {
+            Upsert::Created(key)
+        })
+    }
+
+    /// Fails with [`Error::AccessDenied`] if the gate of the database does not allow the operation on the store.
+    fn check(&self, operation: Operation) -> Result<(), Error> {
+        self.transaction.check(M::NAME, operation)
+    }
+
+    #[cfg(any(feature = "inspect", feature = "sync"))]
+    pub(crate) fn as_idb_object_store(&self) -> &idb::ObjectStore {
+        &self.object_store
+    }
+
+    #[cfg(any(feature = "inspect", feature = "sync"))]
+    pub(crate) fn transaction(&self) -> &'t Transaction {
+        self.transaction
+    }
+
+    /// Returns `true` if the index exists in the opened database (under its name or its alias). Indexes added to a
+    /// model after the database was created only exist once the database is upgraded to a newer version.
+    pub fn has_index<I>(&self) -> bool
+    where
+        I: ModelIndex<Model = M>,
+    {
+        has_index::<I>(&self.object_store)
+    }
+
     /// Returns an [`Index`] for the given model index.
     #[doc(hidden)]
     pub fn index<I>(&self) -> Result<Index<'t, I>, Error>
@@ -207,7 +1284,7 @@ where
         I: ModelIndex<Model = M>,
     {
         Ok(Index::new(
-            self.object_store.index(I::NAME)?,
+            open_index::<I>(&self.object_store)?,
             self.transaction,
         ))
     }
@@ -0,0 +1,116 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    future::poll_fn,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+};
+
+thread_local! {
+    /// Opens of databases in progress, by name.
+    static OPENING: RefCell<HashMap<String, Rc<Open>>> = RefCell::new(HashMap::new());
+}
+
+/// Open of a database in progress (including its upgrade and initial records).
+#[derive(Debug, Default)]
+struct Open {
+    /// Description of the database being opened (see [`begin`])
+    signature: Option<String>,
+    /// Connection opened, once the open succeeded
+    connection: RefCell<Option<Rc<idb::Database>>>,
+    done: Cell<bool>,
+    wakers: RefCell<Vec<Waker>>,
+}
+
+impl Open {
+    fn poll_done(&self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.done.get() {
+            return Poll::Ready(());
+        }
+
+        let mut wakers = self.wakers.borrow_mut();
+
+        if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+
+        Poll::Pending
+    }
+}
+
+/// Outcome of [`begin`].
+#[derive(Debug)]
+pub(crate) enum Begin {
+    /// No other open of the database is in progress: the caller opens the database while holding the guard
+    Open(OpenGuard),
+    /// An open of the same database completed, sharing its connection
+    Shared(Rc<idb::Database>),
+}
+
+/// Held while a database is being opened. Other opens of a database with the same name wait for the guard to be
+/// dropped, so that their upgrades do not race.
+#[derive(Debug)]
+pub(crate) struct OpenGuard {
+    name: String,
+    open: Rc<Open>,
+}
+
+impl OpenGuard {
+    /// Shares the opened connection with the opens of the same database waiting for this one.
+    pub(crate) fn share(&self, connection: &Rc<idb::Database>) {
+        *self.open.connection.borrow_mut() = Some(connection.clone());
+    }
+}
+
+impl Drop for OpenGuard {
+    fn drop(&mut self) {
+        OPENING.with(|opening| opening.borrow_mut().remove(&self.name));
+        self.open.done.set(true);
+
+        // All the waiters are woken up: the ones opening the same database share its connection, the first of the others
+        // polled opens the database next and the remaining ones wait again
+        for waker in self.open.wakers.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Waits until no other open of the database with the given name is in progress. Concurrent opens with the same
+/// signature (describing the version and object stores of the database, `None` if the connection cannot be shared)
+/// coalesce: the first one opens the database and the others share its connection once it succeeds.
+pub(crate) async fn begin(name: &str, signature: Option<&str>) -> Begin {
+    loop {
+        let (open, started) = OPENING.with(|opening| {
+            let mut opening = opening.borrow_mut();
+
+            match opening.get(name) {
+                Some(open) => (open.clone(), false),
+                None => {
+                    let open = Rc::new(Open {
+                        signature: signature.map(ToOwned::to_owned),
+                        ..Default::default()
+                    });
+                    opening.insert(name.to_owned(), open.clone());
+                    (open, true)
+                }
+            }
+        });
+
+        if started {
+            return Begin::Open(OpenGuard {
+                name: name.to_owned(),
+                open,
+            });
+        }
+
+        poll_fn(|cx| open.poll_done(cx)).await;
+
+        if signature.is_some() && open.signature.as_deref() == signature {
+            if let Some(ref connection) = *open.connection.borrow() {
+                return Begin::Shared(connection.clone());
+            }
+        }
+
+        // The open failed or opened the database differently, so it is opened again
+    }
+}
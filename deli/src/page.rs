@@ -0,0 +1,8 @@
+/// Page of records returned by [`ObjectStore::page`](crate::ObjectStore::page).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page<M> {
+    /// Records of the page
+    pub records: Vec<M>,
+    /// `true` if there are records after this page
+    pub has_more: bool,
+}
@@ -0,0 +1,71 @@
+//! Scheduling of background work.
+//!
+//! Work split into batches (e.g. a [`Syncer`](crate::sync::Syncer) writing pulled records in chunks) can be tagged as
+//! [`Priority::Background`] so that it yields to the browser between batches using `requestIdleCallback`, leaving the
+//! main thread to user-facing queries. Transactions are committed when they are idle, so yielding only happens between
+//! batches and never within a transaction.
+use js_sys::{Function, Object, Promise, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+/// Maximum number of milliseconds background work waits for an idle period before running anyway.
+const IDLE_TIMEOUT_MS: f64 = 1000.0;
+
+/// Priority of work split into batches.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Priority {
+    /// Batches run back to back
+    #[default]
+    Interactive,
+    /// Batches wait for the browser to be idle (up to one second) before running
+    Background,
+}
+
+impl Priority {
+    /// Waits before running the next batch: returns immediately for [`Priority::Interactive`] work and waits for an
+    /// idle period for [`Priority::Background`] work. Should be awaited between batches of custom batched work (e.g.
+    /// retention sweeps), outside of transactions.
+    pub async fn between_batches(self) {
+        if self == Priority::Background {
+            idle().await;
+        }
+    }
+}
+
+/// Resolves during the next idle period, falling back to `setTimeout` where `requestIdleCallback` is not available
+/// (e.g. Safari).
+async fn idle() {
+    let promise = Promise::new(&mut |resolve, _| {
+        let global = js_sys::global();
+
+        let scheduled =
+            if let Some(request_idle_callback) = function(&global, "requestIdleCallback") {
+                let options = Object::new();
+                let _ = Reflect::set(
+                    &options,
+                    &JsValue::from_str("timeout"),
+                    &JsValue::from_f64(IDLE_TIMEOUT_MS),
+                );
+                request_idle_callback
+                    .call2(&global, &resolve, &options)
+                    .is_ok()
+            } else if let Some(set_timeout) = function(&global, "setTimeout") {
+                set_timeout.call1(&global, &resolve).is_ok()
+            } else {
+                false
+            };
+
+        if !scheduled {
+            let _ = resolve.call0(&JsValue::UNDEFINED);
+        }
+    });
+
+    let _ = JsFuture::from(promise).await;
+}
+
+fn function(global: &Object, name: &str) -> Option<Function> {
+    Reflect::get(global, &JsValue::from_str(name))
+        .ok()?
+        .dyn_into()
+        .ok()
+}
@@ -0,0 +1,308 @@
+//! Typed query builders generated by the derive macro.
+//!
+//! `#[derive(Model)]` generates a query builder for each model (e.g. `Employee::query()` returning an
+//! `EmployeeQuery`) with a method for each single field index of the model, which can be used to filter records without
+//! choosing the index to query by hand:
+//!
+//! ```rust,ignore
+//! let employees = Employee::query()
+//!     .by_age()
+//!     .gte(&30)
+//!     .by_name()
+//!     .eq("Alice")
+//!     .limit(10)
+//!     .get_all(&transaction)
+//!     .await?;
+//! ```
+//!
+//! The records are read using a cursor on the index of the first equality condition (or of the first condition if
+//! there is no equality condition), and the remaining conditions are checked on each record read. Records are compared
+//! to the conditions using IndexedDB's ordering of keys, so the results are the same as querying each index by hand.
+//! Composite, hashed and nested indexes cannot be queried using the builder.
+//!
+//! Querying an index which does not exist in the opened database (e.g. an index added to the model without bumping the
+//! version of the database) fails with [`Error::IndexMissing`]. Queries can opt in to reading the records of the object
+//! store instead, checking the conditions on each record:
+//!
+//! ```rust,ignore
+//! let employees = Employee::query()
+//!     .by_age()
+//!     .gte(&30)
+//!     .scan_if_index_missing()
+//!     .get_all(&transaction)
+//!     .await?;
+//! ```
+//!
+//! The results of a full scan are the same, except that they are read in the order of the primary key (and reading
+//! every record of a large object store is slow). [`ObjectStore::has_index`](crate::ObjectStore::has_index) checks if an
+//! index exists beforehand.
+use idb::{CursorDirection, Query};
+use serde::Serialize;
+
+use crate::{
+    cursor::Cursor,
+    error::Error,
+    gate::Operation,
+    key_range::{BoundedRange, KeyRange},
+    lookup::Lookup,
+    model::Model,
+    model_index::{has_index, open_index, ModelIndex},
+    transaction::Transaction,
+    JSON_SERIALIZER,
+};
+
+/// Checks if the field of a record is in a key range.
+type Includes<M> = Box<dyn Fn(&M, &idb::KeyRange) -> Result<bool, Error>>;
+
+/// Condition on the key of an index.
+struct Condition<M> {
+    open_index: fn(&idb::ObjectStore) -> Result<idb::Index, Error>,
+    has_index: fn(&idb::ObjectStore) -> bool,
+    range: idb::KeyRange,
+    exact: bool,
+    includes: Includes<M>,
+}
+
+/// Query over the records of a model, wrapped by the query builders generated by the derive macro.
+pub struct QueryBuilder<M> {
+    conditions: Vec<Condition<M>>,
+    error: Option<Error>,
+    limit: Option<u32>,
+    direction: Option<CursorDirection>,
+    scan_if_index_missing: bool,
+}
+
+impl<M> Default for QueryBuilder<M> {
+    fn default() -> Self {
+        Self {
+            conditions: Vec::new(),
+            error: None,
+            limit: None,
+            direction: None,
+            scan_if_index_missing: false,
+        }
+    }
+}
+
+impl<M> QueryBuilder<M>
+where
+    M: Model,
+{
+    /// Creates a query matching all the records of the model.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits the number of records returned by the query (otherwise, up to the model's
+    /// [`DEFAULT_LIMIT`](Model::DEFAULT_LIMIT) records are returned).
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Sets the direction in which the records are read (in the order of the key of the index queried, or of the
+    /// primary key if there is no condition).
+    pub fn direction(mut self, direction: CursorDirection) -> Self {
+        self.direction = Some(direction);
+        self
+    }
+
+    /// Reads all the records of the object store and checks the conditions on each record if the index of a condition
+    /// does not exist in the opened database, instead of failing with [`Error::IndexMissing`].
+    pub fn scan_if_index_missing(mut self) -> Self {
+        self.scan_if_index_missing = true;
+        self
+    }
+
+    /// Retrieves the records matching all the conditions of the query.
+    pub async fn get_all(mut self, transaction: &Transaction) -> Result<Vec<M>, Error> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        transaction.check(M::NAME, Operation::Read)?;
+
+        let object_store = transaction.idb_object_store(M::NAME)?;
+
+        // Conditions on missing indexes are checked on each record when falling back to a full scan
+        let queryable = |condition: &Condition<M>| {
+            !self.scan_if_index_missing || (condition.has_index)(&object_store)
+        };
+
+        // Equality conditions usually match the fewest records, so their index is preferred
+        let position = self
+            .conditions
+            .iter()
+            .position(|condition| condition.exact && queryable(condition))
+            .or_else(|| self.conditions.iter().position(queryable));
+
+        let cursor = match position {
+            Some(position) => {
+                let condition = self.conditions.remove(position);
+                (condition.open_index)(&object_store)?
+                    .open_cursor(Some(Query::KeyRange(condition.range)), self.direction)?
+                    .await?
+            }
+            None => object_store.open_cursor(None, self.direction)?.await?,
+        };
+
+        let mut records = Vec::new();
+
+        let Some(cursor) = cursor else {
+            return Ok(records);
+        };
+
+        let limit = self.limit.or(M::DEFAULT_LIMIT);
+        let mut cursor = Cursor::<M, M::Key>::new(cursor.into_managed(), transaction);
+
+        while limit.is_none_or(|limit| records.len() < limit as usize) {
+            let Some((_, record)) = cursor.next_record().await? else {
+                break;
+            };
+
+            if self.matches(&record)? {
+                records.push(record);
+            }
+        }
+
+        Ok(records)
+    }
+
+    /// Returns `true` if the record satisfies all the conditions of the query.
+    fn matches(&self, record: &M) -> Result<bool, Error> {
+        for condition in self.conditions.iter() {
+            if !(condition.includes)(record, &condition.range)? {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// Conditions on the field of an index, returned by the `by_*` methods of the query builders generated by the derive
+/// macro. Each condition adds to the conditions of the query and returns the query builder.
+pub struct Field<Q, I>
+where
+    I: ModelIndex,
+{
+    query: Q,
+    extract: fn(&I::Model) -> &I::Key,
+}
+
+impl<Q, I> Field<Q, I>
+where
+    Q: From<QueryBuilder<I::Model>> + Into<QueryBuilder<I::Model>>,
+    I: ModelIndex,
+    I::Model: 'static,
+    I::Key: 'static,
+{
+    #[doc(hidden)]
+    pub fn new(query: Q, extract: fn(&I::Model) -> &I::Key) -> Self {
+        Self { query, extract }
+    }
+
+    /// Matches the records whose field is equal to the given value.
+    pub fn eq<V>(self, value: &V) -> Q
+    where
+        I: Lookup<V>,
+        V: Serialize + ?Sized,
+    {
+        self.range(value)
+    }
+
+    /// Matches the records whose field is greater than the given value.
+    pub fn gt<V>(self, value: &V) -> Q
+    where
+        I: Lookup<V>,
+        V: Serialize + ?Sized,
+    {
+        self.condition(greater_than(value), false)
+    }
+
+    /// Matches the records whose field is greater than or equal to the given value.
+    pub fn gte<V>(self, value: &V) -> Q
+    where
+        I: Lookup<V>,
+        V: Serialize + ?Sized,
+    {
+        self.range(value..)
+    }
+
+    /// Matches the records whose field is less than the given value.
+    pub fn lt<V>(self, value: &V) -> Q
+    where
+        I: Lookup<V>,
+        V: Serialize + ?Sized,
+    {
+        self.range(..value)
+    }
+
+    /// Matches the records whose field is less than or equal to the given value.
+    pub fn lte<V>(self, value: &V) -> Q
+    where
+        I: Lookup<V>,
+        V: Serialize + ?Sized,
+    {
+        self.range(..=value)
+    }
+
+    /// Matches the records whose field is in the given key range.
+    pub fn range<'a, V>(self, key_range: impl Into<KeyRange<'a, V, BoundedRange>>) -> Q
+    where
+        I: Lookup<V>,
+        V: Serialize + ?Sized + 'a,
+    {
+        match Query::try_from(&key_range.into()) {
+            Ok(Query::Key(key)) => {
+                let range = idb::KeyRange::only(&key).map_err(Into::into);
+                self.condition(range, true)
+            }
+            Ok(Query::KeyRange(range)) => self.condition(Ok(range), false),
+            Err(error) => self.condition(Err(error), false),
+        }
+    }
+
+    fn condition(self, range: Result<idb::KeyRange, Error>, exact: bool) -> Q {
+        let mut query = self.query.into();
+
+        #[cfg(feature = "datetime")]
+        let range = range.and_then(crate::datetime::index_range::<I>);
+
+        match range {
+            Ok(range) => {
+                let extract = self.extract;
+
+                query.conditions.push(Condition {
+                    open_index: open_index::<I>,
+                    has_index: has_index::<I>,
+                    range,
+                    exact,
+                    includes: Box::new(move |record, range| {
+                        let key = extract(record).serialize(&JSON_SERIALIZER)?;
+
+                        #[cfg(feature = "datetime")]
+                        let key = crate::datetime::index_key::<I>(key);
+
+                        // Values which are not valid keys (e.g. `None`) are not part of the index either
+                        Ok(range.includes(&key).unwrap_or(false))
+                    }),
+                });
+            }
+            Err(error) => {
+                query.error.get_or_insert(error);
+            }
+        }
+
+        Q::from(query)
+    }
+}
+
+/// Returns the key range of the keys greater than the given value (which cannot be expressed using Rust's ranges).
+fn greater_than<V>(value: &V) -> Result<idb::KeyRange, Error>
+where
+    V: Serialize + ?Sized,
+{
+    let lower = value.serialize(&JSON_SERIALIZER)?;
+    Ok(idb::KeyRange::lower_bound(&lower, Some(true))?)
+}
@@ -0,0 +1,228 @@
+use std::fmt;
+
+use idb::{
+    builder::{IndexBuilder, ObjectStoreBuilder},
+    KeyPath,
+};
+
+use crate::{error::Error, model::Model};
+
+/// Prefix of the names of the internal object stores of `deli`.
+const RESERVED_PREFIX: &str = "__deli_";
+
+/// Object stores and indexes of the models of a database.
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    stores: Vec<(&'static str, &'static [&'static str])>,
+}
+
+impl Schema {
+    /// Creates a new empty [`Schema`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a model to the schema
+    pub fn add_model<M>(mut self) -> Self
+    where
+        M: Model,
+    {
+        self.stores.push((M::NAME, M::INDEX_NAMES));
+        self
+    }
+
+    /// Checks the schema for duplicate object store names, duplicate index names within an object store and object
+    /// store names using the reserved `__deli_` prefix. Returns [`Error::SchemaConflicts`] with all the conflicts found.
+    pub fn validate(&self) -> Result<(), Error> {
+        let mut conflicts = Vec::new();
+
+        for (i, (store, indexes)) in self.stores.iter().enumerate() {
+            if store.starts_with(RESERVED_PREFIX) {
+                conflicts.push(SchemaConflict::ReservedStoreName { store });
+            }
+
+            if self.stores[..i].iter().any(|(other, _)| other == store) {
+                // Reported once for each duplicated name
+                if !conflicts.contains(&SchemaConflict::DuplicateStore { store }) {
+                    conflicts.push(SchemaConflict::DuplicateStore { store });
+                }
+            }
+
+            for (j, index) in indexes.iter().enumerate() {
+                if indexes[..j].contains(index) {
+                    let conflict = SchemaConflict::DuplicateIndex { store, index };
+
+                    if !conflicts.contains(&conflict) {
+                        conflicts.push(conflict);
+                    }
+                }
+            }
+        }
+
+        if conflicts.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::SchemaConflicts(conflicts))
+        }
+    }
+}
+
+/// Conflict found while validating a [`Schema`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaConflict {
+    /// Object store name is used by multiple models
+    DuplicateStore {
+        /// Name of the object store
+        store: &'static str,
+    },
+    /// Index name is used multiple times within an object store
+    DuplicateIndex {
+        /// Name of the object store
+        store: &'static str,
+        /// Name of the index
+        index: &'static str,
+    },
+    /// Object store name uses the prefix reserved for the internal object stores of `deli`
+    ReservedStoreName {
+        /// Name of the object store
+        store: &'static str,
+    },
+}
+
+/// Description of the object store of a model (name, key path, auto increment and indexes), returned by
+/// [`Model::store_schema`].
+///
+/// It is rendered by `deli` itself (using [`Display`](fmt::Display), with indexes sorted by name), so that schema
+/// fingerprints and [snapshots](crate::testing::snapshot) only change when the object store does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoreSchema {
+    /// Name of the object store
+    pub name: &'static str,
+    /// Key path of the object store (`None` if keys are given when writing records)
+    pub key_path: Option<KeyPath>,
+    /// Whether keys are generated by incrementing a counter
+    pub auto_increment: bool,
+    /// Indexes of the object store
+    pub indexes: Vec<IndexSchema>,
+}
+
+/// Description of an index of an object store, part of a [`StoreSchema`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexSchema {
+    /// Name of the index
+    pub name: &'static str,
+    /// Key path of the index
+    pub key_path: KeyPath,
+    /// Whether keys of the index must be unique
+    pub unique: bool,
+    /// Whether an array key adds an entry for each of its elements
+    pub multi_entry: bool,
+}
+
+impl StoreSchema {
+    /// Creates the description of an object store with the given name, without key path and indexes
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            key_path: None,
+            auto_increment: false,
+            indexes: Vec::new(),
+        }
+    }
+}
+
+impl From<StoreSchema> for ObjectStoreBuilder {
+    fn from(schema: StoreSchema) -> Self {
+        let builder = ObjectStoreBuilder::new(schema.name).key_path(schema.key_path);
+
+        let builder = if schema.auto_increment {
+            builder.auto_increment(true)
+        } else {
+            builder
+        };
+
+        schema
+            .indexes
+            .into_iter()
+            .fold(builder, |builder, index| builder.add_index(index.into()))
+    }
+}
+
+impl From<IndexSchema> for IndexBuilder {
+    fn from(schema: IndexSchema) -> Self {
+        let builder = IndexBuilder::new(schema.name.to_owned(), schema.key_path);
+
+        let builder = if schema.unique {
+            builder.unique(true)
+        } else {
+            builder
+        };
+
+        if schema.multi_entry {
+            builder.multi_entry(true)
+        } else {
+            builder
+        }
+    }
+}
+
+impl fmt::Display for StoreSchema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "store \"{}\"", self.name)?;
+
+        match self.key_path {
+            Some(ref key_path) => write!(f, "\n  key path {}", DisplayKeyPath(key_path))?,
+            None => write!(f, "\n  out-of-line keys")?,
+        }
+
+        if self.auto_increment {
+            write!(f, ", auto increment")?;
+        }
+
+        let mut indexes = self.indexes.iter().collect::<Vec<_>>();
+        indexes.sort_by_key(|index| index.name);
+
+        for index in indexes {
+            write!(
+                f,
+                "\n  index \"{}\" on {}",
+                index.name,
+                DisplayKeyPath(&index.key_path)
+            )?;
+
+            if index.unique {
+                write!(f, ", unique")?;
+            }
+
+            if index.multi_entry {
+                write!(f, ", multi entry")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Renders a key path as `"a.b"` or `["a", "b"]`.
+struct DisplayKeyPath<'a>(&'a KeyPath);
+
+impl fmt::Display for DisplayKeyPath<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            KeyPath::Single(path) => write!(f, "\"{path}\""),
+            KeyPath::Array(paths) => {
+                write!(f, "[")?;
+
+                for (i, path) in paths.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    write!(f, "\"{path}\"")?;
+                }
+
+                write!(f, "]")
+            }
+        }
+    }
+}
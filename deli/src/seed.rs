@@ -0,0 +1,110 @@
+use std::{cell::Cell, fmt, future::Future, pin::Pin, rc::Rc};
+
+use idb::{Event, Factory, Request, TransactionResult};
+
+use crate::{
+    database::Database, error::Error, model::Model, object_store::ObjectStore,
+    transaction::Transaction,
+};
+
+/// Future returned by the callbacks creating the initial records of an object store.
+pub type SeedFuture<'t> = Pin<Box<dyn Future<Output = Result<(), Error>> + 't>>;
+
+type SeedFn = Box<dyn for<'t> FnOnce(&'t Transaction) -> SeedFuture<'t>>;
+
+/// Callback creating the initial records of a newly created object store.
+pub(crate) struct Seed {
+    store: &'static str,
+    seed: SeedFn,
+}
+
+impl Seed {
+    pub(crate) fn new<M, F>(seed: F) -> Self
+    where
+        M: Model,
+        F: for<'t> FnOnce(ObjectStore<'t, M>) -> SeedFuture<'t> + 'static,
+    {
+        Self {
+            store: M::NAME,
+            seed: Box::new(move |transaction| match transaction.object_store::<M>() {
+                Ok(store) => seed(store),
+                Err(error) => Box::pin(async move { Err(error) }),
+            }),
+        }
+    }
+}
+
+impl fmt::Debug for Seed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Seed")
+            .field("store", &self.store)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns the names of the object stores of an existing database (empty if the database does not exist).
+pub(crate) async fn existing_store_names(name: &str) -> Result<Vec<String>, Error> {
+    let created = Rc::new(Cell::new(false));
+    let mut request = Factory::new()?.open(name, None)?;
+
+    // Opening a database which does not exist creates it, so the creation is aborted to leave it to the actual open
+    // request (aborting the upgrade transaction of a new database deletes it)
+    request.on_upgrade_needed({
+        let created = created.clone();
+
+        move |event| {
+            created.set(true);
+
+            if let Some(transaction) = event
+                .target()
+                .ok()
+                .and_then(|request| request.transaction())
+            {
+                let _ = transaction.abort();
+            }
+        }
+    });
+
+    match request.await {
+        Ok(database) => {
+            let store_names = database.store_names();
+            database.close();
+            Ok(store_names)
+        }
+        Err(_) if created.get() => Ok(Vec::new()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Runs the seeds of the object stores created while opening the database in a single read-write transaction.
+pub(crate) async fn run(
+    database: &Database,
+    seeds: Vec<Seed>,
+    existing_store_names: &[String],
+) -> Result<(), Error> {
+    let seeds = seeds
+        .into_iter()
+        .filter(|seed| !existing_store_names.iter().any(|name| name == seed.store))
+        .collect::<Vec<_>>();
+
+    if seeds.is_empty() {
+        return Ok(());
+    }
+
+    let transaction = seeds
+        .iter()
+        .fold(database.transaction().writable(), |builder, seed| {
+            builder.with_store_name(seed.store)
+        })
+        .build()?;
+
+    for seed in seeds {
+        (seed.seed)(&transaction).await?;
+    }
+
+    if transaction.commit().await? == TransactionResult::Aborted {
+        return Err(Error::TransactionAborted);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,32 @@
+//! Shuffling of unordered results.
+//!
+//! The order of `get_all` and `get_all_keys` results is not part of their contract (use `ordered_get_all` with an
+//! explicit direction instead). With [`shuffle_results`] enabled, they return their results in random order, so tests
+//! catch code which accidentally relies on it. `ordered_get_all` results are never shuffled.
+//!
+//! Shuffling is disabled until [`shuffle_results`] is called, and the `shuffle-get-all` feature is meant to be enabled
+//! in tests only (e.g. as a feature of a dev-dependency).
+use std::cell::Cell;
+
+use js_sys::Math;
+
+thread_local! {
+    static SHUFFLE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Enables (or disables) shuffling of `get_all` and `get_all_keys` results.
+pub fn shuffle_results(enabled: bool) {
+    SHUFFLE.with(|shuffle| shuffle.set(enabled));
+}
+
+/// Returns the results in random order if shuffling is enabled.
+pub(crate) fn shuffled<T>(mut values: Vec<T>) -> Vec<T> {
+    if SHUFFLE.with(Cell::get) {
+        for i in (1..values.len()).rev() {
+            let j = (Math::random() * (i + 1) as f64) as usize;
+            values.swap(i, j);
+        }
+    }
+
+    values
+}
@@ -0,0 +1,199 @@
+//! Zero-downtime splitting of an object store.
+//!
+//! When a model is split into a new model stored in a new object store, old and new versions of an app may run side by
+//! side (e.g. in different tabs) until the migration completes. A [`StoreSplit`] keeps both stores usable during this
+//! transitional period:
+//!
+//! - Writes go to the new store and are merged into the old store.
+//! - Reads prefer the new store and fall back to the old store.
+//!
+//! The state of each split is kept in the meta store of the database (which must be built using
+//! [`DatabaseBuilder::enable_store_splits`](crate::DatabaseBuilder::enable_store_splits)). Once all the clients are
+//! updated, [`StoreSplit::complete`] backfills the new store from the old store and ends the transitional period.
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::Database,
+    error::Error,
+    lookup::Lookup,
+    meta::{MetaStore, META_STORE_NAME},
+    model::Model,
+    object_store::ObjectStore,
+    transaction::Transaction,
+    transaction_builder::TransactionBuilder,
+    TransactionResult,
+};
+
+/// State of a [`StoreSplit`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SplitState {
+    /// Writes go to both stores and reads fall back to the old store
+    #[default]
+    DualWrite,
+    /// Migration is complete and only the new store is used
+    Completed,
+}
+
+/// Split of the object store of model `O` into the object store of model `N` (see
+/// [module level documentation](self)).
+pub struct StoreSplit<O, N> {
+    name: &'static str,
+    split: fn(&O) -> N,
+    merge: fn(&N, Option<O>) -> Option<O>,
+}
+
+impl<O, N> StoreSplit<O, N>
+where
+    O: Model + Lookup<O::Key>,
+    N: Model<Key = O::Key> + Lookup<O::Key>,
+{
+    /// Creates a new [`StoreSplit`] with the given name (used as the key of its state in the meta store).
+    ///
+    /// - `split` extracts the new record from an old record.
+    /// - `merge` merges a new record into the old record with the same key (if any), returning `None` if the old
+    ///   store should not be written.
+    pub fn new(
+        name: &'static str,
+        split: fn(&O) -> N,
+        merge: fn(&N, Option<O>) -> Option<O>,
+    ) -> Self {
+        Self { name, split, merge }
+    }
+
+    /// Returns a [`TransactionBuilder`] with both stores and the meta store in its scope.
+    pub fn transaction<'a>(&self, database: &'a Database) -> TransactionBuilder<'a> {
+        database
+            .transaction()
+            .with_model::<O>()
+            .with_model::<N>()
+            .with_store_name(META_STORE_NAME)
+    }
+
+    /// Returns a [`SplitStore`] for reading and writing records during the split.
+    pub async fn with_transaction<'s, 't>(
+        &'s self,
+        transaction: &'t Transaction,
+    ) -> Result<SplitStore<'s, 't, O, N>, Error> {
+        let state = MetaStore::new(transaction)?
+            .get(&self.state_key())
+            .await?
+            .unwrap_or_default();
+
+        Ok(SplitStore {
+            split: self,
+            state,
+            old: transaction.object_store::<O>()?,
+            new: transaction.object_store::<N>()?,
+        })
+    }
+
+    /// Returns the state of the split.
+    pub async fn state(&self, database: &Database) -> Result<SplitState, Error> {
+        let transaction = self.transaction(database).build()?;
+        let state = MetaStore::new(&transaction)?
+            .get(&self.state_key())
+            .await?
+            .unwrap_or_default();
+        transaction.done().await?;
+
+        Ok(state)
+    }
+
+    /// Completes the split in a single read-write transaction: records of the old store which are missing from the new
+    /// store are split into the new store, and reads and writes stop using the old store.
+    pub async fn complete(&self, database: &Database) -> Result<(), Error> {
+        let transaction = self.transaction(database).writable().build()?;
+
+        {
+            let old = transaction.object_store::<O>()?;
+            let new = transaction.object_store::<N>()?;
+
+            // A cursor is used instead of `get_all` so that the model's default limit does not apply
+            if let Some(mut cursor) = old.cursor::<O::Key>(.., None).await? {
+                while let Some((key, value)) = cursor.next_record().await? {
+                    if new.get(&key).await?.is_none() {
+                        new.update(&(self.split)(&value)).await?;
+                    }
+                }
+            }
+
+            MetaStore::new(&transaction)?
+                .set(&self.state_key(), &SplitState::Completed)
+                .await?;
+        }
+
+        if transaction.commit().await? == TransactionResult::Aborted {
+            return Err(Error::TransactionAborted);
+        }
+
+        Ok(())
+    }
+
+    fn state_key(&self) -> String {
+        format!("split/{}", self.name)
+    }
+}
+
+impl<O, N> fmt::Debug for StoreSplit<O, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StoreSplit")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Reads and writes records of a [`StoreSplit`] within a transaction.
+#[derive(Debug)]
+pub struct SplitStore<'s, 't, O, N> {
+    split: &'s StoreSplit<O, N>,
+    state: SplitState,
+    old: ObjectStore<'t, O>,
+    new: ObjectStore<'t, N>,
+}
+
+impl<O, N> SplitStore<'_, '_, O, N>
+where
+    O: Model + Lookup<O::Key>,
+    N: Model<Key = O::Key> + Lookup<O::Key>,
+{
+    /// Returns the state of the split when the store was created.
+    pub fn state(&self) -> SplitState {
+        self.state
+    }
+
+    /// Retrieves the record with the given key from the new store, falling back to splitting the record of the old
+    /// store during the transitional period.
+    pub async fn get(&self, key: &O::Key) -> Result<Option<N>, Error> {
+        if let Some(value) = self.new.get(key).await? {
+            return Ok(Some(value));
+        }
+
+        if self.state == SplitState::Completed {
+            return Ok(None);
+        }
+
+        Ok(self
+            .old
+            .get(key)
+            .await?
+            .map(|value| (self.split.split)(&value)))
+    }
+
+    /// Updates (or adds) a record in the new store returning its key. During the transitional period, the record is
+    /// also merged into the old store.
+    pub async fn update(&self, value: &N) -> Result<O::Key, Error> {
+        let key = self.new.update(value).await?;
+
+        if self.state == SplitState::DualWrite {
+            let old = self.old.get(&key).await?;
+
+            if let Some(merged) = (self.split.merge)(value, old) {
+                self.old.update(&merged).await?;
+            }
+        }
+
+        Ok(key)
+    }
+}
@@ -0,0 +1,163 @@
+//! Storage quota and eviction signals.
+//!
+//! Browsers evict the data of an origin when the device runs low on storage, wiping the whole database at once. Apps
+//! can degrade gracefully before that happens by watching how much of the quota is used, e.g. to run their own
+//! retention sweep (deleting old records using [`Database::delete_chunked`](crate::Database::delete_chunked)) once
+//! the usage crosses a threshold:
+//!
+//! ```rust,ignore
+//! let watch = storage::watch_pressure(0.8, Duration::from_secs(60), |estimate| {
+//!     log::warn!("storage usage at {:.0}% of quota", estimate.usage_ratio() * 100.0);
+//!     spawn_local(sweep_old_messages());
+//! });
+//! ```
+//!
+//! Browsers do not notify pages about storage pressure, so the usage is polled using the Storage API
+//! (`navigator.storage.estimate()`). Requesting persistent storage using [`persist`] exempts the origin from eviction
+//! where the browser grants it.
+//!
+//! When the database is deleted from outside of the app (e.g. when the browser evicts it or the user clears site
+//! data), its connection is closed by the browser. The hook registered using
+//! [`DatabaseBuilder::on_forced_close`](crate::DatabaseBuilder::on_forced_close) is called, so that the app can stop
+//! using the database and reopen it.
+use std::{cell::Cell, fmt, rc::Rc, time::Duration};
+
+use js_sys::{Promise, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::StorageManager;
+
+use crate::{error::Error, timer::sleep};
+
+/// Estimate of the storage used by the origin, returned by [`estimate`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StorageEstimate {
+    /// Bytes used by the origin (across all of its databases and other storage)
+    pub usage: f64,
+    /// Bytes available to the origin
+    pub quota: f64,
+}
+
+impl StorageEstimate {
+    /// Returns the fraction of the quota used (between 0 and 1).
+    pub fn usage_ratio(&self) -> f64 {
+        if self.quota > 0.0 {
+            self.usage / self.quota
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Returns an estimate of the storage used by the origin, or `None` where the Storage API is not available.
+pub async fn estimate() -> Result<Option<StorageEstimate>, Error> {
+    let Some(storage) = storage_manager() else {
+        return Ok(None);
+    };
+
+    let estimate = call(storage.estimate())
+        .await?
+        .unchecked_into::<web_sys::StorageEstimate>();
+
+    Ok(Some(StorageEstimate {
+        usage: estimate.get_usage().unwrap_or_default(),
+        quota: estimate.get_quota().unwrap_or_default(),
+    }))
+}
+
+/// Requests persistent storage for the origin, so that its data is not evicted under storage pressure. Returns `true`
+/// if the browser granted it (`false` where the Storage API is not available).
+pub async fn persist() -> Result<bool, Error> {
+    match storage_manager() {
+        Some(storage) => Ok(call(storage.persist()).await?.is_truthy()),
+        None => Ok(false),
+    }
+}
+
+/// Returns `true` if the storage of the origin is persistent (`false` where the Storage API is not available).
+pub async fn persisted() -> Result<bool, Error> {
+    match storage_manager() {
+        Some(storage) => Ok(call(storage.persisted()).await?.is_truthy()),
+        None => Ok(false),
+    }
+}
+
+/// Polls the storage usage at the given interval and calls the hook whenever the usage ratio crosses the threshold
+/// (once, until the usage drops below the threshold again). Polling stops when the returned [`PressureWatch`] is
+/// dropped. Failing estimates are skipped.
+pub fn watch_pressure<F>(threshold: f64, interval: Duration, hook: F) -> PressureWatch
+where
+    F: Fn(&StorageEstimate) + 'static,
+{
+    let stopped = Rc::new(Cell::new(false));
+    let watch = PressureWatch {
+        stopped: stopped.clone(),
+    };
+
+    wasm_bindgen_futures::spawn_local(async move {
+        let mut under_pressure = false;
+
+        while !stopped.get() {
+            if let Ok(Some(estimate)) = estimate().await {
+                let pressure = estimate.usage_ratio() >= threshold;
+
+                if pressure && !under_pressure && !stopped.get() {
+                    hook(&estimate);
+                }
+
+                under_pressure = pressure;
+            }
+
+            sleep(interval).await;
+        }
+    });
+
+    watch
+}
+
+/// Handle of a storage pressure watch started using [`watch_pressure`], which stops polling when dropped.
+#[derive(Debug)]
+pub struct PressureWatch {
+    stopped: Rc<Cell<bool>>,
+}
+
+impl Drop for PressureWatch {
+    fn drop(&mut self) {
+        self.stopped.set(true);
+    }
+}
+
+/// Hook called when the connection to a database is closed by the browser (see
+/// [`DatabaseBuilder::on_forced_close`](crate::DatabaseBuilder::on_forced_close)).
+pub(crate) struct ForcedCloseHook(Box<dyn FnOnce()>);
+
+impl ForcedCloseHook {
+    pub(crate) fn new(hook: impl FnOnce() + 'static) -> Self {
+        Self(Box::new(hook))
+    }
+
+    /// Registers the hook on a database connection.
+    pub(crate) fn register(self, database: &mut idb::Database) {
+        database.on_close(move |_| (self.0)());
+    }
+}
+
+impl fmt::Debug for ForcedCloseHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ForcedCloseHook").finish_non_exhaustive()
+    }
+}
+
+/// Returns the storage manager of the global scope (`navigator.storage`), if available.
+fn storage_manager() -> Option<StorageManager> {
+    let navigator = Reflect::get(&js_sys::global(), &JsValue::from_str("navigator")).ok()?;
+    let storage = Reflect::get(&navigator, &JsValue::from_str("storage")).ok()?;
+
+    (!storage.is_undefined() && !storage.is_null()).then(|| storage.unchecked_into())
+}
+
+async fn call(promise: Result<Promise, JsValue>) -> Result<JsValue, Error> {
+    JsFuture::from(promise.map_err(serde_wasm_bindgen::Error::from)?)
+        .await
+        .map_err(|error| serde_wasm_bindgen::Error::from(error).into())
+}
@@ -0,0 +1,275 @@
+use std::{cmp::Ordering, fmt, future::Future};
+
+use idb::{builder::ObjectStoreBuilder, KeyPath, KeyRange, Query};
+use js_sys::{Array, Date, Reflect};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+
+use crate::{
+    error::Error,
+    model::{read_stored, record_serializer, serialize_put, Model},
+    model_index::ModelIndex,
+    object_store::ObjectStore,
+    transaction::Transaction,
+    JSON_SERIALIZER,
+};
+
+use super::Tracked;
+
+/// Name of the internal object store keeping unresolved sync conflicts.
+pub(crate) const CONFLICT_STORE_NAME: &str = "__deli_conflicts";
+
+/// Strategy applied when a pulled record conflicts with a local change that has not been pushed yet.
+pub enum ConflictStrategy<M> {
+    /// Keeps the local record
+    LocalWins,
+    /// Overwrites the local record with the pulled record
+    RemoteWins,
+    /// Keeps the record with the latest `updated_at` (conflicts with equal timestamps are left unresolved)
+    LatestTimestamp,
+    /// Resolves the conflict using the given function (called with the local and the pulled record), the conflict is
+    /// left unresolved if it returns `None`
+    Custom(fn(&M, &M) -> Option<M>),
+}
+
+impl<M> fmt::Debug for ConflictStrategy<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LocalWins => f.write_str("LocalWins"),
+            Self::RemoteWins => f.write_str("RemoteWins"),
+            Self::LatestTimestamp => f.write_str("LatestTimestamp"),
+            Self::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// Unresolved conflict between a local record and a pulled record. The local record is kept in the store until the
+/// conflict is resolved.
+#[derive(Debug, Deserialize)]
+#[serde(bound(deserialize = "M: Model"))]
+pub struct Conflict<M>
+where
+    M: Model,
+{
+    /// Primary key of the conflicting records
+    pub key: M::Key,
+    /// Local record
+    pub local: M,
+    /// Pulled record
+    pub remote: M,
+    /// Time at which the conflict was detected (in milliseconds since unix epoch)
+    pub detected_at: f64,
+}
+
+#[derive(Serialize)]
+struct ConflictRecord<'a> {
+    store: &'a str,
+    #[serde(with = "serde_wasm_bindgen::preserve")]
+    key: JsValue,
+    #[serde(with = "serde_wasm_bindgen::preserve")]
+    local: JsValue,
+    #[serde(with = "serde_wasm_bindgen::preserve")]
+    remote: JsValue,
+    detected_at: f64,
+}
+
+/// Returns the object store builder for the conflict store.
+pub(crate) fn object_store_builder() -> ObjectStoreBuilder {
+    ObjectStoreBuilder::new(CONFLICT_STORE_NAME)
+        .key_path(Some(KeyPath::new_array(["store", "key"])))
+}
+
+/// Returns the key range covering all the conflicts of a model.
+pub(crate) fn model_range<M>() -> Result<Query, Error>
+where
+    M: Model,
+{
+    // Arrays sort after every other key type, so `[name, []]` is an upper bound for all `[name, key]` keys
+    let range = KeyRange::bound(
+        &Array::of1(&JsValue::from_str(M::NAME)),
+        &Array::of2(&JsValue::from_str(M::NAME), &Array::new()),
+        Some(false),
+        Some(true),
+    )?;
+
+    Ok(Query::KeyRange(range))
+}
+
+/// Returns the key of a model's conflict for the given primary key.
+pub(crate) fn conflict_key<M>(key: &JsValue) -> JsValue
+where
+    M: Model,
+{
+    Array::of2(&JsValue::from_str(M::NAME), key).into()
+}
+
+/// Writes pulled records to the object store of a model.
+pub(crate) trait Resolver<M>
+where
+    M: Model,
+{
+    /// Writes the record (or resolves a conflict with the local record), returning `true` if an unresolved conflict
+    /// was recorded.
+    fn write(
+        &self,
+        transaction: &Transaction,
+        store: &ObjectStore<'_, M>,
+        remote: &M,
+    ) -> impl Future<Output = Result<bool, Error>>;
+}
+
+/// Resolver overwriting local records with the pulled ones.
+pub(crate) struct Overwrite;
+
+impl<M> Resolver<M> for Overwrite
+where
+    M: Model,
+{
+    async fn write(
+        &self,
+        _: &Transaction,
+        store: &ObjectStore<'_, M>,
+        remote: &M,
+    ) -> Result<bool, Error> {
+        store.update(remote).await?;
+        Ok(false)
+    }
+}
+
+/// Resolver applying a [`ConflictStrategy`] to records modified locally after the last push checkpoint.
+pub(crate) struct StrategyResolver<'s, M>
+where
+    M: Tracked,
+{
+    pub(crate) strategy: &'s ConflictStrategy<M>,
+    pub(crate) checkpoint: Option<<M::UpdatedAtIndex as ModelIndex>::Key>,
+}
+
+impl<M> StrategyResolver<'_, M>
+where
+    M: Tracked,
+    <M::UpdatedAtIndex as ModelIndex>::Key: PartialOrd,
+{
+    fn is_unsynced(&self, local: &M) -> bool {
+        match self.checkpoint {
+            Some(ref checkpoint) => local.updated_at() > checkpoint,
+            None => true,
+        }
+    }
+}
+
+impl<M> Resolver<M> for StrategyResolver<'_, M>
+where
+    M: Tracked,
+    <M::UpdatedAtIndex as ModelIndex>::Key: PartialOrd,
+{
+    async fn write(
+        &self,
+        transaction: &Transaction,
+        store: &ObjectStore<'_, M>,
+        remote: &M,
+    ) -> Result<bool, Error> {
+        let object_store = store.as_idb_object_store();
+
+        let local = match object_store.key_path()? {
+            Some(key_path) => {
+                // The key is read from the record as stored, since key paths point into the envelope (if any)
+                let key = key_of(&serialize_put::<M, M>(remote)?, &key_path)?;
+                object_store
+                    .get(Query::Key(key.clone()))?
+                    .await?
+                    .map(|local| (key, local))
+            }
+            None => None,
+        };
+
+        let Some((key, local_value)) = local else {
+            store.update(remote).await?;
+            return Ok(false);
+        };
+
+        let local: M = read_stored(transaction)(local_value)?;
+
+        if !self.is_unsynced(&local) {
+            store.update(remote).await?;
+            return Ok(false);
+        }
+
+        let resolution = match self.strategy {
+            ConflictStrategy::LocalWins => Resolution::Keep,
+            ConflictStrategy::RemoteWins => Resolution::Remote,
+            ConflictStrategy::LatestTimestamp => {
+                match remote.updated_at().partial_cmp(local.updated_at()) {
+                    Some(Ordering::Greater) => Resolution::Remote,
+                    Some(Ordering::Less) => Resolution::Keep,
+                    _ => Resolution::Unresolved,
+                }
+            }
+            ConflictStrategy::Custom(resolve) => match resolve(&local, remote) {
+                Some(resolved) => Resolution::Resolved(resolved),
+                None => Resolution::Unresolved,
+            },
+        };
+
+        let conflicts = transaction.idb_object_store(CONFLICT_STORE_NAME)?;
+
+        match resolution {
+            Resolution::Keep => {}
+            Resolution::Remote => {
+                store.update(remote).await?;
+            }
+            Resolution::Resolved(resolved) => {
+                store.update(&resolved).await?;
+            }
+            Resolution::Unresolved => {
+                let record = ConflictRecord {
+                    store: M::NAME,
+                    key,
+                    local: local.serialize(record_serializer::<M>())?,
+                    remote: remote.serialize(record_serializer::<M>())?,
+                    detected_at: Date::now(),
+                }
+                .serialize(&JSON_SERIALIZER)?;
+
+                conflicts.put(&record, None)?.await?;
+
+                return Ok(true);
+            }
+        }
+
+        // The conflict is resolved, so any conflict recorded by a previous pull is stale
+        conflicts
+            .delete(Query::Key(conflict_key::<M>(&key)))?
+            .await?;
+
+        Ok(false)
+    }
+}
+
+/// Outcome of applying a [`ConflictStrategy`] to a conflict.
+enum Resolution<M> {
+    Keep,
+    Remote,
+    Resolved(M),
+    Unresolved,
+}
+
+/// Extracts the primary key of a serialized record using the key path of its store.
+fn key_of(value: &JsValue, key_path: &KeyPath) -> Result<JsValue, Error> {
+    fn evaluate(value: &JsValue, path: &str) -> Result<JsValue, Error> {
+        path.split('.')
+            .try_fold(value.clone(), |value, property| {
+                Reflect::get(&value, &JsValue::from_str(property))
+            })
+            .map_err(|error| serde_wasm_bindgen::Error::from(error).into())
+    }
+
+    match key_path {
+        KeyPath::Single(path) => evaluate(value, path),
+        KeyPath::Array(paths) => paths
+            .iter()
+            .map(|path| evaluate(value, path))
+            .collect::<Result<Array, _>>()
+            .map(Into::into),
+    }
+}
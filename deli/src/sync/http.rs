@@ -0,0 +1,165 @@
+use js_sys::{encode_uri_component, global, Promise, JSON};
+use serde::Serialize;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Headers, Request, RequestInit, Response, Window, WorkerGlobalScope};
+
+use crate::{error::Error, model::Model, JSON_SERIALIZER};
+
+use super::{Pull, SyncAdapter};
+
+/// Reference [`SyncAdapter`] pulling JSON pages from an http endpoint using `fetch`.
+///
+/// The endpoint is requested with a `cursor` query parameter (omitted for the first page) and must respond with a JSON
+/// object of the form `{ "records": [...], "cursor": "...", "has_more": false }`. Entity tags are honoured: the
+/// `ETag` of a response is sent back as `If-None-Match` when the same cursor is requested again and a
+/// `304 Not Modified` response ends the pull. Pushed records are sent to the same endpoint as a JSON array in the body
+/// of a `POST` request.
+#[derive(Debug, Clone)]
+pub struct HttpSyncAdapter {
+    endpoint: String,
+    headers: Vec<(String, String)>,
+}
+
+impl HttpSyncAdapter {
+    /// Creates a new [`HttpSyncAdapter`] for the given endpoint.
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            headers: Vec::new(),
+        }
+    }
+
+    /// Adds a header (e.g. `Authorization`) sent with every request.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Returns the endpoint of the adapter.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    fn url(&self, cursor: Option<&str>) -> String {
+        match cursor {
+            None => self.endpoint.clone(),
+            Some(cursor) => {
+                let separator = if self.endpoint.contains('?') {
+                    '&'
+                } else {
+                    '?'
+                };
+                format!(
+                    "{}{}cursor={}",
+                    self.endpoint,
+                    separator,
+                    String::from(encode_uri_component(cursor))
+                )
+            }
+        }
+    }
+
+    fn headers(&self) -> Result<Headers, JsValue> {
+        let headers = Headers::new()?;
+        headers.set("Accept", "application/json")?;
+
+        for (name, value) in self.headers.iter() {
+            headers.set(name, value)?;
+        }
+
+        Ok(headers)
+    }
+
+    async fn fetch(&self, url: &str, init: &RequestInit) -> Result<Response, JsValue> {
+        let request = Request::new_with_str_and_init(url, init)?;
+
+        let global = global();
+        let promise: Promise = if let Some(window) = global.dyn_ref::<Window>() {
+            window.fetch_with_request(&request)
+        } else if let Some(worker) = global.dyn_ref::<WorkerGlobalScope>() {
+            worker.fetch_with_request(&request)
+        } else {
+            return Err(JsValue::from_str("fetch is not available in this context"));
+        };
+
+        JsFuture::from(promise).await?.dyn_into()
+    }
+}
+
+impl SyncAdapter for HttpSyncAdapter {
+    async fn pull<M>(&self, cursor: Option<&str>, etag: Option<&str>) -> Result<Pull<M>, Error>
+    where
+        M: Model,
+    {
+        let headers = self.headers().map_err(request_failed)?;
+
+        if let Some(etag) = etag {
+            headers.set("If-None-Match", etag).map_err(request_failed)?;
+        }
+
+        let init = RequestInit::new();
+        init.set_method("GET");
+        init.set_headers(&headers);
+
+        let response = self
+            .fetch(&self.url(cursor), &init)
+            .await
+            .map_err(request_failed)?;
+
+        if response.status() == 304 {
+            return Ok(Pull::NotModified);
+        }
+
+        if !response.ok() {
+            return Err(Error::SyncHttpStatus(response.status()));
+        }
+
+        let etag = response.headers().get("ETag").map_err(request_failed)?;
+        let json = JsFuture::from(response.json().map_err(request_failed)?)
+            .await
+            .map_err(request_failed)?;
+
+        Ok(Pull::Modified {
+            page: serde_wasm_bindgen::from_value(json)?,
+            etag,
+        })
+    }
+
+    async fn push<M>(&self, records: &[M]) -> Result<(), Error>
+    where
+        M: Model,
+    {
+        let body =
+            JSON::stringify(&records.serialize(&JSON_SERIALIZER)?).map_err(request_failed)?;
+
+        let headers = self.headers().map_err(request_failed)?;
+        headers
+            .set("Content-Type", "application/json")
+            .map_err(request_failed)?;
+
+        let init = RequestInit::new();
+        init.set_method("POST");
+        init.set_headers(&headers);
+        init.set_body(&body);
+
+        let response = self
+            .fetch(&self.endpoint, &init)
+            .await
+            .map_err(request_failed)?;
+
+        if !response.ok() {
+            return Err(Error::SyncHttpStatus(response.status()));
+        }
+
+        Ok(())
+    }
+}
+
+fn request_failed(err: JsValue) -> Error {
+    Error::SyncRequestFailed(
+        err.as_string()
+            .or_else(|| js_sys::Error::from(err.clone()).message().as_string())
+            .unwrap_or_else(|| format!("{err:?}")),
+    )
+}
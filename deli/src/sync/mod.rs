@@ -0,0 +1,544 @@
+//! Pull based synchronisation of object stores with a remote backend.
+//!
+//! A [`SyncAdapter`] fetches pages of records from a backend and a [`Syncer`] applies them to the object store of a
+//! model. The position in the remote change feed (the sync cursor) is persisted in `deli`'s internal meta store, so
+//! the database must be created with [`DatabaseBuilder::enable_sync`](crate::DatabaseBuilder::enable_sync).
+//!
+//! Models implementing [`Tracked`] (using `#[deli(updated_at)]` on an indexed timestamp field) can also be pushed
+//! incrementally: [`Syncer::push`] only uploads the records modified since the last successful push and keeps the
+//! resulting checkpoint in the meta store.
+//!
+//! Models annotated with `#[deli(tombstones)]` keep a [`Tombstone`] (primary key and deletion time) in an internal
+//! companion store for every deleted record, so deletions can be propagated to remote peers. Tombstones can be expired
+//! automatically using `#[deli(tombstones(expire_after_secs = 604800))]`.
+//!
+//! [`Syncer::pull_with`] applies a [`ConflictStrategy`] when a pulled record conflicts with a local change of a
+//! [`Tracked`] model that has not been pushed yet. Conflicts left unresolved by the strategy are recorded in an internal
+//! conflicts store and can be retrieved using [`Syncer::conflicts`].
+mod conflict;
+mod http;
+mod tombstone;
+mod tracked;
+
+use std::future::Future;
+
+use idb::Query;
+use js_sys::{Array, JSON};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+
+use crate::{
+    cursor::get_all_stored,
+    database::Database,
+    error::Error,
+    meta::{MetaStore, META_STORE_NAME},
+    model::{record_serializer, Model},
+    model_index::ModelIndex,
+    TransactionResult, JSON_SERIALIZER,
+};
+
+use self::conflict::{Overwrite, Resolver, StrategyResolver};
+
+pub use self::{
+    conflict::{Conflict, ConflictStrategy},
+    http::HttpSyncAdapter,
+    tombstone::{Tombstone, TombstonePolicy},
+    tracked::Tracked,
+};
+
+pub(crate) use self::{
+    conflict::{object_store_builder as conflict_store_builder, CONFLICT_STORE_NAME},
+    tombstone::{
+        object_store_builder as tombstone_store_builder, record as record_tombstones,
+        TOMBSTONE_STORE_NAME,
+    },
+};
+
+/// Default number of records written in a single transaction by [`Syncer`].
+pub const DEFAULT_CHUNK_SIZE: usize = 100;
+
+/// A page of records pulled from a remote backend.
+#[derive(Debug, Deserialize)]
+#[serde(bound(deserialize = "M: Model"))]
+pub struct Page<M> {
+    /// Records in the page
+    pub records: Vec<M>,
+    /// Cursor to use for fetching the next page (keeps the previous cursor if absent)
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Whether more pages are available after this one
+    #[serde(default)]
+    pub has_more: bool,
+}
+
+/// Result of pulling a page from a [`SyncAdapter`].
+#[derive(Debug)]
+pub enum Pull<M> {
+    /// Backend reported that nothing changed since the last pull (e.g. `304 Not Modified`)
+    NotModified,
+    /// Backend returned a page of records
+    Modified {
+        /// Page of records
+        page: Page<M>,
+        /// Entity tag of the response (if any)
+        etag: Option<String>,
+    },
+}
+
+/// Source of remote records for a [`Syncer`].
+pub trait SyncAdapter {
+    /// Pulls the page of records following `cursor` (or the first page if `cursor` is `None`). `etag` is the entity
+    /// tag returned by the backend when the same cursor was last requested.
+    fn pull<M>(
+        &self,
+        cursor: Option<&str>,
+        etag: Option<&str>,
+    ) -> impl Future<Output = Result<Pull<M>, Error>>
+    where
+        M: Model;
+
+    /// Pushes locally modified records to the backend.
+    fn push<M>(&self, records: &[M]) -> impl Future<Output = Result<(), Error>>
+    where
+        M: Model;
+}
+
+/// Summary of a [`Syncer::pull`] run.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PullSummary {
+    /// Number of pages applied to the store
+    pub pages: usize,
+    /// Number of records pulled from the backend
+    pub records: usize,
+    /// Number of conflicts left unresolved (see [`Syncer::pull_with`])
+    pub conflicts: usize,
+}
+
+/// Summary of a [`Syncer::push`] run.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PushSummary {
+    /// Number of records pushed to the backend
+    pub records: usize,
+}
+
+/// Persisted position of a model in the remote change feed.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SyncState {
+    cursor: Option<String>,
+    etag: Option<String>,
+    etag_cursor: Option<String>,
+}
+
+impl SyncState {
+    /// Entity tag to send with the next request (only valid when re-requesting the cursor it was returned for).
+    fn etag(&self) -> Option<&str> {
+        if self.etag_cursor == self.cursor {
+            self.etag.as_deref()
+        } else {
+            None
+        }
+    }
+}
+
+/// Applies records pulled from a [`SyncAdapter`] to object stores of a database.
+#[derive(Debug)]
+pub struct Syncer<'a, A> {
+    database: &'a Database,
+    adapter: A,
+    chunk_size: usize,
+    #[cfg(feature = "priority")]
+    priority: crate::priority::Priority,
+}
+
+impl<'a, A> Syncer<'a, A>
+where
+    A: SyncAdapter,
+{
+    /// Creates a new [`Syncer`] for the given database and adapter.
+    pub fn new(database: &'a Database, adapter: A) -> Self {
+        Self {
+            database,
+            adapter,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            #[cfg(feature = "priority")]
+            priority: Default::default(),
+        }
+    }
+
+    /// Sets the maximum number of records written in a single transaction.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Sets the priority of writing pulled records. [`Background`](crate::priority::Priority::Background) syncs wait for
+    /// the browser to be idle between chunks.
+    #[cfg(feature = "priority")]
+    pub fn priority(mut self, priority: crate::priority::Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Returns the adapter used by this syncer.
+    pub fn adapter(&self) -> &A {
+        &self.adapter
+    }
+
+    /// Pulls pages from the adapter until the backend reports no more pages, writing the records to the model's store
+    /// and persisting the sync cursor after each page.
+    pub async fn pull<M>(&self) -> Result<PullSummary, Error>
+    where
+        M: Model,
+    {
+        self.pull_resolved::<M, _>(&Overwrite).await
+    }
+
+    /// Pulls pages from the adapter like [`pull`](Self::pull), applying the given strategy to pulled records that
+    /// conflict with local records modified after the last push checkpoint. Unresolved conflicts keep the local record
+    /// and are recorded in the conflicts store.
+    pub async fn pull_with<M>(&self, strategy: &ConflictStrategy<M>) -> Result<PullSummary, Error>
+    where
+        M: Tracked,
+        <M::UpdatedAtIndex as ModelIndex>::Key: PartialOrd,
+    {
+        let resolver = StrategyResolver {
+            strategy,
+            checkpoint: self.checkpoint::<M>().await?,
+        };
+
+        self.pull_resolved::<M, _>(&resolver).await
+    }
+
+    /// Retrieves the unresolved conflicts of the model, ordered by primary key.
+    pub async fn conflicts<M>(&self) -> Result<Vec<Conflict<M>>, Error>
+    where
+        M: Model,
+    {
+        let transaction = self
+            .database
+            .transaction()
+            .with_store_name(CONFLICT_STORE_NAME)
+            .build()?;
+
+        let conflicts = get_all_stored(
+            &transaction,
+            &transaction.idb_object_store(CONFLICT_STORE_NAME)?,
+            Some(conflict::model_range::<M>()?),
+            None,
+        )
+        .await?
+        .into_iter()
+        .map(serde_wasm_bindgen::from_value)
+        .collect::<Result<_, _>>()?;
+
+        transaction.done().await?;
+
+        Ok(conflicts)
+    }
+
+    /// Removes the conflict recorded for the given primary key (e.g. after resolving it manually).
+    pub async fn clear_conflict<M>(&self, key: &M::Key) -> Result<(), Error>
+    where
+        M: Model,
+    {
+        let key: JsValue = key.serialize(&JSON_SERIALIZER)?;
+
+        let transaction = self
+            .database
+            .transaction()
+            .writable()
+            .with_store_name(CONFLICT_STORE_NAME)
+            .build()?;
+
+        transaction
+            .idb_object_store(CONFLICT_STORE_NAME)?
+            .delete(Query::Key(conflict::conflict_key::<M>(&key)))?
+            .await?;
+
+        if transaction.commit().await? == TransactionResult::Aborted {
+            return Err(Error::TransactionAborted);
+        }
+
+        Ok(())
+    }
+
+    async fn pull_resolved<M, R>(&self, resolver: &R) -> Result<PullSummary, Error>
+    where
+        M: Model,
+        R: Resolver<M>,
+    {
+        let mut summary = PullSummary::default();
+        let mut state = self.load_state::<M>().await?;
+
+        loop {
+            let (page, etag) = match self
+                .adapter
+                .pull::<M>(state.cursor.as_deref(), state.etag())
+                .await?
+            {
+                Pull::NotModified => break,
+                Pull::Modified { page, etag } => (page, etag),
+            };
+
+            let next_state = SyncState {
+                cursor: page.cursor.or_else(|| state.cursor.clone()),
+                etag,
+                etag_cursor: state.cursor,
+            };
+
+            summary.pages += 1;
+            summary.records += page.records.len();
+
+            summary.conflicts += self
+                .apply::<M, _>(&page.records, &next_state, resolver)
+                .await?;
+
+            state = next_state;
+
+            if !page.has_more {
+                break;
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Returns the sync cursor persisted for the model (if any).
+    pub async fn cursor<M>(&self) -> Result<Option<String>, Error>
+    where
+        M: Model,
+    {
+        Ok(self.load_state::<M>().await?.cursor)
+    }
+
+    /// Pushes the records modified since the last checkpoint to the adapter and advances the checkpoint to the latest
+    /// pushed modification time.
+    pub async fn push<M>(&self) -> Result<PushSummary, Error>
+    where
+        M: Tracked,
+    {
+        let checkpoint = self.checkpoint::<M>().await?;
+
+        let (records, pushed) = {
+            let transaction = self
+                .database
+                .transaction()
+                .with_model::<M>()
+                .with_store_name(META_STORE_NAME)
+                .build()?;
+            let records = {
+                let store = transaction.object_store::<M>()?;
+
+                match checkpoint {
+                    Some(ref checkpoint) => store.changed_since(checkpoint).await?,
+                    None => store.all_by_updated_at().await?,
+                }
+            };
+            let pushed: Vec<String> = MetaStore::new(&transaction)?
+                .get(&pushed_key::<M>())
+                .await?
+                .unwrap_or_default();
+            transaction.done().await?;
+            (records, pushed)
+        };
+
+        // Records modified at the checkpoint are read again, so the ones pushed already are skipped
+        let mut versions = Vec::with_capacity(records.len());
+        let mut unpushed = Vec::with_capacity(records.len());
+
+        for record in records {
+            let version = PushedVersion::of(&record)?;
+
+            if !pushed.contains(&version.id) {
+                unpushed.push(record);
+            }
+
+            versions.push(version);
+        }
+
+        let Some(latest) = unpushed.last() else {
+            return Ok(PushSummary::default());
+        };
+
+        self.adapter.push(&unpushed).await?;
+
+        let latest_at = PushedVersion::of(latest)?.updated_at;
+        let pushed: Vec<String> = versions
+            .into_iter()
+            .filter(|version| version.updated_at == latest_at)
+            .map(|version| version.id)
+            .collect();
+
+        let transaction = self
+            .database
+            .transaction()
+            .writable()
+            .with_store_name(META_STORE_NAME)
+            .build()?;
+
+        let meta_store = MetaStore::new(&transaction)?;
+        meta_store
+            .set(&checkpoint_key::<M>(), latest.updated_at())
+            .await?;
+        meta_store.set(&pushed_key::<M>(), &pushed).await?;
+
+        if transaction.commit().await? == TransactionResult::Aborted {
+            return Err(Error::TransactionAborted);
+        }
+
+        Ok(PushSummary {
+            records: unpushed.len(),
+        })
+    }
+
+    /// Returns the modification time of the latest record pushed for the model (if any).
+    pub async fn checkpoint<M>(
+        &self,
+    ) -> Result<Option<<M::UpdatedAtIndex as ModelIndex>::Key>, Error>
+    where
+        M: Tracked,
+    {
+        let transaction = self
+            .database
+            .transaction()
+            .with_store_name(META_STORE_NAME)
+            .build()?;
+
+        let checkpoint = MetaStore::new(&transaction)?
+            .get(&checkpoint_key::<M>())
+            .await?;
+
+        transaction.done().await?;
+
+        Ok(checkpoint)
+    }
+
+    async fn load_state<M>(&self) -> Result<SyncState, Error>
+    where
+        M: Model,
+    {
+        let transaction = self
+            .database
+            .transaction()
+            .with_store_name(META_STORE_NAME)
+            .build()?;
+
+        let state = MetaStore::new(&transaction)?.get(&state_key::<M>()).await?;
+
+        transaction.done().await?;
+
+        Ok(state.unwrap_or_default())
+    }
+
+    /// Writes records in chunks (one transaction per chunk), persisting the sync state with the last chunk. Returns the
+    /// number of unresolved conflicts.
+    async fn apply<M, R>(
+        &self,
+        records: &[M],
+        state: &SyncState,
+        resolver: &R,
+    ) -> Result<usize, Error>
+    where
+        M: Model,
+        R: Resolver<M>,
+    {
+        let mut conflicts = 0;
+        let mut chunks = records.chunks(self.chunk_size).peekable();
+
+        loop {
+            let chunk = chunks.next().unwrap_or_default();
+            let is_last = chunks.peek().is_none();
+
+            let transaction = self
+                .database
+                .transaction()
+                .writable()
+                .with_model::<M>()
+                .with_store_name(META_STORE_NAME)
+                .with_store_name(CONFLICT_STORE_NAME)
+                .build()?;
+
+            {
+                let store = transaction.object_store::<M>()?;
+
+                for record in chunk {
+                    if resolver.write(&transaction, &store, record).await? {
+                        conflicts += 1;
+                    }
+                }
+
+                if is_last {
+                    MetaStore::new(&transaction)?
+                        .set(&state_key::<M>(), state)
+                        .await?;
+                }
+            }
+
+            if transaction.commit().await? == TransactionResult::Aborted {
+                return Err(Error::TransactionAborted);
+            }
+
+            if is_last {
+                return Ok(conflicts);
+            }
+
+            #[cfg(feature = "priority")]
+            self.priority.between_batches().await;
+        }
+    }
+}
+
+fn state_key<M>() -> String
+where
+    M: Model,
+{
+    format!("sync/{}/state", M::NAME)
+}
+
+fn checkpoint_key<M>() -> String
+where
+    M: Model,
+{
+    format!("sync/{}/checkpoint", M::NAME)
+}
+
+/// Key of the versions of the records pushed at the checkpoint (records modified at the checkpoint are read again by
+/// the next push).
+fn pushed_key<M>() -> String
+where
+    M: Model,
+{
+    format!("sync/{}/pushed", M::NAME)
+}
+
+/// Version of a record, identified by its primary key and modification time (serialized as JSON to be compared).
+struct PushedVersion {
+    id: String,
+    updated_at: String,
+}
+
+impl PushedVersion {
+    fn of<M>(record: &M) -> Result<Self, Error>
+    where
+        M: Tracked,
+    {
+        // External keys are not part of the record, so such records are identified by their value instead
+        let key = match record.serialize_key(&JSON_SERIALIZER) {
+            Ok(key) => key,
+            Err(_) => record.serialize(record_serializer::<M>())?,
+        };
+        let updated_at = record.updated_at().serialize(&JSON_SERIALIZER)?;
+
+        Ok(Self {
+            id: stringify(&Array::of2(&key, &updated_at))?,
+            updated_at: stringify(&updated_at)?,
+        })
+    }
+}
+
+/// Returns the JSON representation of a serialized value, used to compare versions.
+fn stringify(value: &JsValue) -> Result<String, Error> {
+    Ok(JSON::stringify(value)
+        .map_err(serde_wasm_bindgen::Error::from)?
+        .into())
+}
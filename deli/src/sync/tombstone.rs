@@ -0,0 +1,133 @@
+use idb::{
+    builder::{IndexBuilder, ObjectStoreBuilder},
+    KeyPath, KeyRange, Query,
+};
+use js_sys::{Array, Date};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+
+use crate::{
+    cursor::{get_all_stored, index_get_all_keys_stored},
+    error::Error,
+    model::Model,
+    object_store::ObjectStore,
+    transaction::Transaction,
+    JSON_SERIALIZER,
+};
+
+/// Name of the internal object store keeping tombstones of deleted records.
+pub(crate) const TOMBSTONE_STORE_NAME: &str = "__deli_tombstones";
+
+const TOMBSTONE_INDEX_NAME: &str = "__deli_tombstones_store_deleted_at";
+
+/// Tombstone policy of a model (configured using `#[deli(tombstones)]`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TombstonePolicy {
+    /// Number of seconds after which tombstones are purged (kept forever if `None`)
+    pub expire_after_secs: Option<u64>,
+}
+
+/// Marker left behind in place of a deleted record.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Tombstone<K> {
+    /// Primary key of the deleted record
+    pub key: K,
+    /// Deletion time (in milliseconds since unix epoch)
+    pub deleted_at: f64,
+}
+
+#[derive(Serialize)]
+struct TombstoneRecord<'a> {
+    store: &'a str,
+    #[serde(with = "serde_wasm_bindgen::preserve")]
+    key: JsValue,
+    deleted_at: f64,
+}
+
+/// Returns the object store builder for the tombstone store.
+pub(crate) fn object_store_builder() -> ObjectStoreBuilder {
+    ObjectStoreBuilder::new(TOMBSTONE_STORE_NAME)
+        .key_path(Some(KeyPath::new_array(["store", "key"])))
+        .add_index(IndexBuilder::new(
+            TOMBSTONE_INDEX_NAME.to_owned(),
+            KeyPath::new_array(["store", "deleted_at"]),
+        ))
+}
+
+/// Records tombstones for the given primary keys (if tombstones are enabled for the model) and purges the expired
+/// tombstones of the model.
+pub(crate) async fn record<M>(transaction: &Transaction, keys: Vec<JsValue>) -> Result<(), Error>
+where
+    M: Model,
+{
+    let Some(policy) = M::TOMBSTONES else {
+        return Ok(());
+    };
+
+    let object_store = transaction.idb_object_store(TOMBSTONE_STORE_NAME)?;
+    let deleted_at = Date::now();
+
+    for key in keys {
+        let record = TombstoneRecord {
+            store: M::NAME,
+            key,
+            deleted_at,
+        }
+        .serialize(&JSON_SERIALIZER)?;
+
+        object_store.put(&record, None)?.await?;
+    }
+
+    if let Some(expire_after_secs) = policy.expire_after_secs {
+        let cutoff = deleted_at - (expire_after_secs as f64) * 1000.0;
+        let range = KeyRange::bound(
+            &Array::of1(&JsValue::from_str(M::NAME)),
+            &Array::of2(&JsValue::from_str(M::NAME), &JsValue::from_f64(cutoff)),
+            Some(false),
+            Some(true),
+        )?;
+
+        let expired = index_get_all_keys_stored(
+            transaction,
+            &object_store.index(TOMBSTONE_INDEX_NAME)?,
+            Some(Query::KeyRange(range)),
+            None,
+        )
+        .await?;
+
+        for key in expired {
+            object_store.delete(Query::Key(key))?.await?;
+        }
+    }
+
+    Ok(())
+}
+
+impl<M> ObjectStore<'_, M>
+where
+    M: Model,
+{
+    /// Retrieves the tombstones of the records deleted from this store, ordered by primary key.
+    pub async fn tombstones(&self) -> Result<Vec<Tombstone<M::Key>>, Error> {
+        // No string sorts between `name` and `name\0`, so `[name\0]` is an upper bound for all `[name, key]` keys
+        // (including array keys, which sort after `[]`)
+        let range = KeyRange::bound(
+            &Array::of1(&JsValue::from_str(M::NAME)),
+            &Array::of1(&JsValue::from_str(&format!("{}\0", M::NAME))),
+            Some(false),
+            Some(true),
+        )?;
+
+        get_all_stored(
+            self.transaction(),
+            &self.transaction().idb_object_store(TOMBSTONE_STORE_NAME)?,
+            Some(Query::KeyRange(range)),
+            None,
+        )
+        .await?
+        .into_iter()
+        .map(serde_wasm_bindgen::from_value)
+        .collect::<Result<_, _>>()
+        .map_err(Into::into)
+    }
+}
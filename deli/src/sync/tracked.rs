@@ -0,0 +1,62 @@
+use idb::{KeyRange, Query};
+use serde::Serialize;
+
+use crate::{
+    cursor::{collect_values, has_get_all},
+    error::Error,
+    gate::Operation,
+    model::{read_stored, Model},
+    model_index::{open_index, ModelIndex},
+    object_store::ObjectStore,
+    JSON_SERIALIZER,
+};
+
+/// Trait for models with an indexed `updated_at` field (implemented by `#[deli(updated_at)]`).
+pub trait Tracked: Model {
+    /// Index over the `updated_at` field of the model
+    type UpdatedAtIndex: ModelIndex<Model = Self>;
+
+    /// Returns the last modification time of the record
+    fn updated_at(&self) -> &<Self::UpdatedAtIndex as ModelIndex>::Key;
+}
+
+impl<M> ObjectStore<'_, M>
+where
+    M: Tracked,
+{
+    /// Retrieves all the records modified at or after the given timestamp, ordered by modification time. Records
+    /// modified at the timestamp itself are included, so that no record sharing its modification time with the last
+    /// pushed record is missed.
+    pub async fn changed_since(
+        &self,
+        timestamp: &<M::UpdatedAtIndex as ModelIndex>::Key,
+    ) -> Result<Vec<M>, Error> {
+        let lower = timestamp.serialize(&JSON_SERIALIZER)?;
+        let query = Query::KeyRange(KeyRange::lower_bound(&lower, Some(false))?);
+
+        self.by_updated_at(Some(query)).await
+    }
+
+    /// Retrieves all the records ordered by modification time.
+    pub(crate) async fn all_by_updated_at(&self) -> Result<Vec<M>, Error> {
+        self.by_updated_at(None).await
+    }
+
+    async fn by_updated_at(&self, query: Option<Query>) -> Result<Vec<M>, Error> {
+        self.transaction().check(M::NAME, Operation::Read)?;
+
+        let index = open_index::<M::UpdatedAtIndex>(self.as_idb_object_store())?;
+
+        if !has_get_all() {
+            let cursor = index.open_cursor(query, None)?.await?;
+            return collect_values(self.transaction(), cursor, 0, None).await;
+        }
+
+        index
+            .get_all(query, None)?
+            .await?
+            .into_iter()
+            .map(read_stored(self.transaction()))
+            .collect()
+    }
+}
@@ -0,0 +1,22 @@
+//! Helpers for testing code using `deli`.
+//!
+//! [`snapshot`] describes the object store generated for a model, so that tests can compare it with a stored snapshot
+//! and catch unexpected changes of stores or indexes (e.g. after upgrading `deli`).
+use crate::model::Model;
+
+/// Returns a human readable description of the object store generated for a model (name, key path, auto increment and
+/// indexes with their key paths and flags), suitable for snapshot tests. It renders the
+/// [`StoreSchema`](crate::StoreSchema) of the model:
+///
+/// ```text
+/// store "employee"
+///   key path "id", auto increment
+///   index "employee_age_index" on "age"
+///   index "employee_email_unique_index" on "email", unique
+/// ```
+pub fn snapshot<M>() -> String
+where
+    M: Model,
+{
+    M::store_schema().to_string()
+}
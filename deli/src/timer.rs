@@ -0,0 +1,80 @@
+//! Timers based on `setTimeout`.
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use js_sys::{Function, Promise, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+/// Future returned by [`sleep`]. The timeout is cleared if the future is dropped before it elapses.
+#[derive(Debug)]
+pub(crate) struct Sleep {
+    promise: JsFuture,
+    /// Identifier of the timeout returned by `setTimeout` (`undefined` where timers are not available)
+    id: JsValue,
+    elapsed: bool,
+}
+
+/// Resolves after the given duration using `setTimeout` (never resolves where timers are not available).
+pub(crate) fn sleep(duration: Duration) -> Sleep {
+    let mut id = JsValue::UNDEFINED;
+
+    let promise = Promise::new(&mut |resolve, _| {
+        let global = js_sys::global();
+
+        if let Some(set_timeout) = function(&global, "setTimeout") {
+            id = set_timeout
+                .call2(
+                    &global,
+                    &resolve,
+                    &JsValue::from_f64(duration.as_millis() as f64),
+                )
+                .unwrap_or(JsValue::UNDEFINED);
+        }
+    });
+
+    Sleep {
+        promise: JsFuture::from(promise),
+        id,
+        elapsed: false,
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match Pin::new(&mut self.promise).poll(cx) {
+            Poll::Ready(_) => {
+                self.elapsed = true;
+                Poll::Ready(())
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if self.elapsed || self.id.is_undefined() {
+            return;
+        }
+
+        let global = js_sys::global();
+
+        if let Some(clear_timeout) = function(&global, "clearTimeout") {
+            let _ = clear_timeout.call1(&global, &self.id);
+        }
+    }
+}
+
+/// Returns the function with the given name of the global scope, if available.
+fn function(global: &JsValue, name: &str) -> Option<Function> {
+    Reflect::get(global, &JsValue::from_str(name))
+        .ok()
+        .and_then(|function| function.dyn_into::<Function>().ok())
+}
@@ -0,0 +1,61 @@
+//! Timestamps maintained by `deli`.
+//!
+//! A field annotated with `#[deli(created_at)]` is set to the current time whenever a record is added (the value given
+//! when adding the record is replaced). The format of the timestamp is chosen using
+//! `#[deli(created_at(unit = ".."))]`, to match how the rest of the app serializes timestamps:
+//!
+//! - `millis` (default): milliseconds since the Unix epoch, stored in an integer field (e.g. `u64`)
+//! - `seconds`: seconds since the Unix epoch, stored in an integer field
+//! - `iso8601`: ISO 8601 string in UTC (e.g. `2024-01-31T12:00:00.000Z`), stored in a `String` field
+//!
+//! All the formats sort in chronological order, so the field can be indexed to list records by creation time:
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize, Model)]
+//! struct Comment {
+//!     #[deli(auto_increment)]
+//!     id: u32,
+//!     text: String,
+//!     #[deli(index, created_at(unit = "iso8601"))]
+//!     created_at: String,
+//! }
+//! ```
+use js_sys::{Date, Reflect};
+use wasm_bindgen::JsValue;
+
+use crate::{error::Error, model::Model};
+
+/// Format of a timestamp maintained by `deli` (see [`timestamps`](crate::timestamps)).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampUnit {
+    /// Milliseconds since the Unix epoch
+    Millis,
+    /// Seconds since the Unix epoch
+    Seconds,
+    /// ISO 8601 string in UTC
+    Iso8601,
+}
+
+impl TimestampUnit {
+    /// Returns the current time in this format.
+    fn now(self) -> JsValue {
+        match self {
+            TimestampUnit::Millis => JsValue::from_f64(Date::now()),
+            TimestampUnit::Seconds => JsValue::from_f64((Date::now() / 1000.0).floor()),
+            TimestampUnit::Iso8601 => Date::new_0().to_iso_string().into(),
+        }
+    }
+}
+
+/// Sets the `created_at` field of a serialized record being added to the current time.
+pub(crate) fn write_created_at<M>(value: &JsValue) -> Result<(), Error>
+where
+    M: Model,
+{
+    if let Some((name, unit)) = M::CREATED_AT {
+        Reflect::set(value, &JsValue::from_str(name), &unit.now())
+            .map_err(serde_wasm_bindgen::Error::from)?;
+    }
+
+    Ok(())
+}
@@ -1,7 +1,14 @@
+use std::{cell::RefCell, collections::HashMap};
+
 use idb::{TransactionMode, TransactionResult};
 
 use crate::{
-    database::Database, error::Error, model::Model, object_store::ObjectStore,
+    cancellation::CancellationToken,
+    database::Database,
+    error::Error,
+    gate::{GateHandle, Operation},
+    model::Model,
+    object_store::ObjectStore,
     transaction_builder::TransactionBuilder,
 };
 
@@ -9,11 +16,41 @@ use crate::{
 #[derive(Debug)]
 pub struct Transaction {
     transaction: idb::Transaction,
+    read_only: bool,
+    gate: Option<GateHandle>,
+    cancellation: Option<CancellationToken>,
+    object_stores: RefCell<HashMap<String, idb::ObjectStore>>,
+    #[cfg(feature = "cache")]
+    query_cache: Option<(crate::cache::QueryCache, u64)>,
 }
 
 impl Transaction {
-    pub(crate) fn new(transaction: idb::Transaction) -> Self {
-        Self { transaction }
+    pub(crate) fn new(
+        transaction: idb::Transaction,
+        mode: TransactionMode,
+        gate: Option<GateHandle>,
+        cancellation: Option<CancellationToken>,
+    ) -> Self {
+        Self {
+            transaction,
+            read_only: mode == TransactionMode::ReadOnly,
+            gate,
+            cancellation,
+            object_stores: Default::default(),
+            #[cfg(feature = "cache")]
+            query_cache: None,
+        }
+    }
+
+    #[cfg(feature = "cache")]
+    pub(crate) fn with_query_cache(
+        self,
+        query_cache: Option<(crate::cache::QueryCache, u64)>,
+    ) -> Self {
+        Self {
+            query_cache,
+            ..self
+        }
     }
 
     /// Creates a new [`TransactionBuilder`] with the given database.
@@ -38,16 +75,30 @@ impl Transaction {
     where
         M: Model,
     {
-        self.transaction
-            .object_store(M::NAME)
+        self.idb_object_store(M::NAME)
             .map(|object_store| ObjectStore::new(object_store, self))
-            .map_err(Into::into)
+    }
+
+    /// Returns a [`DynStore`](crate::DynStore) for an object store in transaction's scope which is not described by a
+    /// model (added to the transaction using [`TransactionBuilder::with_store`]).
+    #[cfg(feature = "dynamic")]
+    pub fn dynamic_store(&self, name: &str) -> Result<crate::DynStore<'_>, Error> {
+        self.idb_object_store(name)
+            .map(|object_store| crate::DynStore::new(name, object_store, self))
     }
 
     /// Attempts to commit the transaction. All pending requests will be allowed to complete, but no new requests will
     /// be accepted. This can be used to force a transaction to quickly finish, without waiting for pending requests to
     /// fire success events before attempting to commit normally.
+    ///
+    /// Where the browser does not support `commit()` (detected with the `capabilities` feature), waits for the
+    /// transaction to complete instead.
     pub async fn commit(self) -> Result<TransactionResult, Error> {
+        #[cfg(feature = "capabilities")]
+        if !crate::capabilities().commit {
+            return self.done().await;
+        }
+
         self.transaction.commit()?.await.map_err(Into::into)
     }
 
@@ -60,4 +111,84 @@ impl Transaction {
     pub async fn done(self) -> Result<TransactionResult, Error> {
         self.transaction.await.map_err(Into::into)
     }
+
+    /// Fails with [`Error::Cancelled`] if the transaction was cancelled, with [`Error::ReadOnlyTransaction`] if the
+    /// operation writes to a read-only transaction, or with [`Error::AccessDenied`] if the gate of the database does not
+    /// allow the operation on the object store.
+    pub(crate) fn check(&self, store: &str, operation: Operation) -> Result<(), Error> {
+        self.check_cancelled()?;
+
+        if self.read_only && operation != Operation::Read {
+            return Err(Error::ReadOnlyTransaction {
+                store: store.to_owned(),
+                operation,
+            });
+        }
+
+        if let Some(ref gate) = self.gate {
+            gate.check(store, operation)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns `true` if the gate of the database (if any) allows the operation on the object store. Unlike
+    /// [`Self::check`], this ignores the mode and cancellation of the transaction and has no side effects, e.g. for
+    /// operations run in a transaction of their own.
+    #[cfg(feature = "envelope")]
+    pub(crate) fn allows(&self, store: &str, operation: Operation) -> bool {
+        self.gate
+            .as_ref()
+            .is_none_or(|gate| gate.allows(store, operation))
+    }
+
+    /// Returns the query cache of the database and the number of the transaction, if the cache is enabled.
+    #[cfg(feature = "cache")]
+    pub(crate) fn query_cache(&self) -> Option<(&crate::cache::QueryCache, u64)> {
+        self.query_cache
+            .as_ref()
+            .map(|(cache, serial)| (cache, *serial))
+    }
+
+    /// Returns the handle of an object store in transaction's scope. Handles are cached, so that requesting the same
+    /// store again does not cross the JS boundary.
+    pub(crate) fn idb_object_store(&self, name: &str) -> Result<idb::ObjectStore, Error> {
+        if let Some(object_store) = self.object_stores.borrow().get(name) {
+            return Ok(object_store.clone());
+        }
+
+        let object_store = self.transaction.object_store(name)?;
+        self.object_stores
+            .borrow_mut()
+            .insert(name.to_owned(), object_store.clone());
+
+        Ok(object_store)
+    }
+
+    /// Returns the handle of the database the transaction was created on.
+    #[cfg(feature = "envelope")]
+    pub(crate) fn idb_database(&self) -> idb::Database {
+        self.transaction.database()
+    }
+
+    /// Aborts the transaction and fails with [`Error::Cancelled`] if its cancellation token was cancelled.
+    pub(crate) fn check_cancelled(&self) -> Result<(), Error> {
+        if !self
+            .cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            return Ok(());
+        }
+
+        // `idb::Transaction::abort` takes the transaction by value, so the transaction is aborted through a handle
+        // obtained from one of its object stores. Aborting an already aborted transaction fails, which is ignored.
+        if let Some(store) = self.transaction.store_names().first() {
+            if let Ok(object_store) = self.idb_object_store(store) {
+                let _ = object_store.transaction().abort();
+            }
+        }
+
+        Err(Error::Cancelled)
+    }
 }
@@ -1,9 +1,16 @@
-use crate::{database::Database, error::Error, model::Model, transaction::Transaction};
+use crate::{
+    cancellation::CancellationToken, database::Database, error::Error, gate::GateHandle,
+    model::Model, transaction::Transaction,
+};
 
 /// Builder for [`Transaction`]
 #[derive(Debug)]
 pub struct TransactionBuilder<'a> {
     database: &'a idb::Database,
+    gate: Option<&'a GateHandle>,
+    #[cfg(feature = "cache")]
+    query_cache: Option<&'a crate::cache::QueryCache>,
+    cancellation: Option<CancellationToken>,
     mode: idb::TransactionMode,
     stores: Vec<&'a str>,
 }
@@ -13,6 +20,10 @@ impl<'a> TransactionBuilder<'a> {
     pub fn new(database: &'a Database) -> Self {
         Self {
             database: database.as_idb_database(),
+            gate: database.gate(),
+            #[cfg(feature = "cache")]
+            query_cache: database.query_cache(),
+            cancellation: None,
             mode: idb::TransactionMode::ReadOnly,
             stores: Vec::new(),
         }
@@ -24,20 +35,88 @@ impl<'a> TransactionBuilder<'a> {
         self
     }
 
+    /// Enables write access to the transaction if the condition holds, e.g. for flows which only write to the models
+    /// in some cases.
+    pub fn writable_if(self, condition: bool) -> Self {
+        if condition {
+            self.writable()
+        } else {
+            self
+        }
+    }
+
+    /// Attaches a [`CancellationToken`] to the transaction. Once the token is cancelled, the transaction is aborted by
+    /// the next operation on it, which fails with [`Error::Cancelled`].
+    pub fn cancellation(mut self, token: &CancellationToken) -> Self {
+        self.cancellation = Some(token.clone());
+        self
+    }
+
     /// Adds a model to transaction
     pub fn with_model<M>(mut self) -> Self
     where
         M: Model,
     {
         self.stores.push(M::NAME);
+
+        #[cfg(feature = "sync")]
+        if M::TOMBSTONES.is_some() && !self.stores.contains(&crate::sync::TOMBSTONE_STORE_NAME) {
+            self.stores.push(crate::sync::TOMBSTONE_STORE_NAME);
+        }
+
+        #[cfg(feature = "inspect")]
+        if !self.stores.contains(&crate::inspect::SIZE_STORE_NAME)
+            && self
+                .database
+                .store_names()
+                .iter()
+                .any(|name| name == crate::inspect::SIZE_STORE_NAME)
+        {
+            self.stores.push(crate::inspect::SIZE_STORE_NAME);
+        }
+
+        self
+    }
+
+    /// Adds an object store which is not described by a model to transaction by its name, to be accessed using
+    /// [`Transaction::dynamic_store`]
+    #[cfg(feature = "dynamic")]
+    pub fn with_store(self, name: &'a str) -> Self {
+        self.with_store_name(name)
+    }
+
+    /// Adds an object store to transaction by its name
+    pub(crate) fn with_store_name(mut self, name: &'a str) -> Self {
+        self.stores.push(name);
         self
     }
 
     /// Builds the transaction
     pub fn build(self) -> Result<Transaction, Error> {
-        self.database
-            .transaction(&self.stores, self.mode)
-            .map(Transaction::new)
-            .map_err(Into::into)
+        let transaction = self.database.transaction(&self.stores, self.mode)?;
+
+        // Transactions are numbered right after being created, in the order IndexedDB runs them
+        #[cfg(feature = "cache")]
+        let query_cache = self.query_cache.map(|cache| (cache.clone(), cache.begin()));
+
+        #[cfg(feature = "cache")]
+        let transaction = match query_cache {
+            Some((ref cache, serial)) if self.mode == idb::TransactionMode::ReadWrite => {
+                cache.track_writes(transaction, serial, &self.stores)
+            }
+            _ => transaction,
+        };
+
+        let transaction = Transaction::new(
+            transaction,
+            self.mode,
+            self.gate.cloned(),
+            self.cancellation,
+        );
+
+        #[cfg(feature = "cache")]
+        let transaction = transaction.with_query_cache(query_cache);
+
+        Ok(transaction)
     }
 }
@@ -0,0 +1,57 @@
+//! Timeouts for operations which may never complete.
+//!
+//! Some IndexedDB requests never settle, e.g. opening a database with a new version while another tab keeps an older
+//! connection open. [`with_timeout`] races a future against a timeout and fails with [`Error::Timeout`] if the timeout
+//! elapses first, so that apps can report the problem instead of hanging forever. Opening a database can be guarded
+//! using [`DatabaseBuilder::timeout`](crate::DatabaseBuilder::timeout).
+//!
+//! IndexedDB requests cannot be cancelled, so the underlying request keeps running after a timeout. Opens which time out
+//! keep running in the background, and their connection is closed once they complete.
+use std::{cell::Cell, future::Future, pin::pin, time::Duration};
+
+use futures_util::future::{select, Either};
+
+use crate::{error::Error, timer::sleep};
+
+/// Waits for the future to complete, failing with [`Error::Timeout`] (for the given operation) if it does not complete
+/// within the timeout.
+pub async fn with_timeout<F, T>(
+    operation: &'static str,
+    timeout: Duration,
+    future: F,
+) -> Result<T, Error>
+where
+    F: Future<Output = Result<T, Error>>,
+{
+    match select(pin!(future), pin!(sleep(timeout))).await {
+        Either::Left((result, _)) => result,
+        Either::Right(_) => Err(Error::Timeout { operation, timeout }),
+    }
+}
+
+/// Waits for the future to complete like [`with_timeout`], but keeps running it to completion in the background if the
+/// timeout elapses first, setting `timed_out` so that it can release what it acquires late (e.g. a connection).
+pub(crate) async fn with_timeout_detached<F, T>(
+    operation: &'static str,
+    timeout: Duration,
+    timed_out: &Cell<bool>,
+    future: F,
+) -> Result<T, Error>
+where
+    F: Future<Output = Result<T, Error>> + 'static,
+    T: 'static,
+{
+    let mut future = Box::pin(future);
+
+    match select(future.as_mut(), pin!(sleep(timeout))).await {
+        Either::Left((result, _)) => result,
+        Either::Right(_) => {
+            timed_out.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                let _ = future.await;
+            });
+
+            Err(Error::Timeout { operation, timeout })
+        }
+    }
+}
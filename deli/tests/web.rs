@@ -1,4 +1,7 @@
-use deli::{Database, Error, Model, Transaction};
+use deli::{
+    CancellationToken, Database, Error, Key, Model, ModelIndex, Operation, OwnedRange, Schema,
+    SchemaConflict, Transaction,
+};
 use serde::{Deserialize, Serialize};
 use wasm_bindgen_test::{wasm_bindgen_test, wasm_bindgen_test_configure};
 
@@ -527,6 +530,35 @@ async fn test_get_key_by_index() {
     close_and_delete_database(database).await.unwrap();
 }
 
+#[wasm_bindgen_test]
+async fn test_index_key_of() {
+    let database = create_database().await.unwrap();
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    let id = store
+        .add(&AddEmployee {
+            name: "Alice".to_string(),
+            email: "alice@example.com".to_string(),
+            age: 25,
+        })
+        .await
+        .unwrap();
+
+    let email = store.by_email_unique().unwrap().key_of(&id).await.unwrap();
+    assert_eq!(email.as_deref(), Some("alice@example.com"));
+
+    let age = store.by_age().unwrap().key_of(&id).await.unwrap();
+    assert_eq!(age, Some(25));
+
+    let missing = store.by_age().unwrap().key_of(&(id + 1)).await.unwrap();
+    assert_eq!(missing, None);
+
+    transaction.done().await.expect("transaction done");
+
+    close_and_delete_database(database).await.unwrap();
+}
+
 #[wasm_bindgen_test]
 async fn test_get_all_by_index() {
     let database = create_database().await.unwrap();
@@ -697,3 +729,5560 @@ async fn test_get_all_keys_by_index() {
 
     close_and_delete_database(database).await.unwrap();
 }
+
+#[cfg(feature = "sync")]
+#[derive(Debug, Serialize, Deserialize, Model)]
+#[deli(tombstones(expire_after_secs = 3600))]
+struct Note {
+    #[deli(key)]
+    id: u32,
+    text: String,
+    #[deli(updated_at)]
+    updated_at: u64,
+}
+
+#[cfg(feature = "sync")]
+#[derive(Serialize)]
+struct NotesAdapter {
+    records: Vec<Note>,
+}
+
+#[cfg(feature = "sync")]
+impl deli::sync::SyncAdapter for NotesAdapter {
+    async fn pull<M>(&self, _: Option<&str>, _: Option<&str>) -> Result<deli::sync::Pull<M>, Error>
+    where
+        M: Model,
+    {
+        let page = serde_wasm_bindgen::from_value(serde_wasm_bindgen::to_value(self)?)?;
+        Ok(deli::sync::Pull::Modified { page, etag: None })
+    }
+
+    async fn push<M>(&self, _: &[M]) -> Result<(), Error>
+    where
+        M: Model,
+    {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sync")]
+async fn create_sync_database() -> Result<Database, Error> {
+    let _ = Database::delete("test_sync_db").await;
+
+    Database::builder("test_sync_db")
+        .version(1)
+        .add_model::<Note>()
+        .enable_sync()
+        .build()
+        .await
+}
+
+#[cfg(feature = "sync")]
+#[wasm_bindgen_test]
+async fn test_changed_since() {
+    let database = create_sync_database().await.unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Note>()
+        .build()
+        .unwrap();
+    let store = Note::with_transaction(&transaction).unwrap();
+
+    for (id, updated_at) in [(1, 30), (2, 10), (3, 20)] {
+        store
+            .add(&Note {
+                id,
+                text: format!("note {id}"),
+                updated_at,
+            })
+            .await
+            .unwrap();
+    }
+
+    let notes = store.changed_since(&20).await.unwrap();
+
+    assert_eq!(notes.len(), 2);
+    assert_eq!(notes[0].id, 3);
+    assert_eq!(notes[1].id, 1);
+
+    let notes = store.changed_since(&30).await.unwrap();
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0].id, 1);
+
+    let notes = store.changed_since(&31).await.unwrap();
+    assert!(notes.is_empty());
+
+    transaction.done().await.expect("transaction done");
+
+    database.close();
+    Database::delete("test_sync_db").await.unwrap();
+}
+
+#[cfg(feature = "sync")]
+#[wasm_bindgen_test]
+async fn test_push_same_timestamp() {
+    use deli::sync::Syncer;
+
+    async fn add_note(database: &Database, id: u32) {
+        let transaction = database
+            .transaction()
+            .writable()
+            .with_model::<Note>()
+            .build()
+            .unwrap();
+        Note::with_transaction(&transaction)
+            .unwrap()
+            .add(&Note {
+                id,
+                text: format!("note {id}"),
+                updated_at: 10,
+            })
+            .await
+            .unwrap();
+        transaction.commit().await.unwrap();
+    }
+
+    let database = create_sync_database().await.unwrap();
+    let syncer = Syncer::new(&database, NotesAdapter { records: vec![] });
+
+    add_note(&database, 1).await;
+    assert_eq!(syncer.push::<Note>().await.unwrap().records, 1);
+
+    // A record modified at the checkpoint after the push is pushed, the already pushed one is not
+    add_note(&database, 2).await;
+    assert_eq!(syncer.push::<Note>().await.unwrap().records, 1);
+    assert_eq!(syncer.push::<Note>().await.unwrap().records, 0);
+    assert_eq!(syncer.checkpoint::<Note>().await.unwrap(), Some(10));
+
+    database.close();
+    Database::delete("test_sync_db").await.unwrap();
+}
+
+#[cfg(feature = "sync")]
+#[wasm_bindgen_test]
+async fn test_delete_records_tombstones() {
+    let database = create_sync_database().await.unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Note>()
+        .build()
+        .unwrap();
+    let store = Note::with_transaction(&transaction).unwrap();
+
+    for id in 1..=3 {
+        store
+            .add(&Note {
+                id,
+                text: format!("note {id}"),
+                updated_at: id.into(),
+            })
+            .await
+            .unwrap();
+    }
+
+    store.delete(&2).await.unwrap();
+    store.delete(&3..).await.unwrap();
+
+    let tombstones = store.tombstones().await.unwrap();
+
+    assert_eq!(tombstones.len(), 2);
+    assert_eq!(tombstones[0].key, 2);
+    assert_eq!(tombstones[1].key, 3);
+    assert_eq!(store.count(..).await.unwrap(), 1);
+
+    transaction.done().await.expect("transaction done");
+
+    database.close();
+    Database::delete("test_sync_db").await.unwrap();
+}
+
+#[cfg(feature = "sync")]
+#[derive(Debug, Serialize, Deserialize, Model)]
+#[deli(key(notebook, id), tombstones)]
+struct NotebookPage {
+    notebook: String,
+    id: u32,
+}
+
+#[cfg(feature = "sync")]
+#[wasm_bindgen_test]
+async fn test_composite_key_tombstones() {
+    let _ = Database::delete("test_composite_tombstones_db").await;
+
+    let database = Database::builder("test_composite_tombstones_db")
+        .version(1)
+        .add_model::<Note>()
+        .add_model::<NotebookPage>()
+        .enable_sync()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Note>()
+        .with_model::<NotebookPage>()
+        .build()
+        .unwrap();
+    let notes = Note::with_transaction(&transaction).unwrap();
+    let pages = NotebookPage::with_transaction(&transaction).unwrap();
+
+    notes
+        .add(&Note {
+            id: 1,
+            text: "note 1".to_owned(),
+            updated_at: 1,
+        })
+        .await
+        .unwrap();
+
+    for (notebook, id) in [("a", 1), ("a", 2), ("b", 1)] {
+        pages
+            .add(&NotebookPage {
+                notebook: notebook.to_owned(),
+                id,
+            })
+            .await
+            .unwrap();
+    }
+
+    notes.delete(&1).await.unwrap();
+    pages.delete(&("a".to_owned(), 2)).await.unwrap();
+    pages.delete(&("b".to_owned(), 1)).await.unwrap();
+
+    let tombstones = pages.tombstones().await.unwrap();
+
+    assert_eq!(tombstones.len(), 2);
+    assert_eq!(tombstones[0].key, ("a".to_owned(), 2));
+    assert_eq!(tombstones[1].key, ("b".to_owned(), 1));
+
+    let tombstones = notes.tombstones().await.unwrap();
+
+    assert_eq!(tombstones.len(), 1);
+    assert_eq!(tombstones[0].key, 1);
+
+    transaction.done().await.expect("transaction done");
+
+    database.close();
+    Database::delete("test_composite_tombstones_db")
+        .await
+        .unwrap();
+}
+
+#[cfg(feature = "sync")]
+#[wasm_bindgen_test]
+async fn test_pull_with_conflict_strategy() {
+    use deli::sync::{ConflictStrategy, Syncer};
+
+    let database = create_sync_database().await.unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Note>()
+        .build()
+        .unwrap();
+    let store = Note::with_transaction(&transaction).unwrap();
+
+    for (id, updated_at) in [(1, 20), (2, 20)] {
+        store
+            .add(&Note {
+                id,
+                text: format!("local {id}"),
+                updated_at,
+            })
+            .await
+            .unwrap();
+    }
+
+    transaction.commit().await.unwrap();
+
+    let adapter = NotesAdapter {
+        records: [(1, 10), (2, 20), (3, 10)]
+            .into_iter()
+            .map(|(id, updated_at)| Note {
+                id,
+                text: format!("remote {id}"),
+                updated_at,
+            })
+            .collect(),
+    };
+    let syncer = Syncer::new(&database, adapter);
+
+    let summary = syncer
+        .pull_with::<Note>(&ConflictStrategy::LatestTimestamp)
+        .await
+        .unwrap();
+
+    assert_eq!(summary.records, 3);
+    assert_eq!(summary.conflicts, 1);
+
+    let conflicts = syncer.conflicts::<Note>().await.unwrap();
+
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].key, 2);
+    assert_eq!(conflicts[0].local.text, "local 2");
+    assert_eq!(conflicts[0].remote.text, "remote 2");
+
+    let summary = syncer
+        .pull_with::<Note>(&ConflictStrategy::RemoteWins)
+        .await
+        .unwrap();
+
+    assert_eq!(summary.conflicts, 0);
+    assert!(syncer.conflicts::<Note>().await.unwrap().is_empty());
+
+    let transaction = database.transaction().with_model::<Note>().build().unwrap();
+    let store = Note::with_transaction(&transaction).unwrap();
+
+    let notes = store.get_all(.., None).await.unwrap();
+
+    assert_eq!(notes.len(), 3);
+    assert_eq!(notes[0].text, "remote 1");
+    assert_eq!(notes[1].text, "remote 2");
+    assert_eq!(notes[2].text, "remote 3");
+
+    transaction.done().await.expect("transaction done");
+
+    database.close();
+    Database::delete("test_sync_db").await.unwrap();
+}
+
+/// Replaces the global `fetch` with a stub recording the requests and answering them with queued responses, until
+/// dropped.
+#[cfg(feature = "sync")]
+struct FetchStub {
+    original: wasm_bindgen::JsValue,
+    requests: js_sys::Array,
+    responses: js_sys::Array,
+}
+
+#[cfg(feature = "sync")]
+impl FetchStub {
+    fn install() -> Self {
+        let global = js_sys::global();
+        let original = js_sys::Reflect::get(&global, &"fetch".into()).unwrap();
+
+        let requests = js_sys::Array::new();
+        let responses = js_sys::Array::new();
+        let fetch = js_sys::Function::new_with_args(
+            "requests, responses, request",
+            "requests.push(request); return Promise.resolve(responses.shift());",
+        )
+        .bind2(&wasm_bindgen::JsValue::NULL, &requests, &responses);
+
+        js_sys::Reflect::set(&global, &"fetch".into(), &fetch).unwrap();
+
+        Self {
+            original,
+            requests,
+            responses,
+        }
+    }
+
+    fn respond(&self, status: u16, body: Option<&str>, etag: Option<&str>) {
+        let response = js_sys::Function::new_with_args(
+            "status, body, etag",
+            "return new Response(body, { status, headers: etag ? { ETag: etag } : {} });",
+        )
+        .call3(
+            &wasm_bindgen::JsValue::NULL,
+            &status.into(),
+            &body.map_or(wasm_bindgen::JsValue::NULL, Into::into),
+            &etag.map_or(wasm_bindgen::JsValue::NULL, Into::into),
+        )
+        .unwrap();
+
+        self.responses.push(&response);
+    }
+
+    fn fail(&self, message: &str) {
+        self.responses
+            .push(&js_sys::Promise::reject(&js_sys::TypeError::new(message)));
+    }
+
+    fn request(&self, index: u32) -> web_sys::Request {
+        wasm_bindgen::JsCast::unchecked_into(self.requests.get(index))
+    }
+}
+
+#[cfg(feature = "sync")]
+impl Drop for FetchStub {
+    fn drop(&mut self) {
+        js_sys::Reflect::set(&js_sys::global(), &"fetch".into(), &self.original).unwrap();
+    }
+}
+
+#[cfg(feature = "sync")]
+#[wasm_bindgen_test]
+async fn test_http_sync_adapter_pull() {
+    use deli::sync::{HttpSyncAdapter, Pull, SyncAdapter};
+
+    let stub = FetchStub::install();
+    let adapter = HttpSyncAdapter::new("https://example.com/sync?model=note")
+        .header("Authorization", "Bearer token");
+
+    stub.respond(
+        200,
+        Some(r#"{"records":[{"id":1,"text":"note 1","updated_at":10}],"cursor":"a b&c","has_more":true}"#),
+        Some("\"v1\""),
+    );
+
+    let Pull::Modified { page, etag } = adapter.pull::<Note>(None, None).await.unwrap() else {
+        panic!("expected a modified page");
+    };
+
+    assert_eq!(page.records.len(), 1);
+    assert_eq!(page.records[0].id, 1);
+    assert_eq!(page.records[0].text, "note 1");
+    assert_eq!(page.cursor.as_deref(), Some("a b&c"));
+    assert!(page.has_more);
+    assert_eq!(etag.as_deref(), Some("\"v1\""));
+
+    let request = stub.request(0);
+    let headers = request.headers();
+
+    assert_eq!(request.method(), "GET");
+    assert_eq!(request.url(), "https://example.com/sync?model=note");
+    assert_eq!(
+        headers.get("Accept").unwrap().as_deref(),
+        Some("application/json")
+    );
+    assert_eq!(
+        headers.get("Authorization").unwrap().as_deref(),
+        Some("Bearer token")
+    );
+    assert_eq!(headers.get("If-None-Match").unwrap(), None);
+
+    // The cursor is appended to the query of the endpoint and the entity tag is sent back
+    stub.respond(304, None, None);
+
+    let pull = adapter
+        .pull::<Note>(Some("a b&c"), Some("\"v1\""))
+        .await
+        .unwrap();
+    assert!(matches!(pull, Pull::NotModified));
+
+    let request = stub.request(1);
+
+    assert_eq!(
+        request.url(),
+        "https://example.com/sync?model=note&cursor=a%20b%26c"
+    );
+    assert_eq!(
+        request.headers().get("If-None-Match").unwrap().as_deref(),
+        Some("\"v1\"")
+    );
+
+    stub.respond(200, Some(r#"{"records":[]}"#), None);
+
+    let Pull::Modified { page, etag } = HttpSyncAdapter::new("https://example.com/sync")
+        .pull::<Note>(Some("next"), None)
+        .await
+        .unwrap()
+    else {
+        panic!("expected a modified page");
+    };
+
+    assert!(page.records.is_empty());
+    assert_eq!(page.cursor, None);
+    assert!(!page.has_more);
+    assert_eq!(etag, None);
+    assert_eq!(
+        stub.request(2).url(),
+        "https://example.com/sync?cursor=next"
+    );
+
+    // Error statuses and failed requests are reported
+    stub.respond(500, Some("internal error"), None);
+    assert!(matches!(
+        adapter.pull::<Note>(None, None).await,
+        Err(Error::SyncHttpStatus(500))
+    ));
+
+    stub.fail("offline");
+    assert!(matches!(
+        adapter.pull::<Note>(None, None).await,
+        Err(Error::SyncRequestFailed(message)) if message == "offline"
+    ));
+}
+
+#[cfg(feature = "sync")]
+#[wasm_bindgen_test]
+async fn test_http_sync_adapter_push() {
+    use deli::sync::{HttpSyncAdapter, SyncAdapter};
+
+    let stub = FetchStub::install();
+    let adapter = HttpSyncAdapter::new("https://example.com/sync?model=note")
+        .header("Authorization", "Bearer token");
+
+    let notes = [(1, 10), (2, 20)].map(|(id, updated_at)| Note {
+        id,
+        text: format!("note {id}"),
+        updated_at,
+    });
+
+    stub.respond(204, None, None);
+    adapter.push(&notes).await.unwrap();
+
+    let request = stub.request(0);
+    let headers = request.headers();
+
+    assert_eq!(request.method(), "POST");
+    assert_eq!(request.url(), "https://example.com/sync?model=note");
+    assert_eq!(
+        headers.get("Content-Type").unwrap().as_deref(),
+        Some("application/json")
+    );
+    assert_eq!(
+        headers.get("Accept").unwrap().as_deref(),
+        Some("application/json")
+    );
+    assert_eq!(
+        headers.get("Authorization").unwrap().as_deref(),
+        Some("Bearer token")
+    );
+
+    let body = wasm_bindgen_futures::JsFuture::from(request.text().unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        body.as_string().as_deref(),
+        Some(
+            r#"[{"id":1,"text":"note 1","updated_at":10},{"id":2,"text":"note 2","updated_at":20}]"#
+        )
+    );
+
+    stub.respond(409, None, None);
+    assert!(matches!(
+        adapter.push(&notes).await,
+        Err(Error::SyncHttpStatus(409))
+    ));
+}
+
+#[cfg(feature = "encryption")]
+#[derive(Debug, Serialize, Deserialize, Model)]
+struct Account {
+    #[deli(auto_increment)]
+    id: u32,
+    #[deli(unique)]
+    username: String,
+    #[deli(encrypt, index(hashed))]
+    token: deli::encryption::Encrypted<String>,
+}
+
+#[cfg(feature = "encryption")]
+struct XorCipher(u8);
+
+#[cfg(feature = "encryption")]
+impl deli::encryption::Cipher for XorCipher {
+    fn seal(&self, plaintext: &[u8]) -> Result<deli::encryption::Envelope, Error> {
+        Ok(deli::encryption::Envelope {
+            key_id: "test".to_owned(),
+            wrapped_key: vec![self.0],
+            nonce: Vec::new(),
+            ciphertext: plaintext.iter().map(|byte| byte ^ self.0).collect(),
+        })
+    }
+
+    fn open(&self, envelope: &deli::encryption::Envelope) -> Result<Vec<u8>, Error> {
+        Ok(envelope
+            .ciphertext
+            .iter()
+            .map(|byte| byte ^ envelope.wrapped_key[0])
+            .collect())
+    }
+
+    fn hash(&self, plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        Ok(plaintext.iter().map(|byte| byte ^ self.0).collect())
+    }
+}
+
+#[cfg(feature = "encryption")]
+#[wasm_bindgen_test]
+async fn test_encrypted_field() {
+    let _ = Database::delete("test_encryption_db").await;
+
+    deli::encryption::set_cipher(XorCipher(0x5a));
+
+    let database = Database::builder("test_encryption_db")
+        .version(1)
+        .add_model::<Account>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Account>()
+        .build()
+        .unwrap();
+    let store = Account::with_transaction(&transaction).unwrap();
+
+    let id = store
+        .add(&AddAccount {
+            username: "alice".to_owned(),
+            token: "secret".to_owned().into(),
+        })
+        .await
+        .unwrap();
+
+    let account = store
+        .by_username_unique()
+        .unwrap()
+        .get("alice")
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(account.id, id);
+    assert_eq!(account.token.as_str(), "secret");
+
+    let accounts = store.by_token_hashed("secret").await.unwrap();
+
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].username, "alice");
+    assert!(store.by_token_hashed("public").await.unwrap().is_empty());
+
+    deli::encryption::clear_cipher();
+
+    assert!(store.get(&id).await.is_err());
+
+    transaction.done().await.expect("transaction done");
+
+    database.close();
+    Database::delete("test_encryption_db").await.unwrap();
+}
+
+#[cfg(feature = "export")]
+#[derive(Debug, Serialize, Deserialize, Model)]
+struct Contact {
+    #[deli(auto_increment)]
+    id: u32,
+    name: String,
+    #[deli(redact)]
+    email: String,
+}
+
+#[cfg(feature = "export")]
+#[wasm_bindgen_test]
+async fn test_export_redacts_fields() {
+    let _ = Database::delete("test_export_db").await;
+
+    let database = Database::builder("test_export_db")
+        .version(1)
+        .add_model::<Contact>()
+        .add_model::<Employee>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Contact>()
+        .build()
+        .unwrap();
+    let store = Contact::with_transaction(&transaction).unwrap();
+
+    store
+        .add(&AddContact {
+            name: "Alice".to_owned(),
+            email: "alice@example.com".to_owned(),
+        })
+        .await
+        .unwrap();
+
+    transaction.commit().await.unwrap();
+
+    let dump = database
+        .export()
+        .with_model::<Contact>()
+        .build()
+        .await
+        .unwrap();
+
+    assert_eq!(dump.database, "test_export_db");
+    assert_eq!(dump.stores.len(), 1);
+    assert_eq!(dump.stores[0].name, "contact");
+    assert_eq!(dump.stores[0].records.len(), 1);
+    assert_eq!(dump.stores[0].records[0]["name"], "Alice");
+    assert_eq!(dump.stores[0].records[0]["email"], deli::export::REDACTED);
+
+    let dump = database
+        .export()
+        .with_model::<Contact>()
+        .redact(false)
+        .build()
+        .await
+        .unwrap();
+
+    assert_eq!(dump.stores[0].records[0]["email"], "alice@example.com");
+
+    let mut names = Vec::new();
+    let manifest = database
+        .export()
+        .with_model::<Contact>()
+        .with_model::<Employee>()
+        .stream(|store| {
+            names.push(store.name);
+            Ok(())
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(names, [Contact::NAME, Employee::NAME]);
+    assert_eq!(manifest.database, "test_export_db");
+    assert_eq!(
+        manifest.stores,
+        [
+            deli::export::ManifestEntry {
+                name: "contact".to_owned(),
+                records: 1,
+            },
+            deli::export::ManifestEntry {
+                name: Employee::NAME.to_owned(),
+                records: 0,
+            },
+        ]
+    );
+
+    database.close();
+    Database::delete("test_export_db").await.unwrap();
+}
+
+#[cfg(feature = "export")]
+#[wasm_bindgen_test]
+async fn test_archive_import() {
+    use deli::{archive::Archive, Error};
+
+    let _ = Database::delete("test_archive_db").await;
+
+    let database = Database::builder("test_archive_db")
+        .version(1)
+        .add_model::<Contact>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Contact>()
+        .build()
+        .unwrap();
+    let store = Contact::with_transaction(&transaction).unwrap();
+    for name in ["Alice", "Bob", "Carol"] {
+        store
+            .add(&AddContact {
+                name: name.to_owned(),
+                email: format!("{}@example.com", name.to_lowercase()),
+            })
+            .await
+            .unwrap();
+    }
+    transaction.commit().await.unwrap();
+
+    let archive = database
+        .export()
+        .with_model::<Contact>()
+        .redact(false)
+        .archive(2)
+        .await
+        .unwrap();
+
+    assert!(!archive.redacted);
+    assert_eq!(archive.manifest.stores[0].records, 3);
+    assert_eq!(archive.chunks.len(), 2);
+    archive.validate().unwrap();
+
+    // The archive survives a round trip through JSON
+    let archive: Archive = serde_json::from_str(&serde_json::to_string(&archive).unwrap()).unwrap();
+    assert_eq!(
+        archive.clone().into_dump().unwrap().stores[0].records.len(),
+        3
+    );
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Contact>()
+        .build()
+        .unwrap();
+    let store = Contact::with_transaction(&transaction).unwrap();
+    store.delete(&1).await.unwrap();
+    transaction.commit().await.unwrap();
+
+    // Corrupted and truncated archives are rejected without touching the stores
+    let mut corrupted = archive.clone();
+    corrupted.chunks[1].data = corrupted.chunks[1].data.replace("Carol", "Carl");
+    assert!(matches!(
+        database.import(&corrupted).await,
+        Err(Error::InvalidArchive(_))
+    ));
+
+    let mut truncated = archive.clone();
+    truncated.chunks.pop();
+    assert!(matches!(
+        database.import(&truncated).await,
+        Err(Error::InvalidArchive(_))
+    ));
+
+    let transaction = database
+        .transaction()
+        .with_model::<Contact>()
+        .build()
+        .unwrap();
+    let store = Contact::with_transaction(&transaction).unwrap();
+    assert_eq!(store.count(..).await.unwrap(), 2);
+    transaction.done().await.unwrap();
+
+    database.import(&archive).await.unwrap();
+
+    let transaction = database
+        .transaction()
+        .with_model::<Contact>()
+        .build()
+        .unwrap();
+    let store = Contact::with_transaction(&transaction).unwrap();
+    let alice = store.get(&1).await.unwrap().unwrap();
+    assert_eq!(alice.email, "alice@example.com");
+    assert_eq!(store.count(..).await.unwrap(), 3);
+    transaction.done().await.unwrap();
+
+    database.close();
+    Database::delete("test_archive_db").await.unwrap();
+}
+
+#[cfg(feature = "export")]
+#[wasm_bindgen_test]
+async fn test_archive_restore() {
+    use deli::archive::{Conflict, RestoreSummary};
+
+    let _ = Database::delete("test_restore_db").await;
+
+    let database = Database::builder("test_restore_db")
+        .version(1)
+        .add_model::<Contact>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Contact>()
+        .build()
+        .unwrap();
+    let store = Contact::with_transaction(&transaction).unwrap();
+    for name in ["Alice", "Bob", "Carol"] {
+        store
+            .add(&AddContact {
+                name: name.to_owned(),
+                email: format!("{}@example.com", name.to_lowercase()),
+            })
+            .await
+            .unwrap();
+    }
+    transaction.commit().await.unwrap();
+
+    let archive = database
+        .export()
+        .with_model::<Contact>()
+        .redact(false)
+        .archive(10)
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Contact>()
+        .build()
+        .unwrap();
+    let store = Contact::with_transaction(&transaction).unwrap();
+    store.delete(&1).await.unwrap();
+    store.delete(&3).await.unwrap();
+    store
+        .update(&Contact {
+            id: 2,
+            name: "Robert".to_owned(),
+            email: "bob@example.com".to_owned(),
+        })
+        .await
+        .unwrap();
+    transaction.commit().await.unwrap();
+
+    // Only the records within the key range are restored, keeping the local ones
+    let summary = database
+        .restore(&archive)
+        .with_model_range::<Contact, _>(..&3)
+        .unwrap()
+        .conflict(Conflict::KeepLocal)
+        .execute()
+        .await
+        .unwrap();
+    assert_eq!(
+        summary,
+        RestoreSummary {
+            restored: 1,
+            skipped: 1
+        }
+    );
+
+    let transaction = database
+        .transaction()
+        .with_model::<Contact>()
+        .build()
+        .unwrap();
+    let store = Contact::with_transaction(&transaction).unwrap();
+    assert_eq!(store.get(&1).await.unwrap().unwrap().name, "Alice");
+    assert_eq!(store.get(&2).await.unwrap().unwrap().name, "Robert");
+    assert!(store.get(&3).await.unwrap().is_none());
+    transaction.done().await.unwrap();
+
+    // Restoring records whose key is used locally fails without writing anything
+    assert!(database
+        .restore(&archive)
+        .conflict(Conflict::Fail)
+        .execute()
+        .await
+        .is_err());
+
+    let summary = database.restore(&archive).execute().await.unwrap();
+    assert_eq!(summary.restored, 3);
+
+    let transaction = database
+        .transaction()
+        .with_model::<Contact>()
+        .build()
+        .unwrap();
+    let store = Contact::with_transaction(&transaction).unwrap();
+    assert_eq!(store.get(&2).await.unwrap().unwrap().name, "Bob");
+    assert_eq!(store.count(..).await.unwrap(), 3);
+    transaction.done().await.unwrap();
+
+    database.close();
+    Database::delete("test_restore_db").await.unwrap();
+}
+
+#[cfg(feature = "inspect")]
+#[wasm_bindgen_test]
+async fn test_record_size_accounting() {
+    let _ = Database::delete("test_inspect_db").await;
+
+    let database = Database::builder("test_inspect_db")
+        .version(1)
+        .add_model::<Employee>()
+        .track_sizes()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Employee>()
+        .build()
+        .unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    let id1 = store
+        .add(&AddEmployee {
+            name: "Alice".to_owned(),
+            email: "alice@example.com".to_owned(),
+            age: 25,
+        })
+        .await
+        .unwrap();
+    let id2 = store
+        .add(&AddEmployee {
+            name: "Bob".to_owned(),
+            email: "bob@example.com".to_owned(),
+            age: 30,
+        })
+        .await
+        .unwrap();
+
+    let sizes = store.record_sizes().await.unwrap();
+
+    assert_eq!(sizes.len(), 2);
+    assert_eq!(sizes[0].key, id1);
+    assert_eq!(sizes[1].key, id2);
+    assert!(sizes[0].bytes > sizes[1].bytes);
+    assert_eq!(
+        store.approx_bytes().await.unwrap(),
+        sizes[0].bytes + sizes[1].bytes
+    );
+
+    store.delete(&id1).await.unwrap();
+
+    let sizes = store.record_sizes().await.unwrap();
+
+    assert_eq!(sizes.len(), 1);
+    assert_eq!(sizes[0].key, id2);
+
+    store.delete_all().await.unwrap();
+
+    assert_eq!(store.approx_bytes().await.unwrap(), 0);
+
+    transaction.done().await.expect("transaction done");
+
+    database.close();
+    Database::delete("test_inspect_db").await.unwrap();
+}
+
+#[cfg(feature = "inspect")]
+#[wasm_bindgen_test]
+async fn test_large_value_hook() {
+    use std::{cell::RefCell, rc::Rc};
+
+    let _ = Database::delete("test_large_value_db").await;
+
+    let database = Database::builder("test_large_value_db")
+        .version(1)
+        .add_model::<Employee>()
+        .build()
+        .await
+        .unwrap();
+
+    let large_values = Rc::new(RefCell::new(Vec::new()));
+    let hook_large_values = large_values.clone();
+
+    deli::inspect::set_large_value_threshold(100);
+    deli::inspect::set_large_value_hook(move |large_value| {
+        hook_large_values.borrow_mut().push((
+            large_value.store.to_owned(),
+            large_value.key.as_f64(),
+            large_value.bytes,
+        ));
+    });
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Employee>()
+        .build()
+        .unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    store
+        .add(&AddEmployee {
+            name: "Alice".to_owned(),
+            email: "alice@example.com".to_owned(),
+            age: 25,
+        })
+        .await
+        .unwrap();
+    let id = store
+        .add(&AddEmployee {
+            name: "A".repeat(200),
+            email: "long@example.com".to_owned(),
+            age: 30,
+        })
+        .await
+        .unwrap();
+
+    transaction.done().await.expect("transaction done");
+
+    deli::inspect::clear_large_value_hook();
+    deli::inspect::set_large_value_threshold(deli::inspect::DEFAULT_LARGE_VALUE_THRESHOLD);
+
+    let large_values = large_values.take();
+
+    assert_eq!(large_values.len(), 1);
+    assert_eq!(large_values[0].0, "employee");
+    assert_eq!(large_values[0].1, Some(id.into()));
+    assert!(large_values[0].2 > 200);
+
+    database.close();
+    Database::delete("test_large_value_db").await.unwrap();
+}
+
+#[cfg(feature = "inspect")]
+#[wasm_bindgen_test]
+async fn test_sample() {
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_read_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    assert!(store.sample(3).await.unwrap().is_empty());
+    transaction.done().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    for i in 0..20 {
+        store
+            .add(&AddEmployee {
+                name: format!("Employee {i}"),
+                email: format!("employee{i}@example.com"),
+                age: 20 + i,
+            })
+            .await
+            .unwrap();
+    }
+
+    transaction.commit().await.unwrap();
+
+    let transaction = begin_read_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    let sample = store.sample(5).await.unwrap();
+    assert!(!sample.is_empty());
+    assert!(sample.len() <= 5);
+    assert!(sample.windows(2).all(|pair| pair[0].id < pair[1].id));
+
+    transaction.done().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[cfg(feature = "integrity")]
+#[derive(Debug, Serialize, Deserialize, Model)]
+struct Team {
+    #[deli(key)]
+    id: u32,
+    name: String,
+}
+
+#[cfg(feature = "integrity")]
+#[derive(Debug, Default, Serialize, Deserialize, Model)]
+struct Player {
+    #[deli(key)]
+    id: u32,
+    name: String,
+    #[deli(references = Team)]
+    team_id: u32,
+}
+
+// Previous version of `Player` (stored in the same object store) used to write malformed records
+#[cfg(feature = "integrity")]
+#[derive(Debug, Serialize, Deserialize, Model)]
+#[deli(name = "player")]
+struct LegacyPlayer {
+    #[deli(key)]
+    id: u32,
+    team_id: u32,
+}
+
+#[cfg(feature = "integrity")]
+#[wasm_bindgen_test]
+async fn test_check_integrity() {
+    use deli::integrity::{Violation, ViolationKind};
+
+    let _ = Database::delete("test_integrity_db").await;
+
+    let database = Database::builder("test_integrity_db")
+        .version(1)
+        .add_model::<Team>()
+        .add_model::<Player>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Team>()
+        .with_model::<Player>()
+        .build()
+        .unwrap();
+
+    Team::with_transaction(&transaction)
+        .unwrap()
+        .add(&Team {
+            id: 1,
+            name: "Red".to_string(),
+        })
+        .await
+        .unwrap();
+
+    let players = Player::with_transaction(&transaction).unwrap();
+
+    players
+        .add(&Player {
+            id: 1,
+            name: "Alice".to_string(),
+            team_id: 1,
+        })
+        .await
+        .unwrap();
+    players
+        .add(&Player {
+            id: 2,
+            name: "Bob".to_string(),
+            team_id: 2,
+        })
+        .await
+        .unwrap();
+
+    LegacyPlayer::with_transaction(&transaction)
+        .unwrap()
+        .add(&LegacyPlayer { id: 3, team_id: 1 })
+        .await
+        .unwrap();
+
+    transaction.commit().await.unwrap();
+
+    let report = database.check_integrity::<Player>().await.unwrap();
+
+    assert_eq!(report.store, "player");
+    assert_eq!(report.checked, 3);
+    assert!(!report.is_ok());
+    assert_eq!(report.violations.len(), 2);
+    assert_eq!(
+        report.violations[0],
+        Violation {
+            key: 2,
+            kind: ViolationKind::DanglingReference {
+                field: "team_id",
+                store: "team",
+            },
+        }
+    );
+    assert_eq!(report.violations[1].key, 3);
+    assert!(matches!(
+        report.violations[1].kind,
+        ViolationKind::Malformed(_)
+    ));
+
+    assert!(database.check_integrity::<Team>().await.unwrap().is_ok());
+
+    database.close();
+    Database::delete("test_integrity_db").await.unwrap();
+}
+
+#[cfg(feature = "integrity")]
+#[wasm_bindgen_test]
+async fn test_repair() {
+    use deli::integrity::RepairStrategy;
+
+    let _ = Database::delete("test_repair_db").await;
+
+    let database = Database::builder("test_repair_db")
+        .version(1)
+        .add_model::<Team>()
+        .add_model::<Player>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Player>()
+        .build()
+        .unwrap();
+
+    Player::with_transaction(&transaction)
+        .unwrap()
+        .add(&Player {
+            id: 1,
+            name: "Alice".to_string(),
+            team_id: 1,
+        })
+        .await
+        .unwrap();
+
+    let legacy = LegacyPlayer::with_transaction(&transaction).unwrap();
+    legacy
+        .add(&LegacyPlayer { id: 2, team_id: 1 })
+        .await
+        .unwrap();
+    legacy
+        .add(&LegacyPlayer { id: 3, team_id: 2 })
+        .await
+        .unwrap();
+
+    transaction.commit().await.unwrap();
+
+    let summary = database
+        .repair::<Player>(RepairStrategy::Backfill(Player::default))
+        .await
+        .unwrap();
+
+    assert_eq!(summary.store, "player");
+    assert_eq!(summary.repaired, vec![2, 3]);
+    assert!(summary.dropped.is_empty());
+
+    let transaction = database
+        .transaction()
+        .with_model::<Player>()
+        .build()
+        .unwrap();
+    let players = Player::with_transaction(&transaction).unwrap();
+
+    let player = players.get(&3).await.unwrap().unwrap();
+    assert_eq!(player.name, "");
+    assert_eq!(player.team_id, 2);
+
+    transaction.done().await.unwrap();
+
+    let summary = database
+        .repair::<Player>(RepairStrategy::Drop)
+        .await
+        .unwrap();
+    assert!(summary.repaired.is_empty());
+    assert!(summary.dropped.is_empty());
+
+    database.close();
+    Database::delete("test_repair_db").await.unwrap();
+}
+
+#[cfg(feature = "fingerprint")]
+#[derive(Debug, Serialize, Deserialize, Model)]
+struct Draft {
+    #[deli(auto_increment)]
+    id: u32,
+    body: String,
+}
+
+#[cfg(feature = "fingerprint")]
+#[wasm_bindgen_test]
+async fn test_schema_fingerprint_guard() {
+    let _ = Database::delete("test_fingerprint_db").await;
+
+    for _ in 0..2 {
+        let database = Database::builder("test_fingerprint_db")
+            .version(1)
+            .add_model::<Employee>()
+            .guard_schema()
+            .build()
+            .await
+            .unwrap();
+        database.close();
+    }
+
+    // Same version with a different schema (e.g. a newer deployment which forgot to bump the version)
+    let result = Database::builder("test_fingerprint_db")
+        .version(1)
+        .add_model::<Employee>()
+        .add_model::<Draft>()
+        .guard_schema()
+        .build()
+        .await;
+    assert!(matches!(
+        result,
+        Err(Error::SchemaFingerprintMismatch { version: 1, .. })
+    ));
+
+    let database = Database::builder("test_fingerprint_db")
+        .version(2)
+        .add_model::<Employee>()
+        .add_model::<Draft>()
+        .guard_schema()
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(database.version().unwrap(), 2);
+    database.close();
+
+    Database::delete("test_fingerprint_db").await.unwrap();
+}
+
+#[derive(Debug, Serialize, Deserialize, Model)]
+#[deli(default_limit = 2)]
+struct LogEntry {
+    #[deli(auto_increment)]
+    id: u32,
+    #[deli(index)]
+    level: String,
+}
+
+#[wasm_bindgen_test]
+async fn test_default_limit() {
+    let _ = Database::delete("test_default_limit_db").await;
+
+    let database = Database::builder("test_default_limit_db")
+        .version(1)
+        .add_model::<LogEntry>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<LogEntry>()
+        .build()
+        .unwrap();
+    let store = LogEntry::with_transaction(&transaction).unwrap();
+
+    for _ in 0..3 {
+        store
+            .add(&AddLogEntry {
+                level: "info".to_string(),
+            })
+            .await
+            .unwrap();
+    }
+
+    transaction.commit().await.unwrap();
+
+    let transaction = database
+        .transaction()
+        .with_model::<LogEntry>()
+        .build()
+        .unwrap();
+    let store = LogEntry::with_transaction(&transaction).unwrap();
+
+    assert_eq!(store.get_all(.., None).await.unwrap().len(), 2);
+    assert_eq!(store.get_all_keys(.., None).await.unwrap().len(), 2);
+    assert_eq!(store.get_all(.., Some(3)).await.unwrap().len(), 3);
+    assert_eq!(
+        store
+            .by_level()
+            .unwrap()
+            .get_all("info", None)
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+
+    transaction.done().await.unwrap();
+
+    database.close();
+    Database::delete("test_default_limit_db").await.unwrap();
+}
+
+#[cfg(feature = "nested-index")]
+#[derive(Debug, Serialize, Deserialize)]
+struct OrderItem {
+    sku: String,
+    quantity: u32,
+}
+
+#[cfg(feature = "nested-index")]
+#[derive(Debug, Serialize, Deserialize, Model)]
+struct Order {
+    #[deli(auto_increment)]
+    id: u32,
+    #[deli(index(key_path = "items[].sku", key_type = "String"))]
+    items: Vec<OrderItem>,
+}
+
+#[cfg(feature = "nested-index")]
+#[wasm_bindgen_test]
+async fn test_nested_index() {
+    let _ = Database::delete("test_nested_index_db").await;
+
+    let database = Database::builder("test_nested_index_db")
+        .version(1)
+        .add_model::<Order>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Order>()
+        .build()
+        .unwrap();
+    let store = Order::with_transaction(&transaction).unwrap();
+
+    let first = store
+        .add(&AddOrder {
+            items: vec![
+                OrderItem {
+                    sku: "apple".to_string(),
+                    quantity: 1,
+                },
+                OrderItem {
+                    sku: "pear".to_string(),
+                    quantity: 2,
+                },
+            ],
+        })
+        .await
+        .unwrap();
+    let second = store
+        .add(&AddOrder {
+            items: vec![OrderItem {
+                sku: "pear".to_string(),
+                quantity: 3,
+            }],
+        })
+        .await
+        .unwrap();
+
+    transaction.commit().await.unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Order>()
+        .build()
+        .unwrap();
+    let store = Order::with_transaction(&transaction).unwrap();
+
+    let pears = store
+        .by_items_sku()
+        .unwrap()
+        .get_all("pear", None)
+        .await
+        .unwrap();
+    assert_eq!(
+        pears.iter().map(|order| order.id).collect::<Vec<_>>(),
+        vec![first, second]
+    );
+
+    let apples = store
+        .by_items_sku()
+        .unwrap()
+        .get_all_keys("apple", None)
+        .await
+        .unwrap();
+    assert_eq!(apples, vec![first]);
+
+    // The shadow field is kept in sync when records are updated
+    let mut order = store.get(&first).await.unwrap().unwrap();
+    order.items.retain(|item| item.sku != "apple");
+    store.update(&order).await.unwrap();
+
+    assert!(store
+        .by_items_sku()
+        .unwrap()
+        .get("apple")
+        .await
+        .unwrap()
+        .is_none());
+
+    transaction.commit().await.unwrap();
+
+    database.close();
+    Database::delete("test_nested_index_db").await.unwrap();
+}
+
+#[cfg(feature = "testing")]
+#[wasm_bindgen_test]
+fn test_snapshot() {
+    let snapshot = deli::testing::snapshot::<Employee>();
+
+    assert_eq!(
+        snapshot,
+        "store \"employee\"\n  \
+         key path \"id\", auto increment\n  \
+         index \"employee_age_index\" on \"age\"\n  \
+         index \"employee_email_unique_index\" on \"email\", unique"
+    );
+
+    assert_eq!(snapshot, deli::testing::snapshot::<Employee>());
+    assert_ne!(snapshot, deli::testing::snapshot::<Office>());
+}
+
+#[cfg(feature = "shuffle-get-all")]
+#[wasm_bindgen_test]
+async fn test_ordered_get_all() {
+    use deli::CursorDirection;
+
+    deli::shuffle::shuffle_results(true);
+
+    let database = create_database().await.unwrap();
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    let mut ids = Vec::new();
+
+    for (name, age) in [("Alice", 30), ("Bob", 25), ("Charlie", 30), ("Dave", 40)] {
+        let id = store
+            .add(&AddEmployee {
+                name: name.to_string(),
+                email: format!("{}@example.com", name.to_lowercase()),
+                age,
+            })
+            .await
+            .unwrap();
+        ids.push(id);
+    }
+
+    let employees = store
+        .ordered_get_all(.., CursorDirection::Next, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        employees
+            .iter()
+            .map(|employee| employee.id)
+            .collect::<Vec<_>>(),
+        ids
+    );
+
+    let employees = store
+        .ordered_get_all(.., CursorDirection::Prev, Some(2))
+        .await
+        .unwrap();
+    assert_eq!(
+        employees
+            .iter()
+            .map(|employee| employee.id)
+            .collect::<Vec<_>>(),
+        vec![ids[3], ids[2]]
+    );
+
+    let employees = store
+        .by_age()
+        .unwrap()
+        .ordered_get_all(&30.., CursorDirection::Prev, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        employees
+            .iter()
+            .map(|employee| employee.id)
+            .collect::<Vec<_>>(),
+        vec![ids[3], ids[2], ids[0]]
+    );
+
+    let employees = store
+        .by_age()
+        .unwrap()
+        .ordered_get_all(.., CursorDirection::NextUnique, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        employees
+            .iter()
+            .map(|employee| employee.id)
+            .collect::<Vec<_>>(),
+        vec![ids[1], ids[0], ids[3]]
+    );
+
+    transaction.done().await.unwrap();
+
+    deli::shuffle::shuffle_results(false);
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[derive(Debug, Serialize, Deserialize, Model)]
+struct Setting {
+    #[deli(key)]
+    name: String,
+    value: String,
+}
+
+async fn open_seeded_database(version: u32) -> Result<Database, Error> {
+    Database::builder("test_seed_db")
+        .version(version)
+        .add_model::<Setting>()
+        .on_create::<Setting, _>(|store| {
+            Box::pin(async move {
+                store
+                    .add(&Setting {
+                        name: "theme".to_string(),
+                        value: "dark".to_string(),
+                    })
+                    .await?;
+                Ok(())
+            })
+        })
+        .build()
+        .await
+}
+
+#[wasm_bindgen_test]
+async fn test_seed_created_store() {
+    let _ = Database::delete("test_seed_db").await;
+
+    let database = open_seeded_database(1).await.unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Setting>()
+        .build()
+        .unwrap();
+    let store = Setting::with_transaction(&transaction).unwrap();
+
+    let theme = store.get("theme").await.unwrap().unwrap();
+    assert_eq!(theme.value, "dark");
+
+    store.delete("theme").await.unwrap();
+    transaction.commit().await.unwrap();
+    database.close();
+
+    // The store already exists, so it is not seeded again (even after an upgrade)
+    let database = open_seeded_database(2).await.unwrap();
+
+    let transaction = database
+        .transaction()
+        .with_model::<Setting>()
+        .build()
+        .unwrap();
+    let store = Setting::with_transaction(&transaction).unwrap();
+    assert_eq!(store.count::<str>(..).await.unwrap(), 0);
+    transaction.done().await.unwrap();
+
+    database.close();
+    Database::delete("test_seed_db").await.unwrap();
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Key)]
+#[serde(transparent)]
+#[deli(transparent)]
+struct WalletId(String);
+
+#[derive(Debug, Serialize, Deserialize, Model)]
+struct Wallet {
+    #[deli(key)]
+    id: WalletId,
+    #[deli(index)]
+    owner: WalletId,
+}
+
+#[wasm_bindgen_test]
+async fn test_transparent_key() {
+    let _ = Database::delete("test_transparent_key_db").await;
+
+    let database = Database::builder("test_transparent_key_db")
+        .version(1)
+        .add_model::<Wallet>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Wallet>()
+        .build()
+        .unwrap();
+    let store = Wallet::with_transaction(&transaction).unwrap();
+
+    store
+        .add(&Wallet {
+            id: WalletId("savings".to_owned()),
+            owner: WalletId("alice".to_owned()),
+        })
+        .await
+        .unwrap();
+
+    // Keys are stored as their inner value
+    let key = store.get_key(&"savings".to_owned()).await.unwrap();
+    assert_eq!(key, Some(WalletId("savings".to_owned())));
+
+    let wallet = store.get(&WalletId("savings".to_owned())).await.unwrap();
+    assert!(wallet.is_some());
+
+    let wallet = store.get("savings").await.unwrap().unwrap();
+    assert_eq!(wallet.owner, WalletId("alice".to_owned()));
+
+    let wallets = store
+        .by_owner()
+        .unwrap()
+        .get_all("alice", None)
+        .await
+        .unwrap();
+    assert_eq!(wallets.len(), 1);
+
+    transaction.commit().await.unwrap();
+
+    database.close();
+    Database::delete("test_transparent_key_db").await.unwrap();
+}
+
+#[derive(Debug, Serialize, Deserialize, Model)]
+#[deli(key(course, student))]
+#[deli(index(fields(course, grade)))]
+struct Enrollment {
+    course: String,
+    student: u32,
+    grade: String,
+}
+
+#[wasm_bindgen_test]
+async fn test_borrowed_composite_lookup() {
+    let _ = Database::delete("test_borrowed_lookup_db").await;
+
+    let database = Database::builder("test_borrowed_lookup_db")
+        .version(1)
+        .add_model::<Enrollment>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Enrollment>()
+        .build()
+        .unwrap();
+    let store = Enrollment::with_transaction(&transaction).unwrap();
+
+    for (student, grade) in [(1, "A"), (2, "B"), (3, "A")] {
+        store
+            .add(&Enrollment {
+                course: "math".to_owned(),
+                student,
+                grade: grade.to_owned(),
+            })
+            .await
+            .unwrap();
+    }
+
+    // Composite keys can be looked up with borrowed values
+    let enrollment = store.get(&("math", &2)).await.unwrap().unwrap();
+    assert_eq!(enrollment.grade, "B");
+
+    let enrollment = store.get(&("math".to_owned(), 3)).await.unwrap().unwrap();
+    assert_eq!(enrollment.grade, "A");
+
+    // Tuples of references can be passed directly
+    let course = "math".to_owned();
+    let enrollment = store.get((&course, &3)).await.unwrap().unwrap();
+    assert_eq!(enrollment.grade, "A");
+
+    let enrollments = store
+        .get_all(&("math", &1)..=&("math", &2), None)
+        .await
+        .unwrap();
+    assert_eq!(enrollments.len(), 2);
+
+    let enrollments = store
+        .by_course_grade_composite()
+        .unwrap()
+        .get_all(&("math", "A"), None)
+        .await
+        .unwrap();
+    assert_eq!(enrollments.len(), 2);
+
+    // Ranges of tuples produce array key ranges
+    let enrollments = store
+        .by_course_grade_composite()
+        .unwrap()
+        .get_all(("math", "A")..=("math", "B"), None)
+        .await
+        .unwrap();
+    assert_eq!(enrollments.len(), 3);
+
+    let enrollments = store
+        .by_course_grade_composite()
+        .unwrap()
+        .get_all(("math", "B").., None)
+        .await
+        .unwrap();
+    assert_eq!(enrollments.len(), 1);
+
+    let enrollments = store.get_all(("math", &2).., None).await.unwrap();
+    assert_eq!(enrollments.len(), 2);
+
+    store.delete(&("math", &1)).await.unwrap();
+    assert_eq!(store.count::<(&str, &u32)>(..).await.unwrap(), 2);
+
+    store.delete(("math", &2)).await.unwrap();
+    assert!(store.get(("math", &2)).await.unwrap().is_none());
+
+    let enrollments = store
+        .get_all(OwnedRange(("math", &1)..=("math", &3)), None)
+        .await
+        .unwrap();
+    assert_eq!(enrollments.len(), 1);
+
+    transaction.commit().await.unwrap();
+
+    database.close();
+    Database::delete("test_borrowed_lookup_db").await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_raw_query() {
+    use deli::{reexports::idb, Query};
+
+    let database = create_database().await.unwrap();
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    for (name, age) in [("Alice", 25), ("Bob", 30), ("Charlie", 35)] {
+        store
+            .add(&AddEmployee {
+                name: name.to_string(),
+                email: format!("{}@example.com", name.to_lowercase()),
+                age,
+            })
+            .await
+            .unwrap();
+    }
+
+    // Raw queries bypass serialization of keys
+    let key = serde_wasm_bindgen::to_value(&2u32).unwrap();
+    let employee = store.get::<u32>(Query::Key(key)).await.unwrap().unwrap();
+    assert_eq!(employee.name, "Bob");
+
+    let lower = serde_wasm_bindgen::to_value(&30u32).unwrap();
+    let range = idb::KeyRange::lower_bound(&lower, None).unwrap();
+    let employees = store
+        .by_age()
+        .unwrap()
+        .get_all::<u32>(range, None)
+        .await
+        .unwrap();
+    assert_eq!(employees.len(), 2);
+
+    transaction.commit().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[derive(Debug, Serialize, Deserialize, Model)]
+struct Memo {
+    #[deli(auto_increment)]
+    id: u32,
+    #[deli(index(name = "memo_text_index"))]
+    title: String,
+    #[deli(index(name = "memo_text_index"))]
+    body: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Model)]
+#[deli(name = "memo")]
+struct CopiedMemo {
+    #[deli(auto_increment)]
+    id: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Model)]
+#[deli(name = "__deli_memos")]
+struct InternalMemo {
+    #[deli(auto_increment)]
+    id: u32,
+}
+
+#[wasm_bindgen_test]
+async fn test_schema_validation() {
+    assert!(Schema::new().add_model::<Employee>().validate().is_ok());
+
+    let error = Schema::new()
+        .add_model::<Memo>()
+        .add_model::<CopiedMemo>()
+        .add_model::<InternalMemo>()
+        .validate()
+        .unwrap_err();
+
+    let Error::SchemaConflicts(conflicts) = error else {
+        panic!("unexpected error: {error:?}");
+    };
+
+    assert_eq!(
+        conflicts,
+        vec![
+            SchemaConflict::DuplicateIndex {
+                store: "memo",
+                index: "memo_text_index",
+            },
+            SchemaConflict::DuplicateStore { store: "memo" },
+            SchemaConflict::ReservedStoreName {
+                store: "__deli_memos",
+            },
+        ]
+    );
+
+    // Invalid schemas are rejected before the database is opened
+    let result = Database::builder("test_schema_validation_db")
+        .version(1)
+        .add_model::<Memo>()
+        .build()
+        .await;
+    assert!(matches!(result, Err(Error::SchemaConflicts(_))));
+}
+
+#[derive(Debug, Serialize, Deserialize, Model)]
+#[deli(on_read = "normalize_ticket")]
+struct Ticket {
+    #[deli(auto_increment)]
+    id: u32,
+    #[deli(index)]
+    status: String,
+    #[serde(default)]
+    label: String,
+}
+
+fn normalize_ticket(ticket: &mut Ticket) {
+    if ticket.status == "done" {
+        ticket.status = "closed".to_owned();
+    }
+
+    ticket.label = format!("#{} ({})", ticket.id, ticket.status);
+}
+
+#[wasm_bindgen_test]
+async fn test_on_read() {
+    let _ = Database::delete("test_on_read_db").await;
+
+    let database = Database::builder("test_on_read_db")
+        .version(1)
+        .add_model::<Ticket>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Ticket>()
+        .build()
+        .unwrap();
+    let store = Ticket::with_transaction(&transaction).unwrap();
+
+    for status in ["open", "done"] {
+        store
+            .add(&AddTicket {
+                status: status.to_owned(),
+                label: String::new(),
+            })
+            .await
+            .unwrap();
+    }
+
+    let ticket = store.get(&2).await.unwrap().unwrap();
+    assert_eq!(ticket.status, "closed");
+    assert_eq!(ticket.label, "#2 (closed)");
+
+    let tickets = store
+        .ordered_get_all::<u32>(.., deli::CursorDirection::Prev, None)
+        .await
+        .unwrap();
+    assert_eq!(tickets[0].label, "#2 (closed)");
+    assert_eq!(tickets[1].label, "#1 (open)");
+
+    // The hook only applies to reads, the stored value is unchanged
+    let tickets = store
+        .by_status()
+        .unwrap()
+        .get_all("done", None)
+        .await
+        .unwrap();
+    assert_eq!(tickets.len(), 1);
+    assert_eq!(tickets[0].status, "closed");
+
+    // Index keys are computed from the stored value, before the hook
+    let status = store.by_status().unwrap().key_of(&2).await.unwrap();
+    assert_eq!(status.as_deref(), Some("done"));
+
+    let cursor = store.cursor::<u32>(.., None).await.unwrap().unwrap();
+    assert_eq!(cursor.value().unwrap().unwrap().label, "#1 (open)");
+
+    transaction.commit().await.unwrap();
+
+    database.close();
+    Database::delete("test_on_read_db").await.unwrap();
+}
+
+#[derive(Debug, Serialize, Deserialize, Model)]
+#[deli(on_write = "canonicalize_subscriber")]
+struct Subscriber {
+    #[deli(auto_increment)]
+    id: u32,
+    #[deli(unique)]
+    email: String,
+    score: u32,
+}
+
+fn canonicalize_subscriber(subscriber: &mut Subscriber) {
+    subscriber.email = subscriber.email.trim().to_lowercase();
+    subscriber.score = subscriber.score.min(100);
+}
+
+#[wasm_bindgen_test]
+async fn test_on_write() {
+    let _ = Database::delete("test_on_write_db").await;
+
+    let database = Database::builder("test_on_write_db")
+        .version(1)
+        .add_model::<Subscriber>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Subscriber>()
+        .build()
+        .unwrap();
+    let store = Subscriber::with_transaction(&transaction).unwrap();
+
+    let id = store
+        .add(&AddSubscriber {
+            email: "  Alice@Example.COM ".to_owned(),
+            score: 250,
+        })
+        .await
+        .unwrap();
+
+    let subscriber = store
+        .by_email_unique()
+        .unwrap()
+        .get("alice@example.com")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(subscriber.id, id);
+    assert_eq!(subscriber.score, 100);
+
+    store
+        .update(&Subscriber {
+            id,
+            email: "ALICE@example.com".to_owned(),
+            score: 101,
+        })
+        .await
+        .unwrap();
+
+    let subscriber = store.get(&id).await.unwrap().unwrap();
+    assert_eq!(subscriber.email, "alice@example.com");
+    assert_eq!(subscriber.score, 100);
+
+    transaction.commit().await.unwrap();
+
+    database.close();
+    Database::delete("test_on_write_db").await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_gate() {
+    let _ = Database::delete("test_gate_db").await;
+
+    let database = Database::builder("test_gate_db")
+        .version(1)
+        .add_model::<Employee>()
+        .add_model::<Ticket>()
+        .gate(|store: &str, operation: Operation| {
+            operation == Operation::Read || store == Ticket::NAME
+        })
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Employee>()
+        .with_model::<Ticket>()
+        .build()
+        .unwrap();
+
+    let tickets = Ticket::with_transaction(&transaction).unwrap();
+    let id = tickets
+        .add(&AddTicket {
+            status: "open".to_owned(),
+            label: String::new(),
+        })
+        .await
+        .unwrap();
+    tickets.delete(&id).await.unwrap();
+
+    let employees = Employee::with_transaction(&transaction).unwrap();
+    assert_eq!(employees.count::<u32>(..).await.unwrap(), 0);
+
+    let result = employees
+        .add(&AddEmployee {
+            name: "Alice".to_string(),
+            email: "alice@example.com".to_string(),
+            age: 25,
+        })
+        .await;
+    assert!(matches!(
+        result,
+        Err(Error::AccessDenied {
+            operation: Operation::Add,
+            ..
+        })
+    ));
+
+    let result = employees.delete_all().await;
+    assert!(matches!(
+        result,
+        Err(Error::AccessDenied {
+            operation: Operation::Delete,
+            ..
+        })
+    ));
+
+    transaction.commit().await.unwrap();
+
+    database.close();
+    Database::delete("test_gate_db").await.unwrap();
+}
+
+#[cfg(feature = "cross-db")]
+#[wasm_bindgen_test]
+async fn test_cross_db_write() {
+    use deli::cross_db::{self, CrossDbWrite};
+
+    let _ = Database::delete("test_cross_db_primary").await;
+    let _ = Database::delete("test_cross_db_secondary").await;
+
+    let primary = Database::builder("test_cross_db_primary")
+        .version(1)
+        .add_model::<Employee>()
+        .enable_journal()
+        .build()
+        .await
+        .unwrap();
+    let secondary = Database::builder("test_cross_db_secondary")
+        .version(1)
+        .add_model::<Ticket>()
+        .build()
+        .await
+        .unwrap();
+
+    for _ in 0..2 {
+        let employee = AddEmployee {
+            name: "Alice".to_string(),
+            email: "alice@example.com".to_string(),
+            age: 25,
+        };
+
+        let result = CrossDbWrite::new("hire-alice", &primary, &secondary)
+            .secondary::<Ticket, _>(|store| {
+                Box::pin(async move {
+                    store
+                        .add(&AddTicket {
+                            status: "open".to_owned(),
+                            label: "onboarding".to_owned(),
+                        })
+                        .await
+                        .map(drop)
+                })
+            })
+            .primary::<Employee, _>(move |store| {
+                Box::pin(async move { store.add(&employee).await.map(drop) })
+            })
+            .compensate::<Ticket, _>(|store| Box::pin(async move { store.delete_all().await }))
+            .execute()
+            .await;
+
+        // The second write violates the unique index on `email`, so the ticket is removed by the compensation
+        let transaction = secondary
+            .transaction()
+            .with_model::<Ticket>()
+            .build()
+            .unwrap();
+        let count = Ticket::with_transaction(&transaction)
+            .unwrap()
+            .count::<u32>(..)
+            .await
+            .unwrap();
+        transaction.done().await.unwrap();
+
+        match result {
+            Ok(()) => assert_eq!(count, 1),
+            Err(_) => assert_eq!(count, 0),
+        }
+    }
+
+    assert!(cross_db::pending(&primary).await.unwrap().is_empty());
+
+    let result = CrossDbWrite::new("incomplete", &primary, &secondary)
+        .primary::<Employee, _>(|store| Box::pin(async move { store.delete_all().await }))
+        .execute()
+        .await;
+    assert!(matches!(
+        result,
+        Err(Error::CrossDbWriteIncomplete("secondary"))
+    ));
+
+    let transaction = begin_read_transaction(&primary).unwrap();
+    let count = Employee::with_transaction(&transaction)
+        .unwrap()
+        .count::<u32>(..)
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+    transaction.done().await.unwrap();
+
+    primary.close();
+    secondary.close();
+    Database::delete("test_cross_db_primary").await.unwrap();
+    Database::delete("test_cross_db_secondary").await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_cancellation() {
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    for (name, age) in [("Alice", 25), ("Bob", 30), ("Carol", 35)] {
+        store
+            .add(&AddEmployee {
+                name: name.to_string(),
+                email: format!("{}@example.com", name.to_lowercase()),
+                age,
+            })
+            .await
+            .unwrap();
+    }
+    transaction.commit().await.unwrap();
+
+    let token = CancellationToken::new();
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Employee>()
+        .cancellation(&token)
+        .build()
+        .unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    let mut cursor = store.cursor::<u32>(.., None).await.unwrap().unwrap();
+    cursor.delete().await.unwrap();
+    cursor.next(None).await.unwrap();
+
+    token.clone().cancel();
+    assert!(token.is_cancelled());
+
+    assert!(matches!(cursor.next(None).await, Err(Error::Cancelled)));
+    assert!(matches!(
+        store.count::<u32>(..).await,
+        Err(Error::Cancelled)
+    ));
+    let _ = transaction.done().await;
+
+    // The transaction was aborted, so the deleted record is restored
+    let transaction = begin_read_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    assert_eq!(store.count::<u32>(..).await.unwrap(), 3);
+    transaction.done().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[cfg(feature = "stream")]
+#[wasm_bindgen_test]
+async fn test_stream() {
+    use deli::CursorDirection;
+    use futures_util::StreamExt;
+
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    for age in 20..25 {
+        store
+            .add(&AddEmployee {
+                name: format!("Employee {age}"),
+                email: format!("employee{age}@example.com"),
+                age,
+            })
+            .await
+            .unwrap();
+    }
+    transaction.commit().await.unwrap();
+
+    let transaction = begin_read_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    let mut stream = store
+        .stream::<u32>(&2.., Some(CursorDirection::Prev))
+        .await
+        .unwrap();
+    let mut ids = Vec::new();
+    while let Some(employee) = stream.next().await {
+        ids.push(employee.unwrap().id);
+    }
+    assert_eq!(ids, vec![5, 4, 3, 2]);
+    drop(stream);
+
+    let empty = store.stream::<u32>(&10.., None).await.unwrap();
+    assert_eq!(empty.count().await, 0);
+
+    let names = store
+        .by_age()
+        .unwrap()
+        .stream::<u32>(..&23, None)
+        .await
+        .unwrap()
+        .map(|employee| employee.unwrap().name)
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(names, vec!["Employee 20", "Employee 21", "Employee 22"]);
+    transaction.done().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_delete_chunked() {
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    for age in 20..30 {
+        store
+            .add(&AddEmployee {
+                name: format!("Employee {age}"),
+                email: format!("employee{age}@example.com"),
+                age,
+            })
+            .await
+            .unwrap();
+    }
+    transaction.commit().await.unwrap();
+
+    let mut progress = Vec::new();
+    let deleted = database
+        .delete_chunked::<Employee, u32>(&2..&10)
+        .unwrap()
+        .chunk_size(3)
+        .on_progress(|deleted| progress.push(deleted))
+        .run()
+        .await
+        .unwrap();
+    assert_eq!(deleted, 8);
+    assert_eq!(progress, vec![3, 6, 8]);
+
+    let transaction = begin_read_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    assert_eq!(store.count::<u32>(..).await.unwrap(), 2);
+    transaction.done().await.unwrap();
+
+    let token = CancellationToken::new();
+    token.cancel();
+    let result = database
+        .delete_chunked::<Employee, u32>(..)
+        .unwrap()
+        .cancellation(&token)
+        .run()
+        .await;
+    assert!(matches!(result, Err(Error::Cancelled)));
+
+    let deleted = database
+        .delete_chunked::<Employee, u32>(..)
+        .unwrap()
+        .run()
+        .await
+        .unwrap();
+    assert_eq!(deleted, 2);
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[cfg(all(feature = "priority", feature = "sync"))]
+#[wasm_bindgen_test]
+async fn test_background_sync() {
+    use deli::{priority::Priority, sync::Syncer};
+
+    let database = create_sync_database().await.unwrap();
+
+    Priority::Background.between_batches().await;
+
+    let adapter = NotesAdapter {
+        records: (1..=3)
+            .map(|id| Note {
+                id,
+                text: format!("remote {id}"),
+                updated_at: 10,
+            })
+            .collect(),
+    };
+    let summary = Syncer::new(&database, adapter)
+        .chunk_size(1)
+        .priority(Priority::Background)
+        .pull::<Note>()
+        .await
+        .unwrap();
+
+    assert_eq!(summary.records, 3);
+
+    let transaction = database.transaction().with_model::<Note>().build().unwrap();
+    let store = Note::with_transaction(&transaction).unwrap();
+    assert_eq!(store.count::<u32>(..).await.unwrap(), 3);
+    transaction.done().await.unwrap();
+
+    database.close();
+    Database::delete("test_sync_db").await.unwrap();
+}
+
+#[derive(Debug, Serialize, Deserialize, Model)]
+#[deli(index(fields(department)), unique(fields(badge)))]
+struct Contractor {
+    #[deli(auto_increment)]
+    id: u32,
+    department: String,
+    badge: String,
+}
+
+#[wasm_bindgen_test]
+async fn test_container_single_field_index() {
+    assert_eq!(
+        <ContractorDepartmentIndex as ModelIndex>::NAME,
+        "contractor_department_index"
+    );
+    assert_eq!(
+        <ContractorBadgeUniqueIndex as ModelIndex>::NAME,
+        "contractor_badge_unique_index"
+    );
+
+    let _ = Database::delete("test_container_index_db").await;
+
+    let database = Database::builder("test_container_index_db")
+        .version(1)
+        .add_model::<Contractor>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Contractor>()
+        .build()
+        .unwrap();
+    let store = Contractor::with_transaction(&transaction).unwrap();
+
+    for (department, badge) in [("it", "A1"), ("it", "A2"), ("hr", "B1")] {
+        store
+            .add(&AddContractor {
+                department: department.to_owned(),
+                badge: badge.to_owned(),
+            })
+            .await
+            .unwrap();
+    }
+
+    let by_department = store.by_department().unwrap();
+    assert_eq!(by_department.count("it").await.unwrap(), 2);
+
+    let contractor = store.by_badge_unique().unwrap().get("B1").await.unwrap();
+    assert_eq!(contractor.unwrap().department, "hr");
+
+    // The duplicate badge violates the unique index, which aborts the transaction
+    let result = store
+        .add(&AddContractor {
+            department: "hr".to_owned(),
+            badge: "A1".to_owned(),
+        })
+        .await;
+    assert!(result.is_err());
+    let _ = transaction.done().await;
+
+    database.close();
+    Database::delete("test_container_index_db").await.unwrap();
+}
+
+#[derive(Debug, Serialize, Deserialize, Model)]
+#[deli(
+    index_naming = "short",
+    index(fields(city, zip), fn_name = "in_city"),
+    expected_names(store = "office", index = "code", index = "cityName,zip")
+)]
+struct Office {
+    #[deli(auto_increment)]
+    id: u32,
+    #[deli(unique)]
+    code: String,
+    #[serde(rename = "cityName")]
+    #[deli(rename = "cityName")]
+    city: String,
+    zip: String,
+    #[deli(index(name = "office_manager", fn_name = "managed_by"))]
+    manager: String,
+}
+
+#[wasm_bindgen_test]
+fn test_short_index_naming() {
+    assert_eq!(<OfficeCodeUniqueIndex as ModelIndex>::NAME, "code");
+    assert_eq!(
+        <OfficeCityZipCompositeIndex as ModelIndex>::NAME,
+        "cityName,zip"
+    );
+    assert_eq!(<OfficeManagerIndex as ModelIndex>::NAME, "office_manager");
+}
+
+#[wasm_bindgen_test]
+async fn test_index_fn_name() {
+    let _ = Database::delete("test_index_fn_name_db").await;
+
+    let database = Database::builder("test_index_fn_name_db")
+        .version(1)
+        .add_model::<Office>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Office>()
+        .build()
+        .unwrap();
+    let store = Office::with_transaction(&transaction).unwrap();
+    store
+        .add(&AddOffice {
+            code: "NYC-1".to_owned(),
+            city: "New York".to_owned(),
+            zip: "10001".to_owned(),
+            manager: "Alice".to_owned(),
+        })
+        .await
+        .unwrap();
+
+    let office = store.managed_by().unwrap().get("Alice").await.unwrap();
+    assert_eq!(office.unwrap().code, "NYC-1");
+    assert_eq!(
+        store
+            .in_city()
+            .unwrap()
+            .count::<(String, String)>(..)
+            .await
+            .unwrap(),
+        1
+    );
+    transaction.commit().await.unwrap();
+
+    database.close();
+    Database::delete("test_index_fn_name_db").await.unwrap();
+}
+
+#[cfg(feature = "envelope")]
+#[derive(Debug, Serialize, Deserialize, Model)]
+#[deli(name = "document", envelope(version = 1))]
+struct DocumentV1 {
+    #[deli(auto_increment)]
+    id: u32,
+    #[deli(index(name = "title_idx"))]
+    title: String,
+}
+
+#[cfg(feature = "envelope")]
+#[derive(Debug, PartialEq, Serialize, Deserialize, Model)]
+#[deli(
+    name = "document",
+    envelope(version = 2, upgrade(from = 1, with = "upgrade_document_v1"))
+)]
+struct Document {
+    #[deli(auto_increment)]
+    id: u32,
+    #[deli(index(name = "title_idx"))]
+    title: String,
+    tags: Vec<String>,
+}
+
+#[cfg(feature = "envelope")]
+fn upgrade_document_v1(document: DocumentV1) -> Document {
+    Document {
+        id: document.id,
+        title: document.title,
+        tags: vec!["legacy".to_owned()],
+    }
+}
+
+#[cfg(feature = "envelope")]
+#[wasm_bindgen_test]
+async fn test_envelope() {
+    let _ = Database::delete("test_envelope_db").await;
+
+    let database = Database::builder("test_envelope_db")
+        .version(1)
+        .add_model::<DocumentV1>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<DocumentV1>()
+        .build()
+        .unwrap();
+    DocumentV1::with_transaction(&transaction)
+        .unwrap()
+        .add(&AddDocumentV1 {
+            title: "Old".to_owned(),
+        })
+        .await
+        .unwrap();
+    transaction.commit().await.unwrap();
+    database.close();
+
+    // The same store is opened by the next version of the model without a database version bump
+    let database = Database::builder("test_envelope_db")
+        .version(1)
+        .add_model::<Document>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Document>()
+        .build()
+        .unwrap();
+    let store = Document::with_transaction(&transaction).unwrap();
+    store
+        .add(&AddDocument {
+            title: "New".to_owned(),
+            tags: vec!["draft".to_owned()],
+        })
+        .await
+        .unwrap();
+
+    // Records of the older version are upgraded when read
+    let documents = store
+        .by_title()
+        .unwrap()
+        .get_all::<str>(.., None)
+        .await
+        .unwrap();
+    assert_eq!(
+        documents,
+        vec![
+            Document {
+                id: 2,
+                title: "New".to_owned(),
+                tags: vec!["draft".to_owned()],
+            },
+            Document {
+                id: 1,
+                title: "Old".to_owned(),
+                tags: vec!["legacy".to_owned()],
+            },
+        ]
+    );
+    transaction.commit().await.unwrap();
+
+    database.close();
+    Database::delete("test_envelope_db").await.unwrap();
+}
+
+#[cfg(all(feature = "envelope", feature = "integrity"))]
+#[wasm_bindgen_test]
+async fn test_envelope_integrity() {
+    use deli::integrity::RepairStrategy;
+
+    let _ = Database::delete("test_envelope_integrity_db").await;
+
+    let database = Database::builder("test_envelope_integrity_db")
+        .version(1)
+        .add_model::<DocumentV1>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<DocumentV1>()
+        .build()
+        .unwrap();
+    DocumentV1::with_transaction(&transaction)
+        .unwrap()
+        .add(&AddDocumentV1 {
+            title: "Old".to_owned(),
+        })
+        .await
+        .unwrap();
+    transaction.commit().await.unwrap();
+    database.close();
+
+    let database = Database::builder("test_envelope_integrity_db")
+        .version(1)
+        .add_model::<Document>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Document>()
+        .build()
+        .unwrap();
+    Document::with_transaction(&transaction)
+        .unwrap()
+        .add(&AddDocument {
+            title: "New".to_owned(),
+            tags: vec![],
+        })
+        .await
+        .unwrap();
+    transaction.commit().await.unwrap();
+
+    // Records are checked once unwrapped from their envelope (and upgraded)
+    let report = database.check_integrity::<Document>().await.unwrap();
+    assert_eq!(report.checked, 2);
+    assert!(report.is_ok());
+
+    let summary = database
+        .repair::<Document>(RepairStrategy::Drop)
+        .await
+        .unwrap();
+    assert!(summary.repaired.is_empty());
+    assert!(summary.dropped.is_empty());
+
+    database.close();
+    Database::delete("test_envelope_integrity_db")
+        .await
+        .unwrap();
+}
+
+#[cfg(feature = "envelope")]
+#[derive(Debug, PartialEq, Serialize, Deserialize, Model)]
+#[deli(
+    name = "document",
+    envelope(
+        version = 2,
+        migrate_on_read = "write_back",
+        upgrade(from = 1, with = "upgrade_migrated_document_v1")
+    )
+)]
+struct MigratedDocument {
+    #[deli(auto_increment)]
+    id: u32,
+    #[deli(index(name = "title_idx"))]
+    title: String,
+}
+
+#[cfg(feature = "envelope")]
+fn upgrade_migrated_document_v1(document: DocumentV1) -> MigratedDocument {
+    MigratedDocument {
+        id: document.id,
+        title: document.title.to_uppercase(),
+    }
+}
+
+#[cfg(feature = "envelope")]
+#[wasm_bindgen_test]
+async fn test_envelope_write_back() {
+    let _ = Database::delete("test_envelope_write_back_db").await;
+
+    let database = Database::builder("test_envelope_write_back_db")
+        .version(1)
+        .add_model::<DocumentV1>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<DocumentV1>()
+        .build()
+        .unwrap();
+    DocumentV1::with_transaction(&transaction)
+        .unwrap()
+        .add(&AddDocumentV1 {
+            title: "Old".to_owned(),
+        })
+        .await
+        .unwrap();
+    transaction.commit().await.unwrap();
+    database.close();
+
+    let database = Database::builder("test_envelope_write_back_db")
+        .version(1)
+        .add_model::<MigratedDocument>()
+        .build()
+        .await
+        .unwrap();
+
+    // Reading the record in a read-only transaction queues its write-back
+    let transaction = database
+        .transaction()
+        .with_model::<MigratedDocument>()
+        .build()
+        .unwrap();
+    let document = MigratedDocument::with_transaction(&transaction)
+        .unwrap()
+        .get(&1)
+        .await
+        .unwrap();
+    assert_eq!(
+        document,
+        Some(MigratedDocument {
+            id: 1,
+            title: "OLD".to_owned(),
+        })
+    );
+    transaction.done().await.unwrap();
+
+    // The write-back transaction was created first, so the record is now stored in the current version
+    let transaction = database
+        .transaction()
+        .with_model::<DocumentV1>()
+        .build()
+        .unwrap();
+    let result = DocumentV1::with_transaction(&transaction)
+        .unwrap()
+        .get(&1)
+        .await;
+    assert!(matches!(
+        result,
+        Err(Error::UnsupportedEnvelopeVersion { version: 2, .. })
+    ));
+    transaction.done().await.unwrap();
+
+    database.close();
+    Database::delete("test_envelope_write_back_db")
+        .await
+        .unwrap();
+}
+
+#[derive(Debug, Serialize, Deserialize, Model)]
+#[deli(name = "book")]
+struct LegacyBook {
+    #[deli(auto_increment)]
+    id: u32,
+    #[deli(index(name = "title_idx"))]
+    title: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Model)]
+struct Book {
+    #[deli(auto_increment)]
+    id: u32,
+    #[deli(index(alias = "title_idx"))]
+    title: String,
+}
+
+#[wasm_bindgen_test]
+async fn test_index_alias() {
+    let _ = Database::delete("test_index_alias_db").await;
+
+    let database = Database::builder("test_index_alias_db")
+        .version(1)
+        .add_model::<LegacyBook>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<LegacyBook>()
+        .build()
+        .unwrap();
+    LegacyBook::with_transaction(&transaction)
+        .unwrap()
+        .add(&AddLegacyBook {
+            title: "Dune".to_owned(),
+        })
+        .await
+        .unwrap();
+    transaction.commit().await.unwrap();
+    database.close();
+
+    // Same version, so the index keeps its old name
+    let database = Database::builder("test_index_alias_db")
+        .version(1)
+        .add_model::<Book>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database.transaction().with_model::<Book>().build().unwrap();
+    let book = Book::with_transaction(&transaction)
+        .unwrap()
+        .by_title()
+        .unwrap()
+        .get("Dune")
+        .await
+        .unwrap();
+    assert_eq!(book.unwrap().id, 1);
+    transaction.done().await.unwrap();
+    database.close();
+
+    // An upgrade recreates the index under its new name
+    let database = Database::builder("test_index_alias_db")
+        .version(2)
+        .add_model::<Book>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database.transaction().with_model::<Book>().build().unwrap();
+    let store = Book::with_transaction(&transaction).unwrap();
+    assert_eq!(store.by_title().unwrap().count("Dune").await.unwrap(), 1);
+    transaction.done().await.unwrap();
+    database.close();
+
+    Database::delete("test_index_alias_db").await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_put_all() {
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    let id = store
+        .add(&AddEmployee {
+            name: "Alice".to_string(),
+            email: "alice@example.com".to_string(),
+            age: 25,
+        })
+        .await
+        .unwrap();
+
+    let employees = [
+        Employee {
+            id,
+            name: "Alice".to_string(),
+            email: "alice@example.com".to_string(),
+            age: 26,
+        },
+        Employee {
+            id: 2,
+            name: "Bob".to_string(),
+            email: "bob@example.com".to_string(),
+            age: 30,
+        },
+    ];
+
+    let keys = store.put_all(&employees).await.unwrap();
+    assert_eq!(keys, vec![id, 2]);
+
+    assert_eq!(store.count::<u32>(..).await.unwrap(), 2);
+    assert_eq!(store.get(&id).await.unwrap().unwrap().age, 26);
+
+    transaction.commit().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[cfg(feature = "split")]
+#[derive(Debug, Clone, Serialize, Deserialize, Model)]
+struct Profile {
+    #[deli(key)]
+    id: u32,
+    name: String,
+    bio: String,
+}
+
+#[cfg(feature = "split")]
+#[derive(Debug, Serialize, Deserialize, Model)]
+struct ProfileBio {
+    #[deli(key)]
+    id: u32,
+    bio: String,
+}
+
+#[cfg(feature = "split")]
+#[wasm_bindgen_test]
+async fn test_store_split() {
+    use deli::split::{SplitState, StoreSplit};
+
+    let _ = Database::delete("test_split_db").await;
+
+    let database = Database::builder("test_split_db")
+        .version(1)
+        .add_model::<Profile>()
+        .add_model::<ProfileBio>()
+        .enable_store_splits()
+        .build()
+        .await
+        .unwrap();
+
+    let split = StoreSplit::<Profile, ProfileBio>::new(
+        "profile_bio",
+        |profile| ProfileBio {
+            id: profile.id,
+            bio: profile.bio.clone(),
+        },
+        |bio, profile| {
+            profile.map(|profile| Profile {
+                bio: bio.bio.clone(),
+                ..profile
+            })
+        },
+    );
+
+    let transaction = split.transaction(&database).writable().build().unwrap();
+    let profiles = Profile::with_transaction(&transaction).unwrap();
+    for id in 1..=2 {
+        profiles
+            .add(&Profile {
+                id,
+                name: format!("user {id}"),
+                bio: format!("old bio {id}"),
+            })
+            .await
+            .unwrap();
+    }
+
+    let store = split.with_transaction(&transaction).await.unwrap();
+    assert_eq!(store.state(), SplitState::DualWrite);
+
+    // Reads fall back to the old store
+    assert_eq!(store.get(&1).await.unwrap().unwrap().bio, "old bio 1");
+
+    // Writes are merged into the old store
+    store
+        .update(&ProfileBio {
+            id: 1,
+            bio: "new bio 1".to_owned(),
+        })
+        .await
+        .unwrap();
+    let profile = profiles.get(&1).await.unwrap().unwrap();
+    assert_eq!(profile.bio, "new bio 1");
+    assert_eq!(profile.name, "user 1");
+
+    transaction.commit().await.unwrap();
+
+    split.complete(&database).await.unwrap();
+    assert_eq!(split.state(&database).await.unwrap(), SplitState::Completed);
+
+    let transaction = split.transaction(&database).build().unwrap();
+    let bios = ProfileBio::with_transaction(&transaction).unwrap();
+    assert_eq!(bios.count::<u32>(..).await.unwrap(), 2);
+    assert_eq!(bios.get(&2).await.unwrap().unwrap().bio, "old bio 2");
+    transaction.done().await.unwrap();
+
+    database.close();
+    Database::delete("test_split_db").await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_get_many() {
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    for (name, age) in [("Alice", 25), ("Bob", 30)] {
+        store
+            .add(&AddEmployee {
+                name: name.to_string(),
+                email: format!("{}@example.com", name.to_lowercase()),
+                age,
+            })
+            .await
+            .unwrap();
+    }
+
+    let employees = store.get_many(&[2, 3, 1]).await.unwrap();
+
+    assert_eq!(employees.len(), 3);
+    assert_eq!(employees[0].as_ref().unwrap().name, "Bob");
+    assert!(employees[1].is_none());
+    assert_eq!(employees[2].as_ref().unwrap().name, "Alice");
+
+    transaction.commit().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+impl EmployeeObjectStore<'_> {
+    async fn older_than(&self, age: u32) -> Result<Vec<Employee>, Error> {
+        self.by_age()?.get_all(&(age + 1).., None).await
+    }
+}
+
+#[wasm_bindgen_test]
+async fn test_object_store_extension() {
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    for age in 20..25 {
+        store
+            .add(&AddEmployee {
+                name: format!("Employee {age}"),
+                email: format!("employee{age}@example.com"),
+                age,
+            })
+            .await
+            .unwrap();
+    }
+
+    let employees = store.older_than(22).await.unwrap();
+    assert_eq!(
+        employees
+            .iter()
+            .map(|employee| employee.age)
+            .collect::<Vec<_>>(),
+        vec![23, 24]
+    );
+    assert_eq!(store.as_object_store().count::<u32>(..).await.unwrap(), 5);
+    transaction.commit().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_object_store_handles() {
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let writer = transaction.object_store::<Employee>().unwrap();
+    let reader = transaction.object_store::<Employee>().unwrap();
+
+    let id = writer
+        .add(&AddEmployee {
+            name: "Alice".to_string(),
+            email: "alice@example.com".to_string(),
+            age: 25,
+        })
+        .await
+        .unwrap();
+    assert_eq!(reader.get(&id).await.unwrap().unwrap().name, "Alice");
+    transaction.commit().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_concurrent_build() {
+    let _ = Database::delete("test_concurrent_build_db").await;
+
+    let build = || {
+        Database::builder("test_concurrent_build_db")
+            .version(1)
+            .add_model::<Employee>()
+            .build()
+    };
+
+    let (first, second) = futures_util::future::join(build(), build()).await;
+    let (first, second) = (first.unwrap(), second.unwrap());
+    assert_eq!(first.version().unwrap(), 1);
+    assert_eq!(second.version().unwrap(), 1);
+
+    // The builds coalesced into one connection, so closing one of the databases closes the other
+    first.close();
+    assert!(second
+        .transaction()
+        .with_model::<Employee>()
+        .build()
+        .is_err());
+
+    second.close();
+    Database::delete("test_concurrent_build_db").await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_cursor_next_record() {
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    for age in 20..24 {
+        store
+            .add(&AddEmployee {
+                name: format!("Employee {age}"),
+                email: format!("employee{age}@example.com"),
+                age,
+            })
+            .await
+            .unwrap();
+    }
+
+    let mut cursor = store.cursor::<u32>(.., None).await.unwrap().unwrap();
+
+    let (key, employee) = cursor.next_record().await.unwrap().unwrap();
+    assert_eq!((key, employee.age), (1, 20));
+
+    // Moving the cursor explicitly makes the next call return the record it was moved to
+    cursor.next(Some(&3)).await.unwrap();
+    let (key, _) = cursor.next_record().await.unwrap().unwrap();
+    assert_eq!(key, 3);
+
+    let (key, _) = cursor.next_record().await.unwrap().unwrap();
+    assert_eq!(key, 4);
+    assert!(cursor.next_record().await.unwrap().is_none());
+    drop(cursor);
+    transaction.commit().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_database_handle() {
+    use std::rc::Rc;
+
+    use deli::DatabaseHandle;
+
+    let _ = Database::delete("test_database_handle_db").await;
+
+    let handle = DatabaseHandle::new(|| {
+        Database::builder("test_database_handle_db")
+            .version(1)
+            .add_model::<Employee>()
+    });
+
+    let (first, second) = futures_util::future::join(handle.get(), handle.get()).await;
+    let (first, second) = (first.unwrap(), second.unwrap());
+    assert!(Rc::ptr_eq(&first, &second));
+    assert!(Rc::ptr_eq(&first, &handle.clone().get().await.unwrap()));
+
+    // The database is opened again after the connection is closed
+    first.close();
+    let reopened = handle.get().await.unwrap();
+    assert!(!Rc::ptr_eq(&first, &reopened));
+    assert!(!reopened.is_closed());
+
+    handle.close();
+    assert!(reopened.is_closed());
+    Database::delete("test_database_handle_db").await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_page() {
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    for age in 20..25 {
+        store
+            .add(&AddEmployee {
+                name: format!("Employee {age}"),
+                email: format!("employee{age}@example.com"),
+                age,
+            })
+            .await
+            .unwrap();
+    }
+    transaction.commit().await.unwrap();
+
+    let transaction = begin_read_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    let ids = |page: &deli::Page<Employee>| page.records.iter().map(|e| e.id).collect::<Vec<_>>();
+
+    let page = store.page::<u32>(.., None, 0, 2).await.unwrap();
+    assert_eq!((ids(&page), page.has_more), (vec![1, 2], true));
+
+    let page = store.page::<u32>(.., None, 2, 2).await.unwrap();
+    assert_eq!((ids(&page), page.has_more), (vec![5], false));
+
+    let page = store.page::<u32>(.., None, 3, 2).await.unwrap();
+    assert_eq!((ids(&page), page.has_more), (vec![], false));
+
+    let page = store
+        .page::<u32>(.., Some(deli::CursorDirection::Prev), 0, 5)
+        .await
+        .unwrap();
+    assert_eq!((ids(&page), page.has_more), (vec![5, 4, 3, 2, 1], false));
+    transaction.done().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_get_all_with_offset() {
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    for age in [24, 20, 23, 21, 22] {
+        store
+            .add(&AddEmployee {
+                name: format!("Employee {age}"),
+                email: format!("employee{age}@example.com"),
+                age,
+            })
+            .await
+            .unwrap();
+    }
+    transaction.commit().await.unwrap();
+
+    let transaction = begin_read_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    let employees = store.get_all_with_offset::<u32>(.., 3, None).await.unwrap();
+    assert_eq!(
+        employees
+            .iter()
+            .map(|employee| employee.id)
+            .collect::<Vec<_>>(),
+        vec![4, 5]
+    );
+    assert_eq!(
+        store
+            .get_all_keys_with_offset::<u32>(.., 1, Some(2))
+            .await
+            .unwrap(),
+        vec![2, 3]
+    );
+    assert!(store
+        .get_all_with_offset::<u32>(.., 10, None)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let index = store.by_age().unwrap();
+    let employees = index
+        .get_all_with_offset::<u32>(.., 1, Some(2))
+        .await
+        .unwrap();
+    assert_eq!(
+        employees
+            .iter()
+            .map(|employee| employee.age)
+            .collect::<Vec<_>>(),
+        vec![21, 22]
+    );
+    assert_eq!(
+        index
+            .get_all_keys_with_offset::<u32>(&22.., 2, None)
+            .await
+            .unwrap(),
+        vec![1]
+    );
+    transaction.done().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_ordered_get_all_keys() {
+    use deli::CursorDirection;
+
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    for age in [24, 20, 24, 21] {
+        store
+            .add(&AddEmployee {
+                name: format!("Employee {age}"),
+                email: format!(
+                    "employee{}@example.com",
+                    store.count::<u32>(..).await.unwrap()
+                ),
+                age,
+            })
+            .await
+            .unwrap();
+    }
+    transaction.commit().await.unwrap();
+
+    let transaction = begin_read_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    // Latest two records
+    assert_eq!(
+        store
+            .ordered_get_all_keys::<u32>(.., CursorDirection::Prev, Some(2))
+            .await
+            .unwrap(),
+        vec![4, 3]
+    );
+
+    let index = store.by_age().unwrap();
+    assert_eq!(
+        index
+            .ordered_get_all_keys::<u32>(.., CursorDirection::Prev, None)
+            .await
+            .unwrap(),
+        vec![3, 1, 4, 2]
+    );
+    assert_eq!(
+        index
+            .ordered_get_all_keys::<u32>(.., CursorDirection::PrevUnique, None)
+            .await
+            .unwrap(),
+        vec![1, 4, 2]
+    );
+    transaction.done().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_index_get_first() {
+    use deli::CursorDirection;
+
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    for age in [24, 20, 26, 21] {
+        store
+            .add(&AddEmployee {
+                name: format!("Employee {age}"),
+                email: format!("employee{age}@example.com"),
+                age,
+            })
+            .await
+            .unwrap();
+    }
+    transaction.commit().await.unwrap();
+
+    let transaction = begin_read_transaction(&database).unwrap();
+    let index = Employee::with_transaction(&transaction)
+        .unwrap()
+        .by_age()
+        .unwrap();
+
+    let oldest = index
+        .get_first::<u32>(.., CursorDirection::Prev)
+        .await
+        .unwrap();
+    assert_eq!(oldest.unwrap().age, 26);
+
+    let youngest = index
+        .get_first::<u32>(&21.., CursorDirection::Next)
+        .await
+        .unwrap();
+    assert_eq!(youngest.unwrap().age, 21);
+
+    assert!(index
+        .get_first::<u32>(&30.., CursorDirection::Prev)
+        .await
+        .unwrap()
+        .is_none());
+
+    assert_eq!(index.first::<u32>(..).await.unwrap().unwrap().age, 20);
+    assert_eq!(index.last::<u32>(..&25).await.unwrap().unwrap().age, 24);
+
+    let store = Employee::with_transaction(&transaction).unwrap();
+    assert_eq!(store.first::<u32>(..).await.unwrap().unwrap().id, 1);
+    assert_eq!(store.last::<u32>(..).await.unwrap().unwrap().id, 4);
+    assert_eq!(store.last::<u32>(..&3).await.unwrap().unwrap().id, 2);
+    assert!(store.first::<u32>(&5..).await.unwrap().is_none());
+    transaction.done().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[cfg(feature = "watchdog")]
+#[wasm_bindgen_test]
+async fn test_watchdog() {
+    use std::time::Duration;
+
+    use deli::watchdog::with_timeout;
+
+    let result = with_timeout(
+        "pending",
+        Duration::from_millis(10),
+        std::future::pending::<Result<(), Error>>(),
+    )
+    .await;
+    assert!(matches!(
+        result,
+        Err(Error::Timeout {
+            operation: "pending",
+            ..
+        })
+    ));
+
+    let result = with_timeout("ready", Duration::from_millis(10), async { Ok(1) }).await;
+    assert_eq!(result.unwrap(), 1);
+
+    let _ = Database::delete("test_watchdog_db").await;
+    let database = Database::builder("test_watchdog_db")
+        .version(1)
+        .add_model::<Employee>()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .await
+        .unwrap();
+
+    // An upgrade blocked by the open connection times out
+    let result = Database::builder("test_watchdog_db")
+        .version(2)
+        .add_model::<Employee>()
+        .timeout(Duration::from_millis(50))
+        .build()
+        .await;
+    assert!(matches!(
+        result,
+        Err(Error::Timeout {
+            operation: "open",
+            ..
+        })
+    ));
+
+    // The upgrade completes once the connection is closed, and the connection it opened late is closed, so that it
+    // does not block deleting the database
+    database.close();
+    with_timeout(
+        "delete",
+        Duration::from_secs(10),
+        Database::delete("test_watchdog_db"),
+    )
+    .await
+    .unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_exists() {
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    let id = store
+        .add(&AddEmployee {
+            name: "Alice".to_string(),
+            email: "alice@example.com".to_string(),
+            age: 25,
+        })
+        .await
+        .unwrap();
+
+    assert!(store.exists(&id).await.unwrap());
+    assert!(!store.exists(&(id + 1)).await.unwrap());
+    assert!(store
+        .by_email_unique()
+        .unwrap()
+        .exists("alice@example.com")
+        .await
+        .unwrap());
+    assert!(!store.by_age().unwrap().exists(&30..&40).await.unwrap());
+    transaction.commit().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_min_max_key() {
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    assert_eq!(store.min_key().await.unwrap(), None);
+    assert_eq!(store.by_age().unwrap().max_key().await.unwrap(), None);
+
+    for (name, age) in [("Alice", 30), ("Bob", 25), ("Carol", 40)] {
+        store
+            .add(&AddEmployee {
+                name: name.to_string(),
+                email: format!("{}@example.com", name.to_lowercase()),
+                age,
+            })
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(store.min_key().await.unwrap(), Some(1));
+    assert_eq!(store.max_key().await.unwrap(), Some(3));
+    assert_eq!(store.by_age().unwrap().min_key().await.unwrap(), Some(25));
+    assert_eq!(store.by_age().unwrap().max_key().await.unwrap(), Some(40));
+    assert_eq!(
+        store.by_email_unique().unwrap().max_key().await.unwrap(),
+        Some("carol@example.com".to_string())
+    );
+    transaction.commit().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_index_aggregates() {
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    for (name, age) in [("Alice", 20), ("Bob", 30), ("Carol", 40), ("Dave", 50)] {
+        store
+            .add(&AddEmployee {
+                name: name.to_string(),
+                email: format!("{}@example.com", name.to_lowercase()),
+                age,
+            })
+            .await
+            .unwrap();
+    }
+
+    let by_age = store.by_age().unwrap();
+    let age = |employee: &Employee| f64::from(employee.age);
+
+    assert_eq!(by_age.sum::<u32, _>(.., age).await.unwrap(), 140.0);
+    assert_eq!(by_age.average(&25..=&45, age).await.unwrap(), Some(35.0));
+    assert_eq!(by_age.average(&60.., age).await.unwrap(), None);
+
+    let names = by_age
+        .fold::<u32, _, _>(.., String::new(), |names, employee| names + &employee.name)
+        .await
+        .unwrap();
+    assert_eq!(names, "AliceBobCarolDave");
+    transaction.commit().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_query_builder() {
+    use deli::CursorDirection;
+
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    for (name, age) in [("Alice", 20), ("Bob", 30), ("Carol", 40), ("Dave", 50)] {
+        store
+            .add(&AddEmployee {
+                name: name.to_string(),
+                email: format!("{}@example.com", name.to_lowercase()),
+                age,
+            })
+            .await
+            .unwrap();
+    }
+    transaction.commit().await.unwrap();
+
+    let transaction = begin_read_transaction(&database).unwrap();
+
+    let names = |employees: Vec<Employee>| {
+        employees
+            .into_iter()
+            .map(|employee| employee.name)
+            .collect::<Vec<_>>()
+    };
+
+    let employees = Employee::query()
+        .by_age()
+        .gte(&30)
+        .get_all(&transaction)
+        .await
+        .unwrap();
+    assert_eq!(names(employees), ["Bob", "Carol", "Dave"]);
+
+    let employees = Employee::query()
+        .by_age()
+        .gt(&20)
+        .by_age()
+        .lt(&50)
+        .direction(CursorDirection::Prev)
+        .limit(1)
+        .get_all(&transaction)
+        .await
+        .unwrap();
+    assert_eq!(names(employees), ["Carol"]);
+
+    // The unique index on email is queried, and the age is checked on the record
+    let employees = Employee::query()
+        .by_age()
+        .lte(&30)
+        .by_email_unique()
+        .eq("bob@example.com")
+        .get_all(&transaction)
+        .await
+        .unwrap();
+    assert_eq!(names(employees), ["Bob"]);
+
+    let employees = Employee::query()
+        .by_age()
+        .range(&40..)
+        .by_email_unique()
+        .eq("bob@example.com")
+        .get_all(&transaction)
+        .await
+        .unwrap();
+    assert!(employees.is_empty());
+
+    let employees = Employee::query().get_all(&transaction).await.unwrap();
+    assert_eq!(employees.len(), 4);
+    transaction.done().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[derive(Debug, Serialize, Deserialize, Model)]
+#[deli(name = "article")]
+struct ArticleV1 {
+    #[deli(auto_increment)]
+    id: u32,
+    title: String,
+    words: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Model)]
+#[deli(name = "article")]
+struct Article {
+    #[deli(auto_increment)]
+    id: u32,
+    title: String,
+    #[deli(index)]
+    words: u32,
+}
+
+#[wasm_bindgen_test]
+async fn test_index_missing() {
+    let _ = Database::delete("test_index_missing_db").await;
+
+    // The database was created before the index on words was added to the model
+    let database = Database::builder("test_index_missing_db")
+        .version(1)
+        .add_model::<ArticleV1>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<ArticleV1>()
+        .build()
+        .unwrap();
+    let store = ArticleV1::with_transaction(&transaction).unwrap();
+
+    for (title, words) in [("Short", 100), ("Medium", 1000), ("Long", 5000)] {
+        store
+            .add(&AddArticleV1 {
+                title: title.to_string(),
+                words,
+            })
+            .await
+            .unwrap();
+    }
+    transaction.commit().await.unwrap();
+
+    let transaction = database
+        .transaction()
+        .with_model::<Article>()
+        .build()
+        .unwrap();
+    let store = Article::with_transaction(&transaction).unwrap();
+    assert!(!store.has_index::<ArticleWordsIndex>());
+
+    let result = Article::query()
+        .by_words()
+        .gte(&1000)
+        .get_all(&transaction)
+        .await;
+    assert!(matches!(
+        result,
+        Err(Error::IndexMissing {
+            store: "article",
+            index: "article_words_index"
+        })
+    ));
+
+    let articles = Article::query()
+        .by_words()
+        .gte(&1000)
+        .scan_if_index_missing()
+        .get_all(&transaction)
+        .await
+        .unwrap();
+    let titles = articles
+        .into_iter()
+        .map(|article| article.title)
+        .collect::<Vec<_>>();
+    assert_eq!(titles, ["Medium", "Long"]);
+    transaction.done().await.unwrap();
+
+    database.close();
+    Database::delete("test_index_missing_db").await.unwrap();
+}
+
+#[cfg(feature = "debounce")]
+#[wasm_bindgen_test]
+async fn test_debounced_writer() {
+    use std::{rc::Rc, time::Duration};
+
+    use deli::debounce::DebouncedWriter;
+
+    let database = Rc::new(create_database().await.unwrap());
+
+    let writer = DebouncedWriter::<Employee>::new(database.clone(), Duration::from_secs(60));
+
+    for name in ["A", "Al", "Alice"] {
+        writer
+            .update(Employee {
+                id: 1,
+                name: name.to_owned(),
+                email: "alice@example.com".to_owned(),
+                age: 30,
+            })
+            .unwrap();
+    }
+    writer
+        .update(Employee {
+            id: 2,
+            name: "Bob".to_owned(),
+            email: "bob@example.com".to_owned(),
+            age: 40,
+        })
+        .unwrap();
+
+    // Successive updates of the same record are coalesced
+    assert_eq!(writer.pending(), 2);
+
+    writer.flush().await.unwrap();
+    assert_eq!(writer.pending(), 0);
+
+    let transaction = begin_read_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    assert_eq!(store.get(&1).await.unwrap().unwrap().name, "Alice");
+    assert_eq!(store.get(&2).await.unwrap().unwrap().name, "Bob");
+    transaction.done().await.unwrap();
+
+    // The writer keeps the database until its scheduled flush
+    database.close();
+    Database::delete("test_db").await.unwrap();
+}
+
+#[cfg(feature = "debounce")]
+#[wasm_bindgen_test]
+async fn test_autosave() {
+    use std::{rc::Rc, time::Duration};
+
+    use deli::debounce::Autosave;
+
+    let database = Rc::new(create_database().await.unwrap());
+
+    let autosave = Autosave::<Employee>::new(database.clone(), 1).interval(Duration::from_secs(60));
+    assert!(!autosave.dirty());
+
+    for name in ["A", "Alice"] {
+        autosave
+            .set(Employee {
+                id: 1,
+                name: name.to_owned(),
+                email: "alice@example.com".to_owned(),
+                age: 30,
+            })
+            .unwrap();
+    }
+    assert!(autosave.dirty());
+
+    let result = autosave.set(Employee {
+        id: 2,
+        name: "Bob".to_owned(),
+        email: "bob@example.com".to_owned(),
+        age: 40,
+    });
+    assert!(matches!(result, Err(Error::AutosaveKeyMismatch { .. })));
+
+    autosave.flush().await.unwrap();
+    assert!(!autosave.dirty());
+
+    // The value stays pending if writing it fails (the email is already used by another record)
+    let transaction = begin_write_transaction(&database).unwrap();
+    Employee::with_transaction(&transaction)
+        .unwrap()
+        .update(&Employee {
+            id: 2,
+            name: "Bob".to_owned(),
+            email: "bob@example.com".to_owned(),
+            age: 40,
+        })
+        .await
+        .unwrap();
+    transaction.commit().await.unwrap();
+
+    let alice = |email: &str| Employee {
+        id: 1,
+        name: "Alice".to_owned(),
+        email: email.to_owned(),
+        age: 30,
+    };
+
+    autosave.set(alice("bob@example.com")).unwrap();
+    assert!(autosave.flush().await.is_err());
+    assert!(autosave.dirty());
+
+    autosave.set(alice("alice@example.com")).unwrap();
+    autosave.flush().await.unwrap();
+    assert!(!autosave.dirty());
+
+    let transaction = begin_read_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    assert_eq!(store.get(&1).await.unwrap().unwrap().name, "Alice");
+    assert_eq!(store.count::<u32>(..).await.unwrap(), 2);
+    transaction.done().await.unwrap();
+
+    database.close();
+    Database::delete("test_db").await.unwrap();
+}
+
+#[cfg(feature = "detached")]
+#[wasm_bindgen_test]
+async fn test_detached_writes() {
+    use std::{cell::RefCell, rc::Rc};
+
+    use deli::detached;
+
+    let failures = Rc::new(RefCell::new(Vec::new()));
+    let hook_failures = failures.clone();
+    detached::set_error_hook(move |failure| {
+        hook_failures
+            .borrow_mut()
+            .push((failure.store.to_owned(), failure.operation));
+    });
+
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    for email in ["alice@example.com", "alice@example.com"] {
+        store
+            .add_detached(&AddEmployee {
+                name: "Alice".to_string(),
+                email: email.to_string(),
+                age: 25,
+            })
+            .unwrap();
+    }
+    // Adding a duplicate email fails the request, which also aborts the transaction
+    let _ = transaction.done().await;
+
+    assert_eq!(
+        *failures.borrow(),
+        vec![("employee".to_owned(), Operation::Add)]
+    );
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    store
+        .update_detached(&Employee {
+            id: 7,
+            name: "Bob".to_string(),
+            email: "bob@example.com".to_string(),
+            age: 30,
+        })
+        .unwrap();
+    transaction.done().await.unwrap();
+
+    let transaction = begin_read_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    assert_eq!(store.get(&7).await.unwrap().unwrap().name, "Bob");
+    assert_eq!(store.count::<u32>(..).await.unwrap(), 1);
+    transaction.done().await.unwrap();
+
+    detached::clear_error_hook();
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_delete_many() {
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    for age in 20..25 {
+        store
+            .add(&AddEmployee {
+                name: format!("Employee {age}"),
+                email: format!("employee{age}@example.com"),
+                age,
+            })
+            .await
+            .unwrap();
+    }
+
+    store.delete_many::<u32, u32>(&[1, 3, 5, 7]).await.unwrap();
+    transaction.commit().await.unwrap();
+
+    let transaction = begin_read_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    let keys = store.get_all_keys::<u32>(.., None).await.unwrap();
+    assert_eq!(keys, vec![2, 4]);
+    transaction.done().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[cfg(feature = "batch")]
+#[wasm_bindgen_test]
+async fn test_batch_results() {
+    use deli::batch::OnError;
+
+    let database = create_database().await.unwrap();
+
+    let employees = ["alice", "bob", "alice", "carol"].map(|name| AddEmployee {
+        name: name.to_string(),
+        email: format!("{name}@example.com"),
+        age: 30,
+    });
+
+    // The duplicate email fails without aborting the transaction
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    let result = store
+        .add_all_batch(&employees, OnError::Continue)
+        .await
+        .unwrap();
+    assert!(!result.is_ok());
+    assert_eq!(result.succeeded, vec![(0, 1), (1, 2), (3, 4)]);
+    assert_eq!(result.failed.len(), 1);
+    assert_eq!(result.failed[0].index, 2);
+
+    let result = store
+        .delete_many_batch::<u32, u32>(&[2, 5], OnError::Continue)
+        .await
+        .unwrap();
+    assert!(result.is_ok());
+    assert_eq!(result.succeeded, vec![(0, 2), (1, 5)]);
+    transaction.commit().await.unwrap();
+
+    let transaction = begin_read_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    let keys = store.get_all_keys::<u32>(.., None).await.unwrap();
+    assert_eq!(keys, vec![1, 4]);
+    transaction.done().await.unwrap();
+
+    // By default, the first failure aborts the transaction and the following items fail too
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    let employees = ["dave", "alice", "erin"].map(|name| Employee {
+        id: 10,
+        name: name.to_string(),
+        email: format!("{name}@example.com"),
+        age: 40,
+    });
+    let result = store
+        .put_all_batch(&employees, OnError::Abort)
+        .await
+        .unwrap();
+    assert_eq!(result.succeeded, vec![(0, 10)]);
+    assert_eq!(
+        result
+            .failed
+            .iter()
+            .map(|failure| failure.index)
+            .collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    assert!(transaction.done().await.unwrap().is_aborted());
+
+    let transaction = begin_read_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    assert_eq!(store.count::<u32>(..).await.unwrap(), 2);
+    transaction.done().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_get_all_filtered() {
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    for (name, age) in [("Alice", 20), ("Bob", 30), ("Carol", 40), ("Dave", 50)] {
+        store
+            .add(&AddEmployee {
+                name: name.to_string(),
+                email: format!("{}@example.com", name.to_lowercase()),
+                age,
+            })
+            .await
+            .unwrap();
+    }
+
+    let names = |employees: Vec<Employee>| {
+        employees
+            .into_iter()
+            .map(|employee| employee.name)
+            .collect::<Vec<_>>()
+    };
+
+    let long_name = |employee: &Employee| employee.name.len() > 3;
+
+    let employees = store
+        .get_all_filtered::<u32, _>(.., long_name, None)
+        .await
+        .unwrap();
+    assert_eq!(names(employees), ["Alice", "Carol", "Dave"]);
+
+    let employees = store
+        .get_all_filtered::<u32, _>(.., long_name, Some(2))
+        .await
+        .unwrap();
+    assert_eq!(names(employees), ["Alice", "Carol"]);
+
+    let employees = store.get_all_filtered(&2.., long_name, None).await.unwrap();
+    assert_eq!(names(employees), ["Carol", "Dave"]);
+    transaction.commit().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_update_where() {
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    for (name, age) in [("Alice", 20), ("Bob", 30), ("Carol", 40), ("Dave", 50)] {
+        store
+            .add(&AddEmployee {
+                name: name.to_string(),
+                email: format!("{}@example.com", name.to_lowercase()),
+                age,
+            })
+            .await
+            .unwrap();
+    }
+
+    let updated = store
+        .update_where(&2.., |employee| {
+            (employee.age < 50).then(|| Employee {
+                age: employee.age + 1,
+                ..employee
+            })
+        })
+        .await
+        .unwrap();
+    assert_eq!(updated, 2);
+
+    let ages = store
+        .get_all::<u32>(.., None)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|employee| (employee.id, employee.age))
+        .collect::<std::collections::BTreeMap<_, _>>();
+    assert_eq!(
+        ages.into_iter().collect::<Vec<_>>(),
+        [(1, 20), (2, 31), (3, 41), (4, 50)]
+    );
+
+    // The age index reflects the updates
+    assert_eq!(store.by_age().unwrap().count(&31).await.unwrap(), 1);
+    transaction.commit().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_delete_where() {
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    for (name, age) in [("Alice", 20), ("Bob", 30), ("Carol", 40), ("Dave", 50)] {
+        store
+            .add(&AddEmployee {
+                name: name.to_string(),
+                email: format!("{}@example.com", name.to_lowercase()),
+                age,
+            })
+            .await
+            .unwrap();
+    }
+
+    let deleted = store
+        .delete_where(&2.., |employee| employee.age < 50)
+        .await
+        .unwrap();
+    assert_eq!(deleted, 2);
+
+    let mut ids = store.get_all_keys::<u32>(.., None).await.unwrap();
+    ids.sort();
+    assert_eq!(ids, [1, 4]);
+    transaction.commit().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_upsert() {
+    use deli::Upsert;
+
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    let employee = Employee {
+        id: 7,
+        name: "Alice".to_string(),
+        email: "alice@example.com".to_string(),
+        age: 30,
+    };
+
+    let upsert = store.upsert(&employee).await.unwrap();
+    assert_eq!(upsert, Upsert::Created(7));
+    assert!(upsert.is_created());
+
+    let upsert = store
+        .upsert(&Employee {
+            age: 31,
+            ..employee
+        })
+        .await
+        .unwrap();
+    assert_eq!(upsert, Upsert::Replaced(7));
+    assert_eq!(upsert.key(), &7);
+
+    let employee = store.get(&7).await.unwrap().unwrap();
+    assert_eq!(employee.age, 31);
+    assert_eq!(store.count::<u32>(..).await.unwrap(), 1);
+    transaction.commit().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_get_or_insert_with() {
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    let add = |name: &str| AddEmployee {
+        name: name.to_string(),
+        email: format!("{}@example.com", name.to_lowercase()),
+        age: 30,
+    };
+
+    let employee = store.get_or_insert_with(&1, || add("Alice")).await.unwrap();
+    assert_eq!(employee.id, 1);
+    assert_eq!(employee.name, "Alice");
+
+    // The existing record is returned without creating a value
+    let employee = store
+        .get_or_insert_with(&1, || -> AddEmployee { panic!("record exists") })
+        .await
+        .unwrap();
+    assert_eq!(employee.name, "Alice");
+    assert_eq!(store.count::<u32>(..).await.unwrap(), 1);
+
+    // The generated key of the added value is not the requested one
+    assert!(matches!(
+        store.get_or_insert_with(&5, || add("Bob")).await,
+        Err(Error::InsertedKeyMismatch { store: "employee" })
+    ));
+    transaction.commit().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_patch() {
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    let id = store
+        .add(&AddEmployee {
+            name: "Alice".to_string(),
+            email: "alice@example.com".to_string(),
+            age: 30,
+        })
+        .await
+        .unwrap();
+
+    let employee = store
+        .patch(
+            &id,
+            EmployeePatch {
+                age: Some(31),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(employee.name, "Alice");
+    assert_eq!(employee.age, 31);
+
+    let employee = store.get(&id).await.unwrap().unwrap();
+    assert_eq!(employee.email, "alice@example.com");
+    assert_eq!(employee.age, 31);
+
+    // The age index reflects the patch
+    assert_eq!(store.by_age().unwrap().count(&31).await.unwrap(), 1);
+
+    let missing = store.patch(&42, EmployeePatch::default()).await.unwrap();
+    assert!(missing.is_none());
+    transaction.commit().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_get_grouped() {
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    for (name, age) in [("Alice", 30), ("Bob", 40), ("Carol", 30), ("Dave", 30)] {
+        store
+            .add(&AddEmployee {
+                name: name.to_string(),
+                email: format!("{}@example.com", name.to_lowercase()),
+                age,
+            })
+            .await
+            .unwrap();
+    }
+
+    let names = |employees: &[Employee]| {
+        employees
+            .iter()
+            .map(|employee| employee.name.clone())
+            .collect::<Vec<_>>()
+    };
+
+    let groups = store
+        .by_age()
+        .unwrap()
+        .get_grouped::<u32>(.., None)
+        .await
+        .unwrap();
+    assert_eq!(groups.keys().copied().collect::<Vec<_>>(), [30, 40]);
+    assert_eq!(names(&groups[&30]), ["Alice", "Carol", "Dave"]);
+    assert_eq!(names(&groups[&40]), ["Bob"]);
+
+    let groups = store
+        .by_age()
+        .unwrap()
+        .get_grouped(&30.., Some(2))
+        .await
+        .unwrap();
+    assert_eq!(names(&groups[&30]), ["Alice", "Carol"]);
+    assert_eq!(names(&groups[&40]), ["Bob"]);
+    transaction.commit().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Model)]
+#[deli(external_key = "String")]
+struct Preference {
+    #[deli(index)]
+    value: u32,
+}
+
+#[wasm_bindgen_test]
+async fn test_external_key() {
+    let _ = Database::delete("test_external_key_db").await;
+
+    let database = Database::builder("test_external_key_db")
+        .version(1)
+        .add_model::<Preference>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Preference>()
+        .build()
+        .unwrap();
+    let store = Preference::with_transaction(&transaction).unwrap();
+
+    let key = store
+        .add_with_key("volume", &Preference { value: 7 })
+        .await
+        .unwrap();
+    assert_eq!(key, "volume");
+
+    store
+        .put_with_key("volume", &Preference { value: 9 })
+        .await
+        .unwrap();
+    store
+        .put_with_key("brightness", &Preference { value: 3 })
+        .await
+        .unwrap();
+
+    assert_eq!(
+        store.get("volume").await.unwrap(),
+        Some(Preference { value: 9 })
+    );
+    assert_eq!(
+        store.get_all_keys::<str>(.., None).await.unwrap(),
+        ["brightness", "volume"]
+    );
+    assert_eq!(store.by_value().unwrap().count(&3).await.unwrap(), 1);
+    transaction.commit().await.unwrap();
+
+    database.close();
+    Database::delete("test_external_key_db").await.unwrap();
+}
+
+#[cfg(feature = "key-generator")]
+#[derive(Debug, Serialize, Deserialize, Model)]
+struct Snippet {
+    #[deli(key_generator = "uuid")]
+    id: String,
+    text: String,
+}
+
+#[cfg(feature = "key-generator")]
+#[derive(Debug, Serialize, Deserialize, Model)]
+struct Receipt {
+    #[deli(key_generator = "ulid")]
+    id: String,
+}
+
+#[cfg(feature = "key-generator")]
+fn next_badge_id() -> String {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    static NEXT: AtomicU32 = AtomicU32::new(1);
+    format!("badge-{}", NEXT.fetch_add(1, Ordering::Relaxed))
+}
+
+#[cfg(feature = "key-generator")]
+#[derive(Debug, Serialize, Deserialize, Model)]
+struct Badge {
+    #[deli(key_generator = "next_badge_id")]
+    id: String,
+}
+
+#[cfg(feature = "key-generator")]
+#[derive(Debug, Serialize, Deserialize, Model)]
+#[deli(key(tenant, id))]
+struct Membership {
+    tenant: String,
+    #[deli(key_generator = "nanoid")]
+    id: String,
+    role: String,
+}
+
+#[cfg(feature = "key-generator")]
+#[wasm_bindgen_test]
+async fn test_key_generator() {
+    let _ = Database::delete("test_key_generator_db").await;
+
+    let database = Database::builder("test_key_generator_db")
+        .version(1)
+        .add_model::<Snippet>()
+        .add_model::<Receipt>()
+        .add_model::<Badge>()
+        .add_model::<Membership>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Snippet>()
+        .with_model::<Receipt>()
+        .with_model::<Badge>()
+        .with_model::<Membership>()
+        .build()
+        .unwrap();
+
+    let snippets = Snippet::with_transaction(&transaction).unwrap();
+    let first = snippets
+        .add(&AddSnippet {
+            text: "Hello".to_string(),
+        })
+        .await
+        .unwrap();
+    let second = snippets
+        .add(&AddSnippet {
+            text: "World".to_string(),
+        })
+        .await
+        .unwrap();
+    assert_ne!(first, second);
+    assert_eq!(first.len(), 36);
+    assert_eq!(&first[14..15], "4");
+
+    let snippet = snippets.get(&first).await.unwrap().unwrap();
+    assert_eq!(snippet.id, first);
+    assert_eq!(snippet.text, "Hello");
+
+    let receipts = Receipt::with_transaction(&transaction).unwrap();
+    let receipt = receipts.add(&AddReceipt {}).await.unwrap();
+    assert_eq!(receipt.len(), 26);
+    assert!(receipts.get(&receipt).await.unwrap().is_some());
+
+    let badges = Badge::with_transaction(&transaction).unwrap();
+    let badge = badges.add(&AddBadge {}).await.unwrap();
+    assert!(badge.starts_with("badge-"));
+
+    // Only the generated component of a composite key is left out of the add type
+    let memberships = Membership::with_transaction(&transaction).unwrap();
+    let (tenant, id) = memberships
+        .add(&AddMembership {
+            tenant: "acme".to_string(),
+            role: "admin".to_string(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(tenant, "acme");
+    assert_eq!(id.len(), 21);
+
+    let membership = memberships.get(("acme", &id)).await.unwrap().unwrap();
+    assert_eq!(membership.role, "admin");
+    transaction.commit().await.unwrap();
+
+    database.close();
+    Database::delete("test_key_generator_db").await.unwrap();
+}
+
+#[cfg(feature = "timestamps")]
+#[derive(Debug, Serialize, Deserialize, Model)]
+struct Comment {
+    #[deli(auto_increment)]
+    id: u32,
+    text: String,
+    #[deli(created_at)]
+    created_at: u64,
+}
+
+#[cfg(feature = "timestamps")]
+#[derive(Debug, Serialize, Deserialize, Model)]
+struct Thread {
+    #[deli(key)]
+    id: u32,
+    #[deli(index, created_at(unit = "iso8601"))]
+    started_at: String,
+}
+
+#[cfg(feature = "timestamps")]
+#[derive(Debug, Serialize, Deserialize, Model)]
+struct Vote {
+    #[deli(key)]
+    id: u32,
+    #[deli(created_at(unit = "seconds"))]
+    cast_at: u64,
+}
+
+#[cfg(feature = "timestamps")]
+#[wasm_bindgen_test]
+async fn test_created_at() {
+    let _ = Database::delete("test_created_at_db").await;
+
+    let database = Database::builder("test_created_at_db")
+        .version(1)
+        .add_model::<Comment>()
+        .add_model::<Thread>()
+        .add_model::<Vote>()
+        .build()
+        .await
+        .unwrap();
+
+    let now = js_sys::Date::now() as u64;
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Comment>()
+        .with_model::<Thread>()
+        .with_model::<Vote>()
+        .build()
+        .unwrap();
+
+    let comments = Comment::with_transaction(&transaction).unwrap();
+    let id = comments
+        .add(&AddComment {
+            text: "Hello".to_string(),
+            created_at: 0,
+        })
+        .await
+        .unwrap();
+    let comment = comments.get(&id).await.unwrap().unwrap();
+    assert!(comment.created_at >= now);
+
+    let threads = Thread::with_transaction(&transaction).unwrap();
+    threads
+        .add(&Thread {
+            id: 1,
+            started_at: String::new(),
+        })
+        .await
+        .unwrap();
+    let thread = threads.get(&1).await.unwrap().unwrap();
+    assert!(thread.started_at.ends_with('Z'));
+
+    let votes = Vote::with_transaction(&transaction).unwrap();
+    votes.add(&Vote { id: 1, cast_at: 0 }).await.unwrap();
+    let vote = votes.get(&1).await.unwrap().unwrap();
+    assert!(vote.cast_at >= now / 1000 && vote.cast_at < now);
+
+    // The timestamp is indexed
+    let started = threads
+        .by_started_at()
+        .unwrap()
+        .get_all(&thread.started_at, None)
+        .await
+        .unwrap();
+    assert_eq!(started.len(), 1);
+    transaction.commit().await.unwrap();
+
+    database.close();
+    Database::delete("test_created_at_db").await.unwrap();
+}
+
+#[cfg(feature = "bytes")]
+#[derive(Debug, Serialize, Deserialize, Model)]
+struct Attachment {
+    #[deli(auto_increment)]
+    id: u32,
+    name: String,
+    #[deli(bytes)]
+    data: Vec<u8>,
+    #[deli(bytes)]
+    thumbnail: Option<Vec<u8>>,
+}
+
+#[cfg(feature = "bytes")]
+#[wasm_bindgen_test]
+async fn test_bytes_fields() {
+    use deli::reexports::idb::{Factory, Query, TransactionMode};
+    use wasm_bindgen::JsCast;
+
+    let _ = Database::delete("test_bytes_db").await;
+
+    let database = Database::builder("test_bytes_db")
+        .version(1)
+        .add_model::<Attachment>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Attachment>()
+        .build()
+        .unwrap();
+    let attachments = Attachment::with_transaction(&transaction).unwrap();
+
+    let data = (0..=255).collect::<Vec<u8>>();
+    let id = attachments
+        .add(&AddAttachment {
+            name: "bytes.bin".to_string(),
+            data: data.clone(),
+            thumbnail: None,
+        })
+        .await
+        .unwrap();
+
+    let attachment = attachments.get(&id).await.unwrap().unwrap();
+    assert_eq!(attachment.data, data);
+    assert_eq!(attachment.thumbnail, None);
+
+    attachments
+        .update(&Attachment {
+            thumbnail: Some(vec![1, 2, 3]),
+            ..attachment
+        })
+        .await
+        .unwrap();
+    let attachment = attachments.get(&id).await.unwrap().unwrap();
+    assert_eq!(attachment.thumbnail, Some(vec![1, 2, 3]));
+    transaction.commit().await.unwrap();
+
+    database.close();
+
+    // The fields are stored as `Uint8Array` instead of arrays of numbers
+    let database = Factory::new()
+        .unwrap()
+        .open("test_bytes_db", None)
+        .unwrap()
+        .await
+        .unwrap();
+    let transaction = database
+        .transaction(&[Attachment::NAME], TransactionMode::ReadOnly)
+        .unwrap();
+    let value = transaction
+        .object_store(Attachment::NAME)
+        .unwrap()
+        .get(Query::Key(serde_wasm_bindgen::to_value(&id).unwrap()))
+        .unwrap()
+        .await
+        .unwrap()
+        .unwrap();
+    for field in ["data", "thumbnail"] {
+        let field = js_sys::Reflect::get(&value, &field.into()).unwrap();
+        assert!(field.is_instance_of::<js_sys::Uint8Array>());
+    }
+    transaction.commit().unwrap().await.unwrap();
+
+    database.close();
+    Database::delete("test_bytes_db").await.unwrap();
+}
+
+#[derive(Debug, Serialize, Deserialize, Model)]
+#[deli(structured_clone)]
+struct Board {
+    #[deli(auto_increment)]
+    id: u32,
+    cells: std::collections::HashMap<(u32, u32), String>,
+    winner: Option<String>,
+}
+
+#[wasm_bindgen_test]
+async fn test_structured_clone() {
+    let _ = Database::delete("test_structured_clone_db").await;
+
+    let database = Database::builder("test_structured_clone_db")
+        .version(1)
+        .add_model::<Board>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Board>()
+        .build()
+        .unwrap();
+    let boards = Board::with_transaction(&transaction).unwrap();
+
+    // Maps with non-string keys cannot be stored as plain objects
+    let cells = [((0, 0), "x".to_string()), ((1, 2), "o".to_string())]
+        .into_iter()
+        .collect::<std::collections::HashMap<_, _>>();
+    let id = boards
+        .add(&AddBoard {
+            cells: cells.clone(),
+            winner: None,
+        })
+        .await
+        .unwrap();
+
+    let board = boards.get(&id).await.unwrap().unwrap();
+    assert_eq!(board.cells, cells);
+    assert_eq!(board.winner, None);
+
+    boards
+        .update(&Board {
+            winner: Some("x".to_string()),
+            ..board
+        })
+        .await
+        .unwrap();
+    let board = boards.get(&id).await.unwrap().unwrap();
+    assert_eq!(board.winner.as_deref(), Some("x"));
+    assert_eq!(board.cells[&(1, 2)], "o");
+    transaction.commit().await.unwrap();
+
+    database.close();
+    Database::delete("test_structured_clone_db").await.unwrap();
+}
+
+#[cfg(feature = "blob")]
+#[derive(Debug, Serialize, Deserialize, Model)]
+struct Upload {
+    #[deli(auto_increment)]
+    id: u32,
+    caption: String,
+    content: deli::JsBlob,
+    preview: Option<deli::JsBlob>,
+}
+
+#[cfg(feature = "blob")]
+#[wasm_bindgen_test]
+async fn test_blob_fields() {
+    use deli::JsBlob;
+
+    let _ = Database::delete("test_blob_db").await;
+
+    let database = Database::builder("test_blob_db")
+        .version(1)
+        .add_model::<Upload>()
+        .build()
+        .await
+        .unwrap();
+
+    let parts = serde_wasm_bindgen::to_value(&["hello, world"]).unwrap();
+    let file = web_sys::File::new_with_str_sequence(&parts, "hello.txt").unwrap();
+    let blob = web_sys::Blob::new_with_str_sequence(&parts).unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Upload>()
+        .build()
+        .unwrap();
+    let uploads = Upload::with_transaction(&transaction).unwrap();
+
+    let id = uploads
+        .add(&AddUpload {
+            caption: "greeting".to_string(),
+            content: file.into(),
+            preview: Some(blob.into()),
+        })
+        .await
+        .unwrap();
+
+    let upload = uploads.get(&id).await.unwrap().unwrap();
+    assert_eq!(upload.caption, "greeting");
+
+    // Files are read back as files, with their name
+    assert_eq!(upload.content.size(), 12.0);
+    assert_eq!(upload.content.as_file().unwrap().name(), "hello.txt");
+
+    let preview = upload.preview.unwrap();
+    assert_eq!(preview.size(), 12.0);
+    assert!(preview.as_file().is_none());
+
+    uploads
+        .update(&Upload {
+            id,
+            caption: "updated".to_string(),
+            content: upload.content.clone(),
+            preview: None,
+        })
+        .await
+        .unwrap();
+    let upload = uploads.get(&id).await.unwrap().unwrap();
+    assert_eq!(upload.caption, "updated");
+    assert!(upload.preview.is_none());
+    assert!(JsBlob::from(upload.content.into_inner())
+        .as_file()
+        .is_some());
+    transaction.commit().await.unwrap();
+
+    database.close();
+    Database::delete("test_blob_db").await.unwrap();
+}
+
+#[cfg(feature = "datetime")]
+#[derive(Debug, Serialize, Deserialize, Model)]
+struct Appointment {
+    #[deli(auto_increment)]
+    id: u32,
+    #[deli(index, datetime(unit = "iso8601"))]
+    starts_at: String,
+    #[deli(datetime)]
+    reminded_at: Option<u64>,
+}
+
+#[cfg(feature = "datetime")]
+#[wasm_bindgen_test]
+async fn test_datetime() {
+    let _ = Database::delete("test_datetime_db").await;
+
+    let database = Database::builder("test_datetime_db")
+        .version(1)
+        .add_model::<Appointment>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Appointment>()
+        .build()
+        .unwrap();
+    let appointments = Appointment::with_transaction(&transaction).unwrap();
+
+    // Sorts after `09:00Z` as a string, but is one hour earlier
+    for starts_at in ["2024-01-01T10:00:00+02:00", "2024-01-01T09:00:00Z"] {
+        appointments
+            .add(&AddAppointment {
+                starts_at: starts_at.to_string(),
+                reminded_at: None,
+            })
+            .await
+            .unwrap();
+    }
+    appointments
+        .add(&AddAppointment {
+            starts_at: "2024-01-01T12:00:00Z".to_string(),
+            reminded_at: Some(1_704_100_000_000),
+        })
+        .await
+        .unwrap();
+
+    // Dates are read back in UTC
+    let appointment = appointments.get(&1).await.unwrap().unwrap();
+    assert_eq!(appointment.starts_at, "2024-01-01T08:00:00.000Z");
+    assert_eq!(appointment.reminded_at, None);
+    let appointment = appointments.get(&3).await.unwrap().unwrap();
+    assert_eq!(appointment.reminded_at, Some(1_704_100_000_000));
+
+    // Index queries compare the time values, whatever the offset of the bounds
+    let by_starts_at = appointments.by_starts_at().unwrap();
+    let morning = by_starts_at
+        .get_all(
+            &"2024-01-01T07:00:00Z".to_string()..&"2024-01-01T11:00:00+01:00".to_string(),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(morning.iter().map(|a| a.id).collect::<Vec<_>>(), [1, 2]);
+    assert_eq!(
+        by_starts_at
+            .count(&"2024-01-01T13:00:00+01:00".to_string())
+            .await
+            .unwrap(),
+        1
+    );
+    transaction.commit().await.unwrap();
+
+    let transaction = database
+        .transaction()
+        .with_model::<Appointment>()
+        .build()
+        .unwrap();
+    let later = Appointment::query()
+        .by_starts_at()
+        .gt(&"2024-01-01T09:30:00+01:00".to_string())
+        .get_all(&transaction)
+        .await
+        .unwrap();
+    assert_eq!(later.iter().map(|a| a.id).collect::<Vec<_>>(), [2, 3]);
+    transaction.commit().await.unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<Appointment>()
+        .build()
+        .unwrap();
+    let error = Appointment::with_transaction(&transaction)
+        .unwrap()
+        .add(&AddAppointment {
+            starts_at: "tomorrow".to_string(),
+            reminded_at: None,
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        Error::InvalidDateTime {
+            field: "starts_at",
+            ..
+        }
+    ));
+    transaction.abort().await.unwrap();
+
+    database.close();
+    Database::delete("test_datetime_db").await.unwrap();
+}
+
+#[cfg(feature = "cache")]
+#[wasm_bindgen_test]
+async fn test_query_cache() {
+    let _ = Database::delete("test_db").await;
+
+    let database = Database::builder("test_db")
+        .version(1)
+        .add_model::<Employee>()
+        .cache_queries()
+        .build()
+        .await
+        .unwrap();
+    // Writes through another connection are not seen by the cache
+    let other = Database::builder("test_db").build().await.unwrap();
+
+    let add = |name: &str, age| AddEmployee {
+        name: name.to_string(),
+        email: format!("{}@example.com", name.to_lowercase()),
+        age,
+    };
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    store.add(&add("Alice", 20)).await.unwrap();
+    transaction.commit().await.unwrap();
+
+    let count = |database: &Database| {
+        let transaction = begin_read_transaction(database).unwrap();
+
+        async move {
+            let store = Employee::with_transaction(&transaction).unwrap();
+            let employees = store.get_all::<u32>(.., None).await.unwrap();
+            transaction.done().await.unwrap();
+            employees.len()
+        }
+    };
+
+    assert_eq!(count(&database).await, 1);
+
+    let transaction = begin_write_transaction(&other).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    store.add(&add("Bob", 30)).await.unwrap();
+    transaction.commit().await.unwrap();
+
+    // The cached result is returned until the cache is cleared
+    assert_eq!(count(&database).await, 1);
+    assert_eq!(count(&other).await, 2);
+
+    database.clear_query_cache();
+    assert_eq!(count(&database).await, 2);
+
+    // Writes through the database invalidate the cached results of the store
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    store.add(&add("Carol", 40)).await.unwrap();
+    transaction.commit().await.unwrap();
+
+    assert_eq!(count(&database).await, 3);
+
+    // Transactions created after a pending write read the store as written, and not the cached results
+    let transaction = begin_write_transaction(&database).unwrap();
+    let queued = count(&database);
+    let store = Employee::with_transaction(&transaction).unwrap();
+    store.add(&add("Dave", 50)).await.unwrap();
+    transaction.commit().await.unwrap();
+
+    assert_eq!(queued.await, 4);
+    assert_eq!(count(&database).await, 4);
+
+    other.close();
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_get_all_map() {
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    for (name, age) in [("Alice", 25), ("Bob", 30), ("Carol", 30)] {
+        store
+            .add(&AddEmployee {
+                name: name.to_string(),
+                email: format!("{}@example.com", name.to_lowercase()),
+                age,
+            })
+            .await
+            .unwrap();
+    }
+
+    let employees = store.get_all_map(.., None).await.unwrap();
+    assert_eq!(employees.len(), 3);
+    assert_eq!(employees[&2].name, "Bob");
+
+    let employees = store
+        .by_age()
+        .unwrap()
+        .get_all_map(&30, None)
+        .await
+        .unwrap();
+    assert_eq!(employees.len(), 2);
+    assert_eq!(employees[&3].name, "Carol");
+
+    transaction.commit().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[cfg(feature = "storage")]
+#[wasm_bindgen_test]
+async fn test_storage_pressure() {
+    use std::{cell::Cell, rc::Rc, time::Duration};
+
+    use deli::storage;
+
+    let estimate = storage::estimate().await.unwrap().unwrap();
+    assert!(estimate.quota > 0.0);
+    assert!((0.0..=1.0).contains(&estimate.usage_ratio()));
+
+    // Every usage is above a threshold of zero, so the hook is called once the first estimate resolves
+    let calls = Rc::new(Cell::new(0));
+    let watch = storage::watch_pressure(0.0, Duration::from_secs(60), {
+        let calls = calls.clone();
+        move |_| calls.set(calls.get() + 1)
+    });
+
+    for _ in 0..100 {
+        if calls.get() > 0 {
+            break;
+        }
+
+        storage::estimate().await.unwrap();
+    }
+
+    assert_eq!(calls.get(), 1);
+    drop(watch);
+
+    let closed = Rc::new(Cell::new(false));
+    let database = Database::builder("test_db")
+        .on_forced_close({
+            let closed = closed.clone();
+            move || closed.set(true)
+        })
+        .build()
+        .await
+        .unwrap();
+
+    // Closing the database from the app does not call the hook
+    database.close();
+    assert!(!closed.get());
+
+    Database::delete("test_db").await.unwrap();
+}
+
+#[cfg(feature = "capabilities")]
+#[wasm_bindgen_test]
+async fn test_capabilities() {
+    let capabilities = deli::capabilities();
+    assert!(capabilities.get_all);
+    assert!(capabilities.commit);
+    assert_eq!(capabilities, deli::capabilities());
+
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    store
+        .add(&AddEmployee {
+            name: "Alice".to_string(),
+            email: "alice@example.com".to_string(),
+            age: 25,
+        })
+        .await
+        .unwrap();
+    transaction.commit().await.unwrap();
+
+    let transaction = begin_read_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    assert_eq!(store.get_all(.., None).await.unwrap().len(), 1);
+    assert_eq!(
+        store
+            .by_age()
+            .unwrap()
+            .get_all(.., None)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+    transaction.done().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_owned_key_range() {
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    for (name, age) in [("Alice", 25), ("Bob", 30), ("Carol", 35)] {
+        store
+            .add(&AddEmployee {
+                name: name.to_string(),
+                email: format!("{}@example.com", name.to_lowercase()),
+                age,
+            })
+            .await
+            .unwrap();
+    }
+
+    let (from, to) = (1 + 1, 2 + 1);
+
+    assert_eq!(store.get(from).await.unwrap().unwrap().name, "Bob");
+    assert_eq!(
+        store
+            .get_all(OwnedRange(from..=to), None)
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+    assert_eq!(
+        store
+            .get_all(OwnedRange(from..to), None)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+    assert_eq!(store.count(OwnedRange(..to)).await.unwrap(), 2);
+    assert_eq!(
+        store
+            .by_age()
+            .unwrap()
+            .get_all(OwnedRange(30..), None)
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+
+    store.delete(OwnedRange(to..)).await.unwrap();
+    assert_eq!(store.count(..).await.unwrap(), 2);
+
+    transaction.commit().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_availability_check() {
+    let availability = Database::availability_check().await;
+    assert!(availability.is_available(), "{availability:?}");
+    assert_eq!(availability.error, None);
+
+    // The probe database is deleted, so repeated checks start from scratch
+    let availability = Database::availability_check().await;
+    assert!(availability.is_available(), "{availability:?}");
+}
+
+#[wasm_bindgen_test]
+async fn test_key_range_bounds() {
+    use std::ops::Bound;
+
+    use deli::KeyRange;
+
+    let database = create_database().await.unwrap();
+
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    for (name, age) in [("Alice", 25), ("Bob", 30), ("Carol", 35)] {
+        store
+            .add(&AddEmployee {
+                name: name.to_string(),
+                email: format!("{}@example.com", name.to_lowercase()),
+                age,
+            })
+            .await
+            .unwrap();
+    }
+
+    let employees = store
+        .get_all((Bound::Excluded(&1), Bound::Included(&3)), None)
+        .await
+        .unwrap();
+    assert_eq!(employees.len(), 2);
+
+    let employees = store
+        .by_age()
+        .unwrap()
+        .get_all(
+            KeyRange::from_bounds(Bound::Excluded(&25), Bound::Unbounded),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(employees.len(), 2);
+
+    let employees = store
+        .get_all(KeyRange::greater_than(&2), None)
+        .await
+        .unwrap();
+    assert_eq!(employees.len(), 1);
+    assert_eq!(employees[0].name, "Carol");
+
+    // Named constructors mirroring `IDBKeyRange`
+    let employees = store.get_all(KeyRange::all_after(&2), None).await.unwrap();
+    assert_eq!(employees.len(), 1);
+    assert_eq!(employees[0].name, "Carol");
+
+    let employees = store.get_all(KeyRange::all_before(&2), None).await.unwrap();
+    assert_eq!(employees.len(), 1);
+
+    let employee = store.get(KeyRange::only(&2)).await.unwrap().unwrap();
+    assert_eq!(employee.id, 2);
+
+    // Custom range types implementing `RangeBounds`
+    struct AtLeast<'a>(&'a u32);
+
+    impl<'a> std::ops::RangeBounds<&'a u32> for AtLeast<'a> {
+        fn start_bound(&self) -> Bound<&&'a u32> {
+            Bound::Included(&self.0)
+        }
+
+        fn end_bound(&self) -> Bound<&&'a u32> {
+            Bound::Unbounded
+        }
+    }
+
+    assert_eq!(
+        store
+            .count(KeyRange::from_range_bounds(AtLeast(&2)))
+            .await
+            .unwrap(),
+        2
+    );
+
+    assert!(matches!(
+        store
+            .get((Bound::<&u32>::Unbounded, Bound::Unbounded))
+            .await,
+        Err(Error::FullKeyRangeNotAllowed)
+    ));
+
+    transaction.commit().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn test_read_only_transaction() {
+    let database = create_database().await.unwrap();
+
+    let add = AddEmployee {
+        name: "Alice".to_string(),
+        email: "alice@example.com".to_string(),
+        age: 25,
+    };
+
+    let transaction = begin_read_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    assert!(matches!(
+        store.add(&add).await,
+        Err(Error::ReadOnlyTransaction {
+            operation: Operation::Add,
+            ..
+        })
+    ));
+    assert!(matches!(
+        store.delete(&1).await,
+        Err(Error::ReadOnlyTransaction {
+            operation: Operation::Delete,
+            ..
+        })
+    ));
+
+    // The transaction is still usable for reads
+    assert_eq!(store.count(..).await.unwrap(), 0);
+    transaction.done().await.unwrap();
+
+    for write in [false, true] {
+        let transaction = database
+            .transaction()
+            .writable_if(write)
+            .with_model::<Employee>()
+            .build()
+            .unwrap();
+        let store = Employee::with_transaction(&transaction).unwrap();
+
+        assert_eq!(store.add(&add).await.is_ok(), write);
+        transaction.done().await.unwrap();
+    }
+
+    close_and_delete_database(database).await.unwrap();
+}
+
+#[derive(Debug, Serialize, Deserialize, Model)]
+struct LedgerEntry {
+    #[deli(key)]
+    id: deli::U64Key,
+    #[deli(index)]
+    delta: deli::I64Key,
+}
+
+#[wasm_bindgen_test]
+async fn test_int64_keys() {
+    use deli::{CursorDirection, I64Key, U64Key};
+
+    let _ = Database::delete("test_int64_keys_db").await;
+
+    let database = Database::builder("test_int64_keys_db")
+        .version(1)
+        .add_model::<LedgerEntry>()
+        .build()
+        .await
+        .unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_model::<LedgerEntry>()
+        .build()
+        .unwrap();
+    let store = LedgerEntry::with_transaction(&transaction).unwrap();
+
+    // Values beyond 2^53 are not representable as JS numbers
+    let base = (1 << 60) + 1;
+
+    for (offset, delta) in [(0, -5), (1, i64::MIN), (2, 7), (3, i64::MAX)] {
+        let id = store
+            .add(&LedgerEntry {
+                id: U64Key(base + offset),
+                delta: I64Key(delta),
+            })
+            .await
+            .unwrap();
+        assert_eq!(id, U64Key(base + offset));
+    }
+
+    let entry = store.get(U64Key(base + 1)).await.unwrap().unwrap();
+    assert_eq!(entry.delta, I64Key(i64::MIN));
+
+    let ids = store
+        .get_all_keys(OwnedRange(U64Key(base + 1)..U64Key(base + 3)), None)
+        .await
+        .unwrap();
+    assert_eq!(ids, [U64Key(base + 1), U64Key(base + 2)]);
+
+    // Encoded keys sort numerically, including negative values
+    let deltas = store
+        .by_delta()
+        .unwrap()
+        .ordered_get_all(.., CursorDirection::Next, None)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.delta.0)
+        .collect::<Vec<_>>();
+    assert_eq!(deltas, [i64::MIN, -5, 7, i64::MAX]);
+
+    transaction.commit().await.unwrap();
+
+    database.close();
+    Database::delete("test_int64_keys_db").await.unwrap();
+}
+
+#[cfg(feature = "dynamic")]
+#[wasm_bindgen_test]
+async fn test_dynamic_store() {
+    use serde_json::json;
+
+    let database = create_database().await.unwrap();
+    let transaction = begin_write_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+
+    let id = store
+        .add(&AddEmployee {
+            name: "Alice".to_string(),
+            email: "alice@example.com".to_string(),
+            age: 25,
+        })
+        .await
+        .unwrap();
+    transaction.commit().await.unwrap();
+
+    let transaction = database
+        .transaction()
+        .writable()
+        .with_store(Employee::NAME)
+        .build()
+        .unwrap();
+    let store = transaction.dynamic_store(Employee::NAME).unwrap();
+
+    assert_eq!(store.name(), Employee::NAME);
+    assert_eq!(store.index_names().len(), 2);
+
+    let record = store.get(&json!(id)).await.unwrap().unwrap();
+    assert_eq!(record["name"], "Alice");
+    assert_eq!(record["age"], 25);
+
+    let key = store
+        .put(
+            &json!({ "name": "Bob", "email": "bob@example.com", "age": 30 }),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(store.count().await.unwrap(), 2);
+    assert_eq!(
+        store.get_all_keys(None).await.unwrap(),
+        [json!(id), key.clone()]
+    );
+
+    store.delete(&json!(id)).await.unwrap();
+    assert_eq!(store.get_all(None).await.unwrap().len(), 1);
+    transaction.commit().await.unwrap();
+
+    // Records written dynamically are read back by the model
+    let transaction = begin_read_transaction(&database).unwrap();
+    let store = Employee::with_transaction(&transaction).unwrap();
+    let bob = store
+        .get(&serde_json::from_value::<u32>(key).unwrap())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(bob.name, "Bob");
+    transaction.done().await.unwrap();
+
+    close_and_delete_database(database).await.unwrap();
+}